@@ -0,0 +1,27 @@
+//! Embeds the commit being built as `OXEYE_GIT_SHA`, reported by GET /version.
+//!
+//! CI and container builds without a `.git` directory can pass `GIT_SHA`.
+
+use std::process::Command;
+
+fn main() {
+    let sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| {
+            let output = Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=OXEYE_GIT_SHA={}", sha);
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+}
@@ -0,0 +1,122 @@
+//! Per-guild limit on how fast `/oxeye connect` hands out connection codes.
+//!
+//! Codes are the only secret guarding POST /connect, so a guild that mints
+//! them in bulk makes guessing one easier. Each guild gets a sliding window of
+//! recent code timestamps; requests beyond the limit are refused until the
+//! oldest one falls out of the window. A `/oxeye connectbulk` run takes one
+//! slot per code, and is refused outright unless all of them are free.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Length of the sliding window.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Recently issued connection codes per guild.
+#[derive(Debug)]
+pub struct CodeThrottle {
+    per_minute: usize,
+    issued: Mutex<HashMap<u64, VecDeque<Instant>>>,
+}
+
+impl CodeThrottle {
+    /// Allow at most `per_minute` codes per guild in any 60 second window.
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute: per_minute.max(1) as usize,
+            issued: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Most codes a guild may get per minute, and so in one request.
+    pub fn per_minute(&self) -> usize {
+        self.per_minute
+    }
+
+    /// Record `count` codes for `guild_id`, or return how long until that many
+    /// are allowed. `count` must not be more than [`CodeThrottle::per_minute`].
+    pub fn try_acquire(&self, guild_id: u64, count: usize) -> Result<(), Duration> {
+        self.try_acquire_at(guild_id, count, Instant::now())
+    }
+
+    fn try_acquire_at(&self, guild_id: u64, count: usize, now: Instant) -> Result<(), Duration> {
+        let mut issued = self.issued.lock().unwrap();
+        // Forget guilds whose window has fully expired so the map stays small
+        issued.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|&t| now.duration_since(t) >= WINDOW)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = issued.entry(guild_id).or_default();
+        let count = count.clamp(1, self.per_minute);
+        if times.len() + count > self.per_minute {
+            // Wait until enough of the oldest codes have left the window
+            let freed_by = times[times.len() + count - self.per_minute - 1];
+            return Err(WINDOW - now.duration_since(freed_by));
+        }
+        times.extend(std::iter::repeat_n(now, count));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_codes_over_the_limit() {
+        let throttle = CodeThrottle::new(2);
+        let start = Instant::now();
+
+        assert!(throttle.try_acquire_at(1, 1, start).is_ok());
+        assert!(throttle.try_acquire_at(1, 1, start).is_ok());
+        let retry_after = throttle
+            .try_acquire_at(1, 1, start + Duration::from_secs(10))
+            .unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(50));
+
+        // Other guilds have their own budget
+        assert!(throttle.try_acquire_at(2, 1, start).is_ok());
+    }
+
+    #[test]
+    fn test_allows_codes_again_after_window() {
+        let throttle = CodeThrottle::new(1);
+        let start = Instant::now();
+
+        assert!(throttle.try_acquire_at(1, 1, start).is_ok());
+        assert!(throttle.try_acquire_at(1, 1, start).is_err());
+        assert!(throttle.try_acquire_at(1, 1, start + WINDOW).is_ok());
+    }
+
+    #[test]
+    fn test_batch_takes_one_slot_per_code() {
+        let throttle = CodeThrottle::new(5);
+        let start = Instant::now();
+
+        assert!(throttle.try_acquire_at(1, 1, start).is_ok());
+        assert!(
+            throttle
+                .try_acquire_at(1, 3, start + Duration::from_secs(10))
+                .is_ok()
+        );
+
+        // Only one slot is left, so a batch of two is refused as a whole
+        let retry_after = throttle
+            .try_acquire_at(1, 2, start + Duration::from_secs(20))
+            .unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(40));
+        assert!(
+            throttle
+                .try_acquire_at(1, 1, start + Duration::from_secs(20))
+                .is_ok()
+        );
+        assert!(throttle.try_acquire_at(1, 1, start + WINDOW).is_ok());
+    }
+}
@@ -1,7 +1,13 @@
 use std::env::var;
+use std::net::{IpAddr, Ipv4Addr};
 use std::time::Duration;
 
 use dotenvy::dotenv;
+use std::str::FromStr;
+
+use crate::mojang::SESSION_SERVER;
+use crate::render::{AnimationConfig, CompositeConfig};
+use crate::validation::{DEFAULT_MAX_PLAYERS, MIN_CODE_LENGTH, NamePolicy};
 
 /// Application configuration with environment variable overrides
 #[derive(Debug, Clone)]
@@ -10,26 +16,60 @@ pub struct Config {
     /// Env: REQUEST_BODY_LIMIT (default: 1048576 = 1MB)
     pub request_body_limit: usize,
 
+    /// Request body size limit in bytes for /join and /leave (single-player payloads)
+    /// Env: JOIN_LEAVE_BODY_LIMIT (default: 4096 = 4KB)
+    pub join_leave_body_limit: usize,
+
     /// Request timeout in seconds
     /// Env: REQUEST_TIMEOUT_SECS (default: 30)
     pub request_timeout: Duration,
 
+    /// How long to wait for in-flight requests during graceful shutdown, in seconds
+    /// Env: SHUTDOWN_TIMEOUT_SECS (default: 10)
+    pub shutdown_timeout: Duration,
+
+    /// File the online player lists are saved to on graceful shutdown and
+    /// restored from on startup, instead of the database's online_snapshot table
+    /// Env: CACHE_SNAPSHOT_PATH (default: none, saved to the database)
+    pub cache_snapshot_path: Option<String>,
+
+    /// How often expired connection codes are deleted, in seconds (a few seconds of jitter is added)
+    /// Env: CLEANUP_INTERVAL_SECS (default: 60)
+    pub cleanup_interval: Duration,
+
     /// Server port
     /// Env: PORT (default: 3000)
     pub port: u16,
 
+    /// IP address to bind the HTTP listener to (use 127.0.0.1 behind a reverse proxy)
+    /// Env: BIND_ADDRESS (default: 0.0.0.0, panics if not a valid IP address)
+    pub bind_address: IpAddr,
+
     /// Database file path
     /// Env: DATABASE_PATH (default: "oxeye.db")
     pub database_path: String,
 
-    /// Discord API Token
-    /// Env: DISCORD_TOKEN (optional, check at runtime, if doesn't exist, panic)
+    /// Database location as a URL; overrides database_path when set.
+    /// Only SQLite (`sqlite://<path>` or a bare path) is supported for now.
+    /// Env: DATABASE_URL (default: none)
+    pub database_url: Option<String>,
+
+    /// Discord API Token; without one only the HTTP API runs
+    /// Env: DISCORD_TOKEN (default: none)
     pub discord_token: Option<String>,
 
     /// Discord Command Prefix
     /// Env: DISCORD_COMMAND_PREFIX (default: "!")
     pub discord_command_prefix: String,
 
+    /// Per-user cooldown (in each Discord server) for /status, which renders an image
+    /// Env: DISCORD_STATUS_COOLDOWN_SECS (default: 5, 0 disables it)
+    pub discord_status_cooldown: Duration,
+
+    /// Per-user cooldown for the cheaper read-only commands (/list, /recent, /stats)
+    /// Env: DISCORD_READ_COOLDOWN_SECS (default: 2, 0 disables it)
+    pub discord_read_cooldown: Duration,
+
     /// Rate limit for /connect endpoint (requests per minute)
     /// Env: RATE_LIMIT_CONNECT_PER_MIN (default: 5)
     /// This is stricter since connect is only used once per server setup
@@ -56,54 +96,427 @@ pub struct Config {
     /// Env: RATE_LIMIT_GENERAL_BURST (default: 20)
     pub rate_limit_general_burst: u32,
 
+    /// Rate limit for image endpoints (/heads, /status-image) in requests per second
+    /// Env: RATE_LIMIT_IMAGE_PER_SEC (default: 50)
+    /// Lenient since one Discord embed can fetch many heads at once
+    pub rate_limit_image_per_sec: u64,
+
+    /// Burst size for image endpoints
+    /// Env: RATE_LIMIT_IMAGE_BURST (default: 100)
+    pub rate_limit_image_burst: u32,
+
+    /// Connection codes one Discord server may generate with /oxeye connect (per minute)
+    /// Env: RATE_LIMIT_GUILD_CODES_PER_MIN (default: 5)
+    pub rate_limit_guild_codes_per_min: u32,
+
     /// Public URL for the backend (used for image URLs in Discord embeds)
     /// Env: PUBLIC_URL (default: "http://localhost:3000")
     pub public_url: String,
+
+    /// Maximum number of players accepted in one /sync or /events list
+    /// Env: MAX_PLAYERS (default: 1000)
+    pub max_players: usize,
+
+    /// Layout of status images; unparseable or out-of-range values use the default
+    /// Env: STATUS_HEAD_SIZE (default: 128), STATUS_H_SPACING (default: 32),
+    /// STATUS_V_SPACING (default: 16), STATUS_MAX_PER_ROW (default: 5, minimum 1),
+    /// STATUS_MAX_PLAYERS (heads drawn before a "+N more" tile, default: 25, minimum 1),
+    /// STATUS_FONT_SIZE (default: 32), STATUS_MIN_FONT_SIZE (default: 20),
+    /// STATUS_MAX_NAME_CHARS (longer names end in "…", default: 16),
+    /// STATUS_GIF_FRAMES (default: 8), STATUS_GIF_FRAME_DELAY_MS (default: 100)
+    pub status_image: CompositeConfig,
+
+    /// Font files tried in order for username characters Inter lacks (e.g. a
+    /// Noto Sans CJK file for Chinese, Japanese and Korean names); loaded into
+    /// `status_image.fallback_fonts` at startup
+    /// Env: STATUS_FALLBACK_FONTS (comma-separated paths, default: none)
+    pub status_fallback_fonts: Vec<String>,
+
+    /// Which player names are accepted (set to "bedrock" for cross-play servers)
+    /// Env: NAME_POLICY ("java", "bedrock" or "custom:<max length>", default: "java")
+    pub name_policy: NamePolicy,
+
+    /// Whether /sync accepts an empty player list (clearing everyone) when the
+    /// request doesn't say; set to false to reject empty syncs by default
+    /// Env: SYNC_ALLOW_EMPTY (default: true)
+    pub sync_allow_empty: bool,
+
+    /// Whether /sync treats names differing only in case ("Steve", "steve") as
+    /// one player, keeping the first spelling; off by default since some servers
+    /// (e.g. offline mode) can have distinct players with such names
+    /// Env: SYNC_FOLD_CASE (default: false)
+    pub sync_fold_case: bool,
+
+    /// How long after its last full sync a server is marked stale in /status and /list
+    /// Env: STALE_SYNC_SECS (default: 300, 0 disables the marker)
+    pub stale_sync_after: Duration,
+
+    /// Days of play session history kept for stats like average session length
+    /// Env: SESSION_RETENTION_DAYS (default: 90, 0 keeps sessions forever)
+    pub session_retention_days: u32,
+
+    /// How long a player who left stays listed, so a quick rejoin never shows them as gone
+    /// Env: LEAVE_GRACE_SECS (default: 0, players are removed immediately)
+    pub leave_grace: Duration,
+
+    /// Maximum number of servers a single guild can link
+    /// Env: MAX_SERVERS_PER_GUILD (default: 25)
+    pub max_servers_per_guild: u32,
+
+    /// Number of characters after "oxeye-" in generated connection codes
+    /// Env: CODE_LENGTH (default: 6, values below MIN_CODE_LENGTH are raised to it)
+    pub code_length: usize,
+
+    /// Shortest code suffix POST /connect accepts
+    /// Env: MIN_CODE_LENGTH (default: 6, values below 6 are raised to 6)
+    pub min_code_length: usize,
+
+    /// Failed /connect attempts (unknown or expired codes) one client IP may make
+    /// within `connect_failure_window` before it is blocked
+    /// Env: CONNECT_MAX_FAILURES (default: 10, 0 disables the block)
+    pub connect_max_failures: u32,
+
+    /// Window over which failed /connect attempts are counted; a blocked client
+    /// can try again once its oldest failure is this old
+    /// Env: CONNECT_FAILURE_WINDOW_SECS (default: 600)
+    pub connect_failure_window: Duration,
+
+    /// Reverse proxies whose X-Forwarded-For / X-Real-IP headers identify the client
+    /// for the /connect failure limit; other peers are keyed on their own address
+    /// Env: TRUSTED_PROXIES (comma-separated IPs, default: none)
+    pub trusted_proxies: Vec<IpAddr>,
+
+    /// Mojang session server used to fetch skins for players who join with a UUID
+    /// but no skin
+    /// Env: MOJANG_SESSION_SERVER (default: https://sessionserver.mojang.com)
+    pub mojang_session_server: String,
+
+    /// How long to wait for a connection to Mojang's session server when fetching skins
+    /// Env: MOJANG_CONNECT_TIMEOUT_SECS (default: 5)
+    pub mojang_connect_timeout: Duration,
+
+    /// How long one request to Mojang (for a profile or a skin) may take in total
+    /// Env: MOJANG_REQUEST_TIMEOUT_SECS (default: 10)
+    pub mojang_request_timeout: Duration,
+
+    /// Expose Prometheus metrics on GET /metrics
+    /// Env: ENABLE_METRICS (default: true)
+    pub enable_metrics: bool,
+
+    /// Log level or filter directives (RUST_LOG takes precedence when set)
+    /// Env: LOG_LEVEL (default: "debug" in debug builds, "info" in release)
+    pub log_level: String,
+
+    /// Log output format
+    /// Env: LOG_FORMAT ("compact" or "json", default: "compact")
+    pub log_format: LogFormat,
+
+    /// Browser origins allowed to call the read API (CORS is disabled when empty)
+    /// Env: ALLOWED_ORIGINS (comma-separated, default: none)
+    pub allowed_origins: Vec<String>,
+
+    /// Bearer token for cross-guild admin endpoints (they are disabled when unset)
+    /// Env: ADMIN_TOKEN (default: none)
+    pub admin_token: Option<String>,
+
+    /// Let guilds send webhooks to localhost and private networks (off so a
+    /// guild admin can't make the backend call internal services)
+    /// Env: WEBHOOK_ALLOW_PRIVATE (default: false)
+    pub webhook_allow_private: bool,
+
+    /// PEM certificate chain for serving HTTPS directly (requires tls_key_path)
+    /// Env: TLS_CERT_PATH (default: none, plain HTTP)
+    pub tls_cert_path: Option<String>,
+
+    /// PEM private key for serving HTTPS directly (requires tls_cert_path)
+    /// Env: TLS_KEY_PATH (default: none, plain HTTP)
+    pub tls_key_path: Option<String>,
+}
+
+/// How log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable single-line output
+    #[default]
+    Compact,
+    /// One JSON object per line, for log aggregation
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "compact" => Ok(LogFormat::Compact),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format {:?}", other)),
+        }
+    }
 }
 
+/// Heads drawn in a status image before the "+N more" tile
+const DEFAULT_STATUS_MAX_PLAYERS: usize = 25;
+
+/// Characters of a username shown in status images before it's cut short
+/// (Java Edition names are at most 16)
+const DEFAULT_STATUS_MAX_NAME_CHARS: usize = 16;
+
+/// Default log level for this build profile
+const DEFAULT_LOG_LEVEL: &str = if cfg!(debug_assertions) {
+    "debug"
+} else {
+    "info"
+};
+
 impl Config {
     /// Load configuration from environment variables with defaults
     pub fn from_env() -> Self {
         let _ = dotenv(); //for debugging mostly
+        let min_code_length =
+            env_or_default("MIN_CODE_LENGTH", MIN_CODE_LENGTH).max(MIN_CODE_LENGTH);
         Self {
             request_body_limit: env_or_default("REQUEST_BODY_LIMIT", 1024 * 1024),
+            join_leave_body_limit: env_or_default("JOIN_LEAVE_BODY_LIMIT", 4 * 1024),
             request_timeout: Duration::from_secs(env_or_default("REQUEST_TIMEOUT_SECS", 30)),
+            shutdown_timeout: Duration::from_secs(env_or_default("SHUTDOWN_TIMEOUT_SECS", 10)),
+            cache_snapshot_path: var("CACHE_SNAPSHOT_PATH")
+                .ok()
+                .filter(|path| !path.is_empty()),
+            cleanup_interval: Duration::from_secs(
+                env_or_default("CLEANUP_INTERVAL_SECS", 60).max(1),
+            ),
             port: env_or_default("PORT", 3000),
+            bind_address: {
+                let value = env_or_default_string("BIND_ADDRESS", "0.0.0.0");
+                value.parse().unwrap_or_else(|_| {
+                    panic!("BIND_ADDRESS must be a valid IP address, got {:?}", value)
+                })
+            },
             database_path: env_or_default_string("DATABASE_PATH", "oxeye.db"),
-            discord_token: var("DISCORD_TOKEN")
-                .expect("DISCORD_TOKEN environment variable is required")
-                .into(),
+            database_url: var("DATABASE_URL").ok().filter(|url| !url.is_empty()),
+            discord_token: discord_token_from_env(),
             discord_command_prefix: env_or_default_string("DISCORD_COMMAND_PREFIX", "!"),
+            discord_status_cooldown: Duration::from_secs(env_or_default(
+                "DISCORD_STATUS_COOLDOWN_SECS",
+                5,
+            )),
+            discord_read_cooldown: Duration::from_secs(env_or_default(
+                "DISCORD_READ_COOLDOWN_SECS",
+                2,
+            )),
             rate_limit_connect_per_min: env_or_default("RATE_LIMIT_CONNECT_PER_MIN", 5),
             rate_limit_connect_burst: env_or_default("RATE_LIMIT_CONNECT_BURST", 2),
             rate_limit_player_per_sec: env_or_default("RATE_LIMIT_PLAYER_PER_SEC", 50),
             rate_limit_player_burst: env_or_default("RATE_LIMIT_PLAYER_BURST", 100),
             rate_limit_general_per_sec: env_or_default("RATE_LIMIT_GENERAL_PER_SEC", 10),
             rate_limit_general_burst: env_or_default("RATE_LIMIT_GENERAL_BURST", 20),
+            rate_limit_image_per_sec: env_or_default("RATE_LIMIT_IMAGE_PER_SEC", 50),
+            rate_limit_image_burst: env_or_default("RATE_LIMIT_IMAGE_BURST", 100),
+            rate_limit_guild_codes_per_min: env_or_default("RATE_LIMIT_GUILD_CODES_PER_MIN", 5),
             public_url: env_or_default_string("PUBLIC_URL", "http://localhost:3000"),
+            max_players: env_or_default("MAX_PLAYERS", DEFAULT_MAX_PLAYERS),
+            status_image: status_image_from_env(),
+            status_fallback_fonts: env_or_default_string("STATUS_FALLBACK_FONTS", "")
+                .split(',')
+                .map(|path| path.trim().to_string())
+                .filter(|path| !path.is_empty())
+                .collect(),
+            name_policy: env_or_default("NAME_POLICY", NamePolicy::Java),
+            sync_allow_empty: env_or_default("SYNC_ALLOW_EMPTY", true),
+            sync_fold_case: env_or_default("SYNC_FOLD_CASE", false),
+            stale_sync_after: Duration::from_secs(env_or_default("STALE_SYNC_SECS", 300)),
+            session_retention_days: env_or_default("SESSION_RETENTION_DAYS", 90),
+            leave_grace: Duration::from_secs(env_or_default("LEAVE_GRACE_SECS", 0)),
+            max_servers_per_guild: env_or_default("MAX_SERVERS_PER_GUILD", 25),
+            // Generated codes must pass the configured minimum
+            code_length: env_or_default("CODE_LENGTH", MIN_CODE_LENGTH).max(min_code_length),
+            min_code_length,
+            connect_max_failures: env_or_default("CONNECT_MAX_FAILURES", 10),
+            connect_failure_window: Duration::from_secs(env_or_default(
+                "CONNECT_FAILURE_WINDOW_SECS",
+                600,
+            )),
+            trusted_proxies: env_or_default_string("TRUSTED_PROXIES", "")
+                .split(',')
+                .filter_map(|ip| ip.trim().parse().ok())
+                .collect(),
+            mojang_session_server: env_or_default_string("MOJANG_SESSION_SERVER", SESSION_SERVER)
+                .trim_end_matches('/')
+                .to_string(),
+            mojang_connect_timeout: Duration::from_secs(env_or_default(
+                "MOJANG_CONNECT_TIMEOUT_SECS",
+                5,
+            )),
+            mojang_request_timeout: Duration::from_secs(env_or_default(
+                "MOJANG_REQUEST_TIMEOUT_SECS",
+                10,
+            )),
+            enable_metrics: env_or_default("ENABLE_METRICS", true),
+            log_level: env_or_default_string("LOG_LEVEL", DEFAULT_LOG_LEVEL),
+            log_format: env_or_default("LOG_FORMAT", LogFormat::Compact),
+            allowed_origins: env_or_default_string("ALLOWED_ORIGINS", "")
+                .split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect(),
+            admin_token: var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty()),
+            webhook_allow_private: env_or_default("WEBHOOK_ALLOW_PRIVATE", false),
+            tls_cert_path: var("TLS_CERT_PATH").ok().filter(|path| !path.is_empty()),
+            tls_key_path: var("TLS_KEY_PATH").ok().filter(|path| !path.is_empty()),
+        }
+    }
+
+    /// SQLite file to open, from DATABASE_URL if set, else DATABASE_PATH.
+    ///
+    /// Other database URLs are rejected rather than silently falling back to
+    /// a local file, since that would split state between instances.
+    pub fn sqlite_path(&self) -> Result<&str, String> {
+        let Some(url) = self.database_url.as_deref() else {
+            return Ok(&self.database_path);
+        };
+        if let Some(path) = url
+            .strip_prefix("sqlite://")
+            .or_else(|| url.strip_prefix("sqlite:"))
+        {
+            return Ok(path);
+        }
+        match url.split_once("://") {
+            Some(("postgres" | "postgresql", _)) => {
+                Err("PostgreSQL is not supported yet; use a SQLite path".to_string())
+            }
+            Some((scheme, _)) => Err(format!("unsupported database URL scheme {:?}", scheme)),
+            None => Ok(url),
+        }
+    }
+
+    /// Certificate and key paths when HTTPS is enabled, or `None` for plain HTTP.
+    ///
+    /// Setting only one of the two is an error rather than a silent fallback.
+    pub fn tls_paths(&self) -> Result<Option<(&str, &str)>, String> {
+        match (&self.tls_cert_path, &self.tls_key_path) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (None, None) => Ok(None),
+            (Some(_), None) => Err("TLS_CERT_PATH is set but TLS_KEY_PATH is not".to_string()),
+            (None, Some(_)) => Err("TLS_KEY_PATH is set but TLS_CERT_PATH is not".to_string()),
         }
     }
+}
 
+impl Default for Config {
     /// Create configuration with all default values
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
             request_body_limit: 1024 * 1024, // 1 MB
+            join_leave_body_limit: 4 * 1024, // 4 KB
             request_timeout: Duration::from_secs(30),
+            shutdown_timeout: Duration::from_secs(10),
+            cache_snapshot_path: None,
+            cleanup_interval: Duration::from_secs(60),
             port: 3000,
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
             database_path: "oxeye.db".to_string(),
+            database_url: None,
             discord_token: None,
             discord_command_prefix: "!oxeye".to_string(),
+            discord_status_cooldown: Duration::from_secs(5),
+            discord_read_cooldown: Duration::from_secs(2),
             rate_limit_connect_per_min: 5,
             rate_limit_connect_burst: 2,
             rate_limit_player_per_sec: 50,
             rate_limit_player_burst: 100,
             rate_limit_general_per_sec: 10,
             rate_limit_general_burst: 20,
+            rate_limit_image_per_sec: 50,
+            rate_limit_image_burst: 100,
+            rate_limit_guild_codes_per_min: 5,
             public_url: "http://localhost:3000".to_string(),
+            max_players: DEFAULT_MAX_PLAYERS,
+            status_image: default_status_image(),
+            status_fallback_fonts: Vec::new(),
+            name_policy: NamePolicy::Java,
+            sync_allow_empty: true,
+            sync_fold_case: false,
+            stale_sync_after: Duration::from_secs(300),
+            session_retention_days: 90,
+            leave_grace: Duration::ZERO,
+            max_servers_per_guild: 25,
+            code_length: MIN_CODE_LENGTH,
+            min_code_length: MIN_CODE_LENGTH,
+            connect_max_failures: 10,
+            connect_failure_window: Duration::from_secs(600),
+            trusted_proxies: Vec::new(),
+            mojang_session_server: SESSION_SERVER.to_string(),
+            mojang_connect_timeout: Duration::from_secs(5),
+            mojang_request_timeout: Duration::from_secs(10),
+            enable_metrics: true,
+            log_level: DEFAULT_LOG_LEVEL.to_string(),
+            log_format: LogFormat::Compact,
+            allowed_origins: Vec::new(),
+            admin_token: None,
+            webhook_allow_private: false,
+            tls_cert_path: None,
+            tls_key_path: None,
         }
     }
 }
 
+/// Status image layout used when no STATUS_* variables are set
+fn default_status_image() -> CompositeConfig {
+    let default = CompositeConfig::default();
+    CompositeConfig {
+        max_players_rendered: Some(DEFAULT_STATUS_MAX_PLAYERS),
+        // Drop columns once names are, on average, wider than their heads
+        wide_name_width: Some(default.head_size),
+        max_display_chars: Some(DEFAULT_STATUS_MAX_NAME_CHARS),
+        ..default
+    }
+}
+
+/// Read the status image layout, keeping the default for any invalid value
+fn status_image_from_env() -> CompositeConfig {
+    let default = default_status_image();
+    let font_size = env_positive_or_default("STATUS_FONT_SIZE", default.font_size);
+    let head_size = env_positive_or_default("STATUS_HEAD_SIZE", default.head_size);
+    // An explicit per-row count is kept as-is, however long the names are
+    let fixed_per_row = var("STATUS_MAX_PER_ROW").is_ok();
+    CompositeConfig {
+        head_size,
+        h_spacing: env_or_default("STATUS_H_SPACING", default.h_spacing),
+        v_spacing: env_or_default("STATUS_V_SPACING", default.v_spacing),
+        max_per_row: env_positive_or_default("STATUS_MAX_PER_ROW", default.max_per_row),
+        wide_name_width: (!fixed_per_row).then_some(head_size),
+        max_players_rendered: Some(env_positive_or_default(
+            "STATUS_MAX_PLAYERS",
+            DEFAULT_STATUS_MAX_PLAYERS,
+        )),
+        font_size,
+        // Long names shrink down to this size, so it can't exceed the base size
+        min_font_size: env_positive_or_default("STATUS_MIN_FONT_SIZE", default.min_font_size)
+            .min(font_size),
+        max_display_chars: Some(env_positive_or_default(
+            "STATUS_MAX_NAME_CHARS",
+            DEFAULT_STATUS_MAX_NAME_CHARS,
+        )),
+        animation: AnimationConfig {
+            frames: env_positive_or_default("STATUS_GIF_FRAMES", default.animation.frames),
+            frame_delay_ms: env_positive_or_default(
+                "STATUS_GIF_FRAME_DELAY_MS",
+                default.animation.frame_delay_ms,
+            ),
+        },
+        ..default
+    }
+}
+
+/// Read the Discord token, treating an empty or blank value as unset
+fn discord_token_from_env() -> Option<String> {
+    var("DISCORD_TOKEN")
+        .ok()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
 /// Parse environment variable or return default value
 fn env_or_default<T: std::str::FromStr>(key: &str, default: T) -> T {
     var(key)
@@ -112,6 +525,15 @@ fn env_or_default<T: std::str::FromStr>(key: &str, default: T) -> T {
         .unwrap_or(default)
 }
 
+/// Parse a positive environment variable or return default value
+fn env_positive_or_default<T: FromStr + PartialOrd + Default>(key: &str, default: T) -> T {
+    var(key)
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .filter(|val| *val > T::default())
+        .unwrap_or(default)
+}
+
 /// Parse environment variable string or return default value
 fn env_or_default_string(key: &str, default: &str) -> String {
     var(key).unwrap_or_else(|_| default.to_string())
@@ -125,14 +547,145 @@ mod tests {
     fn test_default_config() {
         let config = Config::default();
         assert_eq!(config.request_body_limit, 1024 * 1024);
+        assert_eq!(config.join_leave_body_limit, 4 * 1024);
         assert_eq!(config.request_timeout, Duration::from_secs(30));
+        assert_eq!(config.shutdown_timeout, Duration::from_secs(10));
+        assert_eq!(config.cache_snapshot_path, None);
+        assert_eq!(config.cleanup_interval, Duration::from_secs(60));
         assert_eq!(config.port, 3000);
+        assert_eq!(config.bind_address, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
         assert_eq!(config.database_path, "oxeye.db");
+        assert_eq!(config.sqlite_path(), Ok("oxeye.db"));
+        assert_eq!(config.discord_status_cooldown, Duration::from_secs(5));
+        assert_eq!(config.discord_read_cooldown, Duration::from_secs(2));
         assert_eq!(config.rate_limit_connect_per_min, 5);
         assert_eq!(config.rate_limit_connect_burst, 2);
         assert_eq!(config.rate_limit_player_per_sec, 50);
         assert_eq!(config.rate_limit_player_burst, 100);
         assert_eq!(config.rate_limit_general_per_sec, 10);
         assert_eq!(config.rate_limit_general_burst, 20);
+        assert_eq!(config.rate_limit_image_per_sec, 50);
+        assert_eq!(config.rate_limit_image_burst, 100);
+        assert_eq!(config.rate_limit_guild_codes_per_min, 5);
+        assert_eq!(config.max_players, 1000);
+        assert_eq!(config.status_image.head_size, 128);
+        assert_eq!(config.status_image.max_per_row, 5);
+        assert_eq!(config.status_image.wide_name_width, Some(128));
+        assert_eq!(config.status_image.max_players_rendered, Some(25));
+        assert_eq!(config.status_image.max_display_chars, Some(16));
+        assert!(config.status_fallback_fonts.is_empty());
+        assert_eq!(config.name_policy, NamePolicy::Java);
+        assert!(config.sync_allow_empty);
+        assert!(!config.sync_fold_case);
+        assert_eq!(config.stale_sync_after, Duration::from_secs(300));
+        assert_eq!(config.session_retention_days, 90);
+        assert_eq!(config.leave_grace, Duration::ZERO);
+        assert_eq!(config.max_servers_per_guild, 25);
+        assert_eq!(config.code_length, 6);
+        assert_eq!(config.min_code_length, 6);
+        assert_eq!(config.connect_max_failures, 10);
+        assert_eq!(config.connect_failure_window, Duration::from_secs(600));
+        assert!(config.trusted_proxies.is_empty());
+        assert_eq!(config.mojang_session_server, SESSION_SERVER);
+        assert_eq!(config.mojang_connect_timeout, Duration::from_secs(5));
+        assert_eq!(config.mojang_request_timeout, Duration::from_secs(10));
+        assert!(config.enable_metrics);
+        assert!(config.allowed_origins.is_empty());
+        assert!(config.admin_token.is_none());
+        assert!(!config.webhook_allow_private);
+        assert_eq!(config.tls_paths(), Ok(None));
+        assert_eq!(config.log_format, LogFormat::Compact);
+    }
+
+    #[test]
+    fn test_sqlite_path_from_database_url() {
+        let with_url = |url: &str| Config {
+            database_url: Some(url.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            with_url("sqlite:///data/oxeye.db").sqlite_path(),
+            Ok("/data/oxeye.db")
+        );
+        assert_eq!(with_url("sqlite:oxeye.db").sqlite_path(), Ok("oxeye.db"));
+        assert_eq!(
+            with_url("/data/oxeye.db").sqlite_path(),
+            Ok("/data/oxeye.db")
+        );
+        assert!(with_url("postgres://oxeye@db/oxeye").sqlite_path().is_err());
+        assert!(with_url("mysql://oxeye@db/oxeye").sqlite_path().is_err());
+    }
+
+    #[test]
+    fn test_tls_paths_require_both() {
+        let both = Config {
+            tls_cert_path: Some("cert.pem".to_string()),
+            tls_key_path: Some("key.pem".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(both.tls_paths(), Ok(Some(("cert.pem", "key.pem"))));
+
+        let cert_only = Config {
+            tls_cert_path: Some("cert.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(cert_only.tls_paths().is_err());
+
+        let key_only = Config {
+            tls_key_path: Some("key.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(key_only.tls_paths().is_err());
+    }
+
+    #[test]
+    fn test_log_format_from_str() {
+        assert_eq!("json".parse(), Ok(LogFormat::Json));
+        assert_eq!("JSON".parse(), Ok(LogFormat::Json));
+        assert_eq!("compact".parse(), Ok(LogFormat::Compact));
+        assert!("pretty".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_discord_token_from_env() {
+        // Only this test reads DISCORD_TOKEN
+        unsafe { std::env::set_var("DISCORD_TOKEN", "  ") };
+        assert_eq!(discord_token_from_env(), None);
+        unsafe { std::env::remove_var("DISCORD_TOKEN") };
+        assert_eq!(discord_token_from_env(), None);
+        unsafe { std::env::set_var("DISCORD_TOKEN", "bot-token\n") };
+        assert_eq!(discord_token_from_env(), Some("bot-token".to_string()));
+    }
+
+    #[test]
+    fn test_status_image_from_env() {
+        // These variables are only read by this test, so setting them can't
+        // race with the other tests in this module
+        unsafe {
+            std::env::set_var("STATUS_HEAD_SIZE", "64");
+            std::env::set_var("STATUS_H_SPACING", "0");
+            std::env::set_var("STATUS_MAX_PER_ROW", "8");
+            std::env::set_var("STATUS_FONT_SIZE", "18.5");
+            std::env::set_var("STATUS_GIF_FRAMES", "4");
+            std::env::set_var("STATUS_MAX_NAME_CHARS", "12");
+            // Invalid values keep their defaults
+            std::env::set_var("STATUS_V_SPACING", "-4");
+            std::env::set_var("STATUS_MAX_PLAYERS", "0");
+            std::env::set_var("STATUS_MIN_FONT_SIZE", "tiny");
+        }
+        let layout = status_image_from_env();
+
+        assert_eq!(layout.head_size, 64);
+        assert_eq!(layout.h_spacing, 0);
+        assert_eq!(layout.max_per_row, 8);
+        assert_eq!(layout.wide_name_width, None);
+        assert_eq!(layout.font_size, 18.5);
+        assert_eq!(layout.v_spacing, 16);
+        assert_eq!(layout.max_players_rendered, Some(25));
+        // The default minimum (20) is capped at the smaller base size
+        assert_eq!(layout.min_font_size, 18.5);
+        assert_eq!(layout.max_display_chars, Some(12));
+        assert_eq!(layout.animation.frames, 4);
+        assert_eq!(layout.animation.frame_delay_ms, 100);
     }
 }
@@ -0,0 +1,239 @@
+//! Per-client limit on failed POST /connect attempts.
+//!
+//! A connection code is valid for ten minutes, so a client that can make many
+//! attempts could try to guess one. Each client IP gets a sliding window of
+//! recent misses (codes that don't match a pending link); once it holds too
+//! many, further attempts are refused until the oldest miss falls out of the
+//! window. The general /connect rate limit still applies on top of this.
+
+use crate::AppState;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::HeaderMap;
+use axum::http::request::Parts;
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Recent failed /connect attempts per client IP.
+#[derive(Debug)]
+pub struct ConnectGuard {
+    max_failures: usize,
+    window: Duration,
+    trusted_proxies: Vec<IpAddr>,
+    failures: Mutex<Failures>,
+}
+
+#[derive(Debug)]
+struct Failures {
+    by_ip: HashMap<IpAddr, VecDeque<Instant>>,
+    last_sweep: Instant,
+}
+
+impl ConnectGuard {
+    /// Block a client after `max_failures` misses within `window`
+    /// (0 disables the guard).
+    pub fn new(max_failures: u32, window: Duration) -> Self {
+        Self {
+            max_failures: max_failures as usize,
+            window,
+            trusted_proxies: Vec::new(),
+            failures: Mutex::new(Failures {
+                by_ip: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+
+    /// Trust forwarding headers on requests from these peer addresses (reverse
+    /// proxies in front of the backend). Anyone else is keyed on their own
+    /// address, since they could put anything in those headers.
+    pub fn with_trusted_proxies(mut self, proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
+    /// Check whether `ip` may attempt a connection, or return how long until it can.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    /// Record a miss (an unknown or expired code) from `ip`.
+    pub fn record_failure(&self, ip: IpAddr) {
+        self.record_failure_at(ip, Instant::now());
+    }
+
+    /// The client behind a request from `peer`: the peer itself, or for a
+    /// trusted proxy the last address it forwarded that isn't another trusted proxy.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.trusted_proxies.contains(&peer) {
+            return peer;
+        }
+        // Each proxy appends the address it saw, so read from the right
+        let forwarded_for = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+            .collect::<Vec<_>>();
+        let real_ip = headers
+            .get("x-real-ip")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|ip| ip.trim().parse().ok());
+        forwarded_for
+            .into_iter()
+            .rev()
+            .find(|ip| !self.trusted_proxies.contains(ip))
+            .or(real_ip)
+            .unwrap_or(peer)
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        if self.max_failures == 0 {
+            return Ok(());
+        }
+        let mut failures = self.failures.lock().unwrap();
+        self.sweep(&mut failures, now);
+        match failures.by_ip.get_mut(&ip) {
+            Some(times) => {
+                self.expire(times, now);
+                if times.len() >= self.max_failures {
+                    Err(self.window - now.duration_since(times[0]))
+                } else {
+                    Ok(())
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn record_failure_at(&self, ip: IpAddr, now: Instant) {
+        if self.max_failures == 0 {
+            return;
+        }
+        let mut failures = self.failures.lock().unwrap();
+        self.sweep(&mut failures, now);
+        let times = failures.by_ip.entry(ip).or_default();
+        self.expire(times, now);
+        times.push_back(now);
+    }
+
+    /// Drop one client's misses that are older than the window.
+    fn expire(&self, times: &mut VecDeque<Instant>, now: Instant) {
+        while times
+            .front()
+            .is_some_and(|&t| now.duration_since(t) >= self.window)
+        {
+            times.pop_front();
+        }
+    }
+
+    /// Once per window, drop clients with no recent misses so the map stays small.
+    fn sweep(&self, failures: &mut Failures, now: Instant) {
+        if now.duration_since(failures.last_sweep) < self.window {
+            return;
+        }
+        failures.by_ip.retain(|_, times| {
+            self.expire(times, now);
+            !times.is_empty()
+        });
+        failures.last_sweep = now;
+    }
+}
+
+/// Client IP for the connect guard: the peer address, or the forwarded client
+/// address when the peer is a trusted proxy. None if the peer address is unknown.
+pub(crate) struct ClientIp(pub Option<IpAddr>);
+
+impl FromRequestParts<Arc<AppState>> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        Ok(ClientIp(peer.map(|peer| {
+            state.connect_guard.client_ip(peer, &parts.headers)
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    const OTHER: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2));
+    const PROXY: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    #[test]
+    fn test_blocks_after_too_many_failures() {
+        let guard = ConnectGuard::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        guard.record_failure_at(CLIENT, start);
+        assert!(guard.check_at(CLIENT, start).is_ok());
+        guard.record_failure_at(CLIENT, start);
+        let retry_after = guard
+            .check_at(CLIENT, start + Duration::from_secs(10))
+            .unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(50));
+
+        // Other clients aren't affected, and the block lifts with the window
+        assert!(guard.check_at(OTHER, start).is_ok());
+        assert!(
+            guard
+                .check_at(CLIENT, start + Duration::from_secs(60))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_zero_disables_guard() {
+        let guard = ConnectGuard::new(0, Duration::from_secs(60));
+        let start = Instant::now();
+        for _ in 0..100 {
+            guard.record_failure_at(CLIENT, start);
+        }
+        assert!(guard.check_at(CLIENT, start).is_ok());
+    }
+
+    #[test]
+    fn test_sweep_forgets_idle_clients() {
+        let guard = ConnectGuard::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        guard.record_failure_at(CLIENT, start);
+
+        // A check for another client after the window sweeps the idle one out
+        assert!(
+            guard
+                .check_at(OTHER, start + Duration::from_secs(61))
+                .is_ok()
+        );
+        assert!(guard.failures.lock().unwrap().by_ip.is_empty());
+    }
+
+    #[test]
+    fn test_forwarding_headers_only_trusted_from_proxies() {
+        let guard = ConnectGuard::new(2, Duration::from_secs(60)).with_trusted_proxies(vec![PROXY]);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "198.51.100.9, 192.0.2.1".parse().unwrap(),
+        );
+
+        // A direct client can't pick its own address
+        assert_eq!(guard.client_ip(OTHER, &headers), OTHER);
+        // Through the proxy, the address it appended is used, not one the client sent
+        assert_eq!(guard.client_ip(PROXY, &headers), CLIENT);
+        // A proxy that forwarded nothing is keyed on itself
+        assert_eq!(guard.client_ip(PROXY, &HeaderMap::new()), PROXY);
+    }
+}
@@ -1,12 +1,62 @@
 use crate::Context;
 use oxeye_backend::helpers;
-use oxeye_backend::helpers::{format_time_online, now};
+use oxeye_backend::helpers::{escape_markdown, format_time_online, is_sync_stale, now};
+use oxeye_backend::validation;
+use oxeye_db::{DbError, ServerSummary};
 use poise::CreateReply;
 use poise::command;
-use poise::serenity_prelude::{CreateEmbed, CreateEmbedFooter};
+use poise::serenity_prelude::{
+    ButtonStyle, ComponentInteractionCollector, CreateActionRow, CreateButton, CreateEmbed,
+    CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use std::collections::BTreeMap;
+use std::time::Duration;
 
 pub(crate) type Error = Box<dyn std::error::Error + Send + Sync>;
 
+/// Discord blurple, used until a guild picks its own color with /setcolor
+const DEFAULT_EMBED_COLOR: u32 = 0x5865F2;
+
+/// How long /unlinkall waits for the confirm button before giving up
+const UNLINK_ALL_CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The guild's embed color, or the default if none is set (or it can't be read).
+async fn embed_color(ctx: Context<'_>, guild_id: u64) -> u32 {
+    ctx.data()
+        .db
+        .get_embed_color(guild_id)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_EMBED_COLOR)
+}
+
+/// Record an admin action in the guild's audit log. Failures are logged rather
+/// than returned, since the action itself has already happened.
+async fn audit(ctx: Context<'_>, guild_id: u64, action: &str, target: &str) {
+    let user_id = ctx.author().id.get();
+    if let Err(e) = ctx
+        .data()
+        .db
+        .record_audit(
+            guild_id,
+            user_id,
+            action.to_string(),
+            target.to_string(),
+            now(),
+        )
+        .await
+    {
+        tracing::error!(
+            guild_id,
+            user_id,
+            action,
+            "failed to record audit entry: {}",
+            e
+        );
+    }
+}
+
 /// Autocomplete function for server names - suggests servers from current guild
 async fn autocomplete_server_name(ctx: Context<'_>, partial: &str) -> Vec<String> {
     // Get guild_id from context
@@ -32,6 +82,66 @@ async fn autocomplete_server_name(ctx: Context<'_>, partial: &str) -> Vec<String
         .collect::<Vec<_>>()
 }
 
+/// Reply that no server with this name is linked to the guild.
+async fn server_not_found(ctx: Context<'_>, name: &str) -> Result<(), Error> {
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::default()
+                    .title("Server Not Found")
+                    .description(format!(
+                        "No server named '{}' is linked — use `/list` to see linked servers.",
+                        escape_markdown(name)
+                    ))
+                    .color(0xED4245),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Check the server names a command is about to make connection codes for, and
+/// take one slot per name from the guild's code throttle. If a name is invalid
+/// or the guild is over its limit, the admin is told why and false is returned.
+async fn reserve_codes(ctx: Context<'_>, guild_id: u64, names: &[String]) -> Result<bool, Error> {
+    let invalid = names.iter().find_map(|name| {
+        validation::validate_server_name(name)
+            .err()
+            .map(|e| match names.len() {
+                1 => e.to_string(),
+                _ => format!("**{}**: {}", escape_markdown(name), e),
+            })
+    });
+    let problem = match invalid {
+        Some(problem) => problem,
+        None => match ctx.data().code_throttle.try_acquire(guild_id, names.len()) {
+            Ok(()) => return Ok(true),
+            Err(retry_after) => format!(
+                "Slow down! This Discord server has generated too many connection codes. Try again in {} seconds.",
+                retry_after.as_secs().max(1)
+            ),
+        },
+    };
+    ctx.send(CreateReply::default().content(problem).ephemeral(true))
+        .await?;
+    Ok(false)
+}
+
+/// Reply that the guild has no room for another server.
+async fn server_limit_reached(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "This Discord server already has the maximum of {} Minecraft servers, counting unused connection codes (see `/oxeye pending`). Disconnect one or let a code expire before linking another.",
+                ctx.data().max_servers_per_guild
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
 /// Generate a one-time code to link a Minecraft server to this Discord server
 #[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
 pub async fn connect(
@@ -39,24 +149,69 @@ pub async fn connect(
     #[description = "Minecraft Server Name"]
     #[autocomplete = "autocomplete_server_name"]
     name: String,
+    #[description = "Re-link an existing server with this name (its old key stops working)"]
+    overwrite: Option<bool>,
 ) -> Result<(), Error> {
     let data = ctx.data();
     let guild_id = ctx
         .guild_id()
         .ok_or("This command can only be used in a server")?
         .get();
-    let code = helpers::generate_code();
-    data.db
-        .create_pending_link(code.clone(), guild_id, name, now())
-        .await?;
+    if !reserve_codes(ctx, guild_id, std::slice::from_ref(&name)).await? {
+        return Ok(());
+    }
+    let display_name = escape_markdown(&name);
+    let color = embed_color(ctx, guild_id).await;
+    let code = helpers::generate_code(data.code_length);
+    let created_at = now();
+    let result = if overwrite.unwrap_or(false) {
+        data.db
+            .create_relink_code(code.clone(), guild_id, name.clone(), created_at)
+            .await
+    } else {
+        data.db
+            .create_pending_link(
+                code.clone(),
+                guild_id,
+                name.clone(),
+                created_at,
+                data.max_servers_per_guild,
+            )
+            .await
+    };
+    let link = match result {
+        Ok(link) => link,
+        Err(DbError::ServerNotFound) => return server_not_found(ctx, &name).await,
+        Err(DbError::ServerNameConflict) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!(
+                        "**{}** is already linked. If it lost its key (e.g. after a crash), run `/connect` again with `overwrite: True` to give it a new one.",
+                        display_name
+                    ))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+        Err(DbError::ServerLimitReached) => return server_limit_reached(ctx).await,
+        Err(e) => return Err(e.into()),
+    };
+    let action = if link.relink { "relink" } else { "connect" };
+    audit(ctx, guild_id, action, &link.server_name).await;
     ctx.send(
         CreateReply::default().embed(
             CreateEmbed::default()
                 .title("Link Your Server")
                 .description("Run this command in your Minecraft server:")
+                .field("Server", display_name, false)
                 .field("Command", format!("`/oxeye connect {}`", code), false)
-                .field("Expires", "10 minutes", true)
-                .color(0x5865F2)
+                .field(
+                    "Expires",
+                    format_time_online(link.expires_in(created_at)),
+                    true,
+                )
+                .color(color)
                 .footer(CreateEmbedFooter::new(
                     "Only server admins can run this command",
                 )),
@@ -66,6 +221,270 @@ pub async fn connect(
     Ok(())
 }
 
+/// Get a fresh connection code for a server whose code was lost, replacing the old one
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn reconnect(
+    ctx: Context<'_>,
+    #[description = "Minecraft Server Name"]
+    #[autocomplete = "autocomplete_server_name"]
+    name: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    if !reserve_codes(ctx, guild_id, std::slice::from_ref(&name)).await? {
+        return Ok(());
+    }
+    let display_name = escape_markdown(&name);
+    let color = embed_color(ctx, guild_id).await;
+    let code = helpers::generate_code(data.code_length);
+    let created_at = now();
+    let link = match data
+        .db
+        .refresh_pending_link(
+            guild_id,
+            name.clone(),
+            code.clone(),
+            created_at,
+            data.max_servers_per_guild,
+        )
+        .await
+    {
+        Ok(link) => link,
+        Err(DbError::ServerNameConflict) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!(
+                        "**{}** is already linked and has no pending code. If it lost its key, run `/connect` with `overwrite: True` instead.",
+                        display_name
+                    ))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+        Err(DbError::ServerLimitReached) => return server_limit_reached(ctx).await,
+        Err(e) => return Err(e.into()),
+    };
+    audit(ctx, guild_id, "reconnect", &link.server_name).await;
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title("Link Your Server")
+                .description("Run this command in your Minecraft server (any earlier code for it no longer works):")
+                .field("Server", escape_markdown(&link.server_name), false)
+                .field("Command", format!("`/oxeye connect {}`", code), false)
+                .field(
+                    "Expires",
+                    format_time_online(link.expires_in(created_at)),
+                    true,
+                )
+                .color(color)
+                .footer(CreateEmbedFooter::new(
+                    "Only server admins can run this command",
+                )),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Most servers one /connectbulk may create codes for.
+const MAX_BULK_CONNECT: usize = 10;
+
+/// Generate connection codes for several Minecraft servers at once
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn connectbulk(
+    ctx: Context<'_>,
+    #[description = "Minecraft server names, separated by commas"] names: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let names: Vec<String> = names
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    let max_names = MAX_BULK_CONNECT.min(data.code_throttle.per_minute());
+    let problem = if names.is_empty() {
+        Some("Give at least one server name.".to_string())
+    } else if names.len() > max_names {
+        Some(format!(
+            "You can link at most {} servers at once.",
+            max_names
+        ))
+    } else {
+        names.iter().enumerate().find_map(|(i, name)| {
+            names[..i]
+                .iter()
+                .any(|n| n.eq_ignore_ascii_case(name))
+                .then(|| format!("**{}** is listed twice.", escape_markdown(name)))
+        })
+    };
+    if let Some(problem) = problem {
+        ctx.send(CreateReply::default().content(problem).ephemeral(true))
+            .await?;
+        return Ok(());
+    }
+    if !reserve_codes(ctx, guild_id, &names).await? {
+        return Ok(());
+    }
+    let color = embed_color(ctx, guild_id).await;
+    let created_at = now();
+    let links = names
+        .iter()
+        .map(|name| (helpers::generate_code(data.code_length), name.clone()))
+        .collect();
+    let links = match data
+        .db
+        .create_pending_links(guild_id, links, created_at, data.max_servers_per_guild)
+        .await
+    {
+        Ok(links) => links,
+        Err(DbError::ServerNameConflict) => {
+            let mut linked = Vec::new();
+            for name in &names {
+                if data.db.server_name_exists(guild_id, name.clone()).await? {
+                    linked.push(format!("**{}**", escape_markdown(name)));
+                }
+            }
+            ctx.send(
+                CreateReply::default()
+                    .content(format!(
+                        "Already linked: {}. No codes were generated; remove those names and try again.",
+                        linked.join(", ")
+                    ))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+        Err(DbError::ServerLimitReached) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!(
+                        "Linking {} more servers would go over this Discord server's maximum of {} Minecraft servers (unused connection codes count too). No codes were generated.",
+                        names.len(),
+                        data.max_servers_per_guild
+                    ))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    for link in &links {
+        audit(ctx, guild_id, "connect", &link.server_name).await;
+    }
+    let lines: Vec<String> = links
+        .iter()
+        .map(|link| {
+            format!(
+                "**{}**: `/oxeye connect {}`",
+                escape_markdown(&link.server_name),
+                link.code
+            )
+        })
+        .collect();
+    let expires_in = links[0].expires_in(created_at);
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title("Link Your Servers")
+                .description(format!(
+                    "Run the matching command in each Minecraft server:\n{}",
+                    lines.join("\n")
+                ))
+                .field("Expires", format_time_online(expires_in), true)
+                .color(color)
+                .footer(CreateEmbedFooter::new(
+                    "Only server admins can run this command",
+                )),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// List connection codes that haven't been claimed or expired yet
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn pending(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let current_time = now();
+    let links = data
+        .db
+        .get_pending_links_by_guild(guild_id, current_time)
+        .await?;
+
+    let embed = CreateEmbed::default()
+        .title("Pending Connection Codes")
+        .color(embed_color(ctx, guild_id).await);
+    let embed = if links.is_empty() {
+        embed.description("No connection codes are waiting to be used.")
+    } else {
+        let list: String = links
+            .iter()
+            .map(|link| {
+                format!(
+                    "- `{}` for {}{} (expires in {})",
+                    link.code,
+                    escape_markdown(&link.server_name),
+                    if link.relink { " (re-link)" } else { "" },
+                    format_time_online(link.expires_in(current_time))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed.description(list)
+    };
+    // Codes are secrets, so only the admin who asked sees them
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Generate a read-only dashboard token for this Discord server
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn token(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let color = embed_color(ctx, guild_id).await;
+    let token = helpers::generate_guild_token();
+    data.db
+        .create_guild_token(helpers::hash_api_key(&token), guild_id, now())
+        .await?;
+    audit(ctx, guild_id, "token", "").await;
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::default()
+                    .title("Dashboard Token")
+                    .description("Use this as a Bearer token for the guild read API:")
+                    .field("Token", format!("`{}`", token), false)
+                    .color(color)
+                    .footer(CreateEmbedFooter::new(
+                        "Generating a new token revokes the previous one",
+                    )),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
 /// List all Minecraft servers linked to this Discord server
 #[command(slash_command, prefix_command)]
 pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
@@ -74,24 +493,120 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
         .guild_id()
         .ok_or("This command can only be used in a server")?
         .get();
-    let servers = data.db.get_servers_by_guild(guild_id).await?;
+    let servers = data.db.get_servers_by_activity(guild_id).await?;
     let embed = CreateEmbed::default()
         .title("Linked Minecraft Servers")
-        .color(0x5865F2);
+        .color(embed_color(ctx, guild_id).await);
+    let current_time = now();
+    let is_stale =
+        |s: &ServerSummary| is_sync_stale(s.last_sync_at, current_time, data.stale_sync_secs);
+    let server_lines = |servers: &[&ServerSummary]| {
+        servers
+            .iter()
+            .map(|s| {
+                let marker = if is_stale(s) { "⚠️ " } else { "" };
+                format!(
+                    "- {}{} ({} online)",
+                    marker,
+                    escape_markdown(&s.name),
+                    s.player_count
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
     let embed = if servers.is_empty() {
         embed.description("No servers linked yet.")
+    } else if servers.iter().all(|s| s.tag.is_none()) {
+        embed.description(server_lines(&servers.iter().collect::<Vec<_>>()))
     } else {
-        let list: String = servers
+        // One section per tag (alphabetical), untagged servers last under "Other"
+        let mut groups: BTreeMap<&str, Vec<&ServerSummary>> = BTreeMap::new();
+        let mut other = Vec::new();
+        for server in &servers {
+            match server.tag.as_deref() {
+                Some(tag) if tag != UNTAGGED_GROUP => groups.entry(tag).or_default().push(server),
+                _ => other.push(server),
+            }
+        }
+        let sections: Vec<String> = groups
             .iter()
-            .map(|s| format!("- {}", s.name))
-            .collect::<Vec<_>>()
-            .join("\n");
-        embed.description(list)
+            .map(|(tag, servers)| (*tag, servers))
+            .chain((!other.is_empty()).then_some((UNTAGGED_GROUP, &other)))
+            .map(|(tag, servers)| {
+                format!("**{}**\n{}", escape_markdown(tag), server_lines(servers))
+            })
+            .collect();
+        embed.description(sections.join("\n\n"))
+    };
+    let embed = if servers.iter().any(is_stale) {
+        embed.footer(CreateEmbedFooter::new(format!(
+            "⚠️ No sync in over {}, player counts may be out of date",
+            format_time_online(data.stale_sync_secs)
+        )))
+    } else {
+        embed
     };
     ctx.send(CreateReply::default().embed(embed)).await?;
     Ok(())
 }
 
+/// Group heading for servers without a tag in /list
+const UNTAGGED_GROUP: &str = "Other";
+
+/// Tag a linked Minecraft server so /list groups it (omit the tag to clear it)
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn settag(
+    ctx: Context<'_>,
+    #[description = "Minecraft Server Name"]
+    #[autocomplete = "autocomplete_server_name"]
+    name: String,
+    #[description = "Group to list the server under, e.g. SMP (omit to clear)"] tag: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let tag = tag.map(|t| t.trim().to_string());
+    if let Some(Err(e)) = tag.as_deref().map(validation::validate_server_tag) {
+        ctx.send(
+            CreateReply::default()
+                .content(e.to_string())
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+    let group = match &tag {
+        Some(tag) => escape_markdown(tag),
+        None => UNTAGGED_GROUP.to_string(),
+    };
+    let description = format!(
+        "{} is now listed under **{}**.",
+        escape_markdown(&name),
+        group
+    );
+    match data.db.set_server_tag(guild_id, name.clone(), tag).await {
+        Ok(()) => {}
+        Err(DbError::ServerNotFound) => return server_not_found(ctx, &name).await,
+        Err(e) => return Err(e.into()),
+    }
+    audit(ctx, guild_id, "settag", &name).await;
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::default()
+                    .title("Server Tag Updated")
+                    .description(description)
+                    .color(embed_color(ctx, guild_id).await),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
 /// Show online players for a linked Minecraft server
 #[command(slash_command, prefix_command)]
 pub async fn status(
@@ -105,10 +620,15 @@ pub async fn status(
         .guild_id()
         .ok_or("This command can only be used in a server")?
         .get();
-    let server = data
+    let server = match data
         .db
         .get_server_with_players(guild_id, name.clone())
-        .await?;
+        .await
+    {
+        Ok(server) => server,
+        Err(DbError::ServerNotFound) => return server_not_found(ctx, &name).await,
+        Err(e) => return Err(e.into()),
+    };
     let is_synced = data
         .db
         .is_server_synced_by_name(guild_id, &name)
@@ -117,6 +637,11 @@ pub async fn status(
 
     // Get api_key_hash for building image URL
     let api_key_hash = data.db.get_api_key_hash_by_name(guild_id, &name).await?;
+    let last_sync_at = match &api_key_hash {
+        Some(hash) => data.db.get_last_sync(hash).await?,
+        None => None,
+    };
+    let current_time = now();
 
     // Build status text
     let status_text = if is_synced {
@@ -126,18 +651,19 @@ pub async fn status(
     };
 
     let mut embed = CreateEmbed::default()
-        .title(format!("{}", server.name))
-        .color(0x5865F2);
+        .title(escape_markdown(&server.name))
+        .color(embed_color(ctx, guild_id).await);
 
     // Add status image only if synced, players are online, and we have the api_key_hash
-    if is_synced && !server.players.is_empty() {
-        if let Some(ref hash) = api_key_hash {
-            let base_url = data.public_url.trim_end_matches('/');
-            if !base_url.is_empty() {
-                let image_url = format!("{}/status-image/{}.png?t={}", base_url, hash, now());
-                tracing::info!("Generated status image URL: {}", image_url);
-                embed = embed.image(image_url);
-            }
+    if is_synced
+        && !server.players.is_empty()
+        && let Some(ref hash) = api_key_hash
+    {
+        let base_url = data.public_url.trim_end_matches('/');
+        if !base_url.is_empty() {
+            let image_url = format!("{}/status-image/{}.png?t={}", base_url, hash, now());
+            tracing::info!("Generated status image URL: {}", image_url);
+            embed = embed.image(image_url);
         }
     }
 
@@ -150,19 +676,368 @@ pub async fn status(
     } else if server.players.is_empty() {
         embed.description("No players online")
     } else {
-        let current_time = now();
         let player_list: String = server
             .players
             .iter()
             .map(|p| {
                 let time_online = current_time - p.joined_at;
                 let formatted_time = format_time_online(time_online);
-                format!("{} ({})", p.player_name, formatted_time)
+                format!(
+                    "{} ({})",
+                    escape_markdown(p.player_name.as_str()),
+                    formatted_time
+                )
             })
             .collect::<Vec<_>>()
             .join(" | ");
         embed.description(format!("**{}** | {}", status_text, player_list))
     };
+    // Servers awaiting their first sync since restart already say so above
+    let embed = match last_sync_at {
+        Some(at)
+            if is_synced && is_sync_stale(last_sync_at, current_time, data.stale_sync_secs) =>
+        {
+            embed.footer(CreateEmbedFooter::new(format!(
+                "⚠️ Last synced {} ago, the player list may be out of date",
+                format_time_online(current_time - at)
+            )))
+        }
+        _ => embed,
+    };
     ctx.send(CreateReply::default().embed(embed)).await?;
     Ok(())
 }
+
+/// Most players listed by /recent (keeps the embed under Discord's size limit)
+const RECENT_MAX_PLAYERS: usize = 50;
+
+/// Show players who recently left a linked Minecraft server
+#[command(slash_command, prefix_command)]
+pub async fn recent(
+    ctx: Context<'_>,
+    #[description = "Minecraft Server Name"]
+    #[autocomplete = "autocomplete_server_name"]
+    name: String,
+    #[description = "How far back to look, in minutes (default 30)"]
+    #[min = 1]
+    #[max = 10080]
+    minutes: Option<u32>,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let minutes = minutes.unwrap_or(30);
+    let Some(api_key_hash) = data.db.get_api_key_hash_by_name(guild_id, &name).await? else {
+        return server_not_found(ctx, &name).await;
+    };
+
+    let current_time = now();
+    let players = data
+        .db
+        .get_recently_left(api_key_hash, current_time - i64::from(minutes) * 60)
+        .await?;
+
+    let embed = CreateEmbed::default()
+        .title(format!("Recently Left {}", escape_markdown(&name)))
+        .color(embed_color(ctx, guild_id).await)
+        .footer(CreateEmbedFooter::new(format!("Last {} minutes", minutes)));
+    let embed = if players.is_empty() {
+        embed.description("Nobody has left recently.")
+    } else {
+        let list: String = players
+            .iter()
+            .take(RECENT_MAX_PLAYERS)
+            .map(|(player, last_seen)| {
+                format!(
+                    "- {} ({} ago)",
+                    escape_markdown(player.as_str()),
+                    format_time_online(current_time - last_seen)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed.description(list)
+    };
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Show player statistics for a linked Minecraft server
+#[command(slash_command, prefix_command)]
+pub async fn stats(
+    ctx: Context<'_>,
+    #[description = "Minecraft Server Name"]
+    #[autocomplete = "autocomplete_server_name"]
+    name: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let Some(api_key_hash) = data.db.get_api_key_hash_by_name(guild_id, &name).await? else {
+        return server_not_found(ctx, &name).await;
+    };
+
+    let online = data.db.count_online_players(&api_key_hash).await?;
+    let unique = data.db.count_unique_players(&api_key_hash).await?;
+    let avg_session = match data.db.get_avg_session_secs(&api_key_hash).await? {
+        Some(secs) => format_time_online(secs.round() as i64),
+        None => "n/a".to_string(),
+    };
+    let peak = match data.db.get_peak_players(&api_key_hash).await? {
+        Some((players, at)) => format!("{} (<t:{}:R>)", players, at),
+        None => "n/a".to_string(),
+    };
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title(format!("{} Stats", escape_markdown(&name)))
+                .field("Online Now", online.to_string(), true)
+                .field("Unique Players", unique.to_string(), true)
+                .field("Peak Players", peak, true)
+                .field("Average Session", avg_session, true)
+                .color(embed_color(ctx, guild_id).await),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Unlink every Minecraft server from this Discord server (asks for confirmation)
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn unlinkall(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let color = embed_color(ctx, guild_id).await;
+    let linked = data.db.get_servers_by_guild(guild_id).await?.len();
+    if linked == 0 {
+        ctx.send(
+            CreateReply::default()
+                .content("No Minecraft servers are linked to this Discord server.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let confirm_id = format!("{}-unlinkall-confirm", ctx.id());
+    let cancel_id = format!("{}-unlinkall-cancel", ctx.id());
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .embed(
+                    CreateEmbed::default()
+                        .title("Unlink All Servers?")
+                        .description(format!(
+                            "This will unlink all {} Minecraft servers from this Discord server. Their API keys stop working and each one has to be connected again.",
+                            linked
+                        ))
+                        .color(0xED4245),
+                )
+                .components(vec![CreateActionRow::Buttons(vec![
+                    CreateButton::new(confirm_id.clone())
+                        .style(ButtonStyle::Danger)
+                        .label("Unlink all"),
+                    CreateButton::new(cancel_id.clone())
+                        .style(ButtonStyle::Secondary)
+                        .label("Cancel"),
+                ])])
+                .ephemeral(true),
+        )
+        .await?;
+
+    let (ids, author_id) = ([confirm_id.clone(), cancel_id], ctx.author().id);
+    let press = ComponentInteractionCollector::new(ctx)
+        .author_id(author_id)
+        .timeout(UNLINK_ALL_CONFIRM_TIMEOUT)
+        .filter(move |press| ids.contains(&press.data.custom_id))
+        .await;
+
+    let outcome = match press {
+        Some(ref press) if press.data.custom_id == confirm_id => {
+            let removed = data.db.delete_servers_by_guild(guild_id).await?;
+            audit(ctx, guild_id, "unlinkall", &format!("{} servers", removed)).await;
+            CreateEmbed::default()
+                .title("Servers Unlinked")
+                .description(format!("Unlinked {} Minecraft servers.", removed))
+                .color(color)
+        }
+        Some(_) => CreateEmbed::default()
+            .title("Cancelled")
+            .description("No servers were unlinked.")
+            .color(color),
+        None => CreateEmbed::default()
+            .title("Timed Out")
+            .description("No servers were unlinked.")
+            .color(color),
+    };
+
+    match press {
+        Some(press) => {
+            press
+                .create_response(
+                    ctx,
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .embed(outcome)
+                            .components(vec![]),
+                    ),
+                )
+                .await?
+        }
+        None => {
+            reply
+                .edit(
+                    ctx,
+                    CreateReply::default().embed(outcome).components(vec![]),
+                )
+                .await?
+        }
+    }
+    Ok(())
+}
+
+/// Set the color used for Oxeye embeds in this Discord server
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn setcolor(
+    ctx: Context<'_>,
+    #[description = "Hex color, e.g. #5865F2"] color: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let color = match validation::parse_hex_color(&color) {
+        Ok(color) => color,
+        Err(e) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(e.to_string())
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    data.db.set_embed_color(guild_id, color).await?;
+    audit(ctx, guild_id, "setcolor", &format!("#{:06X}", color)).await;
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::default()
+                    .title("Embed Color Updated")
+                    .description(format!("Oxeye embeds here now use `#{:06X}`.", color))
+                    .color(color),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Send player joins and leaves to a webhook URL (omit the URL to stop)
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn setwebhook(
+    ctx: Context<'_>,
+    #[description = "http(s) URL to POST join/leave events to (omit to remove)"] url: Option<
+        String,
+    >,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let url = url.map(|u| u.trim().to_string());
+    if let Some(Err(e)) = url
+        .as_deref()
+        .map(|url| validation::validate_webhook_url(url, data.webhook_allow_private))
+    {
+        ctx.send(
+            CreateReply::default()
+                .content(e.to_string())
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+    let (title, description) = match &url {
+        Some(_) => (
+            "Webhook Set",
+            "Player joins and leaves on this server's linked Minecraft servers will be POSTed to the webhook.",
+        ),
+        None => (
+            "Webhook Removed",
+            "Join and leave events are no longer sent anywhere.",
+        ),
+    };
+    // The URL itself stays out of the log, since it often embeds a secret
+    let target = if url.is_some() { "set" } else { "removed" };
+    data.db.set_guild_webhook(guild_id, url).await?;
+    audit(ctx, guild_id, "setwebhook", target).await;
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::default()
+                    .title(title)
+                    .description(description)
+                    .color(embed_color(ctx, guild_id).await),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Most entries shown by /auditlog
+const AUDIT_LOG_LIMIT: u32 = 20;
+
+/// Show recent admin actions taken with Oxeye commands in this Discord server
+#[command(slash_command, prefix_command, required_permissions = "ADMINISTRATOR")]
+pub async fn auditlog(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?
+        .get();
+    let current_time = now();
+    let entries = data.db.get_audit_log(guild_id, AUDIT_LOG_LIMIT).await?;
+
+    let embed = CreateEmbed::default()
+        .title("Audit Log")
+        .color(embed_color(ctx, guild_id).await);
+    let embed = if entries.is_empty() {
+        embed.description("No admin actions have been recorded yet.")
+    } else {
+        let list: String = entries
+            .iter()
+            .map(|entry| {
+                let target = if entry.target.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", escape_markdown(&entry.target))
+                };
+                format!(
+                    "- <@{}> `{}`{} ({} ago)",
+                    entry.user_id,
+                    entry.action,
+                    target,
+                    format_time_online(current_time - entry.created_at)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed.description(list)
+    };
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
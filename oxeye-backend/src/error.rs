@@ -1,9 +1,12 @@
 use axum::{
     Json,
-    http::StatusCode,
+    extract::rejection::JsonRejection,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
+use axum_macros::FromRequest;
 use serde::Serialize;
+use std::time::Duration;
 
 /// API error response structure
 #[derive(Debug, Serialize)]
@@ -21,6 +24,7 @@ impl ErrorResponse {
         }
     }
 
+    #[allow(dead_code)]
     pub fn with_details(error: impl Into<String>, details: impl Into<String>) -> Self {
         Self {
             error: error.into(),
@@ -34,8 +38,22 @@ impl ErrorResponse {
 pub enum AppError {
     DatabaseError(oxeye_db::DbError),
     ValidationError(String),
+    Unauthorized(String),
+    /// A request body axum couldn't read or parse (keeps axum's status code)
+    BodyRejected(JsonRejection),
+    /// Too many failed connection attempts; retry after the given time
+    TooManyFailures(Duration),
 }
 
+/// JSON body extractor whose rejections are returned as `AppError`s.
+///
+/// Missing fields and fields that don't fit their type (e.g. a player name
+/// longer than a `PlayerName` holds) become 400 validation errors naming the
+/// field, instead of axum's plain-text 422.
+#[derive(FromRequest)]
+#[from_request(via(Json), rejection(AppError))]
+pub(crate) struct AppJson<T>(pub T);
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         match self {
@@ -57,13 +75,22 @@ impl IntoResponse for AppError {
                         StatusCode::CONFLICT,
                         "A server with this name already exists",
                     ),
+                    oxeye_db::DbError::ServerLimitReached => (
+                        StatusCode::FORBIDDEN,
+                        "This guild has reached its server limit",
+                    ),
                     oxeye_db::DbError::InvalidApiKey => {
                         (StatusCode::UNAUTHORIZED, "Invalid or expired API key")
                     }
+                    oxeye_db::DbError::InvalidGuildToken => {
+                        (StatusCode::UNAUTHORIZED, "Invalid guild token")
+                    }
                     oxeye_db::DbError::ServerNotFound => {
                         (StatusCode::NOT_FOUND, "Server not found")
                     }
-                    oxeye_db::DbError::Sqlite(_) | oxeye_db::DbError::Connection(_) => {
+                    oxeye_db::DbError::Sqlite(_)
+                    | oxeye_db::DbError::Connection(_)
+                    | oxeye_db::DbError::Io(_) => {
                         // Don't expose internal database errors
                         tracing::error!("Internal database error: {:?}", db_err);
                         (
@@ -81,8 +108,61 @@ impl IntoResponse for AppError {
                 let error_response = ErrorResponse::new(msg);
                 (StatusCode::BAD_REQUEST, Json(error_response)).into_response()
             }
+            AppError::BodyRejected(rejection) => {
+                tracing::warn!(rejection = %rejection.body_text(), "Request body rejected");
+                let error_response = ErrorResponse::new(rejection.body_text());
+                (rejection.status(), Json(error_response)).into_response()
+            }
+            AppError::Unauthorized(msg) => {
+                tracing::warn!(auth_error = %msg, "Unauthorized request");
+                let error_response = ErrorResponse::new(msg);
+                (StatusCode::UNAUTHORIZED, Json(error_response)).into_response()
+            }
+            AppError::TooManyFailures(retry_after) => {
+                // Round up so clients never retry a moment too early
+                let secs = retry_after.as_millis().div_ceil(1000);
+                tracing::warn!(retry_after_secs = secs, "Too many failed attempts");
+                let error_response = ErrorResponse::new(format!(
+                    "Too many failed connection attempts, try again in {}s",
+                    secs
+                ));
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, secs.to_string())],
+                    Json(error_response),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+/// Give the plain-text 413 and 408 responses produced by tower layers (the
+/// body size limit and the request timeout) the same `{ "error": ... }` JSON
+/// body as every other error. Responses that are already JSON pass through.
+pub(crate) async fn json_layer_errors(response: Response) -> Response {
+    let message = match response.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => "Request body is too large",
+        StatusCode::REQUEST_TIMEOUT => "Request timed out",
+        _ => return response,
+    };
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if is_json {
+        return response;
+    }
+
+    let (parts, _) = response.into_parts();
+    let mut json = (parts.status, Json(ErrorResponse::new(message))).into_response();
+    // Keep headers added by other layers (e.g. the boot ID)
+    for (name, value) in &parts.headers {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            json.headers_mut().append(name, value.clone());
         }
     }
+    json
 }
 
 impl From<oxeye_db::DbError> for AppError {
@@ -96,3 +176,18 @@ impl From<crate::validation::ValidationError> for AppError {
         AppError::ValidationError(err.to_string())
     }
 }
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonDataError(err) => {
+                // The underlying serde error names the field, e.g. "missing field `player`"
+                let detail = std::error::Error::source(&err)
+                    .map(|source| source.to_string())
+                    .unwrap_or_else(|| err.body_text());
+                AppError::ValidationError(format!("invalid request body: {}", detail))
+            }
+            other => AppError::BodyRejected(other),
+        }
+    }
+}
@@ -1,20 +1,104 @@
 use rand::distr::{Alphanumeric, SampleString};
 use rand::rng;
+use rand::seq::IndexedRandom;
 use sha2::{Digest, Sha256};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn generate_code() -> String {
-    format!("oxeye-{}", Alphanumeric.sample_string(&mut rng(), 6))
+/// Characters used in connection codes. Excludes look-alikes (0/O, 1/l/I)
+/// since players type codes into Minecraft by hand.
+const CODE_CHARSET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789";
+
+/// Generate a connection code of the form `oxeye-` plus `length` characters.
+pub fn generate_code(length: usize) -> String {
+    let mut rng = rng();
+    let suffix: String = (0..length)
+        .map(|_| *CODE_CHARSET.choose(&mut rng).unwrap() as char)
+        .collect();
+    format!("oxeye-{}", suffix)
 }
 
+/// Prefix of every server API key.
+const API_KEY_PREFIX: &str = "oxeye-sk-";
+
+/// Random characters after the prefix in a server API key.
+const API_KEY_RANDOM_LEN: usize = 32;
+
 pub fn generate_api_key() -> String {
-    format!("oxeye-sk-{}", Alphanumeric.sample_string(&mut rng(), 32))
+    format!(
+        "{}{}",
+        API_KEY_PREFIX,
+        Alphanumeric.sample_string(&mut rng(), API_KEY_RANDOM_LEN)
+    )
+}
+
+/// Prefix of every guild token.
+const GUILD_TOKEN_PREFIX: &str = "oxeye-gt-";
+
+/// Random characters after the prefix in a guild token.
+const GUILD_TOKEN_RANDOM_LEN: usize = 32;
+
+/// Whether a bearer token has the shape of a server API key (prefix and length).
+pub fn is_well_formed_api_key(token: &str) -> bool {
+    has_token_shape(token, API_KEY_PREFIX, API_KEY_RANDOM_LEN)
+}
+
+/// Generate a guild-scoped token for read-only dashboard access.
+/// Stored hashed with `hash_api_key`, like server API keys.
+pub fn generate_guild_token() -> String {
+    format!(
+        "{}{}",
+        GUILD_TOKEN_PREFIX,
+        Alphanumeric.sample_string(&mut rng(), GUILD_TOKEN_RANDOM_LEN)
+    )
+}
+
+/// Whether a bearer token has the shape of a guild token (prefix and length).
+pub fn is_well_formed_guild_token(token: &str) -> bool {
+    has_token_shape(token, GUILD_TOKEN_PREFIX, GUILD_TOKEN_RANDOM_LEN)
+}
+
+/// Whether `token` is `prefix` followed by `random_len` more bytes.
+///
+/// Every prefix byte is compared even after a mismatch, so the time taken
+/// doesn't reveal how much of the prefix was right.
+fn has_token_shape(token: &str, prefix: &str, random_len: usize) -> bool {
+    let bytes = token.as_bytes();
+    let mut diff = u8::from(bytes.len() != prefix.len() + random_len);
+    for (i, expected) in prefix.bytes().enumerate() {
+        diff |= bytes.get(i).copied().unwrap_or(0) ^ expected;
+    }
+    diff == 0
 }
 
 pub fn hash_api_key(key: &str) -> String {
     format!("{:x}", Sha256::digest(key.as_bytes()))
 }
 
+/// Short prefix of a key's hash, safe to write to logs for correlating requests.
+pub fn api_key_log_prefix(key: &str) -> String {
+    let mut hash = hash_api_key(key);
+    hash.truncate(8);
+    hash
+}
+
+/// Escape Discord markdown and defuse mentions so user-provided text (server
+/// and player names) renders literally when interpolated into embeds.
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '#' | '[' | ']' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            // A zero-width space after '@' stops @everyone/@here and user mentions
+            '@' => escaped.push_str("@\u{200B}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 pub fn now() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -46,9 +130,132 @@ pub fn format_time_online(duration_secs: i64) -> String {
     }
 }
 
+/// Whether a server's last full sync is older than `stale_after_secs`.
+/// Servers that never synced aren't flagged, and a threshold of 0 disables the check.
+pub fn is_sync_stale(last_sync_at: Option<i64>, now: i64, stale_after_secs: i64) -> bool {
+    stale_after_secs > 0 && last_sync_at.is_some_and(|at| now - at > stale_after_secs)
+}
+
+/// Whether an Accept header asks for a PNG rather than JSON. Media ranges are
+/// weighed by their q-values; ties and unrecognized types go to JSON.
+pub fn prefers_png(accept: &str) -> bool {
+    let (mut png, mut json) = (0.0f32, 0.0f32);
+    for range in accept.split(',') {
+        let mut params = range.split(';');
+        let media_type = params.next().unwrap_or_default().trim();
+        let q = params
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if media_type.eq_ignore_ascii_case("image/png")
+            || media_type.eq_ignore_ascii_case("image/*")
+        {
+            png = png.max(q);
+        } else if media_type.eq_ignore_ascii_case("application/json")
+            || media_type.eq_ignore_ascii_case("application/*")
+        {
+            json = json.max(q);
+        } else if media_type == "*/*" {
+            png = png.max(q);
+            json = json.max(q);
+        }
+    }
+    png > json
+}
+
 /// Generate a unique boot ID for this backend instance.
 /// Used by Minecraft mods to detect backend restarts and trigger resyncs.
 pub fn generate_boot_id() -> String {
     // Generate a 12-character alphanumeric ID (similar to short UUID)
     Alphanumeric.sample_string(&mut rng(), 12)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{MIN_CODE_LENGTH, validate_code};
+
+    #[test]
+    fn test_generate_code_length_and_charset() {
+        for length in [6, 12] {
+            let code = generate_code(length);
+            assert_eq!(code.len(), "oxeye-".len() + length);
+            assert!(validate_code(&code, MIN_CODE_LENGTH).is_ok());
+            assert!(!code["oxeye-".len()..].contains(['0', 'O', '1', 'l', 'I']));
+        }
+    }
+
+    #[test]
+    fn test_is_well_formed_api_key() {
+        assert!(is_well_formed_api_key(&generate_api_key()));
+        for token in [
+            "",
+            "oxeye-sk-",
+            "oxeye-sk-tooshort",
+            "oxeye-gt-abcdefghijklmnopqrstuvwxyz012345",
+            "OXEYE-SK-abcdefghijklmnopqrstuvwxyz012345",
+            "oxeye-sk-abcdefghijklmnopqrstuvwxyz0123456",
+        ] {
+            assert!(!is_well_formed_api_key(token), "{:?}", token);
+        }
+    }
+
+    #[test]
+    fn test_is_well_formed_guild_token() {
+        assert!(is_well_formed_guild_token(&generate_guild_token()));
+        for token in [
+            "",
+            "oxeye-gt-tooshort",
+            "oxeye-sk-abcdefghijklmnopqrstuvwxyz012345",
+            "oxeye-gt-abcdefghijklmnopqrstuvwxyz0123456",
+        ] {
+            assert!(!is_well_formed_guild_token(token), "{:?}", token);
+        }
+        assert!(!is_well_formed_guild_token(&generate_api_key()));
+    }
+
+    #[test]
+    fn test_api_key_log_prefix() {
+        let key = "oxeye-sk-secret";
+        let prefix = api_key_log_prefix(key);
+        assert_eq!(prefix.len(), 8);
+        assert!(hash_api_key(key).starts_with(&prefix));
+        assert!(!prefix.contains("secret"));
+    }
+
+    #[test]
+    fn test_escape_markdown_mentions() {
+        assert_eq!(escape_markdown("@everyone"), "@\u{200B}everyone");
+        assert_eq!(escape_markdown("**@here**"), "\\*\\*@\u{200B}here\\*\\*");
+    }
+
+    #[test]
+    fn test_escape_markdown_backticks() {
+        assert_eq!(escape_markdown("`code`"), "\\`code\\`");
+        assert_eq!(escape_markdown("Cool_Guy_"), "Cool\\_Guy\\_");
+    }
+
+    #[test]
+    fn test_escape_markdown_plain_text_unchanged() {
+        assert_eq!(escape_markdown("Survival SMP 2"), "Survival SMP 2");
+    }
+
+    #[test]
+    fn test_is_sync_stale_boundary() {
+        assert!(!is_sync_stale(Some(1_000), 1_300, 300));
+        assert!(is_sync_stale(Some(1_000), 1_301, 300));
+        assert!(!is_sync_stale(None, 1_301, 300));
+        assert!(!is_sync_stale(Some(1_000), 1_301, 0));
+    }
+
+    #[test]
+    fn test_prefers_png() {
+        assert!(prefers_png("image/png"));
+        assert!(prefers_png("image/png, application/json;q=0.5"));
+        assert!(prefers_png("image/*, */*;q=0.8"));
+        assert!(!prefers_png("application/json"));
+        assert!(!prefers_png("*/*"));
+        assert!(!prefers_png("text/html"));
+        assert!(!prefers_png("image/png;q=0.5, application/json"));
+    }
+}
@@ -0,0 +1,113 @@
+//! Replay protection for retried /join and /leave requests.
+//!
+//! Clients may send an `Idempotency-Key` header; the status of the first
+//! successful request with that key is remembered for a few minutes and
+//! returned for repeats without applying them again. Keys are scoped to the
+//! server's API key hash so different servers can't collide.
+//!
+//! A key is reserved before the request is applied, so a retry that arrives
+//! while the first attempt is still running waits for it and then replays its
+//! status. If the first attempt fails, the next one with the key applies instead.
+
+use axum::http::{HeaderMap, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OwnedMutexGuard;
+
+/// Request header carrying the client-chosen key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// How long a key is remembered.
+const KEY_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Longest key accepted; longer keys are ignored rather than stored.
+const MAX_KEY_LENGTH: usize = 128;
+
+/// The status a key produced, once a request with it has succeeded.
+type Slot = Arc<tokio::sync::Mutex<Option<StatusCode>>>;
+
+/// Recently seen idempotency keys and the status they produced.
+#[derive(Debug)]
+pub struct IdempotencyCache {
+    inner: Mutex<Entries>,
+}
+
+#[derive(Debug)]
+struct Entries {
+    slots: HashMap<(String, String), (Instant, Slot)>,
+    last_prune: Instant,
+}
+
+impl Default for IdempotencyCache {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(Entries {
+                slots: HashMap::new(),
+                last_prune: Instant::now(),
+            }),
+        }
+    }
+}
+
+/// Outcome of reserving an idempotency key.
+#[derive(Debug)]
+pub enum Reservation {
+    /// No earlier request with this key succeeded; apply this one and
+    /// [`Claim::complete`] it (dropping the claim lets the next retry apply).
+    New(Claim),
+    /// An earlier request with this key succeeded with this status.
+    Replay(StatusCode),
+}
+
+/// Exclusive hold on an idempotency key while its request is applied.
+#[derive(Debug)]
+pub struct Claim(OwnedMutexGuard<Option<StatusCode>>);
+
+impl Claim {
+    /// Remember the status the request produced for later repeats.
+    pub fn complete(mut self, status: StatusCode) {
+        *self.0 = Some(status);
+    }
+}
+
+impl IdempotencyCache {
+    /// Extract the idempotency key for a server's request, if one was sent.
+    pub fn key(headers: &HeaderMap, api_key_hash: &str) -> Option<(String, String)> {
+        let key = headers.get(IDEMPOTENCY_KEY_HEADER)?.to_str().ok()?.trim();
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+            return None;
+        }
+        Some((api_key_hash.to_string(), key.to_string()))
+    }
+
+    /// Reserve a key before applying its request. Waits while another request
+    /// with the same key is being applied.
+    pub async fn reserve(&self, key: (String, String)) -> Reservation {
+        let slot = {
+            let mut entries = self.inner.lock().unwrap();
+            // Drop expired keys now and then rather than on every request
+            if entries.last_prune.elapsed() >= KEY_TTL {
+                entries
+                    .slots
+                    .retain(|_, (seen_at, _)| seen_at.elapsed() < KEY_TTL);
+                entries.last_prune = Instant::now();
+            }
+            let (seen_at, slot) = entries
+                .slots
+                .entry(key)
+                .or_insert_with(|| (Instant::now(), Slot::default()));
+            if seen_at.elapsed() >= KEY_TTL {
+                *seen_at = Instant::now();
+                *slot = Slot::default();
+            }
+            slot.clone()
+        };
+
+        let guard = slot.lock_owned().await;
+        match *guard {
+            Some(status) => Reservation::Replay(status),
+            None => Reservation::New(Claim(guard)),
+        }
+    }
+}
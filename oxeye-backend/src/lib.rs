@@ -1,38 +1,90 @@
+pub mod code_throttle;
 pub mod config;
+pub mod connect_guard;
 mod error;
 pub mod helpers;
+pub mod idempotency;
+pub mod live;
+pub mod logging;
+pub mod metrics;
+pub mod mojang;
 pub mod render;
 mod routes;
-mod validation;
+pub mod tasks;
+pub mod validation;
+pub mod webhooks;
 
 use axum::{
     Router,
-    http::{HeaderName, HeaderValue, StatusCode},
-    routing::{get, post},
-};
-#[cfg(debug_assertions)]
-use axum::{
     body::Body,
     extract::Request,
-    middleware::{self, Next},
-    response::Response,
+    http::{HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware,
+    routing::{get, post},
 };
 #[cfg(debug_assertions)]
+use axum::{middleware::Next, response::Response};
+use config::Config;
+use connect_guard::ConnectGuard;
+use headers::{Authorization, HeaderMapExt, authorization::Bearer};
+#[cfg(debug_assertions)]
 use http_body_util::BodyExt;
+use idempotency::IdempotencyCache;
+use live::LiveEvents;
+use metrics::Metrics;
+use mojang::SkinFetcher;
+use oxeye_db::Store;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::Instant;
 use tower_governor::{
     GovernorLayer, governor::GovernorConfigBuilder, key_extractor::SmartIpKeyExtractor,
 };
+use tower_http::LatencyUnit;
+use tower_http::cors::CorsLayer;
 use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::timeout::TimeoutLayer;
-use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
-use tracing::Level;
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tracing::{Level, Span};
+use webhooks::Webhooks;
 
 pub struct AppState {
-    pub db: oxeye_db::Database,
+    pub db: Arc<dyn Store>,
     pub boot_id: String,
+    /// When this process started serving (for GET /version uptime)
+    pub started_at: Instant,
+    pub metrics: Metrics,
+    /// Maximum players accepted in one /sync or /events list
+    pub max_players: usize,
+    /// Layout of status images
+    pub status_image: render::CompositeConfig,
+    /// Which player names /join, /leave, /sync and /events accept
+    pub name_policy: validation::NamePolicy,
+    /// Default for whether /sync may clear all players with an empty list
+    pub sync_allow_empty: bool,
+    /// Whether /sync collapses names that differ only in case to the first spelling
+    pub sync_fold_case: bool,
+    /// Player updates streamed to /guilds/{guild_id}/events subscribers
+    pub live: LiveEvents,
+    /// Results of recent /join and /leave requests by Idempotency-Key
+    pub idempotency: IdempotencyCache,
+    /// Hash of the admin token for cross-guild endpoints (None disables them)
+    pub admin_token_hash: Option<String>,
+    /// Queue of join/leave webhooks for guilds that set a URL
+    pub webhooks: Webhooks,
+    /// Shortest connection code suffix POST /connect accepts
+    pub min_code_length: usize,
+    /// Failed POST /connect attempts per client IP
+    pub connect_guard: ConnectGuard,
+    /// Looks up skins on Mojang for players who join without one
+    pub skins: SkinFetcher,
+}
+
+impl AppState {
+    /// Layout for status composite images.
+    pub fn status_config(&self) -> render::CompositeConfig {
+        self.status_image.clone()
+    }
 }
 
 /// Rate limiting configuration
@@ -42,7 +94,7 @@ pub struct RateLimitConfig {
     pub connect_per_min: u64,
     /// Burst size for /connect endpoint
     pub connect_burst: u32,
-    /// Requests per second for player endpoints (/join, /leave, /sync)
+    /// Requests per second for player endpoints (/join, /leave, /sync, /events)
     pub player_per_sec: u64,
     /// Burst size for player endpoints
     pub player_burst: u32,
@@ -50,6 +102,25 @@ pub struct RateLimitConfig {
     pub general_per_sec: u64,
     /// Burst size for general endpoints
     pub general_burst: u32,
+    /// Requests per second for image endpoints
+    pub image_per_sec: u64,
+    /// Burst size for image endpoints
+    pub image_burst: u32,
+}
+
+impl From<&Config> for RateLimitConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            connect_per_min: config.rate_limit_connect_per_min,
+            connect_burst: config.rate_limit_connect_burst,
+            player_per_sec: config.rate_limit_player_per_sec,
+            player_burst: config.rate_limit_player_burst,
+            general_per_sec: config.rate_limit_general_per_sec,
+            general_burst: config.rate_limit_general_burst,
+            image_per_sec: config.rate_limit_image_per_sec,
+            image_burst: config.rate_limit_image_burst,
+        }
+    }
 }
 
 impl Default for RateLimitConfig {
@@ -61,6 +132,8 @@ impl Default for RateLimitConfig {
             player_burst: 100,
             general_per_sec: 10,
             general_burst: 20,
+            image_per_sec: 50,
+            image_burst: 100,
         }
     }
 }
@@ -94,18 +167,56 @@ async fn log_request_body(request: Request, next: Next) -> Response {
     next.run(request).await
 }
 
-/// Create the application router with the given database and configuration
-pub fn create_app(
-    db: oxeye_db::Database,
-    request_body_limit: usize,
-    request_timeout: Duration,
-    rate_limit: RateLimitConfig,
-) -> Router {
+/// Build the tracing span for an HTTP request.
+///
+/// Records method and path (no query string or body). When a bearer token is
+/// present only a short prefix of its hash is recorded, never the token itself.
+fn make_request_span(request: &Request<Body>) -> Span {
+    let api_key = request
+        .headers()
+        .typed_get::<Authorization<Bearer>>()
+        .map(|auth| helpers::api_key_log_prefix(auth.token()));
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        api_key = api_key.as_deref().unwrap_or("-"),
+    )
+}
+
+/// Create the application router with the given store and configuration
+pub fn create_app(db: impl Store + 'static, config: &Config) -> Router {
+    let db: Arc<dyn Store> = Arc::new(db);
+    let rate_limit = RateLimitConfig::from(config);
     let boot_id = helpers::generate_boot_id();
     tracing::info!(boot_id = %boot_id, "generated boot ID");
+    let (webhooks, webhook_queue) = Webhooks::new();
+    tokio::spawn(webhooks::run(
+        db.clone(),
+        webhook_queue,
+        config.webhook_allow_private,
+    ));
     let state = Arc::new(AppState {
         db,
         boot_id: boot_id.clone(),
+        started_at: Instant::now(),
+        metrics: Metrics::default(),
+        max_players: config.max_players,
+        status_image: config.status_image.clone(),
+        name_policy: config.name_policy,
+        sync_allow_empty: config.sync_allow_empty,
+        sync_fold_case: config.sync_fold_case,
+        live: LiveEvents::default(),
+        idempotency: IdempotencyCache::default(),
+        admin_token_hash: config.admin_token.as_deref().map(helpers::hash_api_key),
+        webhooks,
+        min_code_length: config.min_code_length,
+        connect_guard: ConnectGuard::new(
+            config.connect_max_failures,
+            config.connect_failure_window,
+        )
+        .with_trusted_proxies(config.trusted_proxies.clone()),
+        skins: SkinFetcher::new(config).expect("failed to build Mojang HTTP client"),
     });
 
     // Create X-Boot-ID header layer
@@ -138,32 +249,93 @@ pub fn create_app(
         .finish()
         .unwrap();
 
+    // Image rate limit - renders on a cache miss cost CPU, but embeds fetch many heads
+    let image_governor = GovernorConfigBuilder::default()
+        .per_second(rate_limit.image_per_sec)
+        .burst_size(rate_limit.image_burst)
+        .key_extractor(SmartIpKeyExtractor)
+        .finish()
+        .unwrap();
+
+    // Body limits are applied per route group: join/leave only ever carry one
+    // player, so they get a much tighter cap than list and skin uploads
+    let body_limit = RequestBodyLimitLayer::new(config.request_body_limit);
+    let join_leave_body_limit = RequestBodyLimitLayer::new(config.join_leave_body_limit);
+
     // Routes with strict rate limiting (connect is sensitive)
     let connect_routes = Router::new()
         .route("/connect", post(routes::connect))
+        .route("/connect/{code}", get(routes::connect_status))
+        .layer(body_limit)
         .layer(GovernorLayer::new(connect_governor));
 
-    // Routes with lenient rate limiting (high traffic from players)
-    let player_routes = Router::new()
+    // Routes with lenient rate limiting (high traffic from players).
+    // The limits must not nest, or axum can't tell an oversized body (413)
+    // from a malformed one, so join/leave sit in their own router.
+    let join_leave_routes = Router::new()
         .route("/join", post(routes::join))
         .route("/leave", post(routes::leave))
+        .layer(join_leave_body_limit);
+    let player_routes = Router::new()
         .route("/sync", post(routes::sync))
+        .route("/events", post(routes::events))
         .route("/skin", post(routes::upload_skin))
+        .layer(body_limit)
+        .merge(join_leave_routes)
         .layer(GovernorLayer::new(player_governor));
 
     // Routes with general rate limiting
-    let general_routes = Router::new()
+    let mut general_routes = Router::new()
         .route("/status", get(routes::status))
+        .route("/status/detailed", get(routes::status_detailed))
+        .route("/whoami", get(routes::whoami))
         .route("/disconnect", post(routes::disconnect))
+        .route("/reset", post(routes::reset))
+        .route("/rotate", post(routes::rotate))
+        .route("/refresh-skins", post(routes::refresh_skins))
+        .route("/validate", post(routes::validate))
+        .route(
+            "/guilds/{guild_id}/servers/{name}/players",
+            get(routes::server_players),
+        )
+        .route(
+            "/guilds/{guild_id}/servers",
+            get(routes::list_guild_servers),
+        )
+        .route("/guilds/{guild_id}/export", get(routes::export_guild))
+        .route("/guilds/{guild_id}/events", get(routes::guild_events))
+        .route("/guilds/{guild_id}/stats", get(routes::guild_stats));
+
+    // Cross-guild admin endpoints (only registered when an admin token is configured)
+    if config.admin_token.is_some() {
+        general_routes = general_routes
+            .route("/players/{name}/servers", get(routes::player_servers))
+            .route("/merge", post(routes::merge))
+            .route("/transfer", post(routes::transfer));
+    }
+
+    let general_routes = general_routes
+        .layer(body_limit)
         .layer(GovernorLayer::new(general_governor));
 
-    // Image routes (no rate limiting - cacheable and served from DB)
+    // Image routes (cacheable and usually served from DB)
     let image_routes = Router::new()
         .route("/heads/{hash}", get(routes::get_head))
-        .route("/status-image/{hash}", get(routes::get_status_image));
+        .route("/status-image/{hash}", get(routes::get_status_image))
+        .layer(GovernorLayer::new(image_governor));
 
-    let router = Router::new()
+    // Liveness (cheap) and readiness (checks the database) probes
+    let mut router = Router::new()
         .route("/health", get(|| async { StatusCode::OK }))
+        .route("/ready", get(routes::ready))
+        .route("/version", get(routes::version));
+
+    // Prometheus metrics (unauthenticated, can be disabled via config)
+    if config.enable_metrics {
+        router = router.route("/metrics", get(routes::metrics));
+    }
+
+    let router = router
         .merge(connect_routes)
         .merge(player_routes)
         .merge(general_routes)
@@ -171,17 +343,44 @@ pub fn create_app(
         .layer(boot_id_header)
         .layer(TimeoutLayer::with_status_code(
             StatusCode::REQUEST_TIMEOUT,
-            request_timeout,
+            config.request_timeout,
         ))
-        .layer(RequestBodyLimitLayer::new(request_body_limit))
+        .layer(middleware::map_response(error::json_layer_errors))
         .layer(
             TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
-                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+                .make_span_with(make_request_span)
+                .on_response(
+                    DefaultOnResponse::new()
+                        .level(Level::INFO)
+                        .latency_unit(LatencyUnit::Millis),
+                ),
         );
 
     #[cfg(debug_assertions)]
     let router = router.layer(middleware::from_fn(log_request_body));
 
+    // CORS for browser dashboards (read-only, disabled unless origins are configured)
+    let router = if config.allowed_origins.is_empty() {
+        router
+    } else {
+        let origins: Vec<HeaderValue> = config
+            .allowed_origins
+            .iter()
+            .filter_map(|origin| match HeaderValue::from_str(origin) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    tracing::warn!(origin = %origin, "ignoring invalid CORS origin");
+                    None
+                }
+            })
+            .collect();
+        router.layer(
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods([Method::GET])
+                .allow_headers([header::AUTHORIZATION]),
+        )
+    };
+
     router.with_state(state)
 }
@@ -0,0 +1,81 @@
+//! Live player updates for Server-Sent Events subscribers.
+//!
+//! Route handlers publish an event after each successful join, leave or sync;
+//! every `/guilds/{guild_id}/events` stream receives them through one shared
+//! broadcast channel and forwards only the events for its own guild.
+
+use oxeye_db::PlayerName;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before a slow stream starts missing some.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// A player change on one server, as sent to dashboards.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    Join {
+        server: String,
+        player: PlayerName,
+    },
+    Leave {
+        server: String,
+        player: PlayerName,
+    },
+    /// The server replaced its whole player list
+    Sync {
+        server: String,
+        players: Vec<PlayerName>,
+    },
+}
+
+impl LiveEvent {
+    /// SSE event name for this event.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LiveEvent::Join { .. } => "join",
+            LiveEvent::Leave { .. } => "leave",
+            LiveEvent::Sync { .. } => "sync",
+        }
+    }
+}
+
+/// A live event tagged with the guild it belongs to.
+#[derive(Debug, Clone)]
+pub struct GuildEvent {
+    pub guild_id: u64,
+    pub event: LiveEvent,
+}
+
+/// Broadcast channel shared by publishers (route handlers) and SSE streams.
+#[derive(Debug)]
+pub struct LiveEvents {
+    sender: broadcast::Sender<GuildEvent>,
+}
+
+impl Default for LiveEvents {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl LiveEvents {
+    /// Whether any SSE stream is currently listening.
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Publish events for a guild (dropped if nobody is listening).
+    pub fn publish(&self, guild_id: u64, events: impl IntoIterator<Item = LiveEvent>) {
+        for event in events {
+            let _ = self.sender.send(GuildEvent { guild_id, event });
+        }
+    }
+
+    /// Subscribe to events for all guilds.
+    pub fn subscribe(&self) -> broadcast::Receiver<GuildEvent> {
+        self.sender.subscribe()
+    }
+}
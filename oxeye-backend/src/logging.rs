@@ -0,0 +1,81 @@
+//! Tracing subscriber setup: level filter plus compact or JSON output.
+//!
+//! JSON mode uses tracing-subscriber's JSON formatter: one object per line with
+//! `timestamp`, `level`, the event's `fields` (including `message`), the
+//! current `span` and a `spans` list of every enclosing span with its fields.
+//! No ANSI colors.
+
+use crate::config::{Config, LogFormat};
+use tracing_subscriber::EnvFilter;
+
+/// Install the global tracing subscriber described by the config.
+///
+/// `RUST_LOG` directives take precedence over `Config::log_level` when set.
+pub fn init(config: &Config) {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.log_level));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false);
+
+    match config.log_format {
+        LogFormat::Compact => builder.compact().init(),
+        LogFormat::Json => builder.json().with_ansi(false).init(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    /// Shared in-memory sink for captured log output.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_format_emits_structured_fields() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_target(false)
+            .json()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span =
+                tracing::info_span!("request", method = "GET", status = tracing::field::Empty);
+            let _guard = span.enter();
+            span.record("status", 200u64);
+            tracing::info!(latency_ms = 12u64, ok = true, "finished processing request");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            !output.contains('\u{1b}'),
+            "unexpected ANSI codes: {}",
+            output
+        );
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["fields"]["message"], "finished processing request");
+        assert_eq!(line["fields"]["latency_ms"], 12);
+        assert_eq!(line["fields"]["ok"], true);
+        assert_eq!(line["spans"][0]["name"], "request");
+        assert_eq!(line["spans"][0]["method"], "GET");
+        assert_eq!(line["spans"][0]["status"], 200);
+    }
+}
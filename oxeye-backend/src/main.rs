@@ -1,79 +1,278 @@
 mod discord_commands;
-use oxeye_backend::{RateLimitConfig, create_app};
-use oxeye_db::Database;
+use axum_server::tls_rustls::RustlsConfig;
+use oxeye_backend::code_throttle::CodeThrottle;
+use oxeye_backend::create_app;
+use oxeye_db::{Database, Store};
 use poise::{Framework, FrameworkOptions, serenity_prelude as serenity};
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 
+/// How often the background task vacuums and analyzes the database.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
 type Context<'a> = poise::Context<'a, crate::Data, crate::discord_commands::Error>;
+type FrameworkError<'a> = poise::FrameworkError<'a, crate::Data, crate::discord_commands::Error>;
 
 pub(crate) struct Data {
-    pub(crate) db: Database,
+    pub(crate) db: Arc<dyn Store>,
     pub(crate) public_url: String,
+    pub(crate) max_servers_per_guild: u32,
+    pub(crate) code_length: usize,
+    pub(crate) code_throttle: CodeThrottle,
+    /// Seconds after the last sync before a server is marked stale (0 = never)
+    pub(crate) stale_sync_secs: i64,
+    /// Whether /setwebhook accepts localhost and private network URLs
+    pub(crate) webhook_allow_private: bool,
 }
 
 #[tokio::main]
 async fn main() {
+    // Load configuration from environment variables or use defaults
+    let mut config = oxeye_backend::config::Config::from_env();
+    let database_path = config
+        .sqlite_path()
+        .unwrap_or_else(|e| panic!("DATABASE_URL error: {}", e))
+        .to_string();
+    let tls_config = match config.tls_paths() {
+        Ok(Some((cert, key))) => {
+            // Use ring (already used by the Discord client) as the crypto provider
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            Some(
+                RustlsConfig::from_pem_file(cert, key)
+                    .await
+                    .unwrap_or_else(|e| panic!("TLS configuration error: {}", e)),
+            )
+        }
+        Ok(None) => None,
+        Err(e) => panic!("TLS configuration error: {}", e),
+    };
+    config.status_image.fallback_fonts = config
+        .status_fallback_fonts
+        .iter()
+        .map(|path| {
+            oxeye_backend::render::FontData::load(path)
+                .unwrap_or_else(|e| panic!("STATUS_FALLBACK_FONTS error: {}", e))
+        })
+        .collect();
+
     // Initialize tracing for structured logging
-    #[cfg(debug_assertions)]
-    let log_level = tracing::Level::DEBUG;
-    #[cfg(not(debug_assertions))]
-    let log_level = tracing::Level::INFO;
-
-    tracing_subscriber::fmt()
-        .with_max_level(log_level)
-        .with_target(false)
-        .compact()
-        .init();
+    oxeye_backend::logging::init(&config);
     tracing::info!("Starting Oxeye backend server...");
-    // Load configuration from environment variables or use defaults
-    let config = oxeye_backend::config::Config::from_env();
     tracing::info!(
-        "Configuration: port={}, db_path={}, body_limit={}KB, timeout={}s, backend_url={}",
+        "Configuration: bind={}, port={}, db_path={}, body_limit={}KB, timeout={}s, backend_url={}",
+        config.bind_address,
         config.port,
-        config.database_path,
+        database_path,
         config.request_body_limit / 1024,
         config.request_timeout.as_secs(),
         config.public_url
     );
     tracing::info!(
-        "Rate limits: connect={}/min (burst {}), player={}/sec (burst {}), general={}/sec (burst {})",
+        "Rate limits: connect={}/min (burst {}), player={}/sec (burst {}), general={}/sec (burst {}), image={}/sec (burst {}), guild codes={}/min",
         config.rate_limit_connect_per_min,
         config.rate_limit_connect_burst,
         config.rate_limit_player_per_sec,
         config.rate_limit_player_burst,
         config.rate_limit_general_per_sec,
-        config.rate_limit_general_burst
+        config.rate_limit_general_burst,
+        config.rate_limit_image_per_sec,
+        config.rate_limit_image_burst,
+        config.rate_limit_guild_codes_per_min
     );
-    let db = Database::open(&config.database_path).await.unwrap();
-    let rate_limit = RateLimitConfig {
-        connect_per_min: config.rate_limit_connect_per_min,
-        connect_burst: config.rate_limit_connect_burst,
-        player_per_sec: config.rate_limit_player_per_sec,
-        player_burst: config.rate_limit_player_burst,
-        general_per_sec: config.rate_limit_general_per_sec,
-        general_burst: config.rate_limit_general_burst,
-    };
-    let app = create_app(
+    let db = Database::open(&database_path)
+        .await
+        .unwrap()
+        .with_leave_grace(config.leave_grace);
+    if let Some(path) = &config.cache_snapshot_path {
+        db.load_snapshot(path).await;
+    }
+    let app = create_app(db.clone(), &config);
+    let bot_db: Arc<dyn Store> = Arc::new(db.clone());
+
+    // Daily database maintenance (prunes old sessions, reclaims free pages,
+    // refreshes statistics)
+    let maintenance_db = db.clone();
+    let session_retention =
+        Duration::from_secs(u64::from(config.session_retention_days) * 24 * 60 * 60);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+        interval.tick().await; // the first tick completes immediately
+        loop {
+            interval.tick().await;
+            if !session_retention.is_zero()
+                && let Err(e) = oxeye_backend::tasks::prune_sessions(
+                    &maintenance_db,
+                    session_retention,
+                    oxeye_backend::helpers::now(),
+                )
+                .await
+            {
+                tracing::error!("Session pruning failed: {}", e);
+            }
+            if let Err(e) = maintenance_db.maintenance().await {
+                tracing::error!("Database maintenance failed: {}", e);
+            }
+        }
+    });
+
+    // Expired connection codes are removed every minute or so until shutdown
+    let (cleanup_stop_tx, cleanup_stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let cleanup_task = tokio::spawn(oxeye_backend::tasks::run_link_cleanup(
         db.clone(),
-        config.request_body_limit,
-        config.request_timeout,
-        rate_limit,
-    );
-    let addr = format!("0.0.0.0:{}", config.port);
+        config.cleanup_interval,
+        async {
+            let _ = cleanup_stop_rx.await;
+        },
+    ));
+
+    let addr = SocketAddr::new(config.bind_address, config.port);
     let listener = TcpListener::bind(&addr).await.unwrap();
-    tracing::info!("Server listening on {}", addr);
+    tracing::info!(
+        "Server listening on {} ({})",
+        addr,
+        if tls_config.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    );
+
+    // Without a token only the HTTP API runs (e.g. a backend-only deployment)
+    let mut client = match config.discord_token.clone() {
+        Some(token) => Some(discord_client(token, bot_db, &config).await),
+        None => {
+            tracing::warn!(
+                "DISCORD_TOKEN is not set, running the HTTP API without the Discord bot"
+            );
+            None
+        }
+    };
+    let shard_manager = client.as_ref().map(|client| client.shard_manager.clone());
+
+    // Axum runs on its own task so in-flight requests can drain after a signal
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let mut server = tokio::spawn(async move {
+        let stop = async move {
+            let _ = shutdown_rx.await;
+        };
+        let app = app.into_make_service_with_connect_info::<SocketAddr>();
+        match tls_config {
+            Some(tls) => {
+                let handle = axum_server::Handle::new();
+                tokio::spawn({
+                    let handle = handle.clone();
+                    async move {
+                        stop.await;
+                        handle.graceful_shutdown(None);
+                    }
+                });
+                axum_server::from_tcp_rustls(listener.into_std()?, tls)?
+                    .handle(handle)
+                    .serve(app)
+                    .await
+            }
+            None => {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(stop)
+                    .await
+            }
+        }
+    });
 
+    tokio::select! {
+        reason = shutdown_signal() => {
+            tracing::info!("Received {}, shutting down", reason);
+        }
+        result = &mut server => {
+            match result {
+                Ok(Err(e)) => tracing::error!("Axum server error: {}", e),
+                Err(e) => tracing::error!("Axum server task failed: {}", e),
+                Ok(Ok(())) => tracing::info!("Axum server stopped"),
+            }
+        }
+        result = async {
+            match client.as_mut() {
+                Some(client) => client.start().await,
+                None => std::future::pending().await,
+            }
+        } => {
+            if let Err(e) = result {
+                tracing::error!("Discord client error: {:?}", e);
+            }
+        }
+    }
+
+    // Stop accepting connections and give in-flight requests time to finish
+    let _ = shutdown_tx.send(());
+    if !server.is_finished()
+        && tokio::time::timeout(config.shutdown_timeout, server)
+            .await
+            .is_err()
+    {
+        tracing::warn!(
+            "In-flight requests did not finish within {}s",
+            config.shutdown_timeout.as_secs()
+        );
+    }
+    if let Some(shard_manager) = shard_manager {
+        shard_manager.shutdown_all().await;
+    }
+    let _ = cleanup_stop_tx.send(());
+    let _ = cleanup_task.await;
+
+    let saved = match &config.cache_snapshot_path {
+        Some(path) => db.save_snapshot(path).await,
+        None => db.flush_cache().await,
+    };
+    match saved {
+        Ok(count) => tracing::info!("Saved {} online players before exit", count),
+        Err(e) => tracing::error!("Failed to save online players: {}", e),
+    }
+}
+
+/// Build the Discord bot client with its commands and shared data.
+async fn discord_client(
+    token: String,
+    bot_db: Arc<dyn Store>,
+    config: &oxeye_backend::config::Config,
+) -> serenity::Client {
     // send messages, send messages in threads, embed links, attach files, use external stickers and emoji, add reactions
     let intents = serenity::GatewayIntents::default();
 
+    let mut commands = vec![
+        discord_commands::connect(),
+        discord_commands::connectbulk(),
+        discord_commands::reconnect(),
+        discord_commands::list(),
+        discord_commands::status(),
+        discord_commands::pending(),
+        discord_commands::token(),
+        discord_commands::recent(),
+        discord_commands::stats(),
+        discord_commands::setcolor(),
+        discord_commands::setwebhook(),
+        discord_commands::settag(),
+        discord_commands::unlinkall(),
+        discord_commands::auditlog(),
+    ];
+    apply_cooldowns(
+        &mut commands,
+        config.discord_status_cooldown,
+        config.discord_read_cooldown,
+    );
+
+    let public_url = config.public_url.clone();
+    let max_servers_per_guild = config.max_servers_per_guild;
+    let code_length = config.code_length;
+    let code_throttle = CodeThrottle::new(config.rate_limit_guild_codes_per_min);
+    let stale_sync_secs = config.stale_sync_after.as_secs() as i64;
+    let webhook_allow_private = config.webhook_allow_private;
     let framework = Framework::builder()
         .options(FrameworkOptions {
-            commands: vec![
-                discord_commands::connect(),
-                discord_commands::list(),
-                discord_commands::status(),
-            ],
+            commands,
+            on_error: |error| Box::pin(on_error(error)),
             pre_command: |ctx| {
                 Box::pin(async move {
                     tracing::info!(
@@ -95,30 +294,91 @@ async fn main() {
             ..Default::default()
         })
         .setup(move |ctx, _ready, framework| {
-            let public_url = config.public_url.clone();
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
                 Ok(Data {
-                    db: db.clone(),
+                    db: bot_db,
                     public_url,
+                    max_servers_per_guild,
+                    code_length,
+                    code_throttle,
+                    stale_sync_secs,
+                    webhook_allow_private,
                 })
             })
         })
         .build();
 
-    let mut client = serenity::ClientBuilder::new(&config.discord_token.unwrap(), intents)
+    serenity::ClientBuilder::new(token, intents)
         .framework(framework)
         .await
-        .expect("Error creating Discord client");
+        .expect("Error creating Discord client")
+}
+
+/// Wait for Ctrl+C or SIGTERM and return which one arrived.
+async fn shutdown_signal() -> &'static str {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
     tokio::select! {
-        result = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()) => {
-            if let Err(e) = result {
-                tracing::error!("Axum server error: {}", e);
+        _ = ctrl_c => "Ctrl+C",
+        _ = terminate => "SIGTERM",
+    }
+}
+
+/// Set per-user (per Discord server) cooldowns: /status renders an image, so it
+/// gets its own, while the cheaper read-only commands share a looser one.
+/// A zero duration leaves the command without a cooldown.
+fn apply_cooldowns(
+    commands: &mut [poise::Command<Data, discord_commands::Error>],
+    status: Duration,
+    read: Duration,
+) {
+    for command in commands {
+        let cooldown = match command.name.as_str() {
+            "status" => status,
+            "list" | "recent" | "stats" => read,
+            _ => continue,
+        };
+        if let Ok(config) = command.cooldown_config.get_mut() {
+            config.member = (!cooldown.is_zero()).then_some(cooldown);
+        }
+    }
+}
+
+/// Tell users how long to wait when they hit a cooldown; everything else gets
+/// poise's default handling.
+async fn on_error(error: FrameworkError<'_>) {
+    match error {
+        poise::FrameworkError::CooldownHit {
+            remaining_cooldown,
+            ctx,
+            ..
+        } => {
+            let seconds = remaining_cooldown.as_millis().div_ceil(1000);
+            let reply = poise::CreateReply::default()
+                .content(format!("Please wait {}s", seconds))
+                .ephemeral(true);
+            if let Err(e) = ctx.send(reply).await {
+                tracing::warn!("Failed to send cooldown reply: {}", e);
             }
         }
-        result = client.start() => {
-            if let Err(e) = result {
-                tracing::error!("Discord client error: {:?}", e);
+        error => {
+            if let Err(e) = poise::builtins::on_error(error).await {
+                tracing::error!("Error while handling command error: {}", e);
             }
         }
     }
@@ -0,0 +1,57 @@
+//! Prometheus metrics for the HTTP API.
+//!
+//! Counters are plain atomics bumped by the route handlers; the `/metrics`
+//! handler renders them in the Prometheus text exposition format.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Request counters shared across all route handlers.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Successful /connect calls
+    pub connects: AtomicU64,
+    /// Successful /join calls
+    pub joins: AtomicU64,
+    /// Successful /leave calls
+    pub leaves: AtomicU64,
+    /// Successful /sync calls
+    pub syncs: AtomicU64,
+}
+
+impl Metrics {
+    /// Increment a counter by one.
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    /// `online_players` is the current number of online players across all servers.
+    pub fn render(&self, online_players: u64) -> String {
+        let counters = [
+            (
+                "oxeye_connects_total",
+                "Total servers connected",
+                &self.connects,
+            ),
+            ("oxeye_joins_total", "Total player joins", &self.joins),
+            ("oxeye_leaves_total", "Total player leaves", &self.leaves),
+            ("oxeye_syncs_total", "Total player list syncs", &self.syncs),
+        ];
+
+        let mut out = String::new();
+        for (name, help, counter) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP oxeye_online_players Players currently online across all servers"
+        );
+        let _ = writeln!(out, "# TYPE oxeye_online_players gauge");
+        let _ = writeln!(out, "oxeye_online_players {}", online_players);
+        out
+    }
+}
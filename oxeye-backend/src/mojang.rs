@@ -0,0 +1,328 @@
+//! Client for fetching player skins from Mojang's session server.
+//!
+//! Skins normally arrive from the Minecraft server through POST /skin; this
+//! looks one up by player UUID instead, for players whose server reported a
+//! UUID on /join but no skin. Mojang rate-limits the session server
+//! aggressively, so a 429 or 5xx response (or a connection failure) is retried
+//! with jittered exponential backoff, honouring `Retry-After`, up to
+//! `MAX_ATTEMPTS` tries. A 404, or the 204 the session server sends for an
+//! unknown UUID, means there's no such player and fails at once.
+
+use crate::config::Config;
+use base64::Engine;
+use reqwest::StatusCode;
+use reqwest::header::RETRY_AFTER;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// Mojang's session server, which serves player profiles with skin URLs.
+pub const SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+/// Most tries for one request, including the first.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Backoff before the first retry; doubled for each one after.
+const BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait between tries, even if `Retry-After` asks for more.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Why a skin couldn't be fetched.
+#[derive(Debug, thiserror::Error)]
+pub enum FetchError {
+    #[error("player not found")]
+    NotFound,
+
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("unexpected response status {0}")]
+    Status(StatusCode),
+
+    #[error("profile has no skin: {0}")]
+    NoSkin(String),
+}
+
+/// Profile returned by the session server.
+#[derive(Deserialize)]
+struct Profile {
+    properties: Vec<ProfileProperty>,
+}
+
+#[derive(Deserialize)]
+struct ProfileProperty {
+    name: String,
+    /// Base64 encoded JSON
+    value: String,
+}
+
+/// Decoded `textures` profile property.
+#[derive(Deserialize)]
+struct Textures {
+    textures: TextureSet,
+}
+
+#[derive(Deserialize)]
+struct TextureSet {
+    #[serde(rename = "SKIN")]
+    skin: Option<Texture>,
+}
+
+#[derive(Deserialize)]
+struct Texture {
+    url: String,
+}
+
+/// A skin downloaded from Mojang.
+#[derive(Debug)]
+pub struct Skin {
+    /// SHA256 of the profile's `textures` value, the same hash the mod reports
+    pub texture_hash: String,
+    pub texture_url: String,
+    /// Raw PNG data
+    pub data: Vec<u8>,
+}
+
+/// Fetches skins with one shared HTTP client.
+#[derive(Debug, Clone)]
+pub struct SkinFetcher {
+    client: reqwest::Client,
+    session_server: String,
+}
+
+impl SkinFetcher {
+    /// Create a fetcher using the configured Mojang session server and timeouts.
+    pub fn new(config: &Config) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(config.mojang_connect_timeout)
+            .timeout(config.mojang_request_timeout)
+            .build()?;
+        Ok(Self {
+            client,
+            session_server: config.mojang_session_server.clone(),
+        })
+    }
+
+    /// Use a different session server (a mirror, or a mock in tests).
+    pub fn with_session_server(mut self, url: impl Into<String>) -> Self {
+        self.session_server = url.into();
+        self
+    }
+
+    /// Download the skin of the player with this UUID (dashes optional).
+    pub async fn fetch_skin(&self, uuid: &str) -> Result<Skin, FetchError> {
+        let url = format!(
+            "{}/session/minecraft/profile/{}",
+            self.session_server,
+            uuid.replace('-', "")
+        );
+        let profile: Profile = self.get(&url).await?.json().await?;
+        let (texture_hash, texture_url) = skin_texture(&profile)?;
+        let data = self.get(&texture_url).await?.bytes().await?.to_vec();
+        Ok(Skin {
+            texture_hash,
+            texture_url,
+            data,
+        })
+    }
+
+    /// GET `url`, retrying rate limits, server errors and connection failures.
+    async fn get(&self, url: &str) -> Result<reqwest::Response, FetchError> {
+        let mut attempt = 1;
+        loop {
+            let delay = match self.client.get(url).send().await {
+                Ok(response)
+                    if matches!(
+                        response.status(),
+                        StatusCode::NOT_FOUND | StatusCode::NO_CONTENT
+                    ) =>
+                {
+                    return Err(FetchError::NotFound);
+                }
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) if is_retryable(response.status()) && attempt < MAX_ATTEMPTS => {
+                    retry_after(&response).unwrap_or_else(|| backoff(attempt))
+                }
+                Ok(response) => return Err(FetchError::Status(response.status())),
+                Err(e) if (e.is_connect() || e.is_timeout()) && attempt < MAX_ATTEMPTS => {
+                    backoff(attempt)
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let delay = delay.min(MAX_DELAY);
+            tracing::warn!(attempt, "mojang request failed, retrying in {:?}", delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Exponential backoff for the retry after `attempt`, randomized to between
+/// half and all of the full delay so clients don't retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let full = BASE_DELAY * 2u32.pow(attempt - 1);
+    full.mul_f64(rand::random_range(0.5..=1.0))
+}
+
+/// Responses worth trying again: rate limiting and server errors.
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay asked for by a `Retry-After` header given in seconds.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let secs = response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+/// Pull the texture hash and skin URL out of a profile's base64 encoded
+/// `textures` property.
+fn skin_texture(profile: &Profile) -> Result<(String, String), FetchError> {
+    let property = profile
+        .properties
+        .iter()
+        .find(|p| p.name == "textures")
+        .ok_or_else(|| FetchError::NoSkin("no textures property".to_string()))?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(&property.value)
+        .map_err(|e| FetchError::NoSkin(e.to_string()))?;
+    let textures: Textures =
+        serde_json::from_slice(&decoded).map_err(|e| FetchError::NoSkin(e.to_string()))?;
+    let skin = textures
+        .textures
+        .skin
+        .ok_or_else(|| FetchError::NoSkin("no skin texture".to_string()))?;
+    let texture_hash = format!("{:x}", Sha256::digest(property.value.as_bytes()));
+    Ok((texture_hash, skin.url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::extract::{Path, State};
+    use axum::http::{HeaderMap, header};
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const SKIN_PNG: &[u8] = b"\x89PNG not really a skin";
+
+    /// Serve a mock session server whose profile route answers with
+    /// `failures` 429s before succeeding, and 204 for the UUID `unknown`.
+    /// Returns its URL and request counter.
+    async fn mock_session_server(failures: u32) -> (String, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let textures = serde_json::json!({
+            "textures": {"SKIN": {"url": format!("{base}/skin.png")}}
+        });
+        let profile = serde_json::json!({
+            "id": "069a79f444e94726a5befca90e38aaf5",
+            "name": "Notch",
+            "properties": [{
+                "name": "textures",
+                "value": base64::engine::general_purpose::STANDARD.encode(textures.to_string()),
+            }],
+        });
+        let app = Router::new()
+            .route(
+                "/session/minecraft/profile/{uuid}",
+                get(move |State(requests): State<Arc<AtomicU32>>, Path(uuid): Path<String>| async move {
+                    if uuid == "unknown" {
+                        requests.fetch_add(1, Ordering::SeqCst);
+                        return axum::http::StatusCode::NO_CONTENT.into_response();
+                    }
+                    if requests.fetch_add(1, Ordering::SeqCst) < failures {
+                        let mut headers = HeaderMap::new();
+                        headers.insert(header::RETRY_AFTER, "0".parse().unwrap());
+                        return (axum::http::StatusCode::TOO_MANY_REQUESTS, headers)
+                            .into_response();
+                    }
+                    axum::Json(profile).into_response()
+                }),
+            )
+            .route("/skin.png", get(|| async { SKIN_PNG }))
+            .with_state(requests.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (base, requests)
+    }
+
+    fn fetcher(session_server: &str) -> SkinFetcher {
+        SkinFetcher::new(&Config::default())
+            .unwrap()
+            .with_session_server(session_server)
+    }
+
+    #[tokio::test]
+    async fn test_retries_after_rate_limit() {
+        let (base, requests) = mock_session_server(1).await;
+
+        let skin = fetcher(&base)
+            .fetch_skin("069a79f4-44e9-4726-a5be-fca90e38aaf5")
+            .await
+            .unwrap();
+
+        assert_eq!(skin.data, SKIN_PNG);
+        assert_eq!(skin.texture_url, format!("{base}/skin.png"));
+        assert_eq!(skin.texture_hash.len(), 64);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let (base, requests) = mock_session_server(u32::MAX).await;
+
+        let result = fetcher(&base).fetch_skin("notch").await;
+
+        assert!(matches!(
+            result,
+            Err(FetchError::Status(StatusCode::TOO_MANY_REQUESTS))
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_player_fails_fast() {
+        let (base, requests) = mock_session_server(0).await;
+
+        // Only the profile route exists, so any other path is a 404
+        let result = fetcher(&format!("{base}/missing"))
+            .fetch_skin("notch")
+            .await;
+
+        assert!(matches!(result, Err(FetchError::NotFound)));
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_no_content_is_not_found() {
+        let (base, requests) = mock_session_server(0).await;
+
+        let result = fetcher(&base).fetch_skin("unknown").await;
+
+        assert!(matches!(result, Err(FetchError::NotFound)));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_backoff_grows_with_jitter() {
+        for attempt in 1..MAX_ATTEMPTS {
+            let full = BASE_DELAY * 2u32.pow(attempt - 1);
+            let delay = backoff(attempt);
+            assert!(delay >= full / 2 && delay <= full, "{:?}", delay);
+        }
+    }
+}
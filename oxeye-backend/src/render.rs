@@ -2,63 +2,293 @@
 //!
 //! This module handles:
 //! - Rendering player head images (64x64) from skin PNGs
+//! - Rendering full-body front views (64x128) from skin PNGs
 //! - Generating composite status images with multiple player heads
 
-use ab_glyph::{Font, FontRef, PxScale};
-use image::{DynamicImage, GenericImageView, ImageFormat, Rgba, RgbaImage, imageops};
-use imageproc::drawing::draw_text_mut;
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::jpeg::JpegEncoder;
+use image::{
+    Delay, DynamicImage, Frame, GenericImageView, ImageFormat, Rgb, RgbImage, Rgba, RgbaImage,
+    imageops,
+};
+use imageproc::drawing::{draw_filled_rect_mut, draw_text_mut};
+use imageproc::rect::Rect;
+use std::borrow::Cow;
 use std::cmp::min;
 use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
 
 /// Default Steve head image (embedded at compile time).
 pub const DEFAULT_STEVE_HEAD: &[u8] = include_bytes!("../assets/steve_head.png");
 
+/// Default Alex head image (embedded at compile time).
+pub const DEFAULT_ALEX_HEAD: &[u8] = include_bytes!("../assets/alex_head.png");
+
 /// Inter font for rendering usernames (embedded at compile time).
 const INTER_FONT: &[u8] = include_bytes!("../assets/Inter.ttf");
 
+/// NeuQuant sampling factor for GIF frames (1 is slowest and best, 30 fastest).
+/// The composites use few colors, so a faster setting costs little quality.
+const GIF_ENCODE_SPEED: i32 = 10;
+
+/// A font file consulted for characters Inter lacks (see
+/// `CompositeConfig::fallback_fonts`). Clones share the same bytes.
+#[derive(Clone)]
+pub struct FontData(Arc<[u8]>);
+
+impl FontData {
+    /// Read a TrueType or OpenType font file, checking that it parses.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RenderError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .map_err(|e| RenderError::FontLoad(format!("{}: {}", path.display(), e)))?;
+        Self::from_bytes(data)
+    }
+
+    /// Use font data that's already in memory, checking that it parses.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, RenderError> {
+        FontRef::try_from_slice(&data).map_err(|e| RenderError::FontLoad(e.to_string()))?;
+        Ok(Self(data.into()))
+    }
+}
+
+impl std::fmt::Debug for FontData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FontData({} bytes)", self.0.len())
+    }
+}
+
+/// Encoding used for rendered images.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Lossless PNG with transparency
+    Png,
+    /// Lossy JPEG. JPEG has no alpha channel, so the image is first
+    /// composited onto a solid `background` color.
+    Jpeg { quality: u8, background: Rgb<u8> },
+    /// Lossless WebP with transparency
+    WebP,
+}
+
+impl OutputFormat {
+    /// MIME type to send in the Content-Type header.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Jpeg { .. } => "image/jpeg",
+            OutputFormat::WebP => "image/webp",
+        }
+    }
+}
+
 /// Render a 64x64 head image from a Minecraft skin PNG.
 ///
 /// The head is composed of:
 /// - Face layer: 8x8 at position (8, 8)
 /// - Helmet overlay: 8x8 at position (40, 8)
 ///
-/// The two layers are composited and scaled to 64x64 using nearest-neighbor
+/// HD skins (128 pixels wide) use the same layout with every coordinate
+/// doubled. The two layers are composited and scaled to 64x64 using nearest-neighbor
 /// interpolation (to preserve the pixelated Minecraft style).
 pub fn render_head(skin_png: &[u8]) -> Result<Vec<u8>, RenderError> {
-    // Load the skin image
-    let skin =
-        image::load_from_memory(skin_png).map_err(|e| RenderError::ImageLoad(e.to_string()))?;
+    render_head_with_format(skin_png, OutputFormat::Png)
+}
 
-    // Verify skin dimensions (should be 64x64 or 64x32 for old format)
-    let (width, height) = skin.dimensions();
-    if width != 64 || (height != 64 && height != 32) {
-        return Err(RenderError::InvalidSkinDimensions { width, height });
-    }
+/// Render a 64x64 head image, encoded in the given output format.
+pub fn render_head_with_format(
+    skin_png: &[u8],
+    format: OutputFormat,
+) -> Result<Vec<u8>, RenderError> {
+    let skin = load_skin(skin_png)?;
+    let legacy = is_legacy(&skin);
 
     // Crop face (8x8 at position 8,8)
-    let face = skin.crop_imm(8, 8, 8, 8).to_rgba8();
+    let face = crop_part(&skin, 8, 8, 8, 8);
 
     // Crop helmet overlay (8x8 at position 40,8)
     // Only available in new skin format (64x64)
     let mut head = face;
-    if height == 64 {
-        let helmet = skin.crop_imm(40, 8, 8, 8).to_rgba8();
-        // Composite helmet over face (respecting alpha)
-        imageops::overlay(&mut head, &helmet, 0, 0);
+    if !legacy {
+        let helmet = crop_part(&skin, 40, 8, 8, 8);
+        overlay_layer(&mut head, &helmet);
     }
 
     // Scale to 64x64 with nearest-neighbor (pixelated look)
     let head = imageops::resize(&head, 64, 64, imageops::FilterType::Nearest);
 
-    // Encode to PNG
-    let mut buf = Vec::new();
-    head.write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
-        .map_err(|e| RenderError::ImageEncode(e.to_string()))?;
+    encode_image(head, format)
+}
 
-    Ok(buf)
+/// Render a 64x128 front view of the full player model from a Minecraft skin PNG.
+///
+/// Head, torso, arms and legs are cut from their front-face regions and
+/// assembled into a 16x32 standing figure, with the second (overlay) layer
+/// drawn on top, then scaled 4x using nearest-neighbor interpolation.
+///
+/// Legacy 64x32 skins have no left arm/leg regions and no body overlays, so
+/// the right limbs are mirrored in their place (as the game does).
+pub fn render_body(skin_png: &[u8]) -> Result<Vec<u8>, RenderError> {
+    let skin = load_skin(skin_png)?;
+    let legacy = is_legacy(&skin);
+    let scale = skin_scale(&skin);
+    let mut figure = RgbaImage::new(16 * scale, 32 * scale);
+
+    // Each part is (src_x, src_y, width, height, dest_x, dest_y)
+    let base_layer = [
+        (8, 8, 8, 8, 4, 0),    // head
+        (20, 20, 8, 12, 4, 8), // torso
+        (44, 20, 4, 12, 0, 8), // right arm
+        (4, 20, 4, 12, 4, 20), // right leg
+    ];
+    let place = |figure: &mut RgbaImage, part: &RgbaImage, dx: u32, dy: u32| {
+        imageops::overlay(figure, part, (dx * scale) as i64, (dy * scale) as i64);
+    };
+    for (sx, sy, w, h, dx, dy) in base_layer {
+        place(&mut figure, &crop_part(&skin, sx, sy, w, h), dx, dy);
+    }
+
+    if legacy {
+        let left_arm = imageops::flip_horizontal(&crop_part(&skin, 44, 20, 4, 12));
+        place(&mut figure, &left_arm, 12, 8);
+        let left_leg = imageops::flip_horizontal(&crop_part(&skin, 4, 20, 4, 12));
+        place(&mut figure, &left_leg, 8, 20);
+    } else {
+        let left_limbs = [
+            (36, 52, 4, 12, 12, 8), // left arm
+            (20, 52, 4, 12, 8, 20), // left leg
+        ];
+        let overlay_layer = [
+            (40, 8, 8, 8, 4, 0),    // hat
+            (20, 36, 8, 12, 4, 8),  // jacket
+            (44, 36, 4, 12, 0, 8),  // right sleeve
+            (52, 52, 4, 12, 12, 8), // left sleeve
+            (4, 36, 4, 12, 4, 20),  // right pants leg
+            (4, 52, 4, 12, 8, 20),  // left pants leg
+        ];
+        for (sx, sy, w, h, dx, dy) in left_limbs.into_iter().chain(overlay_layer) {
+            place(&mut figure, &crop_part(&skin, sx, sy, w, h), dx, dy);
+        }
+    }
+
+    // Scale to 64x128 with nearest-neighbor (pixelated look)
+    let figure = imageops::resize(&figure, 64, 128, imageops::FilterType::Nearest);
+
+    encode_image(figure, OutputFormat::Png)
+}
+
+/// Decode a skin PNG and verify its dimensions (64x64, or 64x32 for the old format).
+/// HD skins at 128x128 and 128x64 are also accepted.
+fn load_skin(skin_png: &[u8]) -> Result<DynamicImage, RenderError> {
+    let skin =
+        image::load_from_memory(skin_png).map_err(|e| RenderError::ImageLoad(e.to_string()))?;
+
+    let (width, height) = skin.dimensions();
+    if (width != 64 && width != 128) || (height != width && height != width / 2) {
+        return Err(RenderError::InvalidSkinDimensions { width, height });
+    }
+
+    Ok(skin)
+}
+
+/// Multiplier from standard 64-wide skin coordinates to this skin's pixels.
+fn skin_scale(skin: &DynamicImage) -> u32 {
+    skin.width() / 64
+}
+
+/// Whether the skin uses the old half-height layout (no overlays or left limbs).
+fn is_legacy(skin: &DynamicImage) -> bool {
+    skin.height() == skin.width() / 2
+}
+
+/// Crop a region given in standard 64-wide skin coordinates, scaled for HD skins.
+fn crop_part(skin: &DynamicImage, x: u32, y: u32, width: u32, height: u32) -> RgbaImage {
+    let scale = skin_scale(skin);
+    skin.crop_imm(x * scale, y * scale, width * scale, height * scale)
+        .to_rgba8()
+}
+
+/// Composite a second-layer part (e.g. the helmet) over a same-sized base part.
+///
+/// Blends with premultiplied alpha so a partially transparent base doesn't
+/// pick up dark fringes. Fully transparent layer pixels leave the base pixel
+/// untouched, and an entirely transparent layer is skipped.
+fn overlay_layer(base: &mut RgbaImage, layer: &RgbaImage) {
+    if layer.pixels().all(|p| p[3] == 0) {
+        return;
+    }
+
+    for (dst, src) in base.pixels_mut().zip(layer.pixels()) {
+        let src_a = src[3] as f32 / 255.0;
+        if src_a == 0.0 {
+            continue;
+        }
+        let dst_a = dst[3] as f32 / 255.0;
+        let out_a = src_a + dst_a * (1.0 - src_a);
+        let mut out = [0u8; 4];
+        for c in 0..3 {
+            // Premultiply, blend "source over", then un-premultiply
+            let premul = src[c] as f32 * src_a + dst[c] as f32 * dst_a * (1.0 - src_a);
+            out[c] = (premul / out_a).round().clamp(0.0, 255.0) as u8;
+        }
+        out[3] = (out_a * 255.0).round() as u8;
+        *dst = Rgba(out);
+    }
+}
+
+/// What each cell of a composite image shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// Square head images (from `render_head`)
+    #[default]
+    Head,
+    /// Full-body images twice as tall as they are wide (from `render_body`)
+    Body,
+}
+
+impl RenderMode {
+    /// Height of the player image in a cell for the given cell width.
+    fn image_height(self, head_size: u32) -> u32 {
+        match self {
+            RenderMode::Head => head_size,
+            RenderMode::Body => head_size * 2,
+        }
+    }
+}
+
+/// Order in which players are laid out in a composite image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// By username, case-insensitive
+    #[default]
+    Alphabetical,
+    /// By join time, earliest first (players without one go last)
+    JoinedAt,
+    /// Keep the order of the input slice
+    None,
+}
+
+impl SortOrder {
+    /// Indices into `players` in draw order. The slice itself is left untouched.
+    fn order(self, players: &[PlayerEntry]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..players.len()).collect();
+        match self {
+            SortOrder::Alphabetical => {
+                indices.sort_by_cached_key(|&i| players[i].name.to_lowercase())
+            }
+            SortOrder::JoinedAt => indices.sort_by_key(|&i| {
+                let joined_at = players[i].joined_at;
+                (joined_at.is_none(), joined_at)
+            }),
+            SortOrder::None => {}
+        }
+        indices
+    }
 }
 
 /// Configuration for composite image rendering.
+#[derive(Debug, Clone)]
 pub struct CompositeConfig {
     /// Size of each head image
     pub head_size: u32,
@@ -70,10 +300,52 @@ pub struct CompositeConfig {
     pub text_height: u32,
     /// Maximum number of players per row
     pub max_per_row: usize,
+    /// Use fewer columns when the average drawn username is wider than this
+    /// many pixels, giving long names more room (None keeps `max_per_row` fixed)
+    pub wide_name_width: Option<u32>,
+    /// Most players drawn; the rest are summed up in a "+N more" tile
+    /// (None draws everyone)
+    pub max_players_rendered: Option<usize>,
     /// Base font size for usernames
     pub font_size: f32,
     /// Minimum font size when scaling for long names
     pub min_font_size: f32,
+    /// Names longer than this many characters are cut short with a trailing
+    /// "…" instead of shrinking further (None draws names in full)
+    pub max_display_chars: Option<usize>,
+    /// Fonts tried in order for characters Inter has no glyph for, such as
+    /// CJK; a character none of them has is drawn as Inter's missing-glyph box
+    pub fallback_fonts: Vec<FontData>,
+    /// Canvas fill color, or None for a transparent background
+    pub background: Option<Rgba<u8>>,
+    /// Color used for usernames (the empty-state message uses a dimmed variant)
+    pub text_color: Rgba<u8>,
+    /// Whether cells hold heads or full bodies (`head_size` is the cell width)
+    pub render_mode: RenderMode,
+    /// Order of players in the grid
+    pub sort: SortOrder,
+    /// Title drawn left-aligned in a band above the grid (e.g. server name and count)
+    pub header: Option<String>,
+    /// Frames and timing for `render_composite_animated`
+    pub animation: AnimationConfig,
+}
+
+/// Timing of animated (GIF) status images, where players pop in one after another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationConfig {
+    /// Frames from an empty grid to the full one (at least one is always drawn)
+    pub frames: u32,
+    /// How long each frame is shown; the full grid is held for `frames` times as long
+    pub frame_delay_ms: u32,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            frames: 8,
+            frame_delay_ms: 100,
+        }
+    }
 }
 
 impl Default for CompositeConfig {
@@ -84,191 +356,670 @@ impl Default for CompositeConfig {
             v_spacing: 16,
             text_height: 48,
             max_per_row: 5,
+            wide_name_width: None,
+            max_players_rendered: None,
             font_size: 32.0,
             min_font_size: 20.0,
+            max_display_chars: None,
+            fallback_fonts: Vec::new(),
+            background: None,
+            text_color: Rgba([255, 255, 255, 255]),
+            render_mode: RenderMode::Head,
+            sort: SortOrder::Alphabetical,
+            header: None,
+            animation: AnimationConfig::default(),
+        }
+    }
+}
+
+/// Which default skin to draw for a player without a head image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DefaultModel {
+    /// Classic (wide-armed) Steve
+    #[default]
+    Steve,
+    /// Slim-armed Alex
+    Alex,
+}
+
+impl DefaultModel {
+    /// Pick the default the way Minecraft does: Alex when the Java
+    /// `UUID.hashCode()` of the player's UUID is odd, otherwise Steve.
+    ///
+    /// Accepts UUIDs with or without dashes; returns None for anything else.
+    pub fn from_uuid(uuid: &str) -> Option<Self> {
+        let hex: String = uuid.chars().filter(|&c| c != '-').collect();
+        if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let bits = u128::from_str_radix(&hex, 16).ok()?;
+        let folded = (bits >> 64) as u64 ^ bits as u64;
+        let hash = (folded >> 32) as u32 ^ folded as u32;
+        Some(if hash & 1 == 1 {
+            DefaultModel::Alex
+        } else {
+            DefaultModel::Steve
+        })
+    }
+
+    /// The embedded head image for this model.
+    pub fn head_png(self) -> &'static [u8] {
+        match self {
+            DefaultModel::Steve => DEFAULT_STEVE_HEAD,
+            DefaultModel::Alex => DEFAULT_ALEX_HEAD,
         }
     }
 }
 
 /// A player entry for composite rendering.
+#[derive(Default)]
 pub struct PlayerEntry {
     pub name: String,
-    /// Pre-rendered head image (64x64 PNG), or None for Steve fallback
+    /// Pre-rendered head image (64x64 PNG), or None for the default skin.
+    /// In `RenderMode::Body` this holds a pre-rendered body image instead.
     pub head_data: Option<Vec<u8>>,
+    /// Draw the player desaturated with a dimmed name (e.g. recently left).
+    pub grayscale: bool,
+    /// Unix timestamp the player joined at, used by `SortOrder::JoinedAt`.
+    pub joined_at: Option<i64>,
+    /// Default skin drawn when `head_data` is missing or unreadable.
+    pub default_model: DefaultModel,
 }
 
 /// Render a composite status image showing multiple player heads in a grid.
 ///
 /// Layout:
-/// - Maximum 5 players per row
+/// - Up to `max_per_row` players per row, fewer when long names need the room
+///   (see `wide_name_width`)
 /// - Rows are center-aligned
 /// - Each cell contains a 64x64 head with the username below
-/// - Transparent background
+/// - An optional header band above the grid when `config.header` is set
+/// - Transparent background unless `config.background` is set
 ///
 /// Returns "No players online" text if the player list is empty.
 pub fn render_composite(
     players: &[PlayerEntry],
     config: &CompositeConfig,
 ) -> Result<Vec<u8>, RenderError> {
-    // Load the font
-    let font =
-        FontRef::try_from_slice(INTER_FONT).map_err(|e| RenderError::FontLoad(e.to_string()))?;
+    render_composite_with_format(players, config, OutputFormat::Png)
+}
+
+/// Render a composite status image, encoded in the given output format.
+pub fn render_composite_with_format(
+    players: &[PlayerEntry],
+    config: &CompositeConfig,
+    format: OutputFormat,
+) -> Result<Vec<u8>, RenderError> {
+    let fonts = Fonts::load(&config.fallback_fonts)?;
+    let canvas = if players.is_empty() {
+        empty_state_canvas(&fonts, config)
+    } else {
+        draw_composite(&fonts, players, config, usize::MAX)?
+    };
+    encode_image(canvas, format)
+}
+
+/// Render a looping GIF of the composite where players pop in one after another.
+///
+/// Each frame adds a share of the grid (in the configured sort order, "+N more"
+/// tile last) and the final, complete frame is held before the loop restarts.
+/// With no players the GIF is a single frame of the empty state.
+pub fn render_composite_animated(
+    players: &[PlayerEntry],
+    config: &CompositeConfig,
+) -> Result<Vec<u8>, RenderError> {
+    let fonts = Fonts::load(&config.fallback_fonts)?;
+    let delay = |ms: u32| Delay::from_numer_denom_ms(ms, 1);
+    let frames = if players.is_empty() {
+        vec![Frame::from_parts(
+            empty_state_canvas(&fonts, config),
+            0,
+            0,
+            delay(config.animation.frame_delay_ms),
+        )]
+    } else {
+        let count = config.animation.frames.max(1) as usize;
+        let cells = players.len() + 1; // enough to include the "+N more" tile
+        (1..=count)
+            .map(|frame| {
+                let visible = cells * frame / count;
+                let hold = if frame == count { count as u32 } else { 1 };
+                let canvas = draw_composite(&fonts, players, config, visible)?;
+                Ok(Frame::from_parts(
+                    canvas,
+                    0,
+                    0,
+                    delay(config.animation.frame_delay_ms.saturating_mul(hold)),
+                ))
+            })
+            .collect::<Result<Vec<_>, RenderError>>()?
+    };
+
+    let mut buf = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut buf, GIF_ENCODE_SPEED);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| RenderError::ImageEncode(e.to_string()))?;
+        encoder
+            .encode_frames(frames)
+            .map_err(|e| RenderError::ImageEncode(e.to_string()))?;
+    }
+    Ok(buf)
+}
+
+/// The embedded Inter font followed by the configured fallback fonts.
+///
+/// Every character is drawn and measured with the first font that has a glyph
+/// for it, so text is split into runs that each use a single font.
+struct Fonts<'a> {
+    fonts: Vec<FontRef<'a>>,
+}
+
+impl<'a> Fonts<'a> {
+    /// Parse Inter and then each fallback, in order.
+    fn load(fallbacks: &'a [FontData]) -> Result<Self, RenderError> {
+        let fonts = std::iter::once(INTER_FONT)
+            .chain(fallbacks.iter().map(|font| &*font.0))
+            .map(|data| {
+                FontRef::try_from_slice(data).map_err(|e| RenderError::FontLoad(e.to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { fonts })
+    }
+
+    /// Inter, whose ascent every run is aligned to.
+    fn primary(&self) -> &FontRef<'a> {
+        &self.fonts[0]
+    }
 
-    // Handle empty state
-    if players.is_empty() {
-        return render_empty_state(&font, config);
+    /// Index of the first font with a glyph for `c`, or Inter if none has one.
+    fn font_index(&self, c: char) -> usize {
+        self.fonts
+            .iter()
+            .position(|font| font.glyph_id(c).0 != 0)
+            .unwrap_or(0)
     }
 
+    /// Split `text` into consecutive runs that are drawn with the same font.
+    fn runs<'t>(&self, text: &'t str) -> Vec<(&FontRef<'a>, &'t str)> {
+        let mut runs = Vec::new();
+        let mut current: Option<(usize, usize)> = None; // (font index, run start)
+        for (i, c) in text.char_indices() {
+            let index = self.font_index(c);
+            match current {
+                Some((font, start)) if font != index => {
+                    runs.push((&self.fonts[font], &text[start..i]));
+                    current = Some((index, i));
+                }
+                Some(_) => {}
+                None => current = Some((index, i)),
+            }
+        }
+        if let Some((font, start)) = current {
+            runs.push((&self.fonts[font], &text[start..]));
+        }
+        runs
+    }
+}
+
+/// Draw the composite grid for a non-empty player list, leaving every cell
+/// from index `visible` on empty (the layout is the same either way).
+fn draw_composite(
+    fonts: &Fonts<'_>,
+    players: &[PlayerEntry],
+    config: &CompositeConfig,
+    visible: usize,
+) -> Result<RgbaImage, RenderError> {
+    // Players past the cap are replaced by a single "+N more" tile
+    let mut order = config.sort.order(players);
+    let hidden = match config.max_players_rendered {
+        Some(cap) if order.len() > cap => {
+            let hidden = order.len() - cap;
+            order.truncate(cap);
+            hidden
+        }
+        _ => 0,
+    };
+    let cells = order.len() + usize::from(hidden > 0);
+
     // Calculate dimensions
-    let num_rows = (players.len() + config.max_per_row - 1) / config.max_per_row;
-    let cell_height = config.head_size + config.text_height + config.v_spacing;
+    let names: Vec<Cow<'_, str>> = order
+        .iter()
+        .map(|&index| match config.max_display_chars {
+            Some(max_chars) => truncate_name(&players[index].name, max_chars),
+            None => Cow::Borrowed(players[index].name.as_str()),
+        })
+        .collect();
+    let (per_row, column_width) =
+        effective_columns(fonts, names.iter().map(|name| name.as_ref()), config);
+    let num_rows = cells.div_ceil(per_row);
+    let image_height = config.render_mode.image_height(config.head_size);
+    let cell_height = image_height + config.text_height + config.v_spacing;
 
-    // Max width: 5 heads with spacing
+    // Max width: a full row of heads with spacing
     let max_width = (config.head_size * config.max_per_row as u32)
         + (config.h_spacing * (config.max_per_row as u32 - 1));
-    let height = cell_height * num_rows as u32;
+    let header_height = match config.header {
+        Some(_) => config.font_size.ceil() as u32 + config.v_spacing,
+        None => 0,
+    };
+    let height = header_height + cell_height * num_rows as u32;
+
+    let mut canvas = new_canvas(max_width, height, config);
 
-    // Create transparent canvas
-    let mut canvas = RgbaImage::from_pixel(max_width, height, Rgba([0, 0, 0, 0]));
+    if let Some(ref header) = config.header {
+        draw_text(
+            &mut canvas,
+            config.text_color,
+            0,
+            0,
+            PxScale::from(config.font_size),
+            fonts,
+            header,
+        );
+    }
 
-    // Load Steve head fallback
-    let steve_head = image::load_from_memory(DEFAULT_STEVE_HEAD)
-        .map_err(|e| RenderError::ImageLoad(e.to_string()))?
-        .to_rgba8();
+    // Load the default head fallbacks
+    let steve_head = load_default_head(DefaultModel::Steve)?;
+    let alex_head = load_default_head(DefaultModel::Alex)?;
 
-    // Draw each player
-    for (i, player) in players.iter().enumerate() {
-        let row = i / config.max_per_row;
-        let col = i % config.max_per_row;
-        let items_in_row = min(config.max_per_row, players.len() - row * config.max_per_row);
+    // Top-left corner of the i-th head; rows are centered horizontally and
+    // each head is centered in its column
+    let cell_origin = |i: usize| {
+        let row = i / per_row;
+        let col = i % per_row;
+        let items_in_row = min(per_row, cells - row * per_row);
 
         // Calculate row width for centering
-        let row_width = (config.head_size * items_in_row as u32)
+        let row_width = (column_width * items_in_row as u32)
             + (config.h_spacing * (items_in_row as u32).saturating_sub(1));
-        let x_offset = (max_width - row_width) / 2;
+        let x_offset = (max_width - row_width) / 2 + (column_width - config.head_size) / 2;
 
-        let x = x_offset + (col as u32) * (config.head_size + config.h_spacing);
-        let y = (row as u32) * cell_height;
+        let x = x_offset + (col as u32) * (column_width + config.h_spacing);
+        let y = header_height + (row as u32) * cell_height;
+        (x, y)
+    };
 
-        // Load and draw head
-        let head = if let Some(head_data) = &player.head_data {
-            image::load_from_memory(head_data)
-                .map(|img| img.to_rgba8())
-                .unwrap_or_else(|_| steve_head.clone())
-        } else {
-            steve_head.clone()
+    // Draw each player
+    for (i, (player, name)) in order
+        .iter()
+        .map(|&index| &players[index])
+        .zip(&names)
+        .enumerate()
+        .take(visible)
+    {
+        let (x, y) = cell_origin(i);
+
+        // Load and draw head (or body). The default fallback is always a
+        // square head, drawn at the top of the cell.
+        let decoded = player
+            .head_data
+            .as_ref()
+            .and_then(|data| image::load_from_memory(data).ok())
+            .map(|img| img.to_rgba8());
+        let (head, target_height) = match decoded {
+            Some(img) => (img, image_height),
+            None => match player.default_model {
+                DefaultModel::Steve => (steve_head.clone(), config.head_size),
+                DefaultModel::Alex => (alex_head.clone(), config.head_size),
+            },
         };
 
         // Resize if needed (should already be 64x64, but just in case)
-        let head = if head.dimensions() != (config.head_size, config.head_size) {
+        let head = if head.dimensions() != (config.head_size, target_height) {
             imageops::resize(
                 &head,
                 config.head_size,
-                config.head_size,
+                target_height,
                 imageops::FilterType::Nearest,
             )
         } else {
             head
         };
+        let head = if player.grayscale {
+            desaturate(&head)
+        } else {
+            head
+        };
 
         imageops::overlay(&mut canvas, &head, x.into(), y.into());
 
         // Calculate font size (scale down for long names)
-        let font_size = calculate_font_size(&player.name, config);
+        let font_size = calculate_font_size(fonts, name, config);
         let scale = PxScale::from(font_size);
 
         // Measure text width for centering
-        let text_width = measure_text_width(&font, &player.name, scale);
-        let text_x = x + (config.head_size / 2) - (text_width / 2);
-        let text_y = y + config.head_size + 4;
+        let text_width = measure_text_width(fonts, name, scale);
+        let text_x = (x + config.head_size / 2).saturating_sub(text_width / 2);
+        let text_y = y + image_height + 4;
 
-        // Draw username (white text)
-        draw_text_mut(
+        // Draw username
+        let text_color = if player.grayscale {
+            dim(config.text_color)
+        } else {
+            config.text_color
+        };
+        draw_text(
             &mut canvas,
-            Rgba([255, 255, 255, 255]),
+            text_color,
             text_x as i32,
             text_y as i32,
             scale,
-            &font,
-            &player.name,
+            fonts,
+            name,
         );
     }
 
-    // Encode to PNG
-    let mut buf = Vec::new();
-    DynamicImage::ImageRgba8(canvas)
-        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
-        .map_err(|e| RenderError::ImageEncode(e.to_string()))?;
+    if hidden > 0 && order.len() < visible {
+        let (x, y) = cell_origin(order.len());
+        draw_overflow_tile(&mut canvas, fonts, config, x, y, hidden);
+    }
 
-    Ok(buf)
+    Ok(canvas)
 }
 
-/// Render the empty state image ("No players online").
-fn render_empty_state(
-    font: &FontRef<'_>,
+/// Fill color of the "+N more" tile.
+const OVERFLOW_TILE_COLOR: Rgba<u8> = Rgba([64, 64, 64, 200]);
+
+/// Draw the "+N more" placeholder in the cell at (x, y): a head-sized square
+/// with the count centered inside and "more" where a username would go.
+fn draw_overflow_tile(
+    canvas: &mut RgbaImage,
+    fonts: &Fonts<'_>,
     config: &CompositeConfig,
-) -> Result<Vec<u8>, RenderError> {
+    x: u32,
+    y: u32,
+    hidden: usize,
+) {
+    let size = config.head_size;
+    draw_filled_rect_mut(
+        canvas,
+        Rect::at(x as i32, y as i32).of_size(size, size),
+        OVERFLOW_TILE_COLOR,
+    );
+
+    let count = format!("+{}", hidden);
+    let font_size = calculate_font_size(fonts, &count, config);
+    let scale = PxScale::from(font_size);
+    let text_x = (x + size / 2).saturating_sub(measure_text_width(fonts, &count, scale) / 2);
+    let text_y = y + size.saturating_sub(font_size as u32) / 2;
+    draw_text(
+        canvas,
+        config.text_color,
+        text_x as i32,
+        text_y as i32,
+        scale,
+        fonts,
+        &count,
+    );
+
+    let label = "more";
+    let scale = PxScale::from(config.font_size);
+    let label_x = (x + size / 2).saturating_sub(measure_text_width(fonts, label, scale) / 2);
+    let label_y = y + config.render_mode.image_height(size) + 4;
+    draw_text(
+        canvas,
+        dim(config.text_color),
+        label_x as i32,
+        label_y as i32,
+        scale,
+        fonts,
+        label,
+    );
+}
+
+/// Decode the embedded head image for a default model.
+fn load_default_head(model: DefaultModel) -> Result<RgbaImage, RenderError> {
+    image::load_from_memory(model.head_png())
+        .map(|img| img.to_rgba8())
+        .map_err(|e| RenderError::ImageLoad(e.to_string()))
+}
+
+/// Draw the empty state image ("No players online").
+fn empty_state_canvas(fonts: &Fonts<'_>, config: &CompositeConfig) -> RgbaImage {
     let text = "No players online";
     let scale = PxScale::from(config.font_size);
 
     // Measure text
-    let text_width = measure_text_width(font, text, scale);
+    let text_width = measure_text_width(fonts, text, scale);
     let padding = 20u32;
     let width = text_width + padding * 2;
     let height = config.font_size as u32 + padding * 2;
 
-    // Create transparent canvas
-    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 0]));
+    let mut canvas = new_canvas(width, height, config);
 
     // Draw centered text
     let x = padding;
     let y = padding;
 
-    draw_text_mut(
+    draw_text(
         &mut canvas,
-        Rgba([180, 180, 180, 255]), // Light gray
+        dim(config.text_color),
         x as i32,
         y as i32,
         scale,
-        font,
+        fonts,
         text,
     );
 
-    // Encode to PNG
-    let mut buf = Vec::new();
-    DynamicImage::ImageRgba8(canvas)
-        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
-        .map_err(|e| RenderError::ImageEncode(e.to_string()))?;
+    canvas
+}
+
+/// Create a canvas filled with the configured background (transparent if unset).
+fn new_canvas(width: u32, height: u32, config: &CompositeConfig) -> RgbaImage {
+    let fill = config.background.unwrap_or(Rgba([0, 0, 0, 0]));
+    RgbaImage::from_pixel(width, height, fill)
+}
+
+/// Dim a text color for secondary text (white becomes light gray).
+fn dim(color: Rgba<u8>) -> Rgba<u8> {
+    let Rgba([r, g, b, a]) = color;
+    let scale = |c: u8| (c as u32 * 180 / 255) as u8;
+    Rgba([scale(r), scale(g), scale(b), a])
+}
 
+/// Convert an image to grayscale, keeping its alpha channel.
+fn desaturate(image: &RgbaImage) -> RgbaImage {
+    let luma = imageops::grayscale(image);
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let l = luma.get_pixel(x, y)[0];
+        Rgba([l, l, l, image.get_pixel(x, y)[3]])
+    })
+}
+
+/// Encode an RGBA canvas in the requested output format.
+fn encode_image(canvas: RgbaImage, format: OutputFormat) -> Result<Vec<u8>, RenderError> {
+    let mut buf = Vec::new();
+    match format {
+        OutputFormat::Png => DynamicImage::ImageRgba8(canvas)
+            .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+            .map_err(|e| RenderError::ImageEncode(e.to_string()))?,
+        OutputFormat::WebP => DynamicImage::ImageRgba8(canvas)
+            .write_to(&mut Cursor::new(&mut buf), ImageFormat::WebP)
+            .map_err(|e| RenderError::ImageEncode(e.to_string()))?,
+        OutputFormat::Jpeg {
+            quality,
+            background,
+        } => {
+            let flattened = flatten_onto(&canvas, background);
+            JpegEncoder::new_with_quality(&mut buf, quality)
+                .encode_image(&flattened)
+                .map_err(|e| RenderError::ImageEncode(e.to_string()))?;
+        }
+    }
     Ok(buf)
 }
 
+/// Alpha-blend an RGBA image onto a solid background, dropping the alpha channel.
+fn flatten_onto(image: &RgbaImage, background: Rgb<u8>) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *image.get_pixel(x, y);
+        let alpha = a as u32;
+        let blend = |fg: u8, bg: u8| ((fg as u32 * alpha + bg as u32 * (255 - alpha)) / 255) as u8;
+        Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    })
+}
+
 /// Calculate font size for a username, scaling down for long names.
-fn calculate_font_size(name: &str, config: &CompositeConfig) -> f32 {
-    // Approximate: each character is about 0.6 * font_size wide for Inter
-    let char_width_ratio = 0.6;
-    let max_text_width = config.head_size as f32;
-    let estimated_width = name.len() as f32 * config.font_size * char_width_ratio;
-
-    if estimated_width <= max_text_width {
-        config.font_size
-    } else {
-        // Scale down proportionally
-        let scale_factor = max_text_width / estimated_width;
-        (config.font_size * scale_factor).max(config.min_font_size)
+///
+/// Binary-searches for the largest size between `min_font_size` and
+/// `font_size` whose measured width fits within `head_size`.
+fn calculate_font_size(fonts: &Fonts<'_>, name: &str, config: &CompositeConfig) -> f32 {
+    let max_text_width = config.head_size;
+    let fits = |size: f32| measure_text_width(fonts, name, PxScale::from(size)) <= max_text_width;
+
+    if fits(config.font_size) {
+        return config.font_size;
+    }
+
+    // Invariant: `low` is the best known size (or the floor), `high` doesn't fit
+    let mut low = config.min_font_size;
+    let mut high = config.font_size;
+    while high - low > 0.25 {
+        let mid = (low + high) / 2.0;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// Cut `name` down to `max_chars` characters, the last being "…", if it's longer.
+///
+/// Characters are counted as they're seen, so combining marks, variation
+/// selectors and zero-width-joined sequences stay with the character they
+/// modify and are never split from it.
+fn truncate_name(name: &str, max_chars: usize) -> Cow<'_, str> {
+    let mut joined = false;
+    let starts: Vec<usize> = name
+        .char_indices()
+        .filter(|&(i, c)| {
+            let starts_char = i == 0 || !(joined || extends_previous_char(c));
+            joined = c == '\u{200D}';
+            starts_char
+        })
+        .map(|(i, _)| i)
+        .collect();
+    if max_chars == 0 || starts.len() <= max_chars {
+        return Cow::Borrowed(name);
+    }
+    Cow::Owned(format!("{}…", &name[..starts[max_chars - 1]]))
+}
+
+/// Whether `c` modifies the character before it rather than standing alone.
+fn extends_previous_char(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}' // combining diacritical marks
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{FE00}'..='\u{FE0F}' // variation selectors
+        | '\u{200D}' // zero-width joiner
+        | '\u{1F3FB}'..='\u{1F3FF}' // emoji skin tones
+    )
+}
+
+/// Columns per row and the width of each column for the given names.
+///
+/// Columns are normally head-wide and `max_per_row` fit in a row. When
+/// `wide_name_width` is set and the average name (at the size it will be
+/// drawn) is wider than that, columns widen to the average name and as many
+/// as fit in the usual row width are used, always at least one.
+fn effective_columns<'a>(
+    fonts: &Fonts<'_>,
+    names: impl ExactSizeIterator<Item = &'a str>,
+    config: &CompositeConfig,
+) -> (usize, u32) {
+    let fixed = (config.max_per_row, config.head_size);
+    let Some(threshold) = config.wide_name_width else {
+        return fixed;
+    };
+    let count = names.len() as u32;
+    if count == 0 {
+        return fixed;
+    }
+
+    let total: u32 = names
+        .map(|name| {
+            let scale = PxScale::from(calculate_font_size(fonts, name, config));
+            measure_text_width(fonts, name, scale)
+        })
+        .sum();
+    let average = total.div_ceil(count);
+    if average <= threshold {
+        return fixed;
     }
+
+    // Same width as a full row of heads, so the canvas doesn't change size
+    let max_width =
+        (config.head_size + config.h_spacing) * config.max_per_row as u32 - config.h_spacing;
+    let column_width = average.clamp(config.head_size, max_width);
+    let per_row = ((max_width + config.h_spacing) / (column_width + config.h_spacing)) as usize;
+    (per_row.clamp(1, config.max_per_row), column_width)
 }
 
 /// Measure the width of text in pixels.
-fn measure_text_width(font: &FontRef<'_>, text: &str, scale: PxScale) -> u32 {
+/// Sums horizontal advances plus pair kerning, matching how the text is drawn,
+/// with each run measured in the font it's drawn with.
+fn measure_text_width(fonts: &Fonts<'_>, text: &str, scale: PxScale) -> u32 {
+    let width: f32 = fonts
+        .runs(text)
+        .into_iter()
+        .map(|(font, run)| run_width(font, run, scale))
+        .sum();
+    width.ceil() as u32
+}
+
+/// Width in pixels of text drawn entirely in one font.
+fn run_width(font: &FontRef<'_>, text: &str, scale: PxScale) -> f32 {
+    let scaled = font.as_scaled(scale);
     let mut width = 0.0f32;
-    let scale_factor = scale.x / font.height_unscaled();
+    let mut previous = None;
     for c in text.chars() {
-        let glyph_id = font.glyph_id(c);
-        let advance = font.h_advance_unscaled(glyph_id);
-        width += advance * scale_factor;
+        let glyph_id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, glyph_id);
+        }
+        width += scaled.h_advance(glyph_id);
+        previous = Some(glyph_id);
+    }
+    width
+}
+
+/// Draw text with its top-left corner at (x, y), one font run at a time.
+/// Runs in a fallback font are shifted so they share Inter's baseline.
+fn draw_text(
+    canvas: &mut RgbaImage,
+    color: Rgba<u8>,
+    x: i32,
+    y: i32,
+    scale: PxScale,
+    fonts: &Fonts<'_>,
+    text: &str,
+) {
+    let ascent = fonts.primary().as_scaled(scale).ascent();
+    let mut offset = 0.0f32;
+    for (font, run) in fonts.runs(text) {
+        let shift = ascent - font.as_scaled(scale).ascent();
+        draw_text_mut(
+            canvas,
+            color,
+            x + offset.round() as i32,
+            y + shift.round() as i32,
+            scale,
+            font,
+            run,
+        );
+        offset += run_width(font, run, scale);
     }
-    width as u32
 }
 
 /// Errors that can occur during rendering.
@@ -291,7 +1042,7 @@ impl std::fmt::Display for RenderError {
             RenderError::InvalidSkinDimensions { width, height } => {
                 write!(
                     f,
-                    "invalid skin dimensions: {}x{} (expected 64x64 or 64x32)",
+                    "invalid skin dimensions: {}x{} (expected 64x64, 64x32, 128x128 or 128x64)",
                     width, height
                 )
             }
@@ -339,6 +1090,7 @@ mod tests {
         let players = vec![PlayerEntry {
             name: "Steve".to_string(),
             head_data: None, // Uses Steve fallback
+            ..Default::default()
         }];
         let result = render_composite(&players, &config);
         assert!(result.is_ok());
@@ -351,47 +1103,628 @@ mod tests {
             PlayerEntry {
                 name: "Steve".to_string(),
                 head_data: None,
+                ..Default::default()
             },
             PlayerEntry {
                 name: "Alex".to_string(),
                 head_data: None,
+                ..Default::default()
             },
             PlayerEntry {
                 name: "Notch".to_string(),
                 head_data: None,
+                ..Default::default()
             },
             PlayerEntry {
                 name: "jeb_".to_string(),
                 head_data: None,
+                ..Default::default()
             },
             PlayerEntry {
                 name: "Dinnerbone".to_string(),
                 head_data: None,
+                ..Default::default()
             },
             PlayerEntry {
                 name: "Grumm".to_string(),
                 head_data: None,
+                ..Default::default()
             },
             PlayerEntry {
                 name: "LongUsernamePerson".to_string(),
                 head_data: None,
+                ..Default::default()
             },
         ];
         let result = render_composite(&players, &config);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_composite_output_formats_decode() {
+        let config = CompositeConfig::default();
+        let players = vec![PlayerEntry {
+            name: "Steve".to_string(),
+            head_data: None,
+            ..Default::default()
+        }];
+        let formats = [
+            (OutputFormat::Png, ImageFormat::Png),
+            (
+                OutputFormat::Jpeg {
+                    quality: 85,
+                    background: Rgb([49, 51, 56]),
+                },
+                ImageFormat::Jpeg,
+            ),
+            (OutputFormat::WebP, ImageFormat::WebP),
+        ];
+
+        for (format, expected) in formats {
+            let bytes = render_composite_with_format(&players, &config, format).unwrap();
+            assert_eq!(image::guess_format(&bytes).unwrap(), expected);
+            assert!(image::load_from_memory(&bytes).is_ok());
+
+            let empty = render_composite_with_format(&[], &config, format).unwrap();
+            assert!(image::load_from_memory(&empty).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_render_head_output_formats_decode() {
+        let jpeg = OutputFormat::Jpeg {
+            quality: 90,
+            background: Rgb([0, 0, 0]),
+        };
+        for format in [OutputFormat::Png, jpeg, OutputFormat::WebP] {
+            let bytes = render_head_with_format(DEFAULT_STEVE_HEAD, format).unwrap();
+            let decoded = image::load_from_memory(&bytes).unwrap();
+            assert_eq!(decoded.dimensions(), (64, 64));
+        }
+    }
+
+    #[test]
+    fn test_composite_background_and_text_color() {
+        let config = CompositeConfig {
+            background: Some(Rgba([255, 255, 255, 255])),
+            text_color: Rgba([20, 20, 20, 255]),
+            ..CompositeConfig::default()
+        };
+        let players = vec![PlayerEntry {
+            name: "Steve".to_string(),
+            head_data: None,
+            ..Default::default()
+        }];
+
+        let bytes = render_composite(&players, &config).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        // Corner pixel sits outside any head cell, so it shows the background
+        assert_eq!(*image.get_pixel(0, 0), Rgba([255, 255, 255, 255]));
+
+        let empty = render_composite(&[], &config).unwrap();
+        let image = image::load_from_memory(&empty).unwrap().to_rgba8();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_grayscale_entry_differs_from_color() {
+        let config = CompositeConfig::default();
+        let entry = |grayscale| {
+            vec![PlayerEntry {
+                name: "Steve".to_string(),
+                head_data: None,
+                grayscale,
+                ..Default::default()
+            }]
+        };
+
+        let color = render_composite(&entry(false), &config).unwrap();
+        let gray = render_composite(&entry(true), &config).unwrap();
+        assert_ne!(color, gray);
+    }
+
+    #[test]
+    fn test_composite_uses_default_model_hint() {
+        let config = CompositeConfig::default();
+        let render_with = |default_model| {
+            let players = [PlayerEntry {
+                name: "Player".to_string(),
+                default_model,
+                ..Default::default()
+            }];
+            let bytes = render_composite(&players, &config).unwrap();
+            image::load_from_memory(&bytes).unwrap().to_rgba8()
+        };
+        let centre_of = |model| {
+            let head = load_default_head(model).unwrap();
+            *head.get_pixel(head.width() / 2, head.height() / 2)
+        };
+
+        let steve = render_with(DefaultModel::Steve);
+        let alex = render_with(DefaultModel::Alex);
+        // A single head is centred horizontally at the top of the canvas
+        let (x, y) = (steve.width() / 2, config.head_size / 2);
+        assert_eq!(*steve.get_pixel(x, y), centre_of(DefaultModel::Steve));
+        assert_eq!(*alex.get_pixel(x, y), centre_of(DefaultModel::Alex));
+        assert_ne!(steve.get_pixel(x, y), alex.get_pixel(x, y));
+
+        // No hint means Steve
+        assert_eq!(PlayerEntry::default().default_model, DefaultModel::Steve);
+    }
+
+    #[test]
+    fn test_default_model_from_uuid() {
+        assert_eq!(
+            DefaultModel::from_uuid("00000000-0000-0000-0000-000000000000"),
+            Some(DefaultModel::Steve)
+        );
+        assert_eq!(
+            DefaultModel::from_uuid("00000000000000000000000000000001"),
+            Some(DefaultModel::Alex)
+        );
+        // The upper halves are folded in too
+        assert_eq!(
+            DefaultModel::from_uuid("00000001-0000-0000-0000-000000000001"),
+            Some(DefaultModel::Steve)
+        );
+        assert_eq!(DefaultModel::from_uuid("not-a-uuid"), None);
+    }
+
+    #[test]
+    fn test_composite_caps_players_with_overflow_tile() {
+        let config = CompositeConfig {
+            max_players_rendered: Some(25),
+            ..CompositeConfig::default()
+        };
+        let players: Vec<PlayerEntry> = (0..30)
+            .map(|i| PlayerEntry {
+                name: format!("player{:02}", i),
+                ..Default::default()
+            })
+            .collect();
+
+        let bytes = render_composite(&players, &config).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        // 25 heads fill five rows; the tile sits alone, centered, on a sixth
+        let cell_height = config.head_size + config.text_height + config.v_spacing;
+        assert_eq!(image.height(), cell_height * 6);
+        let tile_x = (image.width() - config.head_size) / 2;
+        let tile_y = cell_height * 5;
+        assert_eq!(
+            *image.get_pixel(tile_x + 1, tile_y + 1),
+            OVERFLOW_TILE_COLOR
+        );
+
+        // Without a cap, everyone is drawn and there is no tile
+        let uncapped = render_composite(&players, &CompositeConfig::default()).unwrap();
+        let uncapped = image::load_from_memory(&uncapped).unwrap().to_rgba8();
+        assert_ne!(
+            *uncapped.get_pixel(tile_x + 1, tile_y + 1),
+            OVERFLOW_TILE_COLOR
+        );
+    }
+
+    #[test]
+    fn test_composite_sort_order() {
+        let head = |color| encode_png(&RgbaImage::from_pixel(128, 128, color));
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let players = vec![
+            PlayerEntry {
+                name: "bob".to_string(),
+                head_data: Some(head(red)),
+                joined_at: Some(200),
+                ..Default::default()
+            },
+            PlayerEntry {
+                name: "Alice".to_string(),
+                head_data: Some(head(blue)),
+                joined_at: Some(300),
+                ..Default::default()
+            },
+        ];
+
+        // Color of the first (leftmost) cell
+        let first_cell = |sort| {
+            let config = CompositeConfig {
+                max_per_row: 2,
+                sort,
+                ..CompositeConfig::default()
+            };
+            let image = render_composite(&players, &config).unwrap();
+            *image::load_from_memory(&image)
+                .unwrap()
+                .to_rgba8()
+                .get_pixel(64, 64)
+        };
+
+        assert_eq!(first_cell(SortOrder::Alphabetical), blue);
+        assert_eq!(first_cell(SortOrder::JoinedAt), red);
+        assert_eq!(first_cell(SortOrder::None), red);
+        assert_eq!(players[0].name, "bob");
+    }
+
+    #[test]
+    fn test_composite_long_names_use_fewer_columns() {
+        let players: Vec<_> = (0..5)
+            .map(|i| PlayerEntry {
+                name: format!("AVeryLongBedrockGamertag{}", i),
+                head_data: None,
+                ..Default::default()
+            })
+            .collect();
+        let adaptive = CompositeConfig {
+            wide_name_width: Some(128),
+            ..CompositeConfig::default()
+        };
+
+        let fixed = render_composite(&players, &CompositeConfig::default()).unwrap();
+        let wide = render_composite(&players, &adaptive).unwrap();
+        let fixed = image::load_from_memory(&fixed).unwrap();
+        let wide = image::load_from_memory(&wide).unwrap();
+
+        // Same canvas width, but the five players no longer fit on one row
+        assert_eq!(wide.width(), fixed.width());
+        assert!(wide.height() > fixed.height());
+
+        // Short names keep the full row
+        let short: Vec<_> = (0..5)
+            .map(|i| PlayerEntry {
+                name: format!("Steve{}", i),
+                head_data: None,
+                ..Default::default()
+            })
+            .collect();
+        let short_fixed = render_composite(&short, &CompositeConfig::default()).unwrap();
+        let short_wide = render_composite(&short, &adaptive).unwrap();
+        assert_eq!(
+            image::load_from_memory(&short_wide).unwrap().height(),
+            image::load_from_memory(&short_fixed).unwrap().height()
+        );
+    }
+
+    #[test]
+    fn test_truncate_name() {
+        let long = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcd";
+        assert_eq!(truncate_name(long, 16), "ABCDEFGHIJKLMNO…");
+        assert_eq!(truncate_name(long, 30), long);
+        assert_eq!(truncate_name("Steve", 16), "Steve");
+
+        // Multi-byte characters and combining marks are never split
+        assert_eq!(truncate_name("ÅsaÅsaÅsa", 4), "Åsa…");
+        assert_eq!(truncate_name("Zoe\u{0301}Zoe\u{0301}", 5), "Zoe\u{0301}Z…");
+        assert_eq!(
+            truncate_name("👩\u{200D}💻👩\u{200D}💻👩\u{200D}💻", 2),
+            "👩\u{200D}💻…"
+        );
+    }
+
+    #[test]
+    fn test_composite_truncates_long_names() {
+        let players: Vec<_> = (0..5)
+            .map(|i| PlayerEntry {
+                name: format!("{}{}", "x".repeat(29), i),
+                head_data: None,
+                ..Default::default()
+            })
+            .collect();
+        let adaptive = CompositeConfig {
+            wide_name_width: Some(128),
+            ..CompositeConfig::default()
+        };
+        let truncated = CompositeConfig {
+            max_display_chars: Some(6),
+            ..adaptive.clone()
+        };
+
+        let full =
+            image::load_from_memory(&render_composite(&players, &adaptive).unwrap()).unwrap();
+        let short =
+            image::load_from_memory(&render_composite(&players, &truncated).unwrap()).unwrap();
+
+        // Cut down to "xxxxx…", the names fit under their heads on one row
+        assert!(full.height() > short.height());
+        assert_eq!(
+            short.height(),
+            image::load_from_memory(&render_composite(&players[..1], &adaptive).unwrap())
+                .unwrap()
+                .height()
+        );
+    }
+
+    #[test]
+    fn test_composite_animated_gif() {
+        use image::AnimationDecoder;
+        use image::codecs::gif::GifDecoder;
+
+        let frame_count = |gif: &[u8]| {
+            GifDecoder::new(Cursor::new(gif))
+                .unwrap()
+                .into_frames()
+                .count()
+        };
+        let players: Vec<_> = ["Steve", "Alex", "Notch"]
+            .iter()
+            .map(|name| PlayerEntry {
+                name: name.to_string(),
+                head_data: None,
+                ..Default::default()
+            })
+            .collect();
+        let config = CompositeConfig {
+            animation: AnimationConfig {
+                frames: 4,
+                frame_delay_ms: 50,
+            },
+            ..CompositeConfig::default()
+        };
+
+        let gif = render_composite_animated(&players, &config).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(frame_count(&gif), 4);
+
+        // Nobody online is a single still frame
+        let empty = render_composite_animated(&[], &config).unwrap();
+        assert!(empty.starts_with(b"GIF89a"));
+        assert_eq!(frame_count(&empty), 1);
+    }
+
+    #[test]
+    fn test_composite_header_adds_band() {
+        let players = vec![PlayerEntry {
+            name: "Steve".to_string(),
+            head_data: None,
+            ..Default::default()
+        }];
+        let with_header = CompositeConfig {
+            header: Some("Survival - 1 online".to_string()),
+            ..CompositeConfig::default()
+        };
+
+        let plain = render_composite(&players, &CompositeConfig::default()).unwrap();
+        let titled = render_composite(&players, &with_header).unwrap();
+        let plain = image::load_from_memory(&plain).unwrap();
+        let titled = image::load_from_memory(&titled).unwrap();
+        assert_eq!(titled.width(), plain.width());
+        assert!(titled.height() > plain.height());
+    }
+
+    #[test]
+    fn test_default_background_is_transparent() {
+        let config = CompositeConfig::default();
+        let bytes = render_composite(&[], &config).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+    }
+
+    /// Build a synthetic skin PNG of the given height, filled with one color.
+    fn synthetic_skin(height: u32, fill: Rgba<u8>) -> RgbaImage {
+        RgbaImage::from_pixel(64, height, fill)
+    }
+
+    fn encode_png(image: &RgbaImage) -> Vec<u8> {
+        let mut buf = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_render_body_modern_skin() {
+        let mut skin = synthetic_skin(64, Rgba([0, 0, 255, 255]));
+        // Mark the left arm (only present in 64x64 skins) red and clear
+        // its sleeve overlay so the base layer shows through
+        for y in 52..64 {
+            for x in 36..40 {
+                skin.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+            for x in 52..56 {
+                skin.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+            }
+        }
+
+        let body = render_body(&encode_png(&skin)).unwrap();
+        let body = image::load_from_memory(&body).unwrap().to_rgba8();
+        assert_eq!(body.dimensions(), (64, 128));
+        // Left arm occupies figure x 12..16, y 8..20 (scaled 4x)
+        assert_eq!(*body.get_pixel(50, 40), Rgba([255, 0, 0, 255]));
+        // Right arm keeps the base color
+        assert_eq!(*body.get_pixel(2, 40), Rgba([0, 0, 255, 255]));
+        // Gaps beside the head are transparent
+        assert_eq!(body.get_pixel(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_render_body_legacy_skin_mirrors_limbs() {
+        let mut skin = synthetic_skin(32, Rgba([0, 0, 255, 255]));
+        // Mark the outer column of the right arm front red
+        for y in 20..32 {
+            skin.put_pixel(44, y, Rgba([255, 0, 0, 255]));
+        }
+
+        let body = render_body(&encode_png(&skin)).unwrap();
+        let body = image::load_from_memory(&body).unwrap().to_rgba8();
+        assert_eq!(body.dimensions(), (64, 128));
+        // Right arm column 0 is red; the mirrored left arm has it at column 15
+        assert_eq!(*body.get_pixel(0, 40), Rgba([255, 0, 0, 255]));
+        assert_eq!(*body.get_pixel(63, 40), Rgba([255, 0, 0, 255]));
+        assert_eq!(*body.get_pixel(48, 40), Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_render_head_hd_skin() {
+        let mut skin = RgbaImage::from_pixel(128, 128, Rgba([0, 0, 255, 255]));
+        // Face region at (16,16) 16x16 in HD coordinates, helmet left transparent
+        for y in 16..32 {
+            for x in 16..32 {
+                skin.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+            for x in 80..96 {
+                skin.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+            }
+        }
+
+        let head = render_head(&encode_png(&skin)).unwrap();
+        let head = image::load_from_memory(&head).unwrap().to_rgba8();
+        assert_eq!(head.dimensions(), (64, 64));
+        assert_eq!(*head.get_pixel(32, 32), Rgba([255, 0, 0, 255]));
+
+        let legacy_hd = RgbaImage::from_pixel(128, 64, Rgba([0, 0, 255, 255]));
+        assert!(render_head(&encode_png(&legacy_hd)).is_ok());
+    }
+
+    #[test]
+    fn test_render_head_semi_transparent_hat_keeps_face() {
+        let face = Rgba([200, 100, 50, 128]);
+        let mut skin = synthetic_skin(64, Rgba([0, 0, 0, 0]));
+        for y in 8..16 {
+            for x in 8..16 {
+                skin.put_pixel(x, y, face);
+            }
+            // Hat covers only the right half of the face, semi-transparently
+            for x in 44..48 {
+                skin.put_pixel(x, y, Rgba([255, 255, 255, 128]));
+            }
+        }
+
+        let head = render_head(&encode_png(&skin)).unwrap();
+        let head = image::load_from_memory(&head).unwrap().to_rgba8();
+        // Where the hat is empty the face is unchanged
+        assert_eq!(*head.get_pixel(8, 32), face);
+        // Where the hat is present it lightens the face and adds opacity
+        let covered = head.get_pixel(56, 32);
+        assert!(covered[0] > face[0] && covered[3] > face[3]);
+
+        // A fully transparent hat leaves the whole face untouched
+        for y in 8..16 {
+            for x in 40..48 {
+                skin.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+            }
+        }
+        let head = render_head(&encode_png(&skin)).unwrap();
+        let head = image::load_from_memory(&head).unwrap().to_rgba8();
+        assert!(head.pixels().all(|p| *p == face));
+    }
+
+    #[test]
+    fn test_render_body_rejects_bad_dimensions() {
+        let skin = RgbaImage::from_pixel(32, 32, Rgba([0, 0, 0, 255]));
+        assert!(matches!(
+            render_body(&encode_png(&skin)),
+            Err(RenderError::InvalidSkinDimensions { .. })
+        ));
+    }
+
+    #[test]
+    fn test_composite_body_mode_is_taller() {
+        let skin = synthetic_skin(64, Rgba([0, 0, 255, 255]));
+        let body = render_body(&encode_png(&skin)).unwrap();
+        let players = vec![PlayerEntry {
+            name: "Steve".to_string(),
+            head_data: Some(body),
+            ..Default::default()
+        }];
+
+        let head_config = CompositeConfig::default();
+        let body_config = CompositeConfig {
+            render_mode: RenderMode::Body,
+            ..CompositeConfig::default()
+        };
+
+        let heads = render_composite(&players, &head_config).unwrap();
+        let bodies = render_composite(&players, &body_config).unwrap();
+        let heads = image::load_from_memory(&heads).unwrap();
+        let bodies = image::load_from_memory(&bodies).unwrap();
+        assert_eq!(
+            bodies.height() - heads.height(),
+            body_config.head_size,
+            "body cells should be one head_size taller"
+        );
+    }
+
+    #[test]
+    fn test_render_composite_with_cjk_name() {
+        // Inter has no CJK glyphs, so with no fallback configured they're
+        // drawn as missing-glyph boxes rather than left blank
+        let config = CompositeConfig::default();
+        let players = vec![PlayerEntry {
+            name: "你好".to_string(),
+            ..Default::default()
+        }];
+        let image = image::load_from_memory(&render_composite(&players, &config).unwrap())
+            .unwrap()
+            .to_rgba8();
+
+        let text_top = config.head_size;
+        let drawn = (text_top..image.height())
+            .flat_map(|y| (0..image.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| image.get_pixel(x, y)[3] > 0)
+            .count();
+        assert!(drawn > 0, "name region is empty");
+    }
+
+    #[test]
+    fn test_fonts_fall_back_per_glyph() {
+        let fallback = FontData::from_bytes(INTER_FONT.to_vec()).unwrap();
+        let fallbacks = [fallback];
+        let fonts = Fonts::load(&fallbacks).unwrap();
+
+        // Inter has Latin and Cyrillic; nothing has CJK, which falls back to Inter
+        assert_eq!(fonts.font_index('S'), 0);
+        assert_eq!(fonts.font_index('Ж'), 0);
+        assert_eq!(fonts.font_index('你'), 0);
+        assert_eq!(fonts.runs("Steve你").len(), 1);
+
+        // Missing glyphs still take up room, so CJK names are centered properly
+        let scale = PxScale::from(32.0);
+        assert!(measure_text_width(&fonts, "你好", scale) > 0);
+    }
+
+    #[test]
+    fn test_font_data_rejects_non_fonts() {
+        assert!(matches!(
+            FontData::from_bytes(b"not a font".to_vec()),
+            Err(RenderError::FontLoad(_))
+        ));
+        assert!(matches!(
+            FontData::load("/nonexistent/font.ttf"),
+            Err(RenderError::FontLoad(_))
+        ));
+    }
+
     #[test]
     fn test_font_size_scaling() {
         let config = CompositeConfig::default();
 
         // Short name should use full font size
-        let size = calculate_font_size("Steve", &config);
+        let fonts = Fonts::load(&[]).unwrap();
+
+        let size = calculate_font_size(&fonts, "Steve", &config);
         assert!((size - config.font_size).abs() < 0.01);
 
         // Long name should scale down
-        let size = calculate_font_size("VeryLongUsername123", &config);
+        let size = calculate_font_size(&fonts, "VeryLongUsername123", &config);
         assert!(size < config.font_size);
         assert!(size >= config.min_font_size);
     }
+
+    #[test]
+    fn test_font_size_uses_measured_width() {
+        let config = CompositeConfig {
+            min_font_size: 4.0,
+            ..CompositeConfig::default()
+        };
+        let fonts = Fonts::load(&[]).unwrap();
+
+        // Same character count, very different glyph widths
+        let wide = calculate_font_size(&fonts, "WWWWWWWWWW", &config);
+        let narrow = calculate_font_size(&fonts, "iiiiiiiiii", &config);
+        assert!(wide < narrow, "wide={} narrow={}", wide, narrow);
+
+        // The chosen size actually fits within the head width
+        let width = measure_text_width(&fonts, "WWWWWWWWWW", PxScale::from(wide));
+        assert!(width <= config.head_size);
+    }
 }
@@ -1,24 +1,37 @@
 use crate::AppState;
-use crate::error::AppError;
+use crate::connect_guard::ClientIp;
+use crate::error::{AppError, AppJson, ErrorResponse};
 use crate::helpers::now;
-use crate::render::{self, CompositeConfig, DEFAULT_STEVE_HEAD, PlayerEntry};
+use crate::idempotency::{IdempotencyCache, Reservation};
+use crate::live::LiveEvent;
+use crate::metrics::Metrics;
+use crate::mojang::FetchError;
+use crate::render::{self, CompositeConfig, DEFAULT_STEVE_HEAD, DefaultModel, PlayerEntry};
 use crate::validation;
+use crate::webhooks::{Delivery, WebhookEvent};
 
 use axum::{
     Json,
     body::Body,
-    extract::{Path, State},
-    http::{StatusCode, header},
-    response::{IntoResponse, Response},
+    extract::{Path, Query, State, rejection::QueryRejection},
+    http::{HeaderMap, StatusCode, header},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
 use axum_extra::TypedHeader;
 use axum_macros::debug_handler;
 use base64::Engine;
+use futures_util::stream::{self, Stream};
 use headers::Authorization;
 use headers::authorization::Bearer;
-use oxeye_db::PlayerName;
+use oxeye_db::{PlayerEvent, PlayerName, Store};
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::net::IpAddr;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 
 #[derive(Deserialize)]
 pub(crate) struct ConnRequest {
@@ -28,6 +41,66 @@ pub(crate) struct ConnRequest {
 #[derive(Serialize)]
 pub(crate) struct ConnResponse {
     api_key: String,
+    /// Name the server was linked under, so the mod can confirm the match
+    server_name: String,
+}
+
+/// Response from GET /connect/{code} - an unclaimed code and its time left.
+#[derive(Serialize)]
+pub(crate) struct ConnStatusResponse {
+    valid: bool,
+    expires_in: i64,
+}
+
+/// Response from /rotate - the server's replacement API key.
+#[derive(Serialize)]
+pub(crate) struct RotateResponse {
+    api_key: String,
+}
+
+/// Response from /whoami - the server an API key is linked as.
+#[derive(Serialize)]
+pub(crate) struct WhoamiResponse {
+    server: String,
+    guild_id: u64,
+}
+
+/// JSON response from /status: the caller's server, how many players are
+/// online, and which of them joined at or after `since`.
+#[derive(Serialize)]
+pub(crate) struct StatusResponse {
+    server: String,
+    online: u64,
+    /// Earliest arrival first
+    players: Vec<PlayerName>,
+}
+
+/// Query parameters for GET /status.
+#[derive(Deserialize)]
+pub(crate) struct StatusQuery {
+    /// Only list players who joined at or after this unix timestamp
+    #[serde(default)]
+    since: i64,
+}
+
+/// Response from /reset - how many online players were cleared.
+#[derive(Serialize)]
+pub(crate) struct ResetResponse {
+    cleared: usize,
+}
+
+/// One entry of /status/detailed - an online player and how long they've been on.
+#[derive(Serialize)]
+pub(crate) struct DetailedPlayerResponse {
+    player: PlayerName,
+    joined_at: i64,
+    online_for_secs: i64,
+}
+
+/// Response from /refresh-skins - how many cached images were dropped.
+#[derive(Serialize)]
+pub(crate) struct RefreshSkinsResponse {
+    cleared: u64,
 }
 
 /// Join request - player name with optional skin texture hash.
@@ -36,8 +109,11 @@ pub(crate) struct ConnResponse {
 pub(crate) struct JoinRequest {
     player: PlayerName,
     /// SHA256 hash of the GameProfile texture value (optional for backward compat)
-    #[serde(default)]
+    #[serde(default, alias = "texture")]
     texture_hash: Option<String>,
+    /// Player's UUID, with or without hyphens (optional)
+    #[serde(default)]
+    uuid: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -45,19 +121,60 @@ pub(crate) struct LeaveRequest {
     player: PlayerName,
 }
 
+/// Events request - buffered joins and leaves applied in one batch.
+///
+/// Either `joins` and `leaves` (joins are applied first), or an ordered
+/// `events` list for batches where a player may leave and rejoin. Not both.
+#[derive(Deserialize)]
+pub(crate) struct EventsRequest {
+    #[serde(default)]
+    joins: Vec<PlayerName>,
+    #[serde(default)]
+    leaves: Vec<PlayerName>,
+    #[serde(default)]
+    events: Option<Vec<EventEntry>>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct EventEntry {
+    player: PlayerName,
+    event: EventKind,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EventKind {
+    Join,
+    Leave,
+}
+
+impl From<&EventEntry> for PlayerEvent {
+    fn from(entry: &EventEntry) -> Self {
+        match entry.event {
+            EventKind::Join => PlayerEvent::Join(entry.player),
+            EventKind::Leave => PlayerEvent::Leave(entry.player),
+        }
+    }
+}
+
 /// Sync request - replace the full player list for a server.
 /// Each entry may include an optional texture_hash so the backend can keep
 /// `player_skins` mappings up to date and request uploads for unknown skins.
 #[derive(Deserialize)]
 pub(crate) struct SyncRequest {
     players: Vec<SyncPlayer>,
+    /// Whether an empty list may clear all players (defaults to the server config)
+    #[serde(default)]
+    allow_empty: Option<bool>,
 }
 
 #[derive(Deserialize)]
 pub(crate) struct SyncPlayer {
     player: PlayerName,
-    #[serde(default)]
+    #[serde(default, alias = "texture")]
     texture_hash: Option<String>,
+    #[serde(default)]
+    uuid: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -73,6 +190,139 @@ pub(crate) struct SyncResponse {
     missing: Vec<SyncMissingSkin>,
 }
 
+/// A server and its online players, for guild dashboards.
+#[derive(Serialize)]
+pub(crate) struct ServerPlayersResponse {
+    name: String,
+    players: Vec<OnlinePlayerResponse>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct OnlinePlayerResponse {
+    player: PlayerName,
+    /// Unix timestamp when the player joined
+    joined_at: i64,
+    /// Hash of the player's skin texture, if the backend has it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    texture_hash: Option<String>,
+    /// Player's UUID, if the server has reported it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uuid: Option<String>,
+}
+
+impl From<oxeye_db::ServerWithPlayers> for ServerPlayersResponse {
+    fn from(server: oxeye_db::ServerWithPlayers) -> Self {
+        Self {
+            name: server.name,
+            players: server
+                .players
+                .into_iter()
+                .map(|p| OnlinePlayerResponse {
+                    player: p.player_name,
+                    joined_at: p.joined_at,
+                    texture_hash: p.texture_hash,
+                    uuid: p.uuid,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// All of a guild's servers and their online players (no API key hashes).
+#[derive(Serialize)]
+pub(crate) struct GuildExportResponse {
+    guild_id: u64,
+    servers: Vec<ServerPlayersResponse>,
+}
+
+/// Servers per page of GET /guilds/{guild_id}/servers when `limit` isn't given.
+const DEFAULT_SERVERS_PAGE_LIMIT: u32 = 25;
+
+/// Largest `limit` accepted by GET /guilds/{guild_id}/servers (larger values are clamped).
+const MAX_SERVERS_PAGE_LIMIT: u32 = 100;
+
+/// Query parameters for GET /guilds/{guild_id}/servers.
+#[derive(Deserialize)]
+pub(crate) struct ServersPageQuery {
+    #[serde(default)]
+    limit: Option<u32>,
+    #[serde(default)]
+    offset: u32,
+}
+
+/// One page of a guild's servers, ordered by name.
+#[derive(Serialize)]
+pub(crate) struct ServersPageResponse {
+    guild_id: u64,
+    /// Servers linked to the guild in total, across all pages
+    total: u64,
+    limit: u32,
+    offset: u32,
+    servers: Vec<ServerListing>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ServerListing {
+    name: String,
+    /// Unix timestamp when the server was linked (0 if unknown)
+    created_at: i64,
+}
+
+/// Aggregate stats for a guild's linked servers.
+#[derive(Serialize)]
+pub(crate) struct GuildStatsResponse {
+    guild_id: u64,
+    server_count: u32,
+    online_players: u64,
+    busiest_server: Option<BusiestServerResponse>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BusiestServerResponse {
+    name: String,
+    player_count: u32,
+}
+
+/// Linked servers a player is currently online on, across all guilds.
+#[derive(Serialize)]
+pub(crate) struct PlayerServersResponse {
+    player: PlayerName,
+    servers: Vec<PlayerServerResponse>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct PlayerServerResponse {
+    guild_id: u64,
+    name: String,
+}
+
+/// Merge request - move the source server's online players to the destination.
+#[derive(Deserialize)]
+pub(crate) struct MergeRequest {
+    source: String,
+    destination: String,
+}
+
+/// Response from /merge - the destination's player count after the merge.
+#[derive(Serialize)]
+pub(crate) struct MergeResponse {
+    merged: usize,
+}
+
+/// Transfer request - move a server (by API key hash) to another guild.
+#[derive(Deserialize)]
+pub(crate) struct TransferRequest {
+    server: String,
+    guild_id: u64,
+}
+
+/// Response from /transfer - the server's name and its new guild.
+#[derive(Serialize)]
+pub(crate) struct TransferResponse {
+    server: String,
+    guild_id: u64,
+}
+
 /// Skin upload request - sent when backend returns 202 from /join.
 #[derive(Deserialize)]
 pub(crate) struct SkinRequest {
@@ -90,58 +340,167 @@ pub(crate) struct SkinRequest {
 #[debug_handler]
 pub(crate) async fn connect(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<ConnRequest>,
+    ClientIp(client_ip): ClientIp,
+    AppJson(payload): AppJson<ConnRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     #[cfg(debug_assertions)]
     tracing::debug!(?payload.code, "connect request");
 
+    check_connect_guard(&state, client_ip)?;
+
     // Validate code format
-    validation::validate_code(&payload.code)?;
+    validation::validate_code(&payload.code, state.min_code_length)?;
 
-    let pending_link = state.db.consume_pending_link(payload.code, now()).await?;
+    let pending_link = match state.db.consume_pending_link(payload.code, now()).await {
+        Ok(link) => link,
+        Err(e) => {
+            if matches!(e, oxeye_db::DbError::PendingLinkNotFound) {
+                record_connect_failure(&state, client_ip);
+            }
+            return Err(e.into());
+        }
+    };
 
     let api_key = crate::helpers::generate_api_key();
     let api_key_hash = crate::helpers::hash_api_key(&api_key);
 
-    state
-        .db
-        .create_server(
-            api_key_hash,
-            pending_link.server_name,
-            pending_link.guild_id,
-        )
-        .await?;
+    // A relink code takes over the existing server, unless it was
+    // disconnected since the code was made
+    let relinked = if pending_link.relink {
+        match state
+            .db
+            .relink_server(
+                pending_link.guild_id,
+                pending_link.server_name.clone(),
+                api_key_hash.clone(),
+            )
+            .await
+        {
+            Ok(server) => Some(server),
+            Err(oxeye_db::DbError::ServerNotFound) => None,
+            Err(e) => return Err(e.into()),
+        }
+    } else {
+        None
+    };
+    let server = match relinked {
+        Some(server) => server,
+        None => {
+            state
+                .db
+                .create_server(
+                    api_key_hash,
+                    pending_link.server_name,
+                    pending_link.guild_id,
+                    now(),
+                )
+                .await?
+        }
+    };
+
+    Metrics::inc(&state.metrics.connects);
+    Ok((
+        StatusCode::CREATED,
+        Json(ConnResponse {
+            api_key,
+            server_name: server.name,
+        }),
+    ))
+}
 
-    Ok((StatusCode::CREATED, Json(ConnResponse { api_key })))
+/// Check whether a connection code is still waiting to be claimed, without claiming it.
+///
+/// Unknown and expired codes count as failed attempts, like on POST /connect,
+/// so polling can't be used to guess codes.
+#[debug_handler]
+pub(crate) async fn connect_status(
+    State(state): State<Arc<AppState>>,
+    ClientIp(client_ip): ClientIp,
+    Path(code): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    check_connect_guard(&state, client_ip)?;
+    validation::validate_code(&code, state.min_code_length)?;
+
+    let current_time = now();
+    match state.db.get_pending_link(code).await? {
+        Some(link) if !link.is_expired(current_time) => Ok(Json(ConnStatusResponse {
+            valid: true,
+            expires_in: link.expires_in(current_time),
+        })),
+        _ => {
+            record_connect_failure(&state, client_ip);
+            Err(AppError::DatabaseError(
+                oxeye_db::DbError::PendingLinkNotFound,
+            ))
+        }
+    }
 }
 
 #[debug_handler]
 pub(crate) async fn join(
     State(state): State<Arc<AppState>>,
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(payload): Json<JoinRequest>,
+    headers: HeaderMap,
+    AppJson(payload): AppJson<JoinRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     #[cfg(debug_assertions)]
     tracing::debug!(player = %payload.player, texture_hash = ?payload.texture_hash, "join request");
 
     // Validate player name
-    validation::validate_player_name(payload.player.as_str())?;
+    validation::validate_player_name(payload.player.as_str(), state.name_policy)?;
 
     // Validate texture hash if provided
     if let Some(ref hash) = payload.texture_hash {
         validation::validate_texture_hash(hash)?;
     }
+    if let Some(ref uuid) = payload.uuid {
+        validation::validate_uuid(uuid)?;
+    }
 
-    let api_key = auth.token().to_string();
-    let api_key_hash = crate::helpers::hash_api_key(&api_key);
+    let api_key_hash = server_key_hash(&auth)?;
     let api_key_hash_clone = api_key_hash.clone();
 
+    // A retried request gets the original result without joining again
+    let claim = match IdempotencyCache::key(&headers, &api_key_hash) {
+        Some(key) => match state.idempotency.reserve(key).await {
+            Reservation::Replay(status) => return Ok(status),
+            Reservation::New(claim) => Some(claim),
+        },
+        None => None,
+    };
+
     // Record the player join
     state
         .db
         .player_join(api_key_hash.clone(), payload.player, now())
         .await?;
 
+    // Only store the UUID once the join proved the key belongs to a server. The
+    // join is already applied, so a failure here is logged rather than returned.
+    let uuid = payload.uuid.as_deref().map(validation::normalize_uuid);
+    if let Some(ref uuid) = uuid
+        && let Err(e) = state
+            .db
+            .update_player_uuids(vec![(payload.player, uuid.clone())], now())
+            .await
+    {
+        tracing::error!(?e, player = %payload.player, "failed to store player uuid");
+    }
+    Metrics::inc(&state.metrics.joins);
+    state.webhooks.enqueue(Delivery {
+        api_key_hash: api_key_hash.clone(),
+        player: payload.player,
+        event: WebhookEvent::Join,
+        timestamp: now(),
+    });
+    publish_live(&state, &api_key_hash, |server| {
+        vec![LiveEvent::Join {
+            server,
+            player: payload.player,
+        }]
+    })
+    .await;
+
     // Check if we need the skin data
     let need_skin = if let Some(ref texture_hash) = payload.texture_hash {
         // Check if we already have this skin
@@ -161,43 +520,191 @@ pub(crate) async fn join(
         false
     };
 
+    // Without a skin from the server, look the player up on Mojang instead
+    if payload.texture_hash.is_none()
+        && let Some(uuid) = uuid
+    {
+        tokio::spawn(fetch_mojang_skin(
+            state.clone(),
+            api_key_hash.clone(),
+            payload.player,
+            uuid,
+        ));
+    }
+
     // Spawn async task to regenerate composite image
     let db = state.db.clone();
+    let config = state.status_config();
     tokio::spawn(async move {
-        if let Err(e) = regenerate_status_composite(&db, &api_key_hash_clone).await {
+        if let Err(e) = regenerate_status_composite(&*db, &api_key_hash_clone, &config).await {
             tracing::error!(?e, "failed to regenerate status composite");
         }
     });
 
-    if need_skin {
-        // Return 202 to signal mod should send skin data
-        Ok(StatusCode::ACCEPTED)
+    // Return 202 to signal mod should send skin data
+    let status = if need_skin {
+        StatusCode::ACCEPTED
     } else {
-        Ok(StatusCode::OK)
+        StatusCode::OK
+    };
+    if let Some(claim) = claim {
+        claim.complete(status);
     }
+    Ok(status)
 }
 
 pub(crate) async fn leave(
     State(state): State<Arc<AppState>>,
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(payload): Json<LeaveRequest>,
+    headers: HeaderMap,
+    AppJson(payload): AppJson<LeaveRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     #[cfg(debug_assertions)]
     tracing::debug!(player = %payload.player, "leave request");
 
     // Validate player name
-    validation::validate_player_name(payload.player.as_str())?;
+    validation::validate_player_name(payload.player.as_str(), state.name_policy)?;
 
-    let api_key = auth.token().to_string();
-    let api_key_hash = crate::helpers::hash_api_key(&api_key);
+    let api_key_hash = server_key_hash(&auth)?;
     let api_key_hash_clone = api_key_hash.clone();
 
-    state.db.player_leave(api_key_hash, payload.player).await?;
+    // A retried request gets the original result without leaving again
+    let claim = match IdempotencyCache::key(&headers, &api_key_hash) {
+        Some(key) => match state.idempotency.reserve(key).await {
+            Reservation::Replay(status) => return Ok(status),
+            Reservation::New(claim) => Some(claim),
+        },
+        None => None,
+    };
+
+    state
+        .db
+        .player_leave(api_key_hash.clone(), payload.player, now())
+        .await?;
+    Metrics::inc(&state.metrics.leaves);
+    state.webhooks.enqueue(Delivery {
+        api_key_hash: api_key_hash.clone(),
+        player: payload.player,
+        event: WebhookEvent::Leave,
+        timestamp: now(),
+    });
+    publish_live(&state, &api_key_hash, |server| {
+        vec![LiveEvent::Leave {
+            server,
+            player: payload.player,
+        }]
+    })
+    .await;
 
     // Spawn async task to regenerate composite image
     let db = state.db.clone();
+    let config = state.status_config();
     tokio::spawn(async move {
-        if let Err(e) = regenerate_status_composite(&db, &api_key_hash_clone).await {
+        if let Err(e) = regenerate_status_composite(&*db, &api_key_hash_clone, &config).await {
+            tracing::error!(?e, "failed to regenerate status composite");
+        }
+    });
+
+    if let Some(claim) = claim {
+        claim.complete(StatusCode::OK);
+    }
+    Ok(StatusCode::OK)
+}
+
+pub(crate) async fn events(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    AppJson(payload): AppJson<EventsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let EventsRequest {
+        joins,
+        leaves,
+        events,
+    } = payload;
+    #[cfg(debug_assertions)]
+    tracing::debug!(
+        joins = joins.len(),
+        leaves = leaves.len(),
+        events = events.as_ref().map(Vec::len),
+        "events request"
+    );
+
+    // Validate player names
+    let ordered = events.is_some();
+    if ordered && !(joins.is_empty() && leaves.is_empty()) {
+        return Err(AppError::ValidationError(
+            "send either events or joins/leaves, not both".to_string(),
+        ));
+    }
+    let entries = events.unwrap_or_else(|| {
+        let joins = joins.iter().map(|&player| EventEntry {
+            player,
+            event: EventKind::Join,
+        });
+        let leaves = leaves.iter().map(|&player| EventEntry {
+            player,
+            event: EventKind::Leave,
+        });
+        joins.chain(leaves).collect()
+    });
+    if ordered {
+        let player_names: Vec<PlayerName> = entries.iter().map(|e| e.player).collect();
+        validation::validate_player_list(&player_names, state.max_players, state.name_policy)?;
+    } else {
+        validation::validate_player_list(&joins, state.max_players, state.name_policy)?;
+        validation::validate_player_list(&leaves, state.max_players, state.name_policy)?;
+    }
+
+    let api_key_hash = server_key_hash(&auth)?;
+    let api_key_hash_clone = api_key_hash.clone();
+
+    if ordered {
+        let events = entries.iter().map(PlayerEvent::from).collect();
+        state
+            .db
+            .apply_ordered_events(api_key_hash.clone(), events, now())
+            .await?;
+    } else {
+        state
+            .db
+            .apply_events(api_key_hash.clone(), joins, leaves, now())
+            .await?;
+    }
+    for entry in &entries {
+        let (counter, event) = match entry.event {
+            EventKind::Join => (&state.metrics.joins, WebhookEvent::Join),
+            EventKind::Leave => (&state.metrics.leaves, WebhookEvent::Leave),
+        };
+        Metrics::inc(counter);
+        state.webhooks.enqueue(Delivery {
+            api_key_hash: api_key_hash.clone(),
+            player: entry.player,
+            event,
+            timestamp: now(),
+        });
+    }
+    publish_live(&state, &api_key_hash, |server| {
+        entries
+            .into_iter()
+            .map(|entry| match entry.event {
+                EventKind::Join => LiveEvent::Join {
+                    server: server.clone(),
+                    player: entry.player,
+                },
+                EventKind::Leave => LiveEvent::Leave {
+                    server: server.clone(),
+                    player: entry.player,
+                },
+            })
+            .collect()
+    })
+    .await;
+
+    // Spawn async task to regenerate composite image
+    let db = state.db.clone();
+    let config = state.status_config();
+    tokio::spawn(async move {
+        if let Err(e) = regenerate_status_composite(&*db, &api_key_hash_clone, &config).await {
             tracing::error!(?e, "failed to regenerate status composite");
         }
     });
@@ -208,31 +715,59 @@ pub(crate) async fn leave(
 pub(crate) async fn sync(
     State(state): State<Arc<AppState>>,
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(payload): Json<SyncRequest>,
+    AppJson(mut payload): AppJson<SyncRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     #[cfg(debug_assertions)]
     tracing::debug!(count = payload.players.len(), "sync request");
 
+    // Names differing only in case belong to one account; keep the first spelling
+    if state.sync_fold_case {
+        validation::fold_case_duplicates(&mut payload.players, |p| &p.player);
+    }
+
     // Validate player names + optional texture hashes
-    let player_names: Vec<PlayerName> =
-        payload.players.iter().map(|p| p.player).collect();
-    validation::validate_player_list(&player_names)?;
+    let player_names: Vec<PlayerName> = payload.players.iter().map(|p| p.player).collect();
+    validation::validate_player_list(&player_names, state.max_players, state.name_policy)?;
+    validation::validate_sync_list(
+        &player_names,
+        payload.allow_empty.unwrap_or(state.sync_allow_empty),
+    )?;
     for p in &payload.players {
         if let Some(ref hash) = p.texture_hash {
             validation::validate_texture_hash(hash)?;
         }
+        if let Some(ref uuid) = p.uuid {
+            validation::validate_uuid(uuid)?;
+        }
     }
 
-    let api_key = auth.token().to_string();
-    let api_key_hash = crate::helpers::hash_api_key(&api_key);
+    let api_key_hash = server_key_hash(&auth)?;
     let api_key_hash_clone = api_key_hash.clone();
 
     // Replace the player list for this server.
     state
         .db
-        .sync_players(api_key_hash, player_names, now())
+        .sync_players(api_key_hash.clone(), player_names.clone(), now())
         .await?;
 
+    // Store UUIDs only after the sync proved the key belongs to a server, as on /join
+    let uuids = payload
+        .players
+        .iter()
+        .filter_map(|p| Some((p.player, validation::normalize_uuid(p.uuid.as_deref()?))))
+        .collect();
+    if let Err(e) = state.db.update_player_uuids(uuids, now()).await {
+        tracing::error!(?e, "failed to store player uuids");
+    }
+    Metrics::inc(&state.metrics.syncs);
+    publish_live(&state, &api_key_hash, |server| {
+        vec![LiveEvent::Sync {
+            server,
+            players: player_names,
+        }]
+    })
+    .await;
+
     // For each player that reported a skin, either update the player->skin
     // mapping (if the skin is already stored) or flag it as missing so the
     // mod can follow up with a /skin upload.
@@ -256,8 +791,9 @@ pub(crate) async fn sync(
 
     // Spawn async task to regenerate composite image
     let db = state.db.clone();
+    let config = state.status_config();
     tokio::spawn(async move {
-        if let Err(e) = regenerate_status_composite(&db, &api_key_hash_clone).await {
+        if let Err(e) = regenerate_status_composite(&*db, &api_key_hash_clone, &config).await {
             tracing::error!(?e, "failed to regenerate status composite");
         }
     });
@@ -273,34 +809,597 @@ pub(crate) async fn disconnect(
     #[cfg(debug_assertions)]
     tracing::debug!("disconnect request");
 
-    let api_key = auth.token().to_string();
-    let api_key_hash = crate::helpers::hash_api_key(&api_key);
+    let api_key_hash = server_key_hash(&auth)?;
 
     state.db.delete_server_by_api_key(api_key_hash).await?;
 
     Ok(StatusCode::OK)
 }
 
+/// Clear the caller's online players (e.g. ghosts left by a crash) while
+/// keeping the server linked.
 #[debug_handler]
-pub(crate) async fn status(
+pub(crate) async fn reset(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!("reset request");
+
+    let api_key_hash = server_key_hash(&auth)?;
+
+    let cleared = state.db.clear_players(api_key_hash.clone()).await?;
+    publish_live(&state, &api_key_hash, |server| {
+        vec![LiveEvent::Sync {
+            server,
+            players: Vec::new(),
+        }]
+    })
+    .await;
+
+    // Spawn async task to regenerate composite image
+    let db = state.db.clone();
+    let config = state.status_config();
+    tokio::spawn(async move {
+        if let Err(e) = regenerate_status_composite(&*db, &api_key_hash, &config).await {
+            tracing::error!(?e, "failed to regenerate status composite");
+        }
+    });
+
+    Ok(Json(ResetResponse { cleared }))
+}
+
+/// Replace the caller's API key with a new one, keeping its online players.
+#[debug_handler]
+pub(crate) async fn rotate(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!("rotate request");
+
+    let api_key_hash = server_key_hash(&auth)?;
+    let new_api_key = crate::helpers::generate_api_key();
+    let new_api_key_hash = crate::helpers::hash_api_key(&new_api_key);
+
+    state
+        .db
+        .rotate_api_key(api_key_hash, new_api_key_hash)
+        .await?;
+
+    Ok(Json(RotateResponse {
+        api_key: new_api_key,
+    }))
+}
+
+/// Report which server and guild the caller's API key is linked to.
+#[debug_handler]
+pub(crate) async fn whoami(
     State(state): State<Arc<AppState>>,
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
 ) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!("whoami request");
+
+    let api_key_hash = server_key_hash(&auth)?;
+    let server = state
+        .db
+        .get_server_by_api_key(api_key_hash)
+        .await?
+        .ok_or(AppError::DatabaseError(oxeye_db::DbError::InvalidApiKey))?;
+
+    Ok(Json(WhoamiResponse {
+        server: server.name,
+        guild_id: server.guild_id,
+    }))
+}
+
+/// Drop the caller's cached status image so the next request draws it again.
+#[debug_handler]
+pub(crate) async fn refresh_skins(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!("refresh skins request");
+
+    let api_key_hash = server_key_hash(&auth)?;
+    if state
+        .db
+        .get_server_by_api_key(api_key_hash.clone())
+        .await?
+        .is_none()
+    {
+        return Err(AppError::DatabaseError(oxeye_db::DbError::InvalidApiKey));
+    }
+
+    let cleared = state.db.clear_render_cache(api_key_hash).await?;
+    Ok(Json(RefreshSkinsResponse { cleared }))
+}
+
+/// Report the caller's server as JSON, or its status image for `Accept: image/png`.
+#[debug_handler]
+pub(crate) async fn status(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
+    query: Result<Query<StatusQuery>, QueryRejection>,
+) -> Result<Response, AppError> {
     #[cfg(debug_assertions)]
     tracing::debug!("status request");
 
-    let api_key = auth.token().to_string();
-    let api_key_hash = crate::helpers::hash_api_key(&api_key);
+    let api_key_hash = server_key_hash(&auth)?;
+    let Query(query) = query
+        .map_err(|e| AppError::ValidationError(format!("invalid query: {}", e.body_text())))?;
+    validation::validate_timestamp(query.since, now())?;
 
     // Check if server exists with this API key
-    let server = state.db.get_server_by_api_key(api_key_hash).await?;
+    let server = state
+        .db
+        .get_server_by_api_key(api_key_hash.clone())
+        .await?
+        .ok_or(AppError::DatabaseError(oxeye_db::DbError::InvalidApiKey))?;
+
+    let wants_png = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(crate::helpers::prefers_png);
+    let mut response = if wants_png {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "image/png")
+            .header(header::CACHE_CONTROL, "private, max-age=10")
+            .body(Body::from(status_png(&state, &api_key_hash).await))
+            .unwrap()
+    } else {
+        let online = state.db.count_online_players(&api_key_hash).await?;
+        let players = state
+            .db
+            .get_players_since(api_key_hash.clone(), query.since)
+            .await?;
+        Json(StatusResponse {
+            server: server.name,
+            online,
+            players,
+        })
+        .into_response()
+    };
+    response
+        .headers_mut()
+        .insert(header::VARY, header::HeaderValue::from_static("accept"));
+    Ok(response)
+}
+
+/// List the caller's online players with when they joined, longest online first.
+#[debug_handler]
+pub(crate) async fn status_detailed(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!("detailed status request");
+
+    let api_key_hash = server_key_hash(&auth)?;
+    state
+        .db
+        .get_server_by_api_key(api_key_hash.clone())
+        .await?
+        .ok_or(AppError::DatabaseError(oxeye_db::DbError::InvalidApiKey))?;
+
+    let now = now();
+    let players = state
+        .db
+        .get_online_players_detailed(api_key_hash)
+        .await?
+        .into_iter()
+        .map(|p| DetailedPlayerResponse {
+            player: p.player_name,
+            joined_at: p.joined_at,
+            online_for_secs: (now - p.joined_at).max(0),
+        })
+        .collect::<Vec<_>>();
+    Ok(Json(players))
+}
+
+/// Fields checked by /validate; any subset may be sent.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ValidateRequest {
+    player: Option<PlayerName>,
+    players: Option<Vec<PlayerName>>,
+    code: Option<String>,
+    server_name: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ValidateResponse {
+    ok: bool,
+}
+
+/// Run the request validators on whichever fields are present, without
+/// touching any state (for plugin developers testing their payloads).
+#[debug_handler]
+pub(crate) async fn validate(
+    State(state): State<Arc<AppState>>,
+    AppJson(payload): AppJson<ValidateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let ValidateRequest {
+        player,
+        players,
+        code,
+        server_name,
+    } = payload;
+    if player.is_none() && players.is_none() && code.is_none() && server_name.is_none() {
+        return Err(AppError::ValidationError(
+            "Nothing to validate (send player, players, code or server_name)".to_string(),
+        ));
+    }
+
+    if let Some(player) = player {
+        validation::validate_player_name(player.as_str(), state.name_policy)?;
+    }
+    if let Some(players) = players {
+        validation::validate_player_list(&players, state.max_players, state.name_policy)?;
+    }
+    if let Some(code) = code {
+        validation::validate_code(&code, state.min_code_length)?;
+    }
+    if let Some(server_name) = server_name {
+        validation::validate_server_name(&server_name)?;
+    }
+
+    Ok(Json(ValidateResponse { ok: true }))
+}
+
+/// Read a server's online players by name, authenticated with a guild token.
+#[debug_handler]
+pub(crate) async fn server_players(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path((guild_id, name)): Path<(u64, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!(guild_id, server = %name, "server players request");
+
+    let token_hash = guild_token_hash(&auth)?;
+    state.db.verify_guild_token(guild_id, token_hash).await?;
+
+    let server = state.db.get_server_with_players(guild_id, name).await?;
+    Ok(Json(ServerPlayersResponse::from(server)))
+}
+
+/// Export a guild's linked servers and their online players as JSON.
+#[debug_handler]
+pub(crate) async fn export_guild(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(guild_id): Path<u64>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!(guild_id, "guild export request");
+
+    let token_hash = guild_token_hash(&auth)?;
+    state.db.verify_guild_token(guild_id, token_hash).await?;
+
+    let servers = state.db.get_servers_with_players(guild_id).await?;
+    Ok(Json(GuildExportResponse {
+        guild_id,
+        servers: servers
+            .into_iter()
+            .map(ServerPlayersResponse::from)
+            .collect(),
+    }))
+}
+
+/// List a guild's linked servers a page at a time, authenticated with a guild token.
+#[debug_handler]
+pub(crate) async fn list_guild_servers(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(guild_id): Path<u64>,
+    query: Result<Query<ServersPageQuery>, QueryRejection>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!(guild_id, "guild servers request");
+
+    let token_hash = guild_token_hash(&auth)?;
+    state.db.verify_guild_token(guild_id, token_hash).await?;
+
+    let Query(query) = query
+        .map_err(|e| AppError::ValidationError(format!("invalid query: {}", e.body_text())))?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SERVERS_PAGE_LIMIT)
+        .clamp(1, MAX_SERVERS_PAGE_LIMIT);
+    let (servers, total) = state
+        .db
+        .get_servers_paginated(guild_id, limit, query.offset)
+        .await?;
+
+    Ok(Json(ServersPageResponse {
+        guild_id,
+        total,
+        limit,
+        offset: query.offset,
+        servers: servers
+            .into_iter()
+            .map(|s| ServerListing {
+                name: s.name,
+                created_at: s.created_at,
+            })
+            .collect(),
+    }))
+}
+
+/// Return server count, online total and busiest server for a guild.
+#[debug_handler]
+pub(crate) async fn guild_stats(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(guild_id): Path<u64>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!(guild_id, "guild stats request");
+
+    let token_hash = guild_token_hash(&auth)?;
+    state.db.verify_guild_token(guild_id, token_hash).await?;
+
+    let stats = state.db.get_guild_stats(guild_id).await?;
+    Ok(Json(GuildStatsResponse {
+        guild_id,
+        server_count: stats.server_count,
+        online_players: stats.online_players,
+        busiest_server: stats.busiest_server.map(|s| BusiestServerResponse {
+            name: s.name,
+            player_count: s.player_count,
+        }),
+    }))
+}
+
+/// List the servers a player is online on across every guild (admin token only).
+#[debug_handler]
+pub(crate) async fn player_servers(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(player): Path<PlayerName>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!(player = %player, "player servers request");
+
+    let token_hash = crate::helpers::hash_api_key(auth.token());
+    if state.admin_token_hash.as_deref() != Some(token_hash.as_str()) {
+        return Err(AppError::Unauthorized("Invalid admin token".to_string()));
+    }
+    validation::validate_player_name(player.as_str(), state.name_policy)?;
+
+    let servers = state.db.find_player_servers(player).await?;
+    Ok(Json(PlayerServersResponse {
+        player,
+        servers: servers
+            .into_iter()
+            .map(|(guild_id, name)| PlayerServerResponse { guild_id, name })
+            .collect(),
+    }))
+}
+
+/// Move one server's online players onto another (admin token only).
+///
+/// Servers are identified by API key hash. Players on both keep the earlier
+/// join time; the source is left empty.
+#[debug_handler]
+pub(crate) async fn merge(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    AppJson(payload): AppJson<MergeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!("merge request");
+
+    let token_hash = crate::helpers::hash_api_key(auth.token());
+    if state.admin_token_hash.as_deref() != Some(token_hash.as_str()) {
+        return Err(AppError::Unauthorized("Invalid admin token".to_string()));
+    }
+    if payload.source == payload.destination {
+        return Err(AppError::ValidationError(
+            "source and destination must be different servers".to_string(),
+        ));
+    }
+
+    let merged = state
+        .db
+        .merge_players(payload.source.clone(), payload.destination.clone())
+        .await?;
+
+    // Both player lists changed
+    for api_key_hash in [payload.source, payload.destination] {
+        let db = state.db.clone();
+        let config = state.status_config();
+        tokio::spawn(async move {
+            if let Err(e) = regenerate_status_composite(&*db, &api_key_hash, &config).await {
+                tracing::error!(?e, "failed to regenerate status composite");
+            }
+        });
+    }
+
+    Ok(Json(MergeResponse { merged }))
+}
+
+/// Move a linked server to a different guild, e.g. after a community moves
+/// Discord servers. Admin only, since it spans guilds.
+#[debug_handler]
+pub(crate) async fn transfer(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    AppJson(payload): AppJson<TransferRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!("transfer request");
+
+    let token_hash = crate::helpers::hash_api_key(auth.token());
+    if state.admin_token_hash.as_deref() != Some(token_hash.as_str()) {
+        return Err(AppError::Unauthorized("Invalid admin token".to_string()));
+    }
+
+    let server = state
+        .db
+        .transfer_server(payload.server, payload.guild_id)
+        .await?;
+
+    Ok(Json(TransferResponse {
+        server: server.name,
+        guild_id: server.guild_id,
+    }))
+}
+
+/// Stream a guild's player joins, leaves and syncs as Server-Sent Events.
+///
+/// The stream ends when the client disconnects (axum drops it along with the
+/// broadcast receiver). Events missed by a slow client are skipped.
+#[debug_handler]
+pub(crate) async fn guild_events(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Path(guild_id): Path<u64>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    #[cfg(debug_assertions)]
+    tracing::debug!(guild_id, "guild events request");
+
+    let token_hash = guild_token_hash(&auth)?;
+    state.db.verify_guild_token(guild_id, token_hash).await?;
+
+    let receiver = state.live.subscribe();
+    let stream = stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(guild_event) if guild_event.guild_id == guild_id => {
+                    let event = Event::default()
+                        .event(guild_event.event.name())
+                        .json_data(&guild_event.event)
+                        .ok()?;
+                    return Some((Ok(event), receiver));
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(guild_id, skipped, "live event stream lagged");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
 
-    match server {
-        Some(_) => Ok(StatusCode::OK),
-        None => Err(AppError::DatabaseError(oxeye_db::DbError::InvalidApiKey)),
+/// Refuse clients that have made too many failed connection attempts,
+/// before their next guess is checked.
+fn check_connect_guard(state: &AppState, client_ip: Option<IpAddr>) -> Result<(), AppError> {
+    match client_ip {
+        Some(ip) => state
+            .connect_guard
+            .check(ip)
+            .map_err(AppError::TooManyFailures),
+        None => Ok(()),
     }
 }
 
+/// Count an unknown or expired connection code against the client.
+fn record_connect_failure(state: &AppState, client_ip: Option<IpAddr>) {
+    if let Some(ip) = client_ip {
+        tracing::warn!(%ip, "connect attempt with unknown or expired code");
+        state.connect_guard.record_failure(ip);
+    }
+}
+
+/// Hash the server API key from the Authorization header.
+///
+/// Tokens that can't be an API key (wrong prefix or length) get the same 401
+/// as an unknown key, without hashing them or querying the database.
+fn server_key_hash(auth: &Authorization<Bearer>) -> Result<String, AppError> {
+    if !crate::helpers::is_well_formed_api_key(auth.token()) {
+        return Err(AppError::DatabaseError(oxeye_db::DbError::InvalidApiKey));
+    }
+    Ok(crate::helpers::hash_api_key(auth.token()))
+}
+
+/// Hash the guild token from the Authorization header.
+///
+/// Anything that isn't shaped like a guild token, server API keys included,
+/// gets the same 401 as an unknown token without touching the database.
+fn guild_token_hash(auth: &Authorization<Bearer>) -> Result<String, AppError> {
+    if !crate::helpers::is_well_formed_guild_token(auth.token()) {
+        return Err(AppError::DatabaseError(
+            oxeye_db::DbError::InvalidGuildToken,
+        ));
+    }
+    Ok(crate::helpers::hash_api_key(auth.token()))
+}
+
+/// Publish live events for the server behind `api_key_hash`.
+///
+/// The server lookup is skipped entirely when no SSE stream is listening.
+async fn publish_live(
+    state: &AppState,
+    api_key_hash: &str,
+    make_events: impl FnOnce(String) -> Vec<LiveEvent>,
+) {
+    if !state.live.has_subscribers() {
+        return;
+    }
+    match state
+        .db
+        .get_server_by_api_key(api_key_hash.to_string())
+        .await
+    {
+        Ok(Some(server)) => state
+            .live
+            .publish(server.guild_id, make_events(server.name)),
+        Ok(None) => {}
+        Err(e) => tracing::error!(?e, "failed to look up server for live events"),
+    }
+}
+
+/// Readiness probe: 200 only if the database answers a trivial query.
+pub(crate) async fn ready(State(state): State<Arc<AppState>>) -> Response {
+    match state.db.ping().await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(?e, "readiness check failed");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::new("Database unavailable")),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Build and uptime of the running backend.
+#[derive(Serialize)]
+pub(crate) struct VersionResponse {
+    version: &'static str,
+    git_sha: &'static str,
+    uptime_secs: u64,
+}
+
+/// Report the crate version, git commit and uptime (for deploy verification).
+pub(crate) async fn version(State(state): State<Arc<AppState>>) -> Json<VersionResponse> {
+    Json(VersionResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: env!("OXEYE_GIT_SHA"),
+        uptime_secs: state.started_at.elapsed().as_secs(),
+    })
+}
+
+/// Expose request counters and the online player gauge for Prometheus.
+pub(crate) async fn metrics(State(state): State<Arc<AppState>>) -> Response {
+    let online_players = state.db.count_all_online_players().await;
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/plain; version=0.0.4")
+        .body(Body::from(state.metrics.render(online_players)))
+        .unwrap()
+}
+
 // ============================================================================
 // Skin Endpoints
 // ============================================================================
@@ -310,7 +1409,7 @@ pub(crate) async fn status(
 pub(crate) async fn upload_skin(
     State(state): State<Arc<AppState>>,
     TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    Json(payload): Json<SkinRequest>,
+    AppJson(payload): AppJson<SkinRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     #[cfg(debug_assertions)]
     tracing::debug!(texture_hash = %payload.texture_hash, "skin upload request");
@@ -322,8 +1421,7 @@ pub(crate) async fn upload_skin(
     validation::validate_skin_data(&payload.skin_data)?;
 
     // Verify server exists
-    let api_key = auth.token().to_string();
-    let api_key_hash = crate::helpers::hash_api_key(&api_key);
+    let api_key_hash = server_key_hash(&auth)?;
     let server = state.db.get_server_by_api_key(api_key_hash.clone()).await?;
     if server.is_none() {
         return Err(AppError::DatabaseError(oxeye_db::DbError::InvalidApiKey));
@@ -334,26 +1432,86 @@ pub(crate) async fn upload_skin(
         .decode(&payload.skin_data)
         .map_err(|e| AppError::ValidationError(format!("invalid base64 skin data: {}", e)))?;
 
+    save_player_skin(
+        &state,
+        api_key_hash,
+        payload.player,
+        payload.texture_hash,
+        payload.texture_url,
+        skin_data,
+    )
+    .await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Fetch a player's skin from Mojang and save it, unless they already have one.
+async fn fetch_mojang_skin(
+    state: Arc<AppState>,
+    api_key_hash: String,
+    player: PlayerName,
+    uuid: String,
+) {
+    match state.db.get_player_texture_hash(player.as_str()).await {
+        Ok(None) => {}
+        Ok(Some(_)) => return,
+        Err(e) => {
+            tracing::error!(?e, %player, "failed to look up player skin");
+            return;
+        }
+    }
+
+    let skin = match state.skins.fetch_skin(&uuid).await {
+        Ok(skin) => skin,
+        Err(FetchError::NotFound) => {
+            tracing::debug!(%player, uuid, "no Mojang profile for player");
+            return;
+        }
+        Err(e) => {
+            tracing::warn!(?e, %player, "failed to fetch skin from Mojang");
+            return;
+        }
+    };
+
+    if let Err(e) = save_player_skin(
+        &state,
+        api_key_hash,
+        player,
+        skin.texture_hash,
+        Some(skin.texture_url),
+        skin.data,
+    )
+    .await
+    {
+        tracing::error!(?e, %player, "failed to save skin from Mojang");
+    }
+}
+
+/// Store a skin, point the player at it and render its head in the background.
+async fn save_player_skin(
+    state: &AppState,
+    api_key_hash: String,
+    player: PlayerName,
+    texture_hash: String,
+    texture_url: Option<String>,
+    skin_data: Vec<u8>,
+) -> Result<(), AppError> {
     // Store the skin
     state
         .db
-        .store_skin(
-            payload.texture_hash.clone(),
-            payload.texture_url.clone(),
-            skin_data.clone(),
-        )
+        .store_skin(texture_hash.clone(), texture_url, skin_data.clone())
         .await?;
 
     // Now that the skin exists, update the player's skin mapping
     // (This was deferred from /join because the FK constraint requires the skin to exist first)
     state
         .db
-        .update_player_skin(payload.player.as_str(), &payload.texture_hash, now())
+        .update_player_skin(player.as_str(), &texture_hash, now())
         .await?;
 
     // Spawn async task to render head
     let db = state.db.clone();
-    let texture_hash = payload.texture_hash.clone();
+    let config = state.status_config();
     tokio::spawn(async move {
         match render::render_head(&skin_data) {
             Ok(head_data) => {
@@ -365,7 +1523,7 @@ pub(crate) async fn upload_skin(
                 }
 
                 // Also regenerate composite for the server
-                if let Err(e) = regenerate_status_composite(&db, &api_key_hash).await {
+                if let Err(e) = regenerate_status_composite(&*db, &api_key_hash, &config).await {
                     tracing::error!(
                         ?e,
                         "failed to regenerate status composite after skin upload"
@@ -378,11 +1536,12 @@ pub(crate) async fn upload_skin(
         }
     });
 
-    Ok(StatusCode::OK)
+    Ok(())
 }
 
 /// Serve a rendered player head image by texture hash.
-/// Falls back to Steve head if not found.
+/// A missing head is rendered from the stored skin; falls back to Steve head
+/// if there is no skin either.
 pub(crate) async fn get_head(
     State(state): State<Arc<AppState>>,
     Path(hash_with_ext): Path<String>,
@@ -392,23 +1551,28 @@ pub(crate) async fn get_head(
 
     // Try to get rendered head from database
     let head_data = match state.db.get_rendered_head(texture_hash).await {
-        Ok(Some(data)) => data,
-        Ok(None) => {
-            // Not found, return Steve head
-            tracing::debug!(texture_hash, "head not found, returning Steve fallback");
-            DEFAULT_STEVE_HEAD.to_vec()
-        }
+        Ok(Some(data)) => Some(data),
+        Ok(None) => rerender_head(&*state.db, texture_hash).await,
         Err(e) => {
             tracing::error!(?e, "failed to get rendered head");
-            DEFAULT_STEVE_HEAD.to_vec()
+            None
+        }
+    };
+
+    // A head is content-addressed, so it can be cached for a year (immutable).
+    // The fallback can't: the real head may turn up once the skin is uploaded.
+    let (head_data, cache_control) = match head_data {
+        Some(data) => (data, "public, immutable, max-age=31536000"),
+        None => {
+            tracing::debug!(texture_hash, "head not found, returning Steve fallback");
+            (DEFAULT_STEVE_HEAD.to_vec(), "public, max-age=60")
         }
     };
 
-    // Return PNG with immutable cache headers (1 year)
     Response::builder()
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, "image/png")
-        .header(header::CACHE_CONTROL, "public, immutable, max-age=31536000")
+        .header(header::CACHE_CONTROL, cache_control)
         .body(Body::from(head_data))
         .unwrap()
 }
@@ -418,12 +1582,30 @@ pub(crate) async fn get_status_image(
     State(state): State<Arc<AppState>>,
     Path(hash_with_ext): Path<String>,
 ) -> Response {
+    // A .gif extension opts into the animated version
+    if let Some(api_key_hash) = hash_with_ext.strip_suffix(".gif") {
+        return animated_status_image(&state, api_key_hash).await;
+    }
+
     // Strip .png extension if present
     let api_key_hash = hash_with_ext.strip_suffix(".png").unwrap_or(&hash_with_ext);
     tracing::info!(api_key_hash, "status image requested");
+    let image_data = status_png(&state, api_key_hash).await;
 
+    // Return PNG with short cache (can change when players join/leave)
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "image/png")
+        .header(header::CACHE_CONTROL, "public, max-age=10")
+        .body(Body::from(image_data))
+        .unwrap()
+}
+
+/// A server's status image, from the cache or rendered on demand. Falls back
+/// to the empty state image if rendering fails.
+async fn status_png(state: &AppState, api_key_hash: &str) -> Vec<u8> {
     // Try to get cached status image
-    let image_data = match state.db.get_status_image(api_key_hash).await {
+    match state.db.get_status_image(api_key_hash).await {
         Ok(Some(data)) => {
             tracing::info!(
                 api_key_hash,
@@ -438,27 +1620,52 @@ pub(crate) async fn get_status_image(
                 api_key_hash,
                 "status image not cached, generating on-demand"
             );
-            match generate_status_composite(&state.db, api_key_hash).await {
+            match generate_status_composite(&*state.db, api_key_hash, &state.status_config()).await
+            {
                 Ok(data) => data,
                 Err(e) => {
                     tracing::error!(?e, "failed to generate status image");
                     // Return empty state image
-                    let config = CompositeConfig::default();
+                    let config = state.status_config();
                     render::render_composite(&[], &config).unwrap_or_default()
                 }
             }
         }
         Err(e) => {
             tracing::error!(?e, "failed to get status image");
-            let config = CompositeConfig::default();
+            let config = state.status_config();
             render::render_composite(&[], &config).unwrap_or_default()
         }
+    }
+}
+
+/// Serve a server's animated status GIF, from the cache or rendered on demand.
+/// Unknown servers get a 404 so made-up hashes can't trigger renders.
+async fn animated_status_image(state: &AppState, api_key_hash: &str) -> Response {
+    tracing::info!(api_key_hash, "animated status image requested");
+    let image_data = match state.db.get_status_gif(api_key_hash).await {
+        Ok(Some(data)) => data,
+        Ok(None) => match state
+            .db
+            .get_server_by_api_key(api_key_hash.to_string())
+            .await
+        {
+            Ok(Some(_)) => render_status_gif(state, api_key_hash).await,
+            Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+            Err(e) => {
+                tracing::error!(?e, "failed to look up server for animated status image");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+        Err(e) => {
+            tracing::error!(?e, "failed to get animated status image");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
     };
 
-    // Return PNG with short cache (can change when players join/leave)
     Response::builder()
         .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "image/png")
+        .header(header::CONTENT_TYPE, "image/gif")
         .header(header::CACHE_CONTROL, "public, max-age=10")
         .body(Body::from(image_data))
         .unwrap()
@@ -468,25 +1675,87 @@ pub(crate) async fn get_status_image(
 // Helper Functions
 // ============================================================================
 
+/// Render a server's animated status GIF off the async runtime and cache it.
+/// Falls back to the empty state image if rendering fails.
+async fn render_status_gif(state: &AppState, api_key_hash: &str) -> Vec<u8> {
+    let config = state.status_config();
+    let entries = status_entries(&*state.db, api_key_hash)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!(?e, "failed to load players for animated status image");
+            Vec::new()
+        });
+    let rendered = tokio::task::spawn_blocking(move || {
+        match render::render_composite_animated(&entries, &config) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                tracing::error!(?e, "failed to render animated status image");
+                None
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|e| {
+        tracing::error!(?e, "animated status image render task failed");
+        None
+    });
+
+    match rendered {
+        Some(image_data) => {
+            if let Err(e) = state
+                .db
+                .store_status_gif(api_key_hash.to_string(), image_data.clone(), now())
+                .await
+            {
+                tracing::error!(?e, "failed to cache animated status image");
+            }
+            image_data
+        }
+        // Not cached, so the next request tries again
+        None => render::render_composite_animated(&[], &state.status_config()).unwrap_or_default(),
+    }
+}
+
 /// Generate a status composite image for a server.
 async fn generate_status_composite(
-    db: &oxeye_db::Database,
+    db: &dyn Store,
     api_key_hash: &str,
+    config: &CompositeConfig,
 ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let entries = status_entries(db, api_key_hash).await?;
+
+    // Render composite
+    tracing::info!(entry_count = entries.len(), "rendering composite image");
+    let image_data = render::render_composite(&entries, config)?;
+    tracing::info!(bytes = image_data.len(), "composite image rendered");
+
+    Ok(image_data)
+}
+
+/// Load a server's online players with their rendered heads, ready for compositing.
+async fn status_entries(
+    db: &dyn Store,
+    api_key_hash: &str,
+) -> Result<Vec<PlayerEntry>, Box<dyn std::error::Error + Send + Sync>> {
     // Get players with their texture hashes
     let players = db.get_players_with_heads(api_key_hash).await?;
     tracing::info!(
         api_key_hash,
         player_count = players.len(),
-        players = ?players.iter().map(|(name, hash)| (name.as_str(), hash.as_ref().map(|h| &h[..8]))).collect::<Vec<_>>(),
+        players = ?players.iter().map(|p| (p.player_name.as_str(), p.texture_hash.as_ref().map(|h| &h[..8]))).collect::<Vec<_>>(),
         "generating status composite"
     );
 
     // Build player entries with head data
     let mut entries = Vec::with_capacity(players.len());
-    for (player_name, texture_hash) in players {
-        let head_data = if let Some(ref hash) = texture_hash {
-            let data = db.get_rendered_head(hash).await.ok().flatten();
+    for player in players {
+        let player_name = player.player_name;
+        let head_data = if let Some(ref hash) = player.texture_hash {
+            let data = match db.get_rendered_head(hash).await.ok().flatten() {
+                Some(data) => Some(data),
+                // Never rendered (or the render was lost); redraw it from the stored skin
+                None => rerender_head(db, hash).await,
+            };
             tracing::debug!(
                 player = %player_name,
                 texture_hash = &hash[..8],
@@ -502,25 +1771,48 @@ async fn generate_status_composite(
         entries.push(PlayerEntry {
             name: player_name.to_string(),
             head_data,
+            grayscale: false,
+            joined_at: None,
+            // Skinless players get the default Minecraft would pick for their UUID
+            default_model: player
+                .uuid
+                .as_deref()
+                .and_then(DefaultModel::from_uuid)
+                .unwrap_or_default(),
         });
     }
 
-    // Render composite
-    let config = CompositeConfig::default();
-    tracing::info!(entry_count = entries.len(), "rendering composite image");
-    let image_data = render::render_composite(&entries, &config)?;
-    tracing::info!(bytes = image_data.len(), "composite image rendered");
+    Ok(entries)
+}
 
-    Ok(image_data)
+/// Render and store a head from an already uploaded skin.
+/// Returns None if the skin is unknown or can't be rendered.
+async fn rerender_head(db: &dyn Store, texture_hash: &str) -> Option<Vec<u8>> {
+    let skin_data = db.get_skin_data(texture_hash).await.ok().flatten()?;
+    let head_data = match render::render_head(&skin_data) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::error!(?e, texture_hash, "failed to re-render head from skin");
+            return None;
+        }
+    };
+    if let Err(e) = db
+        .store_rendered_head(texture_hash.to_string(), head_data.clone(), now())
+        .await
+    {
+        tracing::error!(?e, "failed to store rendered head");
+    }
+    Some(head_data)
 }
 
 /// Regenerate and cache the status composite image for a server.
 async fn regenerate_status_composite(
-    db: &oxeye_db::Database,
+    db: &dyn Store,
     api_key_hash: &str,
+    config: &CompositeConfig,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     tracing::info!(api_key_hash, "regenerating status composite");
-    let image_data = generate_status_composite(db, api_key_hash).await?;
+    let image_data = generate_status_composite(db, api_key_hash, config).await?;
 
     db.store_status_image(api_key_hash.to_string(), image_data, now())
         .await?;
@@ -0,0 +1,107 @@
+//! Periodic background jobs run alongside the HTTP server.
+
+use crate::helpers::now;
+use oxeye_db::{DbError, Store};
+use std::time::Duration;
+
+/// Upper bound on the random delay added to each cleanup run.
+const MAX_CLEANUP_JITTER: Duration = Duration::from_secs(5);
+
+/// Delete pending links that expired before `now`, logging how many went.
+pub async fn cleanup_expired_links(db: &dyn Store, now: i64) -> Result<u64, DbError> {
+    let removed = db.cleanup_expired_links(now).await?;
+    if removed > 0 {
+        tracing::info!(removed, "Removed expired pending links");
+    }
+    Ok(removed)
+}
+
+/// Delete play sessions that ended more than `retention` ago, logging how many went.
+pub async fn prune_sessions(db: &dyn Store, retention: Duration, now: i64) -> Result<u64, DbError> {
+    let pruned = db.prune_sessions(retention.as_secs() as i64, now).await?;
+    if pruned > 0 {
+        tracing::info!(pruned, "Pruned old play sessions");
+    }
+    Ok(pruned)
+}
+
+/// Remove expired pending links every `interval` until `shutdown` completes.
+///
+/// Each wait gets up to a tenth of the interval (at most 5s) of random jitter
+/// so instances sharing a database don't all clean up at the same moment.
+pub async fn run_link_cleanup(
+    db: impl Store,
+    interval: Duration,
+    shutdown: impl Future<Output = ()>,
+) {
+    tokio::pin!(shutdown);
+    let max_jitter_ms = (interval / 10).min(MAX_CLEANUP_JITTER).as_millis() as u64;
+    loop {
+        let jitter = Duration::from_millis(rand::random_range(0..=max_jitter_ms));
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = tokio::time::sleep(interval + jitter) => {
+                if let Err(e) = cleanup_expired_links(&db, now()).await {
+                    tracing::error!("Pending link cleanup failed: {}", e);
+                }
+            }
+        }
+    }
+    tracing::debug!("Pending link cleanup stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxeye_db::Database;
+
+    /// Create a pending link that expired well before the current time.
+    async fn db_with_expired_link() -> Database {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_pending_link(
+            "oxeye-stale1".to_string(),
+            12345,
+            "Survival".to_string(),
+            now() - 3600,
+            25,
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_removes_expired_link() {
+        let db = db_with_expired_link().await;
+
+        assert_eq!(cleanup_expired_links(&db, now()).await.unwrap(), 1);
+        assert!(matches!(
+            db.consume_pending_link("oxeye-stale1".to_string(), now())
+                .await,
+            Err(DbError::PendingLinkNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_run_link_cleanup_runs_and_stops_on_shutdown() {
+        let db = db_with_expired_link().await;
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let task = tokio::spawn(run_link_cleanup(
+            db.clone(),
+            Duration::from_millis(10),
+            async {
+                let _ = stop_rx.await;
+            },
+        ));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        stop_tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("cleanup task did not stop")
+            .unwrap();
+
+        // The link was already removed by the task
+        assert_eq!(cleanup_expired_links(&db, now()).await.unwrap(), 0);
+    }
+}
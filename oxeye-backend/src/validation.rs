@@ -1,5 +1,8 @@
 /// Input validation functions for all backend routes
-use oxeye_db::PlayerName;
+use oxeye_db::{MAX_PLAYER_NAME_LEN, PlayerName};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::str::FromStr;
 use thiserror::Error;
 
 #[derive(Debug, Error, PartialEq)]
@@ -7,18 +10,26 @@ pub enum ValidationError {
     #[error("Player name cannot be empty")]
     PlayerNameEmpty,
 
-    #[error("Player name too long (max 16 characters, got {0})")]
-    PlayerNameTooLong(usize),
+    #[error("Player name too long (max {max} characters, got {actual})")]
+    PlayerNameTooLong { max: usize, actual: usize },
 
     #[error("Player name contains invalid characters (only alphanumeric and underscore allowed)")]
     PlayerNameInvalidChars,
 
+    #[error(
+        "Player name contains invalid characters (only alphanumeric, underscore and single inner spaces allowed)"
+    )]
+    PlayerNameInvalidCharsOrSpaces,
+
     #[error("Connection code cannot be empty")]
     CodeEmpty,
 
     #[error("Connection code has invalid format (expected 'oxeye-XXXXXX')")]
     CodeInvalidFormat,
 
+    #[error("Player list cannot be empty (this server rejects empty syncs)")]
+    PlayerListEmpty,
+
     #[error("Player list too large (max {max} players, got {actual})")]
     PlayerListTooLarge { max: usize, actual: usize },
 
@@ -28,6 +39,21 @@ pub enum ValidationError {
     #[error("Server name too long (max 100 characters, got {0})")]
     ServerNameTooLong(usize),
 
+    #[error("Server name contains control or invisible characters")]
+    ServerNameInvalidChars,
+
+    #[error("Server tag cannot be empty")]
+    ServerTagEmpty,
+
+    #[error("Server tag too long (max {max} characters, got {actual})")]
+    ServerTagTooLong { max: usize, actual: usize },
+
+    #[error("Server tag contains control or invisible characters")]
+    ServerTagInvalidChars,
+
+    #[error("UUID has invalid format (expected 32 hex digits, optionally in 8-4-4-4-12 groups)")]
+    UuidInvalidFormat,
+
     #[error("Texture hash cannot be empty")]
     TextureHashEmpty,
 
@@ -39,42 +65,122 @@ pub enum ValidationError {
 
     #[error("Skin data too large (max {max} bytes, got {actual})")]
     SkinDataTooLarge { max: usize, actual: usize },
+
+    #[error("Color must be a 6-digit hex code like #5865F2")]
+    ColorInvalidFormat,
+
+    #[error("Webhook URL must be an http:// or https:// URL (max {max} characters)")]
+    WebhookUrlInvalid { max: usize },
+
+    #[error("Webhook URL must point to a public address, not localhost or a private network")]
+    WebhookUrlPrivate,
+
+    #[error("Timestamp must be unix seconds between 0 and now (got {0})")]
+    TimestampOutOfRange(i64),
+}
+
+/// Which player names a server accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NamePolicy {
+    /// Java Edition usernames: up to 16 alphanumeric or underscore characters
+    #[default]
+    Java,
+    /// Bedrock gamertags (cross-play servers): like Java, plus single spaces
+    /// between words, up to `MAX_PLAYER_NAME_LEN`
+    Bedrock,
+    /// Bedrock characters with a custom length limit (capped at `MAX_PLAYER_NAME_LEN`)
+    Custom { max_length: usize },
 }
 
-/// Validates a Minecraft player name
+impl NamePolicy {
+    /// Longest name accepted, in bytes
+    pub fn max_length(self) -> usize {
+        match self {
+            NamePolicy::Java => 16,
+            NamePolicy::Bedrock => MAX_PLAYER_NAME_LEN,
+            NamePolicy::Custom { max_length } => max_length.min(MAX_PLAYER_NAME_LEN),
+        }
+    }
+}
+
+impl FromStr for NamePolicy {
+    type Err = String;
+
+    /// Parses "java", "bedrock" or "custom:<max_length>"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "java" => Ok(NamePolicy::Java),
+            "bedrock" => Ok(NamePolicy::Bedrock),
+            other => other
+                .strip_prefix("custom:")
+                .and_then(|n| n.parse().ok())
+                .filter(|&max_length| max_length > 0)
+                .map(|max_length| NamePolicy::Custom { max_length })
+                .ok_or_else(|| format!("unknown name policy {:?}", other)),
+        }
+    }
+}
+
+/// Validates a Minecraft player name under the server's name policy
 ///
 /// Rules:
 /// - Cannot be empty
-/// - Max 16 characters (Minecraft username limit)
-/// - Only alphanumeric characters and underscores
-pub fn validate_player_name(name: &str) -> Result<(), ValidationError> {
+/// - At most `policy.max_length()` bytes (16 for Java usernames)
+/// - Only alphanumeric characters and underscores; Bedrock and custom
+///   policies also allow single spaces between words
+pub fn validate_player_name(name: &str, policy: NamePolicy) -> Result<(), ValidationError> {
     if name.is_empty() {
         return Err(ValidationError::PlayerNameEmpty);
     }
 
-    if name.len() > 16 {
-        return Err(ValidationError::PlayerNameTooLong(name.len()));
+    let max = policy.max_length();
+    if name.len() > max {
+        return Err(ValidationError::PlayerNameTooLong {
+            max,
+            actual: name.len(),
+        });
     }
 
-    if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
-        return Err(ValidationError::PlayerNameInvalidChars);
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_';
+    match policy {
+        NamePolicy::Java => {
+            if !name.chars().all(is_name_char) {
+                return Err(ValidationError::PlayerNameInvalidChars);
+            }
+        }
+        NamePolicy::Bedrock | NamePolicy::Custom { .. } => {
+            // Words of name characters separated by single spaces
+            if !name
+                .split(' ')
+                .all(|word| !word.is_empty() && word.chars().all(is_name_char))
+            {
+                return Err(ValidationError::PlayerNameInvalidCharsOrSpaces);
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Shortest connection code suffix accepted (and the default generated length)
+pub const MIN_CODE_LENGTH: usize = 6;
+
 /// Validates a connection code
 ///
 /// Rules:
 /// - Cannot be empty
 /// - Must match format "oxeye-XXXXXX" where X is alphanumeric
-pub fn validate_code(code: &str) -> Result<(), ValidationError> {
+/// - At least `min_length` characters after the prefix (never fewer than
+///   `MIN_CODE_LENGTH`), so shorter codes stay valid when a longer
+///   `CODE_LENGTH` is configured unless the minimum is raised too
+pub fn validate_code(code: &str, min_length: usize) -> Result<(), ValidationError> {
     if code.is_empty() {
         return Err(ValidationError::CodeEmpty);
     }
 
-    // Expected format: "oxeye-XXXXXX" (6+ alphanumeric chars after prefix)
-    if !code.starts_with("oxeye-") || code.len() < 12 {
+    let min_length = min_length.max(MIN_CODE_LENGTH);
+    if !code.starts_with("oxeye-") || code.len() < "oxeye-".len() + min_length {
         return Err(ValidationError::CodeInvalidFormat);
     }
 
@@ -86,34 +192,70 @@ pub fn validate_code(code: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Default maximum number of players in a single bulk request
+pub const DEFAULT_MAX_PLAYERS: usize = 1000;
+
 /// Validates a list of player names for bulk operations
 ///
 /// Rules:
-/// - Max 1000 players per request (prevents DOS)
-/// - Each player name must be valid
-pub fn validate_player_list(players: &[PlayerName]) -> Result<(), ValidationError> {
-    const MAX_PLAYERS: usize = 1000;
-
-    if players.len() > MAX_PLAYERS {
+/// - At most `max_players` players per request (prevents DOS)
+/// - Each player name must be valid under `policy`
+pub fn validate_player_list(
+    players: &[PlayerName],
+    max_players: usize,
+    policy: NamePolicy,
+) -> Result<(), ValidationError> {
+    if players.len() > max_players {
         return Err(ValidationError::PlayerListTooLarge {
-            max: MAX_PLAYERS,
+            max: max_players,
             actual: players.len(),
         });
     }
 
-    // Validate each player name (ArrayString guarantees <= 16 chars, just check content)
     for player in players {
-        validate_player_name(player.as_str())?;
+        validate_player_name(player.as_str(), policy)?;
     }
 
     Ok(())
 }
 
+/// Validates that a sync list is non-empty when empty syncs aren't allowed
+///
+/// Guards against plugins that briefly report no players (e.g. during a world
+/// reload) and would otherwise wipe the online list.
+pub fn validate_sync_list(
+    players: &[PlayerName],
+    allow_empty: bool,
+) -> Result<(), ValidationError> {
+    if players.is_empty() && !allow_empty {
+        return Err(ValidationError::PlayerListEmpty);
+    }
+    Ok(())
+}
+
+/// Drops entries whose player name repeats an earlier one ignoring ASCII
+/// case, keeping the first spelling
+///
+/// Minecraft names are case-insensitive for one account, so "Steve" and
+/// "steve" in a sync list are the same player reported twice.
+pub fn fold_case_duplicates<T>(entries: &mut Vec<T>, name: impl Fn(&T) -> &PlayerName) {
+    let mut seen = HashSet::with_capacity(entries.len());
+    entries.retain(|entry| {
+        let mut folded = *name(entry);
+        folded.make_ascii_lowercase();
+        seen.insert(folded)
+    });
+}
+
 /// Validates a server name
 ///
 /// Rules:
 /// - Cannot be empty
 /// - Max 100 characters
+/// - No control characters (including newlines) or zero-width/bidi characters
+///
+/// Markdown and mentions are allowed here; they are escaped for display
+/// with `helpers::escape_markdown`.
 pub fn validate_server_name(name: &str) -> Result<(), ValidationError> {
     if name.is_empty() {
         return Err(ValidationError::ServerNameEmpty);
@@ -123,9 +265,52 @@ pub fn validate_server_name(name: &str) -> Result<(), ValidationError> {
         return Err(ValidationError::ServerNameTooLong(name.len()));
     }
 
+    if name.chars().any(is_hidden_char) {
+        return Err(ValidationError::ServerNameInvalidChars);
+    }
+
+    Ok(())
+}
+
+/// Longest server tag accepted, in characters
+pub const MAX_SERVER_TAG_LENGTH: usize = 32;
+
+/// Validates a server tag (the group a server is listed under)
+///
+/// Rules:
+/// - Cannot be empty
+/// - Max `MAX_SERVER_TAG_LENGTH` characters
+/// - No control or zero-width/bidi characters (same as server names)
+pub fn validate_server_tag(tag: &str) -> Result<(), ValidationError> {
+    if tag.is_empty() {
+        return Err(ValidationError::ServerTagEmpty);
+    }
+
+    let length = tag.chars().count();
+    if length > MAX_SERVER_TAG_LENGTH {
+        return Err(ValidationError::ServerTagTooLong {
+            max: MAX_SERVER_TAG_LENGTH,
+            actual: length,
+        });
+    }
+
+    if tag.chars().any(is_hidden_char) {
+        return Err(ValidationError::ServerTagInvalidChars);
+    }
+
     Ok(())
 }
 
+/// Control characters and zero-width/bidi characters that would hide or
+/// reorder text in Discord
+fn is_hidden_char(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}'
+        )
+}
+
 /// Validates a texture hash (SHA256 of GameProfile texture value)
 ///
 /// Rules:
@@ -150,6 +335,41 @@ pub fn validate_texture_hash(hash: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Validates a player UUID
+///
+/// Rules:
+/// - 32 hex characters, or
+/// - 36 characters hyphenated as 8-4-4-4-12 hex digits
+pub fn validate_uuid(uuid: &str) -> Result<(), ValidationError> {
+    let valid = match uuid.len() {
+        32 => uuid.chars().all(|c| c.is_ascii_hexdigit()),
+        36 => uuid.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        }),
+        _ => false,
+    };
+    if !valid {
+        return Err(ValidationError::UuidInvalidFormat);
+    }
+
+    Ok(())
+}
+
+/// Normalizes a valid player UUID to lowercase 8-4-4-4-12 hyphenated form,
+/// so the same player is stored the same way whichever form a server sent.
+pub fn normalize_uuid(uuid: &str) -> String {
+    let hex = uuid.replace('-', "").to_ascii_lowercase();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
 /// Validates base64-encoded skin data
 ///
 /// Rules:
@@ -172,6 +392,115 @@ pub fn validate_skin_data(data: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Parses an embed color given as a hex code
+///
+/// Rules:
+/// - Exactly 6 hex digits, optionally prefixed with "#" or "0x"
+/// - Surrounding whitespace is ignored
+pub fn parse_hex_color(input: &str) -> Result<u32, ValidationError> {
+    let input = input.trim();
+    let digits = input
+        .strip_prefix('#')
+        .or_else(|| input.strip_prefix("0x"))
+        .unwrap_or(input);
+
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ValidationError::ColorInvalidFormat);
+    }
+
+    u32::from_str_radix(digits, 16).map_err(|_| ValidationError::ColorInvalidFormat)
+}
+
+/// Maximum length of a guild webhook URL
+pub const MAX_WEBHOOK_URL_LEN: usize = 2048;
+
+/// Validates a webhook URL set with /setwebhook
+///
+/// Rules:
+/// - Starts with "http://" or "https://" followed by a host
+/// - No whitespace or control characters
+/// - Max 2048 characters
+/// - Unless `allow_private`, the host isn't localhost or a non-public IP
+///   (names are checked again when they are resolved for delivery)
+pub fn validate_webhook_url(url: &str, allow_private: bool) -> Result<(), ValidationError> {
+    let invalid = || ValidationError::WebhookUrlInvalid {
+        max: MAX_WEBHOOK_URL_LEN,
+    };
+    if url.len() > MAX_WEBHOOK_URL_LEN {
+        return Err(invalid());
+    }
+
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or_else(invalid)?;
+    let host_ends = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    if host_ends == 0 || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(invalid());
+    }
+
+    let authority = &rest[..host_ends];
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    let private = host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| !is_public_ip(ip));
+    if private && !allow_private {
+        return Err(ValidationError::WebhookUrlPrivate);
+    }
+
+    Ok(())
+}
+
+/// Whether `ip` is reachable on the public internet, i.e. not loopback, private,
+/// link-local, carrier-grade NAT, multicast or otherwise reserved.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ipv4) => is_public_ip(IpAddr::V4(ipv4)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// How far past the server clock a client's timestamp may be (clock skew).
+const MAX_TIMESTAMP_SKEW_SECS: i64 = 300;
+
+/// Validates a unix timestamp sent by a client, like `since` on /status
+///
+/// Rules:
+/// - Not negative
+/// - No more than 5 minutes after `now` (catches milliseconds sent as seconds)
+pub fn validate_timestamp(timestamp: i64, now: i64) -> Result<(), ValidationError> {
+    if !(0..=now.saturating_add(MAX_TIMESTAMP_SKEW_SECS)).contains(&timestamp) {
+        return Err(ValidationError::TimestampOutOfRange(timestamp));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,17 +508,22 @@ mod tests {
     // Player name validation tests
     #[test]
     fn test_valid_player_names() {
-        assert!(validate_player_name("Steve").is_ok());
-        assert!(validate_player_name("Alex").is_ok());
-        assert!(validate_player_name("Player_123").is_ok());
-        assert!(validate_player_name("a").is_ok());
-        assert!(validate_player_name("1234567890123456").is_ok()); // exactly 16 chars
+        let java = NamePolicy::Java;
+        assert!(validate_player_name("Steve", java).is_ok());
+        assert!(validate_player_name("Alex", java).is_ok());
+        assert!(validate_player_name("Player_123", java).is_ok());
+        assert!(validate_player_name("a", java).is_ok());
+        assert!(validate_player_name("1234567890123456", java).is_ok()); // exactly 16 chars
     }
 
     #[test]
     fn test_empty_player_name() {
         assert_eq!(
-            validate_player_name(""),
+            validate_player_name("", NamePolicy::Java),
+            Err(ValidationError::PlayerNameEmpty)
+        );
+        assert_eq!(
+            validate_player_name("", NamePolicy::Bedrock),
             Err(ValidationError::PlayerNameEmpty)
         );
     }
@@ -198,76 +532,153 @@ mod tests {
     fn test_player_name_too_long() {
         let long_name = "12345678901234567"; // 17 characters
         assert_eq!(
-            validate_player_name(long_name),
-            Err(ValidationError::PlayerNameTooLong(17))
+            validate_player_name(long_name, NamePolicy::Java),
+            Err(ValidationError::PlayerNameTooLong {
+                max: 16,
+                actual: 17
+            })
         );
     }
 
     #[test]
     fn test_player_name_invalid_chars() {
+        let java = NamePolicy::Java;
         assert_eq!(
-            validate_player_name("Player-123"),
+            validate_player_name("Player-123", java),
             Err(ValidationError::PlayerNameInvalidChars)
         );
         assert_eq!(
-            validate_player_name("Player@123"),
+            validate_player_name("Player@123", java),
             Err(ValidationError::PlayerNameInvalidChars)
         );
         assert_eq!(
-            validate_player_name("Player 123"),
+            validate_player_name("Player 123", java),
             Err(ValidationError::PlayerNameInvalidChars)
         );
     }
 
+    #[test]
+    fn test_bedrock_player_names() {
+        let bedrock = NamePolicy::Bedrock;
+        assert!(validate_player_name("Cool Gamer 42", bedrock).is_ok());
+        assert!(validate_player_name("Steve", bedrock).is_ok());
+        assert!(validate_player_name("A Much Longer Gamertag Name", bedrock).is_ok());
+        assert_eq!(
+            validate_player_name(" Leading", bedrock),
+            Err(ValidationError::PlayerNameInvalidCharsOrSpaces)
+        );
+        assert_eq!(
+            validate_player_name("Double  Space", bedrock),
+            Err(ValidationError::PlayerNameInvalidCharsOrSpaces)
+        );
+        assert_eq!(
+            validate_player_name("Player-123", bedrock),
+            Err(ValidationError::PlayerNameInvalidCharsOrSpaces)
+        );
+    }
+
+    #[test]
+    fn test_custom_name_policy_length() {
+        let policy = NamePolicy::Custom { max_length: 20 };
+        assert!(validate_player_name("Twenty Chars Exactly", policy).is_ok());
+        assert_eq!(
+            validate_player_name("Twenty One Characters", policy),
+            Err(ValidationError::PlayerNameTooLong {
+                max: 20,
+                actual: 21
+            })
+        );
+        // Never more than fits in a PlayerName
+        assert_eq!(
+            NamePolicy::Custom { max_length: 100 }.max_length(),
+            MAX_PLAYER_NAME_LEN
+        );
+    }
+
+    #[test]
+    fn test_parse_name_policy() {
+        assert_eq!("java".parse(), Ok(NamePolicy::Java));
+        assert_eq!("Bedrock".parse(), Ok(NamePolicy::Bedrock));
+        assert_eq!(
+            "custom:24".parse(),
+            Ok(NamePolicy::Custom { max_length: 24 })
+        );
+        assert!("custom:0".parse::<NamePolicy>().is_err());
+        assert!("pocket".parse::<NamePolicy>().is_err());
+    }
+
     // Code validation tests
     #[test]
     fn test_valid_codes() {
-        assert!(validate_code("oxeye-abc123").is_ok());
-        assert!(validate_code("oxeye-ABCDEF").is_ok());
-        assert!(validate_code("oxeye-123456").is_ok());
-        assert!(validate_code("oxeye-aB3DeF").is_ok());
+        assert!(validate_code("oxeye-abc123", MIN_CODE_LENGTH).is_ok());
+        assert!(validate_code("oxeye-ABCDEF", MIN_CODE_LENGTH).is_ok());
+        assert!(validate_code("oxeye-123456", MIN_CODE_LENGTH).is_ok());
+        assert!(validate_code("oxeye-aB3DeF", MIN_CODE_LENGTH).is_ok());
+        assert!(validate_code("oxeye-aB3DeFgH4jKm", MIN_CODE_LENGTH).is_ok());
     }
 
     #[test]
     fn test_empty_code() {
-        assert_eq!(validate_code(""), Err(ValidationError::CodeEmpty));
+        assert_eq!(
+            validate_code("", MIN_CODE_LENGTH),
+            Err(ValidationError::CodeEmpty)
+        );
     }
 
     #[test]
     fn test_code_invalid_format() {
         assert_eq!(
-            validate_code("invalid-abc123"),
+            validate_code("invalid-abc123", MIN_CODE_LENGTH),
             Err(ValidationError::CodeInvalidFormat)
         );
         assert_eq!(
-            validate_code("oxeye-"),
+            validate_code("oxeye-", MIN_CODE_LENGTH),
             Err(ValidationError::CodeInvalidFormat)
         );
         assert_eq!(
-            validate_code("oxeye-abc"),
+            validate_code("oxeye-abc", MIN_CODE_LENGTH),
             Err(ValidationError::CodeInvalidFormat)
         );
         assert_eq!(
-            validate_code("oxeye-abc-123"),
+            validate_code("oxeye-abc-123", MIN_CODE_LENGTH),
             Err(ValidationError::CodeInvalidFormat)
         );
     }
 
+    #[test]
+    fn test_code_min_length() {
+        // A raised minimum rejects codes that the default would accept
+        assert!(validate_code("oxeye-abc123", 8).is_err());
+        assert!(validate_code("oxeye-abc12345", 8).is_ok());
+        // The minimum never drops below MIN_CODE_LENGTH
+        assert!(validate_code("oxeye-abc", 2).is_err());
+    }
+
     // Player list validation tests
     fn pn(s: &str) -> PlayerName {
         PlayerName::from(s).unwrap()
     }
 
+    #[test]
+    fn test_sync_list_empty() {
+        assert!(validate_sync_list(&[], true).is_ok());
+        assert_eq!(
+            validate_sync_list(&[], false),
+            Err(ValidationError::PlayerListEmpty)
+        );
+        assert!(validate_sync_list(&[pn("Steve")], false).is_ok());
+    }
+
     #[test]
     fn test_valid_player_list() {
         let players = vec![pn("Steve"), pn("Alex"), pn("Notch")];
-        assert!(validate_player_list(&players).is_ok());
+        assert!(validate_player_list(&players, DEFAULT_MAX_PLAYERS, NamePolicy::Java).is_ok());
     }
 
     #[test]
     fn test_empty_player_list() {
         let players: Vec<PlayerName> = vec![];
-        assert!(validate_player_list(&players).is_ok()); // Empty list is valid
+        assert!(validate_player_list(&players, DEFAULT_MAX_PLAYERS, NamePolicy::Java).is_ok()); // Empty list is valid
     }
 
     #[test]
@@ -276,7 +687,7 @@ mod tests {
             .map(|i| PlayerName::from(&format!("P{:04}", i % 10000)).unwrap())
             .collect();
         assert_eq!(
-            validate_player_list(&players),
+            validate_player_list(&players, DEFAULT_MAX_PLAYERS, NamePolicy::Java),
             Err(ValidationError::PlayerListTooLarge {
                 max: 1000,
                 actual: 1001
@@ -284,12 +695,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_player_list_custom_limit() {
+        let players = vec![pn("Steve"), pn("Alex"), pn("Notch")];
+        assert!(validate_player_list(&players, 3, NamePolicy::Java).is_ok());
+        assert_eq!(
+            validate_player_list(&players, 2, NamePolicy::Java),
+            Err(ValidationError::PlayerListTooLarge { max: 2, actual: 3 })
+        );
+    }
+
     #[test]
     fn test_player_list_with_invalid_name() {
         // Note: Empty string can't be deserialized into PlayerName at route level,
         // but we test that validation catches invalid chars
         let players = vec![pn("Steve"), pn("Player_1")];
-        assert!(validate_player_list(&players).is_ok());
+        assert!(validate_player_list(&players, DEFAULT_MAX_PLAYERS, NamePolicy::Java).is_ok());
     }
 
     // Server name validation tests
@@ -309,6 +730,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_server_name_with_newline() {
+        assert_eq!(
+            validate_server_name("Survival\n@everyone"),
+            Err(ValidationError::ServerNameInvalidChars)
+        );
+        assert_eq!(
+            validate_server_name("Tab\tName"),
+            Err(ValidationError::ServerNameInvalidChars)
+        );
+    }
+
+    #[test]
+    fn test_server_name_with_zero_width_space() {
+        assert_eq!(
+            validate_server_name("Sur\u{200B}vival"),
+            Err(ValidationError::ServerNameInvalidChars)
+        );
+    }
+
+    #[test]
+    fn test_server_name_with_markdown_is_allowed() {
+        // Escaped at display time rather than rejected
+        assert!(validate_server_name("**@everyone**").is_ok());
+        assert!(validate_server_name("`Creative`").is_ok());
+    }
+
     #[test]
     fn test_server_name_too_long() {
         let long_name = "a".repeat(101);
@@ -396,4 +844,197 @@ mod tests {
             })
         );
     }
+
+    // Server tag validation tests
+    #[test]
+    fn test_validate_server_tag() {
+        assert!(validate_server_tag("SMP").is_ok());
+        assert!(validate_server_tag("Mini Games ⚔").is_ok());
+        assert_eq!(
+            validate_server_tag(""),
+            Err(ValidationError::ServerTagEmpty)
+        );
+        assert_eq!(
+            validate_server_tag(&"x".repeat(33)),
+            Err(ValidationError::ServerTagTooLong {
+                max: 32,
+                actual: 33
+            })
+        );
+        assert_eq!(
+            validate_server_tag("SMP\n@everyone"),
+            Err(ValidationError::ServerTagInvalidChars)
+        );
+        assert_eq!(
+            validate_server_tag("S\u{200B}MP"),
+            Err(ValidationError::ServerTagInvalidChars)
+        );
+    }
+
+    // UUID validation tests
+    #[test]
+    fn test_valid_uuids() {
+        assert!(validate_uuid("069a79f444e94726a5befca90e38aaf5").is_ok());
+        assert!(validate_uuid("069a79f4-44e9-4726-a5be-fca90e38aaf5").is_ok());
+        assert!(validate_uuid("069A79F4-44E9-4726-A5BE-FCA90E38AAF5").is_ok());
+    }
+
+    #[test]
+    fn test_normalize_uuid() {
+        for input in [
+            "069a79f444e94726a5befca90e38aaf5",
+            "069A79F444E94726A5BEFCA90E38AAF5",
+            "069a79f4-44e9-4726-a5be-fca90e38aaf5",
+            "069A79F4-44E9-4726-A5BE-FCA90E38AAF5",
+        ] {
+            assert_eq!(
+                normalize_uuid(input),
+                "069a79f4-44e9-4726-a5be-fca90e38aaf5",
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_uuids() {
+        for input in [
+            "",
+            "069a79f444e94726a5befca90e38aaf",
+            "069a79f444e94726a5befca90e38aaf5a",
+            "069a79f444e94726a5befca90e38aazz",
+            "069a79f4-44e9-4726-a5be-fca90e38aaf",
+            "069a79f444-e9-4726-a5be-fca90e38aaf5",
+            "069a79f4-44e9-4726-a5be-fca90e38aaf5-",
+            "{069a79f4-44e9-4726-a5be-fca90e38aa}",
+        ] {
+            assert_eq!(
+                validate_uuid(input),
+                Err(ValidationError::UuidInvalidFormat),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    // Color parsing tests
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#5865F2"), Ok(0x5865F2));
+        assert_eq!(parse_hex_color("ff8800"), Ok(0xFF8800));
+        assert_eq!(parse_hex_color("0x00aa55"), Ok(0x00AA55));
+        assert_eq!(parse_hex_color("  #000000 "), Ok(0));
+    }
+
+    #[test]
+    fn test_parse_hex_color_invalid() {
+        for input in ["", "#", "#FFF", "#12345G", "#1234567", "blurple", "+12345"] {
+            assert_eq!(
+                parse_hex_color(input),
+                Err(ValidationError::ColorInvalidFormat),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    // Webhook URL validation tests
+    #[test]
+    fn test_valid_webhook_urls() {
+        assert!(validate_webhook_url("https://example.com/hook", false).is_ok());
+        assert!(validate_webhook_url("http://93.184.216.34:8080", false).is_ok());
+        assert!(
+            validate_webhook_url("https://discord.com/api/webhooks/1/abc?wait=true", false).is_ok()
+        );
+        // Local targets only when explicitly allowed
+        assert!(validate_webhook_url("http://127.0.0.1:8080", true).is_ok());
+    }
+
+    #[test]
+    fn test_private_webhook_urls() {
+        for input in [
+            "http://localhost/hook",
+            "http://LOCALHOST:8080",
+            "http://127.0.0.1:8080",
+            "http://10.1.2.3/hook",
+            "http://192.168.0.10",
+            "http://169.254.169.254/latest/meta-data",
+            "http://user@172.16.0.1/hook",
+            "http://[::1]:8080/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert_eq!(
+                validate_webhook_url(input, false),
+                Err(ValidationError::WebhookUrlPrivate),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["93.184.216.34", "2606:4700::1111", "1.1.1.1"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "0.0.0.0",
+            "100.64.0.1",
+            "127.0.0.1",
+            "::",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_invalid_webhook_urls() {
+        let too_long = format!("https://example.com/{}", "a".repeat(MAX_WEBHOOK_URL_LEN));
+        for input in [
+            "",
+            "example.com/hook",
+            "ftp://example.com",
+            "https://",
+            "https:///path",
+            "https://exa mple.com",
+            too_long.as_str(),
+        ] {
+            assert_eq!(
+                validate_webhook_url(input, false),
+                Err(ValidationError::WebhookUrlInvalid {
+                    max: MAX_WEBHOOK_URL_LEN
+                }),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_fold_case_duplicates() {
+        let mut players: Vec<PlayerName> = ["Steve", "steve", "Alex", "STEVE", "alex", "Notch"]
+            .iter()
+            .map(|name| PlayerName::from(name).unwrap())
+            .collect();
+        fold_case_duplicates(&mut players, |player| player);
+        let names: Vec<&str> = players.iter().map(|player| player.as_str()).collect();
+        assert_eq!(names, ["Steve", "Alex", "Notch"]);
+    }
+
+    #[test]
+    fn test_validate_timestamp() {
+        let now = 1_700_000_000;
+        for timestamp in [0, now - 3600, now, now + MAX_TIMESTAMP_SKEW_SECS] {
+            assert!(validate_timestamp(timestamp, now).is_ok(), "{}", timestamp);
+        }
+        for timestamp in [-1, now + MAX_TIMESTAMP_SKEW_SECS + 1, now * 1000] {
+            assert_eq!(
+                validate_timestamp(timestamp, now),
+                Err(ValidationError::TimestampOutOfRange(timestamp))
+            );
+        }
+    }
 }
@@ -0,0 +1,163 @@
+//! Outgoing join/leave webhooks for guilds that set one with /setwebhook.
+//!
+//! Route handlers only enqueue a delivery, so a slow or unreachable endpoint
+//! never holds up the Minecraft server. A background worker drains the bounded
+//! queue, sending up to `MAX_CONCURRENT_DELIVERIES` at once, so one slow guild
+//! endpoint doesn't delay everyone else's. Each send looks up the guild's URL,
+//! checks where it points and POSTs the payload; failures are logged and
+//! dropped rather than retried.
+
+use crate::validation::is_public_ip;
+use oxeye_db::{PlayerName, Store};
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Semaphore, mpsc};
+
+/// Deliveries waiting for the worker before new ones are dropped.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// How long one webhook POST may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Webhook POSTs in flight at once; further deliveries wait in the queue.
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// Which player change a webhook reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Join,
+    Leave,
+}
+
+/// A queued webhook, resolved to a guild and URL by the worker.
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub api_key_hash: String,
+    pub player: PlayerName,
+    pub event: WebhookEvent,
+    pub timestamp: i64,
+}
+
+/// JSON body POSTed to the guild's webhook URL.
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload {
+    pub server: String,
+    pub player: PlayerName,
+    pub event: WebhookEvent,
+    pub timestamp: i64,
+}
+
+/// Sending half of the delivery queue, held in `AppState`.
+#[derive(Debug, Clone)]
+pub struct Webhooks {
+    sender: mpsc::Sender<Delivery>,
+}
+
+impl Webhooks {
+    /// Create the queue; the receiver goes to [`run`].
+    pub fn new() -> (Self, mpsc::Receiver<Delivery>) {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        (Self { sender }, receiver)
+    }
+
+    /// Queue a delivery without waiting; dropped (with a warning) if the queue is full.
+    pub fn enqueue(&self, delivery: Delivery) {
+        if let Err(e) = self.sender.try_send(delivery) {
+            tracing::warn!("dropping webhook delivery: {}", e);
+        }
+    }
+}
+
+/// Deliver queued webhooks until every sender is dropped, several at a time.
+///
+/// Unless `allow_private`, a URL whose host is (or resolves to) localhost or a
+/// private network address is refused, so a guild can't use the backend to
+/// reach internal services.
+pub async fn run(db: Arc<dyn Store>, mut receiver: mpsc::Receiver<Delivery>, allow_private: bool) {
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES));
+    while let Some(delivery) = receiver.recv().await {
+        let Ok(permit) = permits.clone().acquire_owned().await else {
+            break;
+        };
+        let db = db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = deliver(&*db, delivery, allow_private).await {
+                tracing::warn!("webhook delivery failed: {}", e);
+            }
+            drop(permit);
+        });
+    }
+}
+
+/// POST one delivery to its guild's webhook, if the guild has one.
+async fn deliver(
+    db: &dyn Store,
+    delivery: Delivery,
+    allow_private: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // The server may have been unlinked since the event was queued
+    let Some(server) = db.get_server_by_api_key(delivery.api_key_hash).await? else {
+        return Ok(());
+    };
+    let Some(url) = db.get_guild_webhook(server.guild_id).await? else {
+        return Ok(());
+    };
+    let url = reqwest::Url::parse(&url)?;
+    let client = client_for(&url, allow_private).await?;
+
+    let payload = WebhookPayload {
+        server: server.name,
+        player: delivery.player,
+        event: delivery.event,
+        timestamp: delivery.timestamp,
+    };
+    client
+        .post(url)
+        .json(&payload)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Build a client that can only reach the addresses `url`'s host resolves to
+/// now, after checking them. Pinning the addresses means a second lookup
+/// (DNS rebinding) can't swap in a private one, and redirects aren't followed
+/// since they could point anywhere.
+async fn client_for(
+    url: &reqwest::Url,
+    allow_private: bool,
+) -> Result<reqwest::Client, Box<dyn std::error::Error + Send + Sync>> {
+    let host = url.host_str().ok_or("webhook URL has no host")?;
+    let port = url
+        .port_or_known_default()
+        .ok_or("webhook URL has no port")?;
+    let literal = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .ok();
+    let addrs: Vec<SocketAddr> = match literal {
+        Some(ip) => vec![SocketAddr::new(ip, port)],
+        None => tokio::net::lookup_host((host, port)).await?.collect(),
+    };
+    if !allow_private && let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(format!(
+            "webhook host {} resolves to non-public address {}",
+            host,
+            addr.ip()
+        )
+        .into());
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+    if literal.is_none() {
+        builder = builder.resolve_to_addrs(host, &addrs);
+    }
+    Ok(builder.build()?)
+}
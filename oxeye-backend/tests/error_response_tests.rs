@@ -3,7 +3,7 @@ use axum::{
     http::{Request, StatusCode},
 };
 use http_body_util::BodyExt;
-use oxeye_backend::{RateLimitConfig, create_app, helpers};
+use oxeye_backend::{create_app, helpers};
 use serde_json::{Value, json};
 use tower::ServiceExt;
 
@@ -17,12 +17,7 @@ async fn setup_test_db() -> oxeye_db::Database {
 /// Helper to create app with default test configuration
 fn create_test_app(db: oxeye_db::Database) -> axum::Router {
     let config = oxeye_backend::config::Config::default();
-    create_app(
-        db,
-        config.request_body_limit,
-        config.request_timeout,
-        RateLimitConfig::default(),
-    )
+    create_app(db, &config)
 }
 
 /// Helper to send a request and get response
@@ -132,14 +127,19 @@ async fn test_error_response_for_validation_failure() {
     let api_key = helpers::generate_api_key();
     let api_key_hash = helpers::hash_api_key(&api_key);
 
-    db.create_server(api_key_hash, "TestServer".to_string(), 123456)
-        .await
-        .expect("Failed to create server");
+    db.create_server(
+        api_key_hash,
+        "TestServer".to_string(),
+        123456,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
 
     let app = create_test_app(db);
 
     // WHEN: Sending invalid player name (too long)
-    let (status, body) = send_request(
+    let (status, _body) = send_request(
         app,
         "POST",
         "/join",
@@ -149,7 +149,7 @@ async fn test_error_response_for_validation_failure() {
     .await;
 
     // THEN: Should return 400 with validation error
-    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(status, StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
@@ -202,9 +202,14 @@ async fn test_validation_error_has_details() {
     let api_key = helpers::generate_api_key();
     let api_key_hash = helpers::hash_api_key(&api_key);
 
-    db.create_server(api_key_hash, "TestServer".to_string(), 123456)
-        .await
-        .expect("Failed to create server");
+    db.create_server(
+        api_key_hash,
+        "TestServer".to_string(),
+        123456,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
 
     let app = create_test_app(db);
 
@@ -2,9 +2,10 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
+use base64::Engine;
 use http_body_util::BodyExt;
-use oxeye_backend::{RateLimitConfig, create_app, helpers};
-use oxeye_db::PlayerName;
+use oxeye_backend::{create_app, helpers};
+use oxeye_db::{PlayerName, RecordingStore};
 use serde_json::{Value, json};
 use tower::ServiceExt;
 // for `oneshot` method
@@ -23,13 +24,12 @@ async fn setup_test_db() -> oxeye_db::Database {
 
 /// Helper to create app with default test configuration
 fn create_test_app(db: oxeye_db::Database) -> axum::Router {
-    let config = oxeye_backend::config::Config::default();
-    create_app(
-        db,
-        config.request_body_limit,
-        config.request_timeout,
-        RateLimitConfig::default(),
-    )
+    let config = oxeye_backend::config::Config {
+        // Keep joins with a UUID from reaching the real session server
+        mojang_session_server: "http://127.0.0.1:1".to_string(),
+        ..Default::default()
+    };
+    create_app(db, &config)
 }
 
 /// Helper to send a request and get response
@@ -109,6 +109,149 @@ async fn test_health_endpoint_with_post_method() {
     assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
 }
 
+#[tokio::test]
+async fn test_ready_endpoint_pings_database() {
+    // GIVEN: A running application with a working database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a GET request to /ready
+    let (status, _body) = send_request(app, "GET", "/ready", None, None).await;
+
+    // THEN: Should return 200 OK
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_version_endpoint_reports_build() {
+    // GIVEN: A running application
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a GET request to /version
+    let (status, body) = send_request(app, "GET", "/version", None, None).await;
+
+    // THEN: Should report this crate's version, a commit and the uptime
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(!body["git_sha"].as_str().unwrap().is_empty());
+    assert!(body["uptime_secs"].is_u64());
+}
+
+// =============================================================================
+// CORS TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_cors_preflight_allows_configured_origin() {
+    // GIVEN: An application with a dashboard origin allowed
+    let db = setup_test_db().await;
+    let config = oxeye_backend::config::Config {
+        allowed_origins: vec!["https://dash.example.com".to_string()],
+        ..Default::default()
+    };
+    let app = create_app(db, &config);
+
+    // WHEN: The browser sends a preflight for an authenticated GET
+    let request = Request::builder()
+        .uri("/guilds/123/export")
+        .method("OPTIONS")
+        .header("Origin", "https://dash.example.com")
+        .header("Access-Control-Request-Method", "GET")
+        .header("Access-Control-Request-Headers", "authorization")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    // THEN: The origin and Authorization header are allowed
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "https://dash.example.com"
+    );
+    assert!(
+        headers["access-control-allow-headers"]
+            .to_str()
+            .unwrap()
+            .contains("authorization")
+    );
+}
+
+#[tokio::test]
+async fn test_cors_disabled_by_default() {
+    // GIVEN: An application with the default configuration
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: A cross-origin request is made
+    let request = Request::builder()
+        .uri("/health")
+        .method("GET")
+        .header("Origin", "https://dash.example.com")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    // THEN: No CORS headers are returned
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        !response
+            .headers()
+            .contains_key("access-control-allow-origin")
+    );
+}
+
+// =============================================================================
+// METRICS ENDPOINT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_metrics_counts_joins() {
+    // GIVEN: A connected server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+
+    db.create_server(
+        api_key_hash,
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: A player joins
+    let (status, _body) = send_request(
+        app.clone(),
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve" })),
+        Some(&api_key),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // AND: Metrics are scraped
+    let request = Request::builder()
+        .uri("/metrics")
+        .method("GET")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+
+    // THEN: The join counter and online gauge reflect the join
+    assert!(body.contains("oxeye_joins_total 1"), "metrics: {}", body);
+    assert!(body.contains("oxeye_leaves_total 0"), "metrics: {}", body);
+    assert!(body.contains("oxeye_online_players 1"), "metrics: {}", body);
+}
+
 // =============================================================================
 // CONNECT ENDPOINT TESTS
 // =============================================================================
@@ -117,12 +260,12 @@ async fn test_health_endpoint_with_post_method() {
 async fn test_connect_success() {
     // GIVEN: A pending link exists in the database
     let db = setup_test_db().await;
-    let code = helpers::generate_code();
+    let code = helpers::generate_code(6);
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
     let now = helpers::now();
 
-    db.create_pending_link(code.clone(), guild_id, server_name.clone(), now)
+    db.create_pending_link(code.clone(), guild_id, server_name.clone(), now, 25)
         .await
         .expect("Failed to create pending link");
 
@@ -132,8 +275,9 @@ async fn test_connect_success() {
     let (status, body) =
         send_request(app, "POST", "/connect", Some(json!({ "code": code })), None).await;
 
-    // THEN: Should return 201 OK with an API key
+    // THEN: Should return 201 OK with an API key and the linked server name
     assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["server_name"], "TestServer");
     assert!(body.get("api_key").is_some());
     let api_key = body["api_key"].as_str().unwrap();
     assert!(api_key.starts_with("oxeye-sk-"));
@@ -164,13 +308,13 @@ async fn test_connect_with_nonexistent_code() {
 async fn test_connect_with_expired_code() {
     // GIVEN: An expired pending link (created 11 minutes ago)
     let db = setup_test_db().await;
-    let code = helpers::generate_code();
+    let code = helpers::generate_code(6);
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
     let now = helpers::now();
     let eleven_minutes_ago = now - 11 * 60; // 11 minutes = 660 seconds
 
-    db.create_pending_link(code.clone(), guild_id, server_name, eleven_minutes_ago)
+    db.create_pending_link(code.clone(), guild_id, server_name, eleven_minutes_ago, 25)
         .await
         .expect("Failed to create pending link");
 
@@ -188,12 +332,12 @@ async fn test_connect_with_expired_code() {
 async fn test_connect_with_already_used_code() {
     // GIVEN: A pending link that has been consumed
     let db = setup_test_db().await;
-    let code = helpers::generate_code();
+    let code = helpers::generate_code(6);
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
     let now = helpers::now();
 
-    db.create_pending_link(code.clone(), guild_id, server_name.clone(), now)
+    db.create_pending_link(code.clone(), guild_id, server_name.clone(), now, 25)
         .await
         .expect("Failed to create pending link");
 
@@ -205,11 +349,41 @@ async fn test_connect_with_already_used_code() {
     let app = create_test_app(db);
 
     // WHEN: Making a POST request to /connect with already used code
-    let (status, _body) =
+    let (status, body) =
         send_request(app, "POST", "/connect", Some(json!({ "code": code })), None).await;
 
-    // THEN: Should return 404 Not Found (code no longer exists)
-    assert_eq!(status, StatusCode::NOT_FOUND);
+    // THEN: Should return 409 Conflict (the code was real, but is spent)
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"], "Connection code has already been used");
+}
+
+#[tokio::test]
+async fn test_concurrent_connects_on_one_code() {
+    // GIVEN: A pending link
+    let db = setup_test_db().await;
+    let code = helpers::generate_code(6);
+    db.create_pending_link(
+        code.clone(),
+        123456789,
+        "TestServer".to_string(),
+        helpers::now(),
+        25,
+    )
+    .await
+    .expect("Failed to create pending link");
+    let app = create_test_app(db);
+
+    // WHEN: Two plugins try to claim it at the same time
+    let body = json!({ "code": code });
+    let (first, second) = tokio::join!(
+        send_request(app.clone(), "POST", "/connect", Some(body.clone()), None),
+        send_request(app, "POST", "/connect", Some(body), None),
+    );
+
+    // THEN: Exactly one gets the key, and the other is told the code was claimed
+    let mut statuses = [first.0, second.0];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::CREATED, StatusCode::CONFLICT]);
 }
 
 #[tokio::test]
@@ -222,17 +396,17 @@ async fn test_connect_with_server_name_conflict() {
     // Create existing server
     let existing_api_key = helpers::generate_api_key();
     let existing_hash = helpers::hash_api_key(&existing_api_key);
-    db.create_server(existing_hash, server_name.clone(), guild_id)
+    db.create_server(existing_hash, server_name.clone(), guild_id, helpers::now())
         .await
         .expect("Failed to create existing server");
 
     // Create pending link with same server name
-    let code = helpers::generate_code();
+    let code = helpers::generate_code(6);
     let now = helpers::now();
 
     // This should fail at creation time
     let result = db
-        .create_pending_link(code.clone(), guild_id, server_name, now)
+        .create_pending_link(code.clone(), guild_id, server_name, now, 25)
         .await;
 
     // THEN: Should return error for name conflict
@@ -243,6 +417,136 @@ async fn test_connect_with_server_name_conflict() {
     ));
 }
 
+#[tokio::test]
+async fn test_connect_relinks_existing_server() {
+    // GIVEN: A linked server whose key was lost, and a relink code for its name
+    let db = setup_test_db().await;
+    let guild_id = 123456789u64;
+    let old_api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&old_api_key),
+        "TestServer".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create existing server");
+    let code = helpers::generate_code(6);
+    db.create_relink_code(
+        code.clone(),
+        guild_id,
+        "TestServer".to_string(),
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create relink code");
+    let app = create_test_app(db.clone());
+
+    // WHEN: The server redeems the code
+    let (status, body) = send_request(
+        app.clone(),
+        "POST",
+        "/connect",
+        Some(json!({ "code": code })),
+        None,
+    )
+    .await;
+
+    // THEN: It gets a new key for the same server instead of a name conflict
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["server_name"], "TestServer");
+    let new_api_key = body["api_key"].as_str().unwrap();
+    assert_ne!(new_api_key, old_api_key);
+    assert_eq!(db.count_servers_in_guild(guild_id).await.unwrap(), 1);
+
+    // AND: Only the new key works
+    let (status, _) = send_request(app.clone(), "GET", "/status", None, Some(new_api_key)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_request(app, "GET", "/status", None, Some(&old_api_key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_connect_status_for_valid_code() {
+    // GIVEN: A pending link created a minute ago
+    let db = setup_test_db().await;
+    let code = helpers::generate_code(6);
+    db.create_pending_link(
+        code.clone(),
+        123456789,
+        "TestServer".to_string(),
+        helpers::now() - 60,
+        25,
+    )
+    .await
+    .expect("Failed to create pending link");
+    let app = create_test_app(db);
+
+    // WHEN: Checking the code
+    let (status, body) = send_request(
+        app.clone(),
+        "GET",
+        &format!("/connect/{}", code),
+        None,
+        None,
+    )
+    .await;
+
+    // THEN: It is valid with about nine minutes left
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["valid"], true);
+    let expires_in = body["expires_in"].as_i64().unwrap();
+    assert!((535..=540).contains(&expires_in), "{}", expires_in);
+
+    // AND: Checking didn't claim it
+    let (status, _) =
+        send_request(app, "POST", "/connect", Some(json!({ "code": code })), None).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_connect_status_for_expired_code() {
+    // GIVEN: A pending link created 11 minutes ago
+    let db = setup_test_db().await;
+    let code = helpers::generate_code(6);
+    db.create_pending_link(
+        code.clone(),
+        123456789,
+        "TestServer".to_string(),
+        helpers::now() - 11 * 60,
+        25,
+    )
+    .await
+    .expect("Failed to create pending link");
+    let app = create_test_app(db);
+
+    // WHEN: Checking the code
+    let (status, _body) = send_request(app, "GET", &format!("/connect/{}", code), None, None).await;
+
+    // THEN: Should return 404 Not Found
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_connect_status_for_unknown_and_malformed_codes() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Checking a well-formed code that was never issued
+    let (status, _body) =
+        send_request(app.clone(), "GET", "/connect/oxeye-abc123", None, None).await;
+
+    // THEN: Should return 404 Not Found
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // WHEN: Checking a malformed code
+    let (status, _body) = send_request(app, "GET", "/connect/not-a-code", None, None).await;
+
+    // THEN: Should return 400 Bad Request
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_connect_with_invalid_code_format() {
     // GIVEN: A running application
@@ -263,6 +567,101 @@ async fn test_connect_with_invalid_code_format() {
     assert_eq!(status, StatusCode::BAD_REQUEST);
 }
 
+#[tokio::test]
+async fn test_connect_blocks_repeated_bad_codes() {
+    // GIVEN: An app that blocks a client after 3 misses, with a pending link
+    let (app, code) = connect_guard_app(Vec::new()).await;
+
+    // WHEN: One client guesses wrong three times, claiming a new address each time
+    for forwarded in ["198.51.100.1", "198.51.100.2", "198.51.100.3"] {
+        let response = connect_from(&app, "203.0.113.7", forwarded, helpers::generate_code(6))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    // THEN: Its next attempt is refused, even with the right code
+    let response = connect_from(&app, "203.0.113.7", "198.51.100.4", code.clone())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+
+    // AND: Other clients can still connect
+    let response = connect_from(&app, "203.0.113.8", "198.51.100.1", code)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_connect_guard_uses_forwarded_ip_behind_trusted_proxy() {
+    // GIVEN: An app behind a trusted proxy that blocks a client after 3 misses
+    let (app, code) = connect_guard_app(vec!["10.0.0.1".parse().unwrap()]).await;
+
+    // WHEN: One client behind the proxy guesses wrong three times
+    for _ in 0..3 {
+        let response = connect_from(&app, "10.0.0.1", "203.0.113.7", helpers::generate_code(6))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    // THEN: That client is blocked
+    let response = connect_from(&app, "10.0.0.1", "203.0.113.7", code.clone())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // AND: Other clients behind the same proxy are not
+    let response = connect_from(&app, "10.0.0.1", "203.0.113.8", code)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+/// App for connect guard tests (3 misses allowed) and a valid code for it.
+async fn connect_guard_app(trusted_proxies: Vec<std::net::IpAddr>) -> (axum::Router, String) {
+    let db = setup_test_db().await;
+    let code = helpers::generate_code(6);
+    db.create_pending_link(
+        code.clone(),
+        123456789,
+        "TestServer".to_string(),
+        helpers::now(),
+        25,
+    )
+    .await
+    .expect("Failed to create pending link");
+    let config = oxeye_backend::config::Config {
+        rate_limit_connect_per_min: 6000,
+        rate_limit_connect_burst: 100,
+        connect_max_failures: 3,
+        trusted_proxies,
+        ..Default::default()
+    };
+    (create_app(db, &config), code)
+}
+
+/// POST /connect from peer address `peer` with an X-Forwarded-For of `forwarded`.
+async fn connect_from(
+    app: &axum::Router,
+    peer: &str,
+    forwarded: &str,
+    code: String,
+) -> Result<axum::response::Response, std::convert::Infallible> {
+    let peer: std::net::SocketAddr = format!("{peer}:40000").parse().unwrap();
+    let request = Request::builder()
+        .uri("/connect")
+        .method("POST")
+        .header("X-Forwarded-For", forwarded)
+        .header("Content-Type", "application/json")
+        .extension(axum::extract::ConnectInfo(peer))
+        .body(Body::from(json!({ "code": code }).to_string()))
+        .unwrap();
+    app.clone().oneshot(request).await
+}
+
 #[tokio::test]
 async fn test_connect_without_body() {
     // GIVEN: A running application
@@ -302,7 +701,7 @@ async fn test_join_success() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash, server_name, guild_id)
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -342,6 +741,69 @@ async fn test_join_with_invalid_api_key() {
     assert_eq!(status, StatusCode::UNAUTHORIZED);
 }
 
+#[tokio::test]
+async fn test_malformed_api_key_matches_unknown_key_rejection() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+
+    // WHEN: Joining with a token that isn't shaped like an API key, and with
+    // a well-formed key that isn't linked
+    let (malformed_status, malformed_body) = send_request(
+        create_test_app(db.clone()),
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve" })),
+        Some("garbage"),
+    )
+    .await;
+    let (unknown_status, unknown_body) = send_request(
+        create_test_app(db),
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve" })),
+        Some("oxeye-sk-00000000000000000000000000000000"),
+    )
+    .await;
+
+    // THEN: Both get the same 401 response
+    assert_eq!(malformed_status, StatusCode::UNAUTHORIZED);
+    assert_eq!(unknown_status, StatusCode::UNAUTHORIZED);
+    assert_eq!(malformed_body, unknown_body);
+}
+
+#[tokio::test]
+async fn test_malformed_api_key_skips_database() {
+    // GIVEN: A store that records every call made to it
+    let (store, calls) = RecordingStore::new(setup_test_db().await);
+    let app = create_app(store, &oxeye_backend::config::Config::default());
+
+    // WHEN: Joining with a token that isn't shaped like an API key
+    let (status, _body) = send_request(
+        app.clone(),
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve" })),
+        Some("garbage"),
+    )
+    .await;
+
+    // THEN: It is rejected without a join reaching the database
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(calls.count("player_join"), 0);
+
+    // AND: A well-formed (but unknown) key does reach it
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve" })),
+        Some("oxeye-sk-00000000000000000000000000000000"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(calls.count("player_join"), 1);
+}
+
 #[tokio::test]
 async fn test_join_without_authorization() {
     // GIVEN: A running application
@@ -379,7 +841,7 @@ async fn test_join_same_player_twice() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash.clone(), server_name, guild_id)
+    db.create_server(api_key_hash.clone(), server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -413,7 +875,7 @@ async fn test_join_multiple_players() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash, server_name, guild_id)
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -443,7 +905,7 @@ async fn test_join_with_empty_player_name() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash, server_name, guild_id)
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -464,44 +926,187 @@ async fn test_join_with_empty_player_name() {
 }
 
 #[tokio::test]
-async fn test_join_with_invalid_player_name_chars() {
+async fn test_join_with_missing_player_field() {
     // GIVEN: A valid server exists
     let db = setup_test_db().await;
     let api_key = helpers::generate_api_key();
-    let api_key_hash = helpers::hash_api_key(&api_key);
-    let guild_id = 123456789u64;
-    let server_name = "TestServer".to_string();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let app = create_test_app(db);
 
-    db.create_server(api_key_hash, server_name, guild_id)
-        .await
-        .expect("Failed to create server");
+    // WHEN: Posting a body without the `player` field
+    let (status, body) = send_request(app, "POST", "/join", Some(json!({})), Some(&api_key)).await;
 
-    let app = create_test_app(db);
+    // THEN: Should return 400 with a JSON error naming the field
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error = body["error"].as_str().unwrap();
+    assert!(error.contains("missing field `player`"), "{}", error);
+}
 
-    // WHEN: Making a request with invalid player name (contains special chars)
-    let (status, _body) = send_request(
+#[tokio::test]
+async fn test_join_with_malformed_uuid() {
+    // GIVEN: A valid server exists
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let app = create_test_app(db.clone());
+
+    // WHEN: A player joins with a UUID that is too short
+    let (status, body) = send_request(
         app,
         "POST",
         "/join",
-        Some(json!({ "player": "Player-123" })),
+        Some(json!({ "player": "Steve", "uuid": "069a79f4-44e9-4726-a5be" })),
         Some(&api_key),
     )
     .await;
 
-    // THEN: Should return 400 Bad Request
+    // THEN: Should return 400 and not record the join
     assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("UUID"));
+    assert!(
+        db.get_online_players(helpers::hash_api_key(&api_key))
+            .await
+            .unwrap()
+            .is_empty()
+    );
 }
 
 #[tokio::test]
-async fn test_join_with_too_long_player_name() {
+async fn test_join_stores_uuid_in_hyphenated_form() {
     // GIVEN: A valid server exists
     let db = setup_test_db().await;
     let api_key = helpers::generate_api_key();
-    let api_key_hash = helpers::hash_api_key(&api_key);
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let app = create_test_app(db.clone());
+
+    // WHEN: A player joins with an uppercase UUID without hyphens
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve", "uuid": "069A79F444E94726A5BEFCA90E38AAF5" })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: The UUID is stored lowercase and hyphenated
+    assert_eq!(status, StatusCode::OK);
+    let server = db
+        .get_server_with_players(123456789, "TestServer".to_string())
+        .await
+        .expect("Failed to get server");
+    assert_eq!(
+        server.players[0].uuid.as_deref(),
+        Some("069a79f4-44e9-4726-a5be-fca90e38aaf5")
+    );
+}
+
+#[tokio::test]
+async fn test_join_with_unknown_key_does_not_store_uuid() {
+    // GIVEN: Steve's UUID was recorded through a linked server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let app = create_test_app(db.clone());
+    let (status, _body) = send_request(
+        app.clone(),
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve", "uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5" })),
+        Some(&api_key),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // WHEN: A well-formed key that belongs to no server reports another UUID
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve", "uuid": "853c80ef-3c37-49fd-aa49-938b674adae6" })),
+        Some(&helpers::generate_api_key()),
+    )
+    .await;
+
+    // THEN: The request is rejected and the stored UUID is untouched
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let server = db
+        .get_server_with_players(123456789, "TestServer".to_string())
+        .await
+        .expect("Failed to get server");
+    assert_eq!(
+        server.players[0].uuid.as_deref(),
+        Some("069a79f4-44e9-4726-a5be-fca90e38aaf5")
+    );
+}
+
+#[tokio::test]
+async fn test_join_with_invalid_player_name_chars() {
+    // GIVEN: A valid server exists
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let guild_id = 123456789u64;
+    let server_name = "TestServer".to_string();
+
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
+        .await
+        .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: Making a request with invalid player name (contains special chars)
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "Player-123" })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 400 Bad Request
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_join_with_too_long_player_name() {
+    // GIVEN: A valid server exists
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash, server_name, guild_id)
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -517,8 +1122,336 @@ async fn test_join_with_too_long_player_name() {
     )
     .await;
 
-    // THEN: Should return 422 Unprocessable Entity (ArrayString<16> can't deserialize > 16 chars)
-    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    // THEN: Should return 400 Bad Request (the Java name policy allows at most 16)
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_join_with_name_over_capacity() {
+    // GIVEN: A valid server exists
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789u64,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: Making a request with a name longer than any policy allows (33 chars)
+    let (status, body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "A".repeat(33) })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 400 with a JSON error (PlayerName can't hold > 32 bytes)
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("player"));
+}
+
+#[tokio::test]
+async fn test_sync_with_name_over_capacity() {
+    // GIVEN: A valid server exists
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789u64,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: One name in the sync list is too long to deserialize (40 chars)
+    let (status, body) = send_request(
+        app,
+        "POST",
+        "/sync",
+        Some(json!({ "players": [{ "player": "Steve" }, { "player": "B".repeat(40) }] })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: The whole request is rejected with a clean 400
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.get("error").is_some());
+}
+
+#[tokio::test]
+async fn test_join_bedrock_name_with_space() {
+    // GIVEN: A server on a backend using the Bedrock name policy
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    db.create_server(
+        api_key_hash.clone(),
+        "CrossPlay".to_string(),
+        123456789u64,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let config = oxeye_backend::config::Config {
+        name_policy: oxeye_backend::validation::NamePolicy::Bedrock,
+        ..Default::default()
+    };
+    let app = create_app(db.clone(), &config);
+
+    // WHEN: A Bedrock player with a spaced gamertag joins
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "Cool Gamer 42" })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: The join is accepted and the player is online
+    assert!(status.is_success(), "unexpected status {}", status);
+    assert_eq!(
+        db.get_online_players(api_key_hash).await.unwrap(),
+        vec![pn("Cool Gamer 42")]
+    );
+}
+
+#[tokio::test]
+async fn test_join_name_with_space_rejected_under_java_policy() {
+    // GIVEN: A server on a backend using the default (Java) name policy
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789u64,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: A name with a space is reported
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "Cool Gamer 42" })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 400 Bad Request
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+/// Helper to POST a JSON body with an Idempotency-Key header
+async fn send_with_idempotency_key(
+    app: axum::Router,
+    uri: &str,
+    body: Value,
+    auth_token: &str,
+    idempotency_key: &str,
+) -> StatusCode {
+    let request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("X-Forwarded-For", "127.0.0.1")
+        .header("Authorization", format!("Bearer {}", auth_token))
+        .header("Idempotency-Key", idempotency_key)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        .unwrap();
+    app.oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_join_with_repeated_idempotency_key_applies_once() {
+    // GIVEN: A connected server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: A join succeeds, the player leaves, and the join is retried with the same key
+    let join = json!({ "player": "Steve" });
+    let status =
+        send_with_idempotency_key(app.clone(), "/join", join.clone(), &api_key, "join-1").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_request(
+        app.clone(),
+        "POST",
+        "/leave",
+        Some(json!({ "player": "Steve" })),
+        Some(&api_key),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let status = send_with_idempotency_key(app.clone(), "/join", join, &api_key, "join-1").await;
+
+    // THEN: The retry returns the original status without re-adding the player
+    assert_eq!(status, StatusCode::OK);
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Query failed");
+    assert!(players.is_empty());
+
+    // AND: Only one join was counted
+    let request = Request::builder()
+        .uri("/metrics")
+        .method("GET")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+    assert!(body.contains("oxeye_joins_total 1"), "metrics: {}", body);
+}
+
+#[tokio::test]
+async fn test_concurrent_joins_with_same_idempotency_key_apply_once() {
+    // GIVEN: A connected server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: Several retries of the same join are in flight at once
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let (app, api_key) = (app.clone(), api_key.clone());
+            tokio::spawn(async move {
+                send_with_idempotency_key(
+                    app,
+                    "/join",
+                    json!({ "player": "Steve" }),
+                    &api_key,
+                    "join-1",
+                )
+                .await
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.await.unwrap(), StatusCode::OK);
+    }
+
+    // THEN: Only one of them was applied
+    let request = Request::builder()
+        .uri("/metrics")
+        .method("GET")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+    assert!(body.contains("oxeye_joins_total 1"), "metrics: {}", body);
+}
+
+/// Serve a mock Mojang session server that knows one player's skin.
+async fn mock_session_server(skin: &'static [u8]) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let textures = json!({ "textures": { "SKIN": { "url": format!("{base}/skin.png") } } });
+    let profile = json!({
+        "id": "069a79f444e94726a5befca90e38aaf5",
+        "name": "Notch",
+        "properties": [{
+            "name": "textures",
+            "value": base64::engine::general_purpose::STANDARD.encode(textures.to_string()),
+        }],
+    });
+    let app = axum::Router::new()
+        .route(
+            "/session/minecraft/profile/{uuid}",
+            axum::routing::get(move || async move { axum::Json(profile) }),
+        )
+        .route("/skin.png", axum::routing::get(move || async move { skin }));
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    base
+}
+
+#[tokio::test]
+async fn test_join_with_uuid_fetches_skin_from_mojang() {
+    // GIVEN: A server and a session server that knows Notch's skin
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let config = oxeye_backend::config::Config {
+        mojang_session_server: mock_session_server(b"\x89PNG not really a skin").await,
+        ..Default::default()
+    };
+    let app = create_app(db.clone(), &config);
+
+    // WHEN: Notch joins with a UUID but no skin
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "Notch", "uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5" })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: The join succeeds and the skin is fetched in the background
+    assert_eq!(status, StatusCode::OK);
+    let mut texture_hash = None;
+    for _ in 0..100 {
+        texture_hash = db
+            .get_player_texture_hash("Notch")
+            .await
+            .expect("Failed to get texture hash");
+        if texture_hash.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let texture_hash = texture_hash.expect("skin was not fetched");
+    let skin = db
+        .get_skin_data(&texture_hash)
+        .await
+        .expect("Failed to get skin");
+    assert_eq!(skin.as_deref(), Some(&b"\x89PNG not really a skin"[..]));
 }
 
 // =============================================================================
@@ -534,7 +1467,7 @@ async fn test_leave_success() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash.clone(), server_name, guild_id)
+    db.create_server(api_key_hash.clone(), server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -568,7 +1501,7 @@ async fn test_leave_player_not_online() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash, server_name, guild_id)
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -649,7 +1582,7 @@ async fn test_sync_success() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash.clone(), server_name, guild_id)
+    db.create_server(api_key_hash.clone(), server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -669,7 +1602,7 @@ async fn test_sync_success() {
         app,
         "POST",
         "/sync",
-        Some(json!({ "players": ["Notch", "Jeb"] })),
+        Some(json!({ "players": [{ "player": "Notch" }, { "player": "Jeb" }] })),
         Some(&api_key),
     )
     .await;
@@ -687,7 +1620,7 @@ async fn test_sync_empty_list() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash.clone(), server_name, guild_id)
+    db.create_server(api_key_hash.clone(), server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -713,20 +1646,156 @@ async fn test_sync_empty_list() {
 }
 
 #[tokio::test]
-async fn test_sync_with_invalid_api_key() {
-    // GIVEN: An empty database
+async fn test_sync_empty_list_rejected_when_not_allowed() {
+    // GIVEN: A server with a player online, on a backend that rejects empty syncs
     let db = setup_test_db().await;
-    let app = create_test_app(db);
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
 
-    // WHEN: Making a request with invalid API key
-    let (status, _body) = send_request(
-        app,
-        "POST",
-        "/sync",
-        Some(json!({ "players": ["Steve"] })),
-        Some("oxeye-sk-invalid12345678901234567890"),
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
     )
-    .await;
+    .await
+    .expect("Failed to create server");
+    db.player_join(api_key_hash.clone(), pn("Steve"), helpers::now())
+        .await
+        .expect("Failed to add player");
+
+    let config = oxeye_backend::config::Config {
+        sync_allow_empty: false,
+        ..Default::default()
+    };
+    let app = create_app(db.clone(), &config);
+
+    // WHEN: Syncing an empty list without opting in
+    let (status, body) = send_request(
+        app.clone(),
+        "POST",
+        "/sync",
+        Some(json!({ "players": [] })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 400 and keep the player online
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("empty"));
+    let players = db
+        .get_online_players(api_key_hash.clone())
+        .await
+        .expect("Query failed");
+    assert_eq!(players, vec![pn("Steve")]);
+
+    // AND: The request can still opt in to clearing the list
+    let (status, _) = send_request(
+        app,
+        "POST",
+        "/sync",
+        Some(json!({ "players": [], "allow_empty": true })),
+        Some(&api_key),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Query failed");
+    assert!(players.is_empty());
+}
+
+#[tokio::test]
+async fn test_sync_folds_case_duplicates_when_enabled() {
+    // GIVEN: A server with case folding of sync lists enabled
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let config = oxeye_backend::config::Config {
+        sync_fold_case: true,
+        ..Default::default()
+    };
+    let app = create_app(db.clone(), &config);
+
+    // WHEN: Syncing the same name in two spellings
+    let (status, _) = send_request(
+        app,
+        "POST",
+        "/sync",
+        Some(json!({ "players": [{ "player": "Steve" }, { "player": "steve" }] })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Only the first spelling is online
+    assert_eq!(status, StatusCode::OK);
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Query failed");
+    assert_eq!(players, vec![pn("Steve")]);
+}
+
+#[tokio::test]
+async fn test_sync_keeps_case_variants_by_default() {
+    // GIVEN: A server with the default config
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let app = create_test_app(db.clone());
+
+    // WHEN: Syncing names that differ only in case
+    let (status, _) = send_request(
+        app,
+        "POST",
+        "/sync",
+        Some(json!({ "players": [{ "player": "Steve" }, { "player": "steve" }] })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Both are kept as distinct players
+    assert_eq!(status, StatusCode::OK);
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Query failed");
+    assert_eq!(players, vec![pn("Steve"), pn("steve")]);
+}
+
+#[tokio::test]
+async fn test_sync_with_invalid_api_key() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a request with invalid API key
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/sync",
+        Some(json!({ "players": [{ "player": "Steve" }] })),
+        Some("oxeye-sk-invalid12345678901234567890"),
+    )
+    .await;
 
     // THEN: Should return 401 Unauthorized
     assert_eq!(status, StatusCode::UNAUTHORIZED);
@@ -741,7 +1810,7 @@ async fn test_sync_replaces_entire_list() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash.clone(), server_name, guild_id)
+    db.create_server(api_key_hash.clone(), server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -759,7 +1828,7 @@ async fn test_sync_replaces_entire_list() {
         app,
         "POST",
         "/sync",
-        Some(json!({ "players": ["Notch", "Jeb", "Dinnerbone"] })),
+        Some(json!({ "players": [{ "player": "Notch" }, { "player": "Jeb" }, { "player": "Dinnerbone" }] })),
         Some(&api_key),
     )
     .await;
@@ -789,14 +1858,16 @@ async fn test_sync_with_large_player_list() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash, server_name, guild_id)
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
     let app = create_test_app(db);
 
     // WHEN: Syncing with large player list (1001 players - exceeds limit)
-    let players: Vec<String> = (0..1001).map(|i| format!("Player{}", i)).collect();
+    let players: Vec<Value> = (0..1001)
+        .map(|i| json!({ "player": format!("Player{}", i) }))
+        .collect();
     let (status, _body) = send_request(
         app,
         "POST",
@@ -819,7 +1890,7 @@ async fn test_sync_with_oversized_payload() {
     let guild_id = 123456789u64;
     let server_name = "TestServer".to_string();
 
-    db.create_server(api_key_hash, server_name, guild_id)
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
         .await
         .expect("Failed to create server");
 
@@ -830,88 +1901,1833 @@ async fn test_sync_with_oversized_payload() {
     let huge_name = "A".repeat(10 * 1024); // 10KB per name
     let players: Vec<String> = (0..150).map(|_| huge_name.clone()).collect(); // 1.5MB total
 
-    let (status, _body) = send_request(
+    let (status, body) = send_request(
+        app,
+        "POST",
+        "/sync",
+        Some(json!({ "players": players })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 413 Payload Too Large with a JSON error
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(body.get("error").is_some());
+}
+
+#[tokio::test]
+async fn test_sync_with_oversized_content_length() {
+    // GIVEN: A valid server exists
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+
+    db.create_server(
+        api_key_hash,
+        "TestServer".to_string(),
+        123456789u64,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: Sending a body whose Content-Length is over the limit, which the
+    // body limit layer rejects before the handler runs
+    let huge_name = "A".repeat(10 * 1024);
+    let players: Vec<String> = (0..150).map(|_| huge_name.clone()).collect();
+    let payload = serde_json::to_vec(&json!({ "players": players })).unwrap();
+    let request = Request::builder()
+        .method("POST")
+        .uri("/sync")
+        .header("X-Forwarded-For", "127.0.0.1")
+        .header("Content-Type", "application/json")
+        .header("Content-Length", payload.len())
+        .header("Authorization", format!("Bearer {}", api_key))
+        .body(Body::from(payload))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    // THEN: Should return 413 with our standard JSON error body
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "Request body is too large");
+}
+
+#[tokio::test]
+async fn test_join_with_oversized_player_name() {
+    // GIVEN: A valid server exists
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let guild_id = 123456789u64;
+    let server_name = "TestServer".to_string();
+
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
+        .await
+        .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: Making a request with a massive player name (> 1MB)
+    let huge_name = "A".repeat(2 * 1024 * 1024); // 2MB player name
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": huge_name })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 413 Payload Too Large
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_join_and_leave_use_tighter_body_limit() {
+    // GIVEN: A valid server exists
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+
+    db.create_server(
+        api_key_hash,
+        "TestServer".to_string(),
+        123456789u64,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: Sending join and leave bodies just over the 4KB join/leave limit
+    let big_name = "A".repeat(8 * 1024);
+    let (join_status, _) = send_request(
+        app.clone(),
+        "POST",
+        "/join",
+        Some(json!({ "player": big_name })),
+        Some(&api_key),
+    )
+    .await;
+    let (leave_status, _) = send_request(
+        app.clone(),
+        "POST",
+        "/leave",
+        Some(json!({ "player": big_name })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Both are rejected before reaching validation
+    assert_eq!(join_status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(leave_status, StatusCode::PAYLOAD_TOO_LARGE);
+
+    // AND: /sync still accepts a body of that size under the general limit
+    let players: Vec<Value> = (0..800)
+        .map(|i| json!({ "player": format!("Player{}", i) }))
+        .collect();
+    let body = json!({ "players": players });
+    assert!(body.to_string().len() > 8 * 1024);
+    let (sync_status, _) = send_request(app, "POST", "/sync", Some(body), Some(&api_key)).await;
+    assert_eq!(sync_status, StatusCode::OK);
+}
+
+// =============================================================================
+// EVENTS ENDPOINT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_events_applies_joins_and_leaves() {
+    // GIVEN: A server exists with one player online
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let guild_id = 123456789u64;
+    let server_name = "TestServer".to_string();
+
+    db.create_server(api_key_hash.clone(), server_name, guild_id, helpers::now())
+        .await
+        .expect("Failed to create server");
+
+    let now = helpers::now();
+    db.player_join(api_key_hash.clone(), pn("Steve"), now)
+        .await
+        .expect("Failed to add player");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: Flushing a batch of buffered events
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/events",
+        Some(json!({ "joins": ["Alex", "Notch"], "leaves": ["Steve"] })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 200 OK and apply every event
+    assert_eq!(status, StatusCode::OK);
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Failed to get players");
+    assert_eq!(players, vec![pn("Alex"), pn("Notch")]);
+}
+
+#[tokio::test]
+async fn test_events_applied_in_order() {
+    // GIVEN: A server exists with one player online
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789u64,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.player_join(api_key_hash.clone(), pn("Steve"), helpers::now())
+        .await
+        .expect("Failed to add player");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: Steve reconnects and Alex joins then leaves within one batch
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/events",
+        Some(json!({ "events": [
+            { "player": "Steve", "event": "leave" },
+            { "player": "Alex", "event": "join" },
+            { "player": "Steve", "event": "join" },
+            { "player": "Alex", "event": "leave" },
+        ] })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Each player ends in the state of their last event
+    assert_eq!(status, StatusCode::OK);
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Failed to get players");
+    assert_eq!(players, vec![pn("Steve")]);
+}
+
+#[tokio::test]
+async fn test_events_with_invalid_player_name() {
+    // GIVEN: A server exists with one player online
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let guild_id = 123456789u64;
+    let server_name = "TestServer".to_string();
+
+    db.create_server(api_key_hash.clone(), server_name, guild_id, helpers::now())
+        .await
+        .expect("Failed to create server");
+
+    let now = helpers::now();
+    db.player_join(api_key_hash.clone(), pn("Steve"), now)
+        .await
+        .expect("Failed to add player");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: One name in the batch is invalid
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/events",
+        Some(json!({ "joins": ["Alex", "bad-name"], "leaves": ["Steve"] })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 400 Bad Request and apply nothing
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Failed to get players");
+    assert_eq!(players, vec![pn("Steve")]);
+}
+
+#[tokio::test]
+async fn test_events_rejects_both_shapes() {
+    // GIVEN: A server exists with one player online
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789u64,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.player_join(api_key_hash.clone(), pn("Steve"), helpers::now())
+        .await
+        .expect("Failed to add player");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: A batch mixes an ordered event list with joins/leaves
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/events",
+        Some(json!({
+            "joins": ["Alex"],
+            "events": [{ "player": "Steve", "event": "leave" }],
+        })),
+        Some(&api_key),
+    )
+    .await;
+
+    // THEN: Should return 400 Bad Request and apply nothing
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Failed to get players");
+    assert_eq!(players, vec![pn("Steve")]);
+}
+
+#[tokio::test]
+async fn test_events_with_invalid_api_key() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a request with invalid API key
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/events",
+        Some(json!({ "joins": ["Steve"] })),
+        Some("oxeye-sk-invalid12345678901234567890"),
+    )
+    .await;
+
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+// =============================================================================
+// GUILD READ API TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_server_players_with_guild_token() {
+    // GIVEN: A server with players and a guild token
+    let db = setup_test_db().await;
+    let api_key_hash = helpers::hash_api_key(&helpers::generate_api_key());
+    let guild_id = 123456789u64;
+
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let now = helpers::now();
+    db.player_join(api_key_hash.clone(), pn("Steve"), now)
+        .await
+        .expect("Failed to add player");
+    db.player_join(api_key_hash, pn("Alex"), now)
+        .await
+        .expect("Failed to add player");
+
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), guild_id, now)
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: Reading the server's players with the guild token
+    let (status, body) = send_request(
+        app,
+        "GET",
+        "/guilds/123456789/servers/TestServer/players",
+        None,
+        Some(&token),
+    )
+    .await;
+
+    // THEN: Should return the players sorted by name
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["name"], "TestServer");
+    assert_eq!(body["players"][0]["player"], "Alex");
+    assert_eq!(body["players"][1]["player"], "Steve");
+    assert_eq!(body["players"][0]["joined_at"], now);
+}
+
+#[tokio::test]
+async fn test_server_players_include_texture_hash_from_join() {
+    // GIVEN: A server, a stored skin and a guild token
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let guild_id = 123456789u64;
+    let texture_hash = "b".repeat(64);
+
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.store_skin(texture_hash.clone(), None, vec![1, 2, 3])
+        .await
+        .expect("Failed to store skin");
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), guild_id, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: A player joins reporting their skin via the `texture` field
+    let (status, _) = send_request(
+        app.clone(),
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve", "texture": texture_hash })),
+        Some(&api_key),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // THEN: The guild read API returns the stored hash with the player
+    let (status, body) = send_request(
+        app,
+        "GET",
+        "/guilds/123456789/servers/TestServer/players",
+        None,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["players"][0]["player"], "Steve");
+    assert_eq!(body["players"][0]["texture_hash"], texture_hash);
+}
+
+#[tokio::test]
+async fn test_server_players_include_uuid_from_sync() {
+    // GIVEN: A server and a guild token
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let guild_id = 123456789u64;
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), guild_id, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+    let app = create_test_app(db);
+
+    // WHEN: The server syncs one player with a hyphenated UUID and one without
+    let (status, _) = send_request(
+        app.clone(),
+        "POST",
+        "/sync",
+        Some(json!({ "players": [
+            { "player": "Steve", "uuid": "069A79F4-44E9-4726-A5BE-FCA90E38AAF5" },
+            { "player": "Alex" },
+        ]})),
+        Some(&api_key),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // THEN: The guild read API returns the (lowercased) UUID only where known
+    let (status, body) = send_request(
+        app,
+        "GET",
+        "/guilds/123456789/servers/TestServer/players",
+        None,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["players"][0].get("uuid").is_none());
+    assert_eq!(
+        body["players"][1]["uuid"],
+        "069a79f4-44e9-4726-a5be-fca90e38aaf5"
+    );
+}
+
+#[tokio::test]
+async fn test_server_players_with_invalid_token() {
+    // GIVEN: A server exists but the token belongs to another guild
+    let db = setup_test_db().await;
+    let api_key_hash = helpers::hash_api_key(&helpers::generate_api_key());
+
+    db.create_server(
+        api_key_hash,
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), 987654321, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: Reading with a token for a different guild
+    let (status, _body) = send_request(
+        app,
+        "GET",
+        "/guilds/123456789/servers/TestServer/players",
+        None,
+        Some(&token),
+    )
+    .await;
+
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_server_players_unknown_server() {
+    // GIVEN: A guild token but no servers
+    let db = setup_test_db().await;
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), 123456789, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: Reading a server that doesn't exist
+    let (status, _body) = send_request(
+        app,
+        "GET",
+        "/guilds/123456789/servers/Missing/players",
+        None,
+        Some(&token),
+    )
+    .await;
+
+    // THEN: Should return 404 Not Found
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_guild_endpoints_reject_server_key() {
+    // GIVEN: A server linked to a guild that also has a guild token
+    let db = setup_test_db().await;
+    let guild_id = 123456789u64;
+    let api_key = helpers::generate_api_key();
+
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), guild_id, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: Reading the guild's endpoints with the server's API key
+    for uri in [
+        "/guilds/123456789/servers",
+        "/guilds/123456789/servers/TestServer/players",
+        "/guilds/123456789/export",
+        "/guilds/123456789/stats",
+    ] {
+        let (status, _body) = send_request(app.clone(), "GET", uri, None, Some(&api_key)).await;
+
+        // THEN: Should return 401 Unauthorized
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", uri);
+    }
+
+    // AND: The guild token still works
+    let (status, _body) =
+        send_request(app, "GET", "/guilds/123456789/servers", None, Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_server_endpoints_reject_guild_token() {
+    // GIVEN: A server and a guild token for its guild
+    let db = setup_test_db().await;
+    let guild_id = 123456789u64;
+
+    db.create_server(
+        helpers::hash_api_key(&helpers::generate_api_key()),
+        "TestServer".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), guild_id, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: Calling server endpoints, including mutating ones, with the guild token
+    for (method, uri, body) in [
+        ("GET", "/status", None),
+        ("GET", "/whoami", None),
+        ("POST", "/join", Some(json!({ "player": "Steve" }))),
+        (
+            "POST",
+            "/sync",
+            Some(json!({ "players": [{ "player": "Steve" }] })),
+        ),
+        ("POST", "/reset", None),
+        ("POST", "/disconnect", None),
+    ] {
+        let (status, _body) = send_request(app.clone(), method, uri, body, Some(&token)).await;
+
+        // THEN: Should return 401 Unauthorized
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{} {}", method, uri);
+    }
+}
+
+#[tokio::test]
+async fn test_export_guild() {
+    // GIVEN: Two servers in a guild, one with players, and a guild token
+    let db = setup_test_db().await;
+    let guild_id = 123456789u64;
+    let survival_hash = helpers::hash_api_key(&helpers::generate_api_key());
+    let creative_hash = helpers::hash_api_key(&helpers::generate_api_key());
+
+    db.create_server(
+        survival_hash.clone(),
+        "Survival".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.create_server(
+        creative_hash.clone(),
+        "Creative".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.player_join(survival_hash.clone(), pn("Steve"), helpers::now())
+        .await
+        .expect("Failed to add player");
+
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), guild_id, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: Exporting the guild
+    let (status, body) =
+        send_request(app, "GET", "/guilds/123456789/export", None, Some(&token)).await;
+
+    // THEN: Should list every server with its player array and no key hashes
+    assert_eq!(status, StatusCode::OK);
+    let servers = body["servers"].as_array().expect("servers array");
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers[0]["name"], "Creative");
+    assert_eq!(servers[0]["players"], json!([]));
+    assert_eq!(servers[1]["name"], "Survival");
+    assert_eq!(servers[1]["players"][0]["player"], "Steve");
+
+    let raw = body.to_string();
+    assert!(!raw.contains(&survival_hash));
+    assert!(!raw.contains(&creative_hash));
+}
+
+#[tokio::test]
+async fn test_export_guild_with_invalid_token() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Exporting with a token that was never issued
+    let (status, _body) = send_request(
+        app,
+        "GET",
+        "/guilds/123456789/export",
+        None,
+        Some("oxeye-gt-invalid12345678901234567890"),
+    )
+    .await;
+
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_list_guild_servers_pages() {
+    // GIVEN: A guild with five servers and a guild token
+    let db = setup_test_db().await;
+    let guild_id = 123456789u64;
+    for name in ["Alpha", "Bravo", "Charlie", "Delta", "Echo"] {
+        db.create_server(
+            helpers::hash_api_key(&helpers::generate_api_key()),
+            name.to_string(),
+            guild_id,
+            helpers::now(),
+        )
+        .await
+        .expect("Failed to create server");
+    }
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), guild_id, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+    let app = create_test_app(db);
+    let names = |body: &Value| {
+        body["servers"]
+            .as_array()
+            .expect("servers array")
+            .iter()
+            .map(|s| s["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // WHEN: Reading the first page of two
+    let (status, body) = send_request(
+        app.clone(),
+        "GET",
+        "/guilds/123456789/servers?limit=2",
+        None,
+        Some(&token),
+    )
+    .await;
+
+    // THEN: The first two servers by name and the total are returned
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&body), vec!["Alpha", "Bravo"]);
+    assert_eq!(body["total"], 5);
+    assert_eq!(body["offset"], 0);
+
+    // AND: The last page holds only the remaining server
+    let (status, body) = send_request(
+        app.clone(),
+        "GET",
+        "/guilds/123456789/servers?limit=2&offset=4",
+        None,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&body), vec!["Echo"]);
+    assert_eq!(body["total"], 5);
+
+    // AND: Oversized limits are clamped
+    let (status, body) = send_request(
+        app.clone(),
+        "GET",
+        "/guilds/123456789/servers?limit=5000",
+        None,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["limit"], 100);
+    assert_eq!(names(&body).len(), 5);
+
+    // AND: A malformed limit is a 400 with a JSON error
+    let (status, body) = send_request(
+        app,
+        "GET",
+        "/guilds/123456789/servers?limit=lots",
+        None,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("invalid query"));
+}
+
+#[tokio::test]
+async fn test_guild_stats() {
+    // GIVEN: A guild with one populated and one empty server
+    let db = setup_test_db().await;
+    let guild_id = 123456789u64;
+    let api_key_hash = helpers::hash_api_key(&helpers::generate_api_key());
+    db.create_server(
+        api_key_hash.clone(),
+        "Survival".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.create_server(
+        helpers::hash_api_key(&helpers::generate_api_key()),
+        "Creative".to_string(),
+        guild_id,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.player_join(api_key_hash.clone(), pn("Steve"), helpers::now())
+        .await
+        .expect("Failed to add player");
+    db.player_join(api_key_hash, pn("Alex"), helpers::now())
+        .await
+        .expect("Failed to add player");
+
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), guild_id, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: Reading the guild's stats
+    let (status, body) =
+        send_request(app, "GET", "/guilds/123456789/stats", None, Some(&token)).await;
+
+    // THEN: Counts and the busiest server are returned
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["guild_id"], guild_id);
+    assert_eq!(body["server_count"], 2);
+    assert_eq!(body["online_players"], 2);
+    assert_eq!(body["busiest_server"]["name"], "Survival");
+    assert_eq!(body["busiest_server"]["player_count"], 2);
+}
+
+// =============================================================================
+// LIVE EVENTS (SSE) TESTS
+// =============================================================================
+
+/// Helper to read the next SSE frame as text, failing if none arrives in time
+async fn next_sse_frame(body: &mut Body) -> String {
+    let frame = tokio::time::timeout(std::time::Duration::from_secs(2), body.frame())
+        .await
+        .expect("timed out waiting for SSE event")
+        .expect("SSE stream ended")
+        .unwrap();
+    String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_guild_events_streams_only_own_guild() {
+    // GIVEN: Two servers in different guilds and a guild token for the first
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let other_api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        111,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.create_server(
+        helpers::hash_api_key(&other_api_key),
+        "OtherServer".to_string(),
+        222,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), 111, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: A dashboard subscribes to the guild's events
+    let request = Request::builder()
+        .uri("/guilds/111/events")
+        .method("GET")
+        .header("X-Forwarded-For", "127.0.0.1")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut body = response.into_body();
+
+    // AND: A player joins the other guild's server, then this guild's server
+    let (status, _) = send_request(
+        app.clone(),
+        "POST",
+        "/join",
+        Some(json!({ "player": "Alex" })),
+        Some(&other_api_key),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({ "player": "Steve" })),
+        Some(&api_key),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // THEN: Only this guild's join is streamed
+    let frame = next_sse_frame(&mut body).await;
+    assert!(frame.starts_with("event: join\n"), "frame: {}", frame);
+    assert!(
+        frame.contains(r#""server":"TestServer""#),
+        "frame: {}",
+        frame
+    );
+    assert!(frame.contains(r#""player":"Steve""#), "frame: {}", frame);
+}
+
+#[tokio::test]
+async fn test_guild_events_with_invalid_token() {
+    // GIVEN: A guild token for a different guild
+    let db = setup_test_db().await;
+    let token = helpers::generate_guild_token();
+    db.create_guild_token(helpers::hash_api_key(&token), 222, helpers::now())
+        .await
+        .expect("Failed to create guild token");
+
+    let app = create_test_app(db);
+
+    // WHEN: Subscribing to another guild's events
+    let (status, _body) = send_request(app, "GET", "/guilds/111/events", None, Some(&token)).await;
+
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+// =============================================================================
+// STATUS ENDPOINT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_status_authenticated() {
+    // GIVEN: A connected server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let guild_id = 123456789u64;
+    let server_name = "TestServer".to_string();
+
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
+        .await
+        .expect("Failed to create server");
+
+    let app = create_test_app(db);
+
+    // WHEN: Making a GET request to /status with valid API key
+    let (status, _body) = send_request(app, "GET", "/status", None, Some(&api_key)).await;
+
+    // THEN: Should return 200 OK
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_status_invalid_api_key() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a GET request to /status with invalid API key
+    let (status, body) = send_request(
+        app,
+        "GET",
+        "/status",
+        None,
+        Some("oxeye-sk-invalid12345678901234567890"),
+    )
+    .await;
+
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body.get("error").is_some());
+}
+
+#[tokio::test]
+async fn test_status_without_authorization() {
+    // GIVEN: A running application
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a GET request to /status without Authorization header
+    let request = Request::builder()
+        .uri("/status")
+        .method("GET")
+        .header("X-Forwarded-For", "127.0.0.1")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+
+    // THEN: Should return 400 Bad Request (missing required header)
+    assert!(
+        status.is_client_error(),
+        "Expected client error, got {}",
+        status
+    );
+}
+
+/// GET /status with an optional Accept header, returning the content type and body.
+async fn get_status_with_accept(
+    app: axum::Router,
+    api_key: &str,
+    accept: Option<&str>,
+) -> (String, Vec<u8>) {
+    let mut request_builder = Request::builder()
+        .uri("/status")
+        .method("GET")
+        .header("X-Forwarded-For", "127.0.0.1")
+        .header("Authorization", format!("Bearer {}", api_key));
+    if let Some(accept) = accept {
+        request_builder = request_builder.header("Accept", accept);
+    }
+    let response = app
+        .oneshot(request_builder.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["vary"], "accept");
+    let content_type = response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (content_type, body.to_vec())
+}
+
+#[tokio::test]
+async fn test_status_negotiates_json_and_png() {
+    // GIVEN: A connected server with one player online
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .unwrap();
+    db.player_join(api_key_hash, pn("Steve"), helpers::now())
+        .await
+        .unwrap();
+    let app = create_test_app(db);
+
+    // WHEN: Asking for JSON explicitly
+    let (content_type, body) =
+        get_status_with_accept(app.clone(), &api_key, Some("application/json")).await;
+
+    // THEN: The server, its online count and players come back as JSON
+    assert_eq!(content_type, "application/json");
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({"server": "TestServer", "online": 1, "players": ["Steve"]})
+    );
+
+    // WHEN: Asking for a PNG
+    let (content_type, body) = get_status_with_accept(app, &api_key, Some("image/png")).await;
+
+    // THEN: The status image comes back
+    assert_eq!(content_type, "image/png");
+    assert!(body.starts_with(b"\x89PNG"));
+}
+
+#[tokio::test]
+async fn test_status_defaults_to_json() {
+    // GIVEN: A connected server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .unwrap();
+    let app = create_test_app(db);
+
+    // WHEN: Sending no Accept header, or one that takes anything
+    for accept in [None, Some("*/*")] {
+        let (content_type, body) = get_status_with_accept(app.clone(), &api_key, accept).await;
+
+        // THEN: JSON is returned
+        assert_eq!(content_type, "application/json");
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["online"], 0);
+    }
+}
+
+#[tokio::test]
+async fn test_status_detailed_lists_join_times() {
+    // GIVEN: A connected server where Alex joined an hour before Steve
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let now = helpers::now();
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        now,
+    )
+    .await
+    .unwrap();
+    db.player_join(api_key_hash.clone(), pn("Steve"), now - 600)
+        .await
+        .unwrap();
+    db.player_join(api_key_hash, pn("Alex"), now - 4200)
+        .await
+        .unwrap();
+    let app = create_test_app(db);
+
+    // WHEN: Requesting the detailed status
+    let (status, body) = send_request(app, "GET", "/status/detailed", None, Some(&api_key)).await;
+
+    // THEN: Players come back longest online first, with their join times
+    assert_eq!(status, StatusCode::OK);
+    let players = body.as_array().unwrap();
+    assert_eq!(players.len(), 2);
+    assert_eq!(players[0]["player"], "Alex");
+    assert_eq!(players[0]["joined_at"], now - 4200);
+    assert!(players[0]["online_for_secs"].as_i64().unwrap() >= 4200);
+    assert_eq!(players[1]["player"], "Steve");
+    assert_eq!(players[1]["joined_at"], now - 600);
+}
+
+#[tokio::test]
+async fn test_status_since_filters_to_recent_joins() {
+    // GIVEN: A connected server where Alex joined an hour before Steve
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let now = helpers::now();
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        now,
+    )
+    .await
+    .unwrap();
+    db.player_join(api_key_hash.clone(), pn("Steve"), now - 600)
+        .await
+        .unwrap();
+    db.player_join(api_key_hash, pn("Alex"), now - 4200)
+        .await
+        .unwrap();
+    let app = create_test_app(db);
+
+    // WHEN: Asking only for players who joined in the last 30 minutes
+    let uri = format!("/status?since={}", now - 1800);
+    let (status, body) = send_request(app.clone(), "GET", &uri, None, Some(&api_key)).await;
+
+    // THEN: Only Steve is listed, but the online count covers everyone
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["players"], json!(["Steve"]));
+    assert_eq!(body["online"], 2);
+
+    // WHEN: Leaving `since` out
+    let (status, body) = send_request(app, "GET", "/status", None, Some(&api_key)).await;
+
+    // THEN: Every online player is listed, earliest arrival first
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["players"], json!(["Alex", "Steve"]));
+}
+
+#[tokio::test]
+async fn test_status_rejects_implausible_since() {
+    // GIVEN: A connected server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .unwrap();
+    let app = create_test_app(db);
+
+    // WHEN: Sending a negative, millisecond or non-numeric `since`
+    let millis = helpers::now() * 1000;
+    for since in [
+        "-1".to_string(),
+        millis.to_string(),
+        "yesterday".to_string(),
+    ] {
+        let uri = format!("/status?since={}", since);
+        let (status, body) = send_request(app.clone(), "GET", &uri, None, Some(&api_key)).await;
+
+        // THEN: Should return 400 Bad Request
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", since);
+        assert!(body.get("error").is_some());
+    }
+}
+
+// =============================================================================
+// WHOAMI ENDPOINT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_whoami_returns_linked_server() {
+    // GIVEN: A connected server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let app = create_test_app(db);
+
+    // WHEN: The server asks who it is
+    let (status, body) = send_request(app, "GET", "/whoami", None, Some(&api_key)).await;
+
+    // THEN: Should return the name and guild it was linked with
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["server"], "TestServer");
+    assert_eq!(body["guild_id"], 123456789);
+}
+
+#[tokio::test]
+async fn test_whoami_invalid_api_key() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a request with invalid API key
+    let (status, _body) = send_request(
+        app,
+        "GET",
+        "/whoami",
+        None,
+        Some("oxeye-sk-invalid12345678901234567890"),
+    )
+    .await;
+
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+// =============================================================================
+// DISCONNECT ENDPOINT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_disconnect_success() {
+    // GIVEN: A connected server with players
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let guild_id = 123456789u64;
+    let server_name = "TestServer".to_string();
+
+    db.create_server(api_key_hash.clone(), server_name, guild_id, helpers::now())
+        .await
+        .expect("Failed to create server");
+
+    // Add some players
+    let now = helpers::now();
+    db.player_join(api_key_hash.clone(), pn("Steve"), now)
+        .await
+        .expect("Failed to add player");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: Making a POST request to /disconnect with valid API key
+    let (status, _body) = send_request(app, "POST", "/disconnect", None, Some(&api_key)).await;
+
+    // THEN: Should return 200 OK
+    assert_eq!(status, StatusCode::OK);
+
+    // AND: Server should be deleted
+    let server = db
+        .get_server_by_api_key(api_key_hash.clone())
+        .await
+        .expect("Query failed");
+    assert!(server.is_none());
+
+    // AND: Players should be deleted (cascade)
+    let players = db
+        .get_online_players(api_key_hash)
+        .await
+        .expect("Query failed");
+    assert!(players.is_empty());
+}
+
+#[tokio::test]
+async fn test_disconnect_with_invalid_api_key() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a request with invalid API key
+    let (status, body) = send_request(
+        app,
+        "POST",
+        "/disconnect",
+        None,
+        Some("oxeye-sk-invalid12345678901234567890"),
+    )
+    .await;
+
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body.get("error").is_some());
+}
+
+#[tokio::test]
+async fn test_disconnect_without_authorization() {
+    // GIVEN: A running application
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Making a request without Authorization header
+    let request = Request::builder()
+        .uri("/disconnect")
+        .method("POST")
+        .header("X-Forwarded-For", "127.0.0.1")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+
+    // THEN: Should return 400 Bad Request (missing required header)
+    assert!(
+        status.is_client_error(),
+        "Expected client error, got {}",
+        status
+    );
+}
+
+#[tokio::test]
+async fn test_reset_clears_players_but_keeps_server() {
+    // GIVEN: A connected server with ghost players left by a crash
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.sync_players(
+        api_key_hash.clone(),
+        vec![pn("Steve"), pn("Alex")],
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to sync players");
+    let app = create_test_app(db.clone());
+
+    // WHEN: Making a POST request to /reset
+    let (status, body) = send_request(app.clone(), "POST", "/reset", None, Some(&api_key)).await;
+
+    // THEN: Both players are cleared
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["cleared"], 2);
+    assert!(
+        db.get_online_players(api_key_hash.clone())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // AND: The server stays linked and its key still works
+    assert!(
+        db.get_server_by_api_key(api_key_hash)
+            .await
+            .unwrap()
+            .is_some()
+    );
+    let (status, _) = send_request(
+        app,
+        "POST",
+        "/join",
+        Some(json!({"player": "Steve"})),
+        Some(&api_key),
+    )
+    .await;
+    assert!(status.is_success(), "join failed with {}", status);
+}
+
+#[tokio::test]
+async fn test_disconnect_twice() {
+    // GIVEN: A connected server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let guild_id = 123456789u64;
+    let server_name = "TestServer".to_string();
+
+    db.create_server(api_key_hash, server_name, guild_id, helpers::now())
+        .await
+        .expect("Failed to create server");
+
+    // First disconnect
+    let app = create_test_app(db.clone());
+    let (status, _) = send_request(app, "POST", "/disconnect", None, Some(&api_key)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // WHEN: Trying to disconnect again with same API key
+    let app = create_test_app(db);
+    let (status, _) = send_request(app, "POST", "/disconnect", None, Some(&api_key)).await;
+
+    // THEN: Should return 401 Unauthorized (key no longer valid)
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+// =============================================================================
+// ROTATE ENDPOINT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_rotate_issues_new_key_and_keeps_players() {
+    // GIVEN: A connected server with a player online
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.player_join(api_key_hash, pn("Steve"), helpers::now())
+        .await
+        .expect("Failed to add player");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: Rotating the API key
+    let (status, body) = send_request(app.clone(), "POST", "/rotate", None, Some(&api_key)).await;
+
+    // THEN: A different key is returned
+    assert_eq!(status, StatusCode::OK);
+    let new_api_key = body["api_key"].as_str().unwrap().to_string();
+    assert!(new_api_key.starts_with("oxeye-sk-"));
+    assert_ne!(new_api_key, api_key);
+
+    // AND: The new key works and the old one doesn't
+    let (status, _) = send_request(app.clone(), "GET", "/status", None, Some(&new_api_key)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_request(app, "GET", "/status", None, Some(&api_key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // AND: Online players were preserved
+    let players = db
+        .get_online_players(helpers::hash_api_key(&new_api_key))
+        .await
+        .expect("Query failed");
+    assert_eq!(players, vec![pn("Steve")]);
+}
+
+#[tokio::test]
+async fn test_rotate_with_invalid_api_key() {
+    // GIVEN: An empty database
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Rotating an unknown key
+    let (status, _body) = send_request(
+        app,
+        "POST",
+        "/rotate",
+        None,
+        Some("oxeye-sk-invalid12345678901234567890"),
+    )
+    .await;
+
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+// =============================================================================
+// VALIDATE ENDPOINT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_validate_accepts_valid_fields() {
+    // GIVEN: A running application
+    let db = setup_test_db().await;
+    let app = create_test_app(db.clone());
+
+    // WHEN: Validating a well-formed payload
+    let (status, body) = send_request(
+        app,
+        "POST",
+        "/validate",
+        Some(json!({
+            "player": "Steve",
+            "players": ["Alex", "Notch"],
+            "code": "oxeye-abc123",
+            "server_name": "Survival SMP",
+        })),
+        None,
+    )
+    .await;
+
+    // THEN: Should return 200 with ok and store nothing
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "ok": true }));
+    assert!(db.get_servers_by_guild(123456789).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_validate_reports_invalid_player_name() {
+    // GIVEN: A running application
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Validating a player name with invalid characters
+    let (status, body) = send_request(
+        app,
+        "POST",
+        "/validate",
+        Some(json!({ "player": "Player-123" })),
+        None,
+    )
+    .await;
+
+    // THEN: Should return 400 with the validator's message
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body["error"],
+        oxeye_backend::validation::ValidationError::PlayerNameInvalidChars.to_string()
+    );
+}
+
+#[tokio::test]
+async fn test_validate_rejects_empty_and_unknown_fields() {
+    // GIVEN: A running application
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Sending no fields, or a misspelled one
+    let (empty_status, _) =
+        send_request(app.clone(), "POST", "/validate", Some(json!({})), None).await;
+    let (typo_status, _) = send_request(
+        app,
+        "POST",
+        "/validate",
+        Some(json!({ "playr": "Steve" })),
+        None,
+    )
+    .await;
+
+    // THEN: Both are 400s rather than a silent ok
+    assert_eq!(empty_status, StatusCode::BAD_REQUEST);
+    assert_eq!(typo_status, StatusCode::BAD_REQUEST);
+}
+
+// =============================================================================
+// REFRESH SKINS ENDPOINT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_refresh_skins_drops_status_image_only() {
+    // GIVEN: A server with a cached status image and a rendered head
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    let texture_hash = "c".repeat(64);
+
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.store_skin(texture_hash.clone(), None, vec![1, 2, 3])
+        .await
+        .expect("Failed to store skin");
+    db.store_rendered_head(texture_hash.clone(), vec![1, 2, 3], helpers::now())
+        .await
+        .expect("Failed to store head");
+    db.store_status_image(api_key_hash.clone(), vec![4, 5, 6], helpers::now())
+        .await
+        .expect("Failed to store status image");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: The server asks for a refresh
+    let (status, body) = send_request(app, "POST", "/refresh-skins", None, Some(&api_key)).await;
+
+    // THEN: Its status image is dropped
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["cleared"], 1);
+    assert!(db.get_status_image(&api_key_hash).await.unwrap().is_none());
+
+    // AND: The head, which other servers share, is kept
+    assert!(db.get_rendered_head(&texture_hash).await.unwrap().is_some());
+}
+
+/// GET a head image, returning the status, Cache-Control header and body.
+async fn get_head(app: axum::Router, texture_hash: &str) -> (StatusCode, String, Vec<u8>) {
+    let request = Request::builder()
+        .uri(format!("/heads/{}.png", texture_hash))
+        .header("X-Forwarded-For", "127.0.0.1")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let cache_control = response.headers()["cache-control"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, cache_control, body.to_vec())
+}
+
+#[tokio::test]
+async fn test_head_is_rendered_from_stored_skin_when_missing() {
+    // GIVEN: A stored skin whose head hasn't been rendered
+    let db = setup_test_db().await;
+    let texture_hash = "c".repeat(64);
+    let mut skin_png = Vec::new();
+    image::RgbaImage::from_pixel(64, 64, image::Rgba([200, 40, 40, 255]))
+        .write_to(
+            &mut std::io::Cursor::new(&mut skin_png),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+    db.store_skin(texture_hash.clone(), None, skin_png)
+        .await
+        .expect("Failed to store skin");
+
+    let app = create_test_app(db.clone());
+
+    // WHEN: The head is requested
+    let (status, cache_control, body) = get_head(app, &texture_hash).await;
+
+    // THEN: It is rendered, stored and served as immutable
+    assert_eq!(status, StatusCode::OK);
+    assert!(cache_control.contains("immutable"));
+    assert!(image::load_from_memory(&body).is_ok());
+    assert_eq!(
+        db.get_rendered_head(&texture_hash).await.unwrap(),
+        Some(body)
+    );
+}
+
+#[tokio::test]
+async fn test_head_fallback_is_not_cached_as_immutable() {
+    // GIVEN: No skin for a texture hash
+    let db = setup_test_db().await;
+    let app = create_test_app(db);
+
+    // WHEN: Its head is requested
+    let (status, cache_control, body) = get_head(app, &"d".repeat(64)).await;
+
+    // THEN: Steve is served, but only briefly cacheable
+    assert_eq!(status, StatusCode::OK);
+    assert!(!body.is_empty());
+    assert!(!cache_control.contains("immutable"));
+}
+
+#[tokio::test]
+async fn test_animated_status_image_is_cached_for_known_servers_only() {
+    // GIVEN: A linked server
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    let api_key_hash = helpers::hash_api_key(&api_key);
+    db.create_server(
+        api_key_hash.clone(),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let app = create_test_app(db.clone());
+
+    // WHEN: Its GIF is requested
+    let request = Request::builder()
+        .uri(format!("/status-image/{}.gif", api_key_hash))
+        .header("X-Forwarded-For", "127.0.0.1")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+
+    // THEN: It is rendered and cached for the next request
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(body.starts_with(b"GIF"));
+    assert_eq!(
+        db.get_status_gif(&api_key_hash).await.unwrap().as_deref(),
+        Some(&body[..])
+    );
+
+    // AND: A made-up hash is a 404 rather than a render
+    let (status, _) = send_request(
         app,
-        "POST",
-        "/sync",
-        Some(json!({ "players": players })),
-        Some(&api_key),
+        "GET",
+        &format!("/status-image/{}.gif", "e".repeat(64)),
+        None,
+        None,
     )
     .await;
-
-    // THEN: Should return 413 Payload Too Large
-    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(status, StatusCode::NOT_FOUND);
 }
 
 #[tokio::test]
-async fn test_join_with_oversized_player_name() {
-    // GIVEN: A valid server exists
+async fn test_refresh_skins_with_invalid_api_key() {
+    // GIVEN: An empty database
     let db = setup_test_db().await;
-    let api_key = helpers::generate_api_key();
-    let api_key_hash = helpers::hash_api_key(&api_key);
-    let guild_id = 123456789u64;
-    let server_name = "TestServer".to_string();
-
-    db.create_server(api_key_hash, server_name, guild_id)
-        .await
-        .expect("Failed to create server");
-
     let app = create_test_app(db);
 
-    // WHEN: Making a request with a massive player name (> 1MB)
-    let huge_name = "A".repeat(2 * 1024 * 1024); // 2MB player name
+    // WHEN: Making a request with invalid API key
     let (status, _body) = send_request(
         app,
         "POST",
-        "/join",
-        Some(json!({ "player": huge_name })),
-        Some(&api_key),
+        "/refresh-skins",
+        None,
+        Some("oxeye-sk-invalid12345678901234567890"),
     )
     .await;
 
-    // THEN: Should return 413 Payload Too Large
-    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    // THEN: Should return 401 Unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
 }
 
 // =============================================================================
-// STATUS ENDPOINT TESTS
+// ADMIN ENDPOINT TESTS
 // =============================================================================
 
 #[tokio::test]
-async fn test_status_authenticated() {
-    // GIVEN: A connected server
+async fn test_player_servers_across_guilds() {
+    // GIVEN: A player online on servers in two different guilds
     let db = setup_test_db().await;
-    let api_key = helpers::generate_api_key();
-    let api_key_hash = helpers::hash_api_key(&api_key);
-    let guild_id = 123456789u64;
-    let server_name = "TestServer".to_string();
+    for (hash, name, guild_id) in [
+        ("hash-a", "Survival", 111u64),
+        ("hash-b", "Creative", 222u64),
+        ("hash-c", "Hub", 222u64),
+    ] {
+        db.create_server(hash.to_string(), name.to_string(), guild_id, helpers::now())
+            .await
+            .expect("Failed to create server");
+    }
+    for hash in ["hash-a", "hash-b"] {
+        db.player_join(hash.to_string(), pn("Steve"), helpers::now())
+            .await
+            .expect("Failed to add player");
+    }
 
-    db.create_server(api_key_hash, server_name, guild_id)
-        .await
-        .expect("Failed to create server");
+    let config = oxeye_backend::config::Config {
+        admin_token: Some("admin-secret".to_string()),
+        ..Default::default()
+    };
+    let app = create_app(db, &config);
 
-    let app = create_test_app(db);
+    // WHEN: The admin looks the player up
+    let (status, body) = send_request(
+        app.clone(),
+        "GET",
+        "/players/Steve/servers",
+        None,
+        Some("admin-secret"),
+    )
+    .await;
 
-    // WHEN: Making a GET request to /status with valid API key
-    let (status, _body) = send_request(app, "GET", "/status", None, Some(&api_key)).await;
+    // THEN: Both servers are returned, ordered by guild
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["player"], "Steve");
+    assert_eq!(
+        body["servers"],
+        json!([
+            { "guild_id": 111, "name": "Survival" },
+            { "guild_id": 222, "name": "Creative" },
+        ])
+    );
 
-    // THEN: Should return 200 OK
+    // AND: A player who isn't online anywhere gets an empty list
+    let (status, body) = send_request(
+        app,
+        "GET",
+        "/players/Alex/servers",
+        None,
+        Some("admin-secret"),
+    )
+    .await;
     assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["servers"], json!([]));
 }
 
 #[tokio::test]
-async fn test_status_invalid_api_key() {
-    // GIVEN: An empty database
+async fn test_player_servers_requires_admin_token() {
+    // GIVEN: An application with an admin token configured
     let db = setup_test_db().await;
-    let app = create_test_app(db);
+    let config = oxeye_backend::config::Config {
+        admin_token: Some("admin-secret".to_string()),
+        ..Default::default()
+    };
+    let app = create_app(db, &config);
 
-    // WHEN: Making a GET request to /status with invalid API key
+    // WHEN: Calling the endpoint with a different token
     let (status, body) = send_request(
         app,
         "GET",
-        "/status",
+        "/players/Steve/servers",
         None,
-        Some("oxeye-sk-invalid12345678901234567890"),
+        Some("not-the-admin"),
     )
     .await;
 
@@ -921,146 +3737,169 @@ async fn test_status_invalid_api_key() {
 }
 
 #[tokio::test]
-async fn test_status_without_authorization() {
-    // GIVEN: A running application
+async fn test_player_servers_disabled_without_admin_token() {
+    // GIVEN: The default config (no admin token)
     let db = setup_test_db().await;
     let app = create_test_app(db);
 
-    // WHEN: Making a GET request to /status without Authorization header
-    let request = Request::builder()
-        .uri("/status")
-        .method("GET")
-        .header("X-Forwarded-For", "127.0.0.1")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-    let status = response.status();
+    // WHEN: Calling the admin endpoint
+    let (status, _) = send_request(app, "GET", "/players/Steve/servers", None, Some("")).await;
 
-    // THEN: Should return 400 Bad Request (missing required header)
-    assert!(
-        status.is_client_error(),
-        "Expected client error, got {}",
-        status
-    );
+    // THEN: The route isn't registered
+    assert_eq!(status, StatusCode::NOT_FOUND);
 }
 
-// =============================================================================
-// DISCONNECT ENDPOINT TESTS
-// =============================================================================
-
 #[tokio::test]
-async fn test_disconnect_success() {
-    // GIVEN: A connected server with players
+async fn test_merge_combines_players_without_duplicates() {
+    // GIVEN: Two proxies of one network with an overlapping player
     let db = setup_test_db().await;
-    let api_key = helpers::generate_api_key();
-    let api_key_hash = helpers::hash_api_key(&api_key);
-    let guild_id = 123456789u64;
-    let server_name = "TestServer".to_string();
-
-    db.create_server(api_key_hash.clone(), server_name, guild_id)
-        .await
-        .expect("Failed to create server");
-
-    // Add some players
-    let now = helpers::now();
-    db.player_join(api_key_hash.clone(), pn("Steve"), now)
-        .await
-        .expect("Failed to add player");
-
-    let app = create_test_app(db.clone());
-
-    // WHEN: Making a POST request to /disconnect with valid API key
-    let (status, _body) = send_request(app, "POST", "/disconnect", None, Some(&api_key)).await;
-
-    // THEN: Should return 200 OK
-    assert_eq!(status, StatusCode::OK);
-
-    // AND: Server should be deleted
-    let server = db
-        .get_server_by_api_key(api_key_hash.clone())
-        .await
-        .expect("Query failed");
-    assert!(server.is_none());
-
-    // AND: Players should be deleted (cascade)
-    let players = db
-        .get_online_players(api_key_hash)
-        .await
-        .expect("Query failed");
-    assert!(players.is_empty());
-}
+    for hash in ["proxy-a", "proxy-b"] {
+        db.create_server(hash.to_string(), hash.to_string(), 111, helpers::now())
+            .await
+            .expect("Failed to create server");
+    }
+    db.sync_players(
+        "proxy-a".to_string(),
+        vec![pn("Steve"), pn("Alex")],
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to sync players");
+    db.sync_players(
+        "proxy-b".to_string(),
+        vec![pn("Alex"), pn("Notch")],
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to sync players");
 
-#[tokio::test]
-async fn test_disconnect_with_invalid_api_key() {
-    // GIVEN: An empty database
-    let db = setup_test_db().await;
-    let app = create_test_app(db);
+    let config = oxeye_backend::config::Config {
+        admin_token: Some("admin-secret".to_string()),
+        ..Default::default()
+    };
+    let app = create_app(db.clone(), &config);
 
-    // WHEN: Making a request with invalid API key
+    // WHEN: The admin merges proxy-a into proxy-b
     let (status, body) = send_request(
         app,
         "POST",
-        "/disconnect",
-        None,
-        Some("oxeye-sk-invalid12345678901234567890"),
+        "/merge",
+        Some(json!({"source": "proxy-a", "destination": "proxy-b"})),
+        Some("admin-secret"),
     )
     .await;
 
-    // THEN: Should return 401 Unauthorized
-    assert_eq!(status, StatusCode::UNAUTHORIZED);
-    assert!(body.get("error").is_some());
+    // THEN: proxy-b lists each player once and proxy-a is empty
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["merged"], 3);
+    let mut players = db.get_online_players("proxy-b".to_string()).await.unwrap();
+    players.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    assert_eq!(players, vec![pn("Alex"), pn("Notch"), pn("Steve")]);
+    assert!(
+        db.get_online_players("proxy-a".to_string())
+            .await
+            .unwrap()
+            .is_empty()
+    );
 }
 
 #[tokio::test]
-async fn test_disconnect_without_authorization() {
-    // GIVEN: A running application
+async fn test_merge_rejects_unknown_or_same_server() {
+    // GIVEN: One linked server
     let db = setup_test_db().await;
-    let app = create_test_app(db);
+    db.create_server(
+        "proxy-a".to_string(),
+        "Hub".to_string(),
+        111,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    let config = oxeye_backend::config::Config {
+        admin_token: Some("admin-secret".to_string()),
+        ..Default::default()
+    };
+    let app = create_app(db, &config);
 
-    // WHEN: Making a request without Authorization header
-    let request = Request::builder()
-        .uri("/disconnect")
-        .method("POST")
-        .header("X-Forwarded-For", "127.0.0.1")
-        .body(Body::empty())
-        .unwrap();
+    // WHEN: Merging into a server that doesn't exist
+    let (status, _) = send_request(
+        app.clone(),
+        "POST",
+        "/merge",
+        Some(json!({"source": "proxy-a", "destination": "missing"})),
+        Some("admin-secret"),
+    )
+    .await;
 
-    let response = app.oneshot(request).await.unwrap();
-    let status = response.status();
+    // THEN: Should return 404
+    assert_eq!(status, StatusCode::NOT_FOUND);
 
-    // THEN: Should return 400 Bad Request (missing required header)
-    assert!(
-        status.is_client_error(),
-        "Expected client error, got {}",
-        status
-    );
+    // AND: Merging a server into itself is rejected
+    let (status, _) = send_request(
+        app.clone(),
+        "POST",
+        "/merge",
+        Some(json!({"source": "proxy-a", "destination": "proxy-a"})),
+        Some("admin-secret"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // AND: Server API keys aren't admin tokens
+    let (status, _) = send_request(
+        app,
+        "POST",
+        "/merge",
+        Some(json!({"source": "proxy-a", "destination": "missing"})),
+        Some("oxeye-sk-invalid12345678901234567890"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
 }
 
 #[tokio::test]
-async fn test_disconnect_twice() {
-    // GIVEN: A connected server
+async fn test_transfer_moves_server_to_new_guild() {
+    // GIVEN: A server in one guild, and a server with a clashing name in another
     let db = setup_test_db().await;
-    let api_key = helpers::generate_api_key();
-    let api_key_hash = helpers::hash_api_key(&api_key);
-    let guild_id = 123456789u64;
-    let server_name = "TestServer".to_string();
-
-    db.create_server(api_key_hash, server_name, guild_id)
+    db.create_server("hash-a".to_string(), "Hub".to_string(), 111, helpers::now())
+        .await
+        .expect("Failed to create server");
+    db.create_server("hash-b".to_string(), "hub".to_string(), 333, helpers::now())
         .await
         .expect("Failed to create server");
+    let config = oxeye_backend::config::Config {
+        admin_token: Some("admin-secret".to_string()),
+        ..Default::default()
+    };
+    let app = create_app(db.clone(), &config);
 
-    // First disconnect
-    let app = create_test_app(db.clone());
-    let (status, _) = send_request(app, "POST", "/disconnect", None, Some(&api_key)).await;
-    assert_eq!(status, StatusCode::OK);
+    // WHEN: The admin transfers it to a new guild
+    let (status, body) = send_request(
+        app.clone(),
+        "POST",
+        "/transfer",
+        Some(json!({"server": "hash-a", "guild_id": 222})),
+        Some("admin-secret"),
+    )
+    .await;
 
-    // WHEN: Trying to disconnect again with same API key
-    let app = create_test_app(db);
-    let (status, _) = send_request(app, "POST", "/disconnect", None, Some(&api_key)).await;
+    // THEN: It now belongs to the new guild
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({"server": "Hub", "guild_id": 222}));
+    let servers = db.get_servers_by_guild(222).await.unwrap();
+    assert_eq!(servers.len(), 1);
+    assert_eq!(servers[0].api_key_hash, "hash-a");
 
-    // THEN: Should return 401 Unauthorized (key no longer valid)
-    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    // AND: Moving it into a guild with a server of the same name conflicts
+    let (status, _) = send_request(
+        app,
+        "POST",
+        "/transfer",
+        Some(json!({"server": "hash-a", "guild_id": 333})),
+        Some("admin-secret"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
 }
 
 // =============================================================================
@@ -1071,13 +3910,13 @@ async fn test_disconnect_twice() {
 async fn test_complete_server_lifecycle() {
     // GIVEN: A fresh database
     let db = setup_test_db().await;
-    let code = helpers::generate_code();
+    let code = helpers::generate_code(6);
     let guild_id = 123456789u64;
     let server_name = "MyServer".to_string();
     let now = helpers::now();
 
     // Step 1: Create pending link
-    db.create_pending_link(code.clone(), guild_id, server_name, now)
+    db.create_pending_link(code.clone(), guild_id, server_name, now, 25)
         .await
         .expect("Failed to create pending link");
 
@@ -1130,7 +3969,7 @@ async fn test_complete_server_lifecycle() {
         app,
         "POST",
         "/sync",
-        Some(json!({ "players": ["Alex", "Notch", "Jeb"] })),
+        Some(json!({ "players": [{ "player": "Alex" }, { "player": "Notch" }, { "player": "Jeb" }] })),
         Some(&api_key),
     )
     .await;
@@ -1153,8 +3992,8 @@ async fn test_multiple_servers_in_same_guild() {
     let now = helpers::now();
 
     // Create first server
-    let code1 = helpers::generate_code();
-    db.create_pending_link(code1.clone(), guild_id, "Server1".to_string(), now)
+    let code1 = helpers::generate_code(6);
+    db.create_pending_link(code1.clone(), guild_id, "Server1".to_string(), now, 25)
         .await
         .expect("Failed to create pending link 1");
 
@@ -1171,8 +4010,8 @@ async fn test_multiple_servers_in_same_guild() {
     let api_key1 = body["api_key"].as_str().unwrap().to_string();
 
     // Create second server
-    let code2 = helpers::generate_code();
-    db.create_pending_link(code2.clone(), guild_id, "Server2".to_string(), now)
+    let code2 = helpers::generate_code(6);
+    db.create_pending_link(code2.clone(), guild_id, "Server2".to_string(), now, 25)
         .await
         .expect("Failed to create pending link 2");
 
@@ -1236,8 +4075,8 @@ async fn test_api_key_isolation() {
     let now = helpers::now();
 
     // Server 1
-    let code1 = helpers::generate_code();
-    db.create_pending_link(code1.clone(), 111, "Server1".to_string(), now)
+    let code1 = helpers::generate_code(6);
+    db.create_pending_link(code1.clone(), 111, "Server1".to_string(), now, 25)
         .await
         .expect("Failed to create pending link 1");
     let app = create_test_app(db.clone());
@@ -1252,8 +4091,8 @@ async fn test_api_key_isolation() {
     let api_key1 = body["api_key"].as_str().unwrap().to_string();
 
     // Server 2
-    let code2 = helpers::generate_code();
-    db.create_pending_link(code2.clone(), 222, "Server2".to_string(), now)
+    let code2 = helpers::generate_code(6);
+    db.create_pending_link(code2.clone(), 222, "Server2".to_string(), now, 25)
         .await
         .expect("Failed to create pending link 2");
     let app = create_test_app(db.clone());
@@ -1282,3 +4121,119 @@ async fn test_api_key_isolation() {
     // This is expected behavior - API keys are for server identification, not authorization
     assert_eq!(status, StatusCode::OK);
 }
+
+// =============================================================================
+// WEBHOOK TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_join_delivers_webhook() {
+    // GIVEN: A local endpoint, allowed as a webhook target, that forwards every POST body
+    let (hook_url, mut rx) = webhook_receiver("127.0.0.1").await;
+    let config = oxeye_backend::config::Config {
+        webhook_allow_private: true,
+        ..Default::default()
+    };
+
+    // WHEN: A player joins a server whose guild has that webhook set
+    join_with_webhook(&config, hook_url.clone()).await;
+
+    // THEN: The webhook receives the join
+    let payload = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+        .await
+        .expect("Webhook was not delivered")
+        .unwrap();
+    assert_eq!(payload["server"], "TestServer");
+    assert_eq!(payload["player"], "Steve");
+    assert_eq!(payload["event"], "join");
+    assert!(payload["timestamp"].as_i64().unwrap() > 0);
+
+    // WHEN: A batch of events is reported through /events instead
+    report_with_webhook(
+        &config,
+        hook_url,
+        "/events",
+        json!({ "events": [
+            { "player": "Alex", "event": "join" },
+            { "player": "Alex", "event": "leave" },
+        ] }),
+    )
+    .await;
+
+    // THEN: The webhook receives each event in the batch
+    let mut events = Vec::new();
+    for _ in 0..2 {
+        let payload = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .expect("Webhook was not delivered")
+            .unwrap();
+        assert_eq!(payload["player"], "Alex");
+        events.push(payload["event"].as_str().unwrap().to_string());
+    }
+    events.sort();
+    assert_eq!(events, vec!["join", "leave"]);
+}
+
+#[tokio::test]
+async fn test_webhook_not_delivered_to_private_address() {
+    // GIVEN: A local endpoint reached through a name that resolves to loopback
+    let (hook_url, mut rx) = webhook_receiver("localhost").await;
+
+    // WHEN: A player joins with the default config (private targets not allowed)
+    join_with_webhook(&oxeye_backend::config::Config::default(), hook_url).await;
+
+    // THEN: Nothing is POSTed to it
+    let delivered = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv()).await;
+    assert!(delivered.is_err(), "Webhook reached a private address");
+}
+
+/// Start an endpoint on loopback that forwards every POST body to a channel,
+/// returning its URL with `host` as the host.
+async fn webhook_receiver(host: &str) -> (String, tokio::sync::mpsc::Receiver<Value>) {
+    let (tx, rx) = tokio::sync::mpsc::channel::<Value>(1);
+    let receiver = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |axum::Json(body): axum::Json<Value>| async move {
+            tx.send(body).await.unwrap();
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let hook_url = format!(
+        "http://{}:{}/hook",
+        host,
+        listener.local_addr().unwrap().port()
+    );
+    tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+    (hook_url, rx)
+}
+
+/// Have "Steve" join a new server whose guild's webhook is `hook_url`.
+async fn join_with_webhook(config: &oxeye_backend::config::Config, hook_url: String) {
+    report_with_webhook(config, hook_url, "/join", json!({"player": "Steve"})).await;
+}
+
+/// POST `body` to `uri` for a new server whose guild's webhook is `hook_url`.
+async fn report_with_webhook(
+    config: &oxeye_backend::config::Config,
+    hook_url: String,
+    uri: &str,
+    body: Value,
+) {
+    let db = setup_test_db().await;
+    let api_key = helpers::generate_api_key();
+    db.create_server(
+        helpers::hash_api_key(&api_key),
+        "TestServer".to_string(),
+        123456789,
+        helpers::now(),
+    )
+    .await
+    .expect("Failed to create server");
+    db.set_guild_webhook(123456789, Some(hook_url))
+        .await
+        .expect("Failed to set webhook");
+    let app = create_app(db, config);
+
+    let (status, _body) = send_request(app, "POST", uri, Some(body), Some(&api_key)).await;
+    assert_eq!(status, StatusCode::OK);
+}
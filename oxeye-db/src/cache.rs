@@ -1,9 +1,14 @@
 //! In-memory cache for online players.
 //!
 //! This module provides a lock-free in-memory storage for ephemeral player data.
-//! Player data resyncs on reconnect, so durability isn't needed.
+//! Player data resyncs on reconnect, so durability isn't needed; on graceful
+//! shutdown the cache is still flushed to SQLite (see `Database::flush_cache`),
+//! or written to a snapshot file (see `Database::save_snapshot`), so lists
+//! aren't empty while servers resync.
 
 use crate::models::PlayerName;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// State of a single Minecraft server's online players.
 #[derive(Debug, Default)]
@@ -14,6 +19,15 @@ pub struct ServerState {
     pub players: Vec<(PlayerName, i64)>,
     /// Whether this server has synced since backend restart.
     pub synced_since_boot: bool,
+    /// When the server last sent a full player list, if ever (survives restarts).
+    pub last_sync_at: Option<i64>,
+    /// Players who left but stay listed until their leave grace period ends,
+    /// with the id of that leave. Each is still in `players`.
+    pub pending_leaves: Vec<(PlayerName, u64)>,
+    /// Id given to the most recent leave, so a grace timer only finishes its own leave.
+    pub last_leave_id: u64,
+    /// Most players ever online at once, as stored in SQLite.
+    pub peak_players: usize,
 }
 
 impl ServerState {
@@ -22,13 +36,21 @@ impl ServerState {
         Self {
             players: Vec::new(),
             synced_since_boot: false,
+            last_sync_at: None,
+            pending_leaves: Vec::new(),
+            last_leave_id: 0,
+            peak_players: 0,
         }
     }
 
     /// Add a player to the server.
-    /// If player already exists, updates their join time.
+    /// If player already exists, updates their join time, unless they are
+    /// rejoining within their leave grace period (their session carries on).
     pub fn add_player(&mut self, name: PlayerName, joined_at: i64) {
         self.synced_since_boot = true;
+        if self.cancel_leave(&name) && self.players.iter().any(|(n, _)| *n == name) {
+            return;
+        }
         // Check if player already exists
         if let Some(idx) = self.players.iter().position(|(n, _)| *n == name) {
             self.players[idx].1 = joined_at;
@@ -37,19 +59,78 @@ impl ServerState {
         }
     }
 
-    /// Remove a player from the server.
+    /// Remove a player from the server, returning when they joined.
     /// Uses swap_remove for O(1) removal (order doesn't matter for players).
-    pub fn remove_player(&mut self, name: &PlayerName) {
+    pub fn remove_player(&mut self, name: &PlayerName) -> Option<i64> {
         self.synced_since_boot = true;
-        if let Some(idx) = self.players.iter().position(|(n, _)| n == name) {
-            self.players.swap_remove(idx);
-        }
+        self.cancel_leave(name);
+        let idx = self.players.iter().position(|(n, _)| n == name)?;
+        Some(self.players.swap_remove(idx).1)
     }
 
     /// Replace all players (for sync operation).
-    pub fn sync_players(&mut self, players: Vec<(PlayerName, i64)>) {
+    pub fn sync_players(&mut self, players: Vec<(PlayerName, i64)>, now: i64) {
         self.players = players;
         self.synced_since_boot = true;
+        self.last_sync_at = Some(now);
+        self.pending_leaves.clear();
+    }
+
+    /// Drop every online player (and pending leave), returning how many were listed.
+    pub fn clear_players(&mut self) -> usize {
+        self.pending_leaves.clear();
+        std::mem::take(&mut self.players).len()
+    }
+
+    /// Start a player's leave grace period, keeping them listed until
+    /// `finish_leave` is called with the returned leave id. Returns None (doing
+    /// nothing) if they aren't online.
+    pub fn begin_leave(&mut self, name: PlayerName) -> Option<u64> {
+        self.synced_since_boot = true;
+        if !self.players.iter().any(|(n, _)| *n == name) {
+            return None;
+        }
+        self.cancel_leave(&name);
+        self.last_leave_id += 1;
+        self.pending_leaves.push((name, self.last_leave_id));
+        Some(self.last_leave_id)
+    }
+
+    /// Remove a player whose grace period for leave `leave_id` is over.
+    /// Returns None if that leave was cancelled by a rejoin (or superseded),
+    /// otherwise when the player joined, as `remove_player` does.
+    pub fn finish_leave(&mut self, name: &PlayerName, leave_id: u64) -> Option<Option<i64>> {
+        let idx = self
+            .pending_leaves
+            .iter()
+            .position(|(n, id)| n == name && *id == leave_id)?;
+        self.pending_leaves.swap_remove(idx);
+        Some(self.remove_player(name))
+    }
+
+    /// Drop a player's pending leave, returning whether there was one.
+    fn cancel_leave(&mut self, name: &PlayerName) -> bool {
+        let before = self.pending_leaves.len();
+        self.pending_leaves.retain(|(n, _)| n != name);
+        self.pending_leaves.len() != before
+    }
+
+    /// Raise the peak to the current player count if it is higher, returning the new peak.
+    pub fn note_peak(&mut self) -> Option<usize> {
+        if self.players.len() <= self.peak_players {
+            return None;
+        }
+        self.peak_players = self.players.len();
+        Some(self.peak_players)
+    }
+
+    /// Players to keep across a restart: everyone listed except those in their
+    /// leave grace period, whose timers don't survive it.
+    pub fn persistent_players(&self) -> impl Iterator<Item = (PlayerName, i64)> + '_ {
+        self.players
+            .iter()
+            .filter(|(name, _)| !self.pending_leaves.iter().any(|(n, _)| n == name))
+            .copied()
     }
 
     /// Get player count.
@@ -66,3 +147,17 @@ pub type OnlineCache = scc::HashMap<String, ServerState>;
 pub fn new_cache() -> OnlineCache {
     OnlineCache::new()
 }
+
+/// Online players of every server, in the form saved to a snapshot file.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheSnapshot {
+    /// Each server's online players as (name, joined_at), by API key hash
+    pub servers: HashMap<String, Vec<(String, i64)>>,
+}
+
+impl CacheSnapshot {
+    /// Total players across all servers.
+    pub fn player_count(&self) -> usize {
+        self.servers.values().map(Vec::len).sum()
+    }
+}
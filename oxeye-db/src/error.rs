@@ -8,6 +8,9 @@ pub enum DbError {
     #[error("database connection error: {0}")]
     Connection(#[from] tokio_rusqlite::Error),
 
+    #[error("snapshot file error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("pending link not found or expired")]
     PendingLinkNotFound,
 
@@ -20,8 +23,14 @@ pub enum DbError {
     #[error("server name already exists in this guild")]
     ServerNameConflict,
 
+    #[error("guild has reached its server limit")]
+    ServerLimitReached,
+
     #[error("invalid api key")]
     InvalidApiKey,
+
+    #[error("invalid guild token")]
+    InvalidGuildToken,
 }
 
 pub type Result<T> = std::result::Result<T, DbError>;
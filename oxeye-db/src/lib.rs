@@ -1,56 +1,260 @@
 mod cache;
 mod error;
 mod models;
+mod store;
 
-pub use cache::{OnlineCache, ServerState, new_cache};
+pub use cache::{CacheSnapshot, OnlineCache, ServerState, new_cache};
 pub use error::{DbError, Result};
 pub use models::{
-    OnlinePlayer, PendingLink, PlayerInfo, PlayerName, Server, ServerSummary, ServerWithPlayers,
+    AuditEntry, GuildStats, MAX_PLAYER_NAME_LEN, OnlinePlayer, PendingLink, PlayerEvent,
+    PlayerInfo, PlayerName, Server, ServerSummary, ServerWithPlayers,
 };
+pub use store::Store;
+#[cfg(feature = "test-util")]
+pub use store::{CallLog, RecordingStore};
 
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use tokio_rusqlite::Connection;
-use tokio_rusqlite::rusqlite::{OptionalExtension, params};
-use tracing::{debug, info};
+use tokio_rusqlite::rusqlite::{self, ErrorCode, OptionalExtension, params, params_from_iter};
+use tracing::{debug, info, warn};
+
+/// Number of read-only connections opened alongside the writer.
+const READER_CONNECTIONS: usize = 4;
+
+/// How many times a write is attempted while SQLite reports busy/locked.
+const BUSY_RETRY_ATTEMPTS: u32 = 5;
+
+/// Delay before the first busy retry; doubles after each further attempt.
+const BUSY_RETRY_BASE_DELAY: Duration = Duration::from_millis(20);
+
+/// Whether an error is a transient SQLITE_BUSY / SQLITE_LOCKED failure.
+fn is_busy(err: &tokio_rusqlite::Error) -> bool {
+    matches!(
+        err,
+        tokio_rusqlite::Error::Error(rusqlite::Error::SqliteFailure(e, _))
+            if matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Run a closure on the connection, retrying with exponential backoff while
+/// SQLite reports the database as busy or locked.
+///
+/// The closure is cloned for each attempt, so it must not have side effects
+/// outside its (rolled back on failure) transaction.
+async fn call_with_retry<F, R>(conn: &Connection, function: F) -> tokio_rusqlite::Result<R>
+where
+    F: FnOnce(&mut rusqlite::Connection) -> rusqlite::Result<R> + Clone + Send + 'static,
+    R: Send + 'static,
+{
+    let mut delay = BUSY_RETRY_BASE_DELAY;
+    let mut attempt = 1;
+    loop {
+        match conn.call(function.clone()).await {
+            Err(e) if attempt < BUSY_RETRY_ATTEMPTS && is_busy(&e) => {
+                warn!(attempt, "database busy, retrying in {:?}", delay);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Move a server and its stored rows from one API key hash to another inside
+/// `tx`. Returns false (changing nothing) if no server has the old hash.
+fn rekey_server(tx: &rusqlite::Transaction<'_>, old: &str, new: &str) -> rusqlite::Result<bool> {
+    // Child rows still point at the old hash until they're updated below
+    tx.pragma_update(None, "defer_foreign_keys", "ON")?;
+
+    let updated = tx
+        .prepare_cached("UPDATE servers SET api_key_hash = ?2 WHERE api_key_hash = ?1")?
+        .execute(params![old, new])?;
+    if updated == 0 {
+        return Ok(false);
+    }
+
+    for table in [
+        "status_images",
+        "status_gifs",
+        "player_last_seen",
+        "player_sessions",
+        "online_snapshot",
+    ] {
+        tx.prepare_cached(&format!(
+            "UPDATE {table} SET api_key_hash = ?2 WHERE api_key_hash = ?1"
+        ))?
+        .execute(params![old, new])?;
+    }
+    Ok(true)
+}
+
+/// Rename servers whose names differ only in case from another server in the
+/// same guild, so names can be made unique regardless of case. The oldest
+/// server keeps its name; the others get " (2)", " (3)", ... appended.
+fn rename_case_duplicates(tx: &rusqlite::Transaction<'_>) -> rusqlite::Result<()> {
+    let duplicates: Vec<(String, u64, String)> = tx
+        .prepare(
+            "SELECT s.api_key_hash, s.guild_id, s.name FROM servers s
+             WHERE EXISTS(SELECT 1 FROM servers o WHERE o.guild_id = s.guild_id
+                          AND o.name = s.name COLLATE NOCASE AND o.api_key_hash != s.api_key_hash)
+             ORDER BY s.guild_id, s.created_at, s.rowid",
+        )?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    // NOCASE only folds ASCII, so names are grouped the same way here
+    let mut kept = HashSet::new();
+    for (api_key_hash, guild_id, name) in duplicates {
+        if kept.insert((guild_id, name.to_ascii_lowercase())) {
+            continue;
+        }
+        let mut suffix = 2;
+        let renamed = loop {
+            let candidate = format!("{name} ({suffix})");
+            let taken: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE)",
+                params![guild_id, &candidate],
+                |row| row.get(0),
+            )?;
+            if !taken {
+                break candidate;
+            }
+            suffix += 1;
+        };
+        tx.execute(
+            "UPDATE servers SET name = ?2 WHERE api_key_hash = ?1",
+            params![&api_key_hash, &renamed],
+        )?;
+        warn!(guild_id, %name, %renamed, "renamed server whose name differed only in case from another");
+    }
+    Ok(())
+}
+
+/// Whether linking servers named `new_names` would take a guild past
+/// `max_servers`. Unexpired codes count toward the limit like linked servers,
+/// so claiming them all can't go over it; a name with several codes counts
+/// once, and relink codes don't count since they don't add a server.
+fn over_server_limit<'a>(
+    tx: &rusqlite::Transaction<'_>,
+    guild_id: u64,
+    new_names: impl IntoIterator<Item = &'a str>,
+    now: i64,
+    max_servers: u32,
+) -> rusqlite::Result<bool> {
+    let linked: usize = tx
+        .prepare_cached("SELECT COUNT(*) FROM servers WHERE guild_id = ?1")?
+        .query_row(params![guild_id], |row| row.get(0))?;
+
+    // Names compare like COLLATE NOCASE, which only folds ASCII
+    let mut names = tx
+        .prepare_cached(
+            "SELECT server_name FROM pending_links WHERE guild_id = ?1 AND relink = 0 AND created_at >= ?2",
+        )?
+        .query_map(params![guild_id, now - PendingLink::TTL_SECONDS], |row| {
+            row.get::<_, String>(0)
+        })?
+        .map(|name| name.map(|name| name.to_ascii_lowercase()))
+        .collect::<rusqlite::Result<HashSet<_>>>()?;
+    names.extend(new_names.into_iter().map(str::to_ascii_lowercase));
+
+    Ok(linked + names.len() > max_servers as usize)
+}
 
 /// Database wrapper for all Oxeye operations.
 ///
 /// Persistent data (servers, pending_links) is stored in SQLite.
 /// Ephemeral data (online_players) is stored in an in-memory cache.
+///
+/// Writes go through a single connection; read-only queries are spread
+/// round-robin over a small pool of reader connections (WAL mode lets
+/// them run concurrently with the writer).
 #[derive(Clone)]
 pub struct Database {
     conn: Connection,
+    readers: Arc<[Connection]>,
+    next_reader: Arc<AtomicUsize>,
     cache: Arc<OnlineCache>,
+    /// How long a leaving player stays listed in case they rejoin (zero removes at once)
+    leave_grace: Duration,
 }
 
 impl Database {
     /// Open or create a database at the given path.
     pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
         let conn = Connection::open(path).await.map_err(DbError::Sqlite)?;
-        let cache = Arc::new(new_cache());
-        let db = Self { conn, cache };
+        let mut db = Self::with_connection(conn);
         db.initialize().await?;
+
+        // Readers are opened after initialize() so the file is already in WAL mode
+        let mut readers = Vec::with_capacity(READER_CONNECTIONS);
+        for _ in 0..READER_CONNECTIONS {
+            let reader = Connection::open(path).await.map_err(DbError::Sqlite)?;
+            reader
+                .call(|conn| {
+                    conn.pragma_update(None, "query_only", "ON")?;
+                    Ok(())
+                })
+                .await?;
+            readers.push(reader);
+        }
+        db.readers = readers.into();
+
         db.populate_cache().await?;
         Ok(db)
     }
 
     /// Create an in-memory database (useful for testing).
+    ///
+    /// In-memory databases are private to one connection, so reads share the writer.
     pub async fn open_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()
             .await
             .map_err(DbError::Sqlite)?;
-        let cache = Arc::new(new_cache());
-        let db = Self { conn, cache };
+        let db = Self::with_connection(conn);
         db.initialize().await?;
         db.populate_cache().await?;
         Ok(db)
     }
 
+    /// Build a database that uses one connection for both reads and writes.
+    fn with_connection(conn: Connection) -> Self {
+        Self {
+            readers: Arc::new([conn.clone()]),
+            next_reader: Arc::new(AtomicUsize::new(0)),
+            conn,
+            cache: Arc::new(new_cache()),
+            leave_grace: Duration::ZERO,
+        }
+    }
+
+    /// Keep leaving players listed for `grace` so a quick rejoin (e.g. a flaky
+    /// connection) never shows them as gone. Their departure is recorded, with
+    /// the original leave time, only if the grace period ends without a rejoin.
+    pub fn with_leave_grace(mut self, grace: Duration) -> Self {
+        self.leave_grace = grace;
+        self
+    }
+
+    /// Pick the next reader connection for a read-only query.
+    fn reader(&self) -> &Connection {
+        let index = self.next_reader.fetch_add(1, Ordering::Relaxed);
+        &self.readers[index % self.readers.len()]
+    }
+
     /// Initialize the database schema.
     async fn initialize(&self) -> Result<()> {
         self.conn
             .call(|conn| {
+                // Let maintenance() reclaim free pages; only takes effect for new
+                // database files (existing ones keep their mode until a full VACUUM)
+                conn.pragma_update(None, "auto_vacuum", "INCREMENTAL")?;
+
                 // Enable WAL mode for better concurrent read/write performance
                 conn.pragma_update(None, "journal_mode", "WAL")?;
 
@@ -64,6 +268,17 @@ impl Database {
                         code TEXT PRIMARY KEY,
                         guild_id INTEGER NOT NULL,
                         server_name TEXT NOT NULL,
+                        created_at INTEGER NOT NULL,
+                        relink INTEGER NOT NULL DEFAULT 0
+                    );
+
+                    -- Index for listing a guild's outstanding codes
+                    CREATE INDEX IF NOT EXISTS idx_pending_links_guild ON pending_links(guild_id);
+
+                    -- Codes claimed before they expired, kept until then so a
+                    -- second claim is told the code was used rather than unknown
+                    CREATE TABLE IF NOT EXISTS claimed_links (
+                        code TEXT PRIMARY KEY,
                         created_at INTEGER NOT NULL
                     );
 
@@ -72,6 +287,11 @@ impl Database {
                         api_key_hash TEXT PRIMARY KEY,
                         name TEXT NOT NULL,
                         guild_id INTEGER NOT NULL,
+                        created_at INTEGER NOT NULL DEFAULT 0,
+                        tag TEXT,
+                        last_sync_at INTEGER,
+                        peak_players INTEGER NOT NULL DEFAULT 0,
+                        peak_at INTEGER,
                         UNIQUE(guild_id, name)
                     );
 
@@ -93,6 +313,13 @@ impl Database {
                         FOREIGN KEY (texture_hash) REFERENCES skins(texture_hash)
                     );
 
+                    -- Maps players to their UUID (names can change, UUIDs don't)
+                    CREATE TABLE IF NOT EXISTS player_uuids (
+                        player_name TEXT PRIMARY KEY,
+                        uuid TEXT NOT NULL,
+                        last_updated INTEGER NOT NULL
+                    );
+
                     -- Stores rendered head images (one per unique skin)
                     CREATE TABLE IF NOT EXISTS rendered_heads (
                         texture_hash TEXT PRIMARY KEY,
@@ -108,8 +335,108 @@ impl Database {
                         updated_at INTEGER NOT NULL,
                         FOREIGN KEY (api_key_hash) REFERENCES servers(api_key_hash) ON DELETE CASCADE
                     );
+
+                    -- Caches animated status GIFs (dropped whenever the PNG is replaced)
+                    CREATE TABLE IF NOT EXISTS status_gifs (
+                        api_key_hash TEXT PRIMARY KEY,
+                        image_data BLOB NOT NULL,
+                        updated_at INTEGER NOT NULL,
+                        FOREIGN KEY (api_key_hash) REFERENCES servers(api_key_hash) ON DELETE CASCADE
+                    );
+
+                    -- Guild-scoped read tokens for dashboards (one per guild)
+                    CREATE TABLE IF NOT EXISTS guild_tokens (
+                        token_hash TEXT PRIMARY KEY,
+                        guild_id INTEGER NOT NULL UNIQUE,
+                        created_at INTEGER NOT NULL
+                    );
+
+                    -- Per-guild preferences set from Discord (unset columns use defaults)
+                    CREATE TABLE IF NOT EXISTS guild_settings (
+                        guild_id INTEGER PRIMARY KEY,
+                        embed_color INTEGER
+                    );
+
+                    -- Where each guild wants player join/leave events POSTed
+                    CREATE TABLE IF NOT EXISTS guild_webhooks (
+                        guild_id INTEGER PRIMARY KEY,
+                        url TEXT NOT NULL
+                    );
+
+                    -- Admin actions taken from Discord commands, kept for /auditlog
+                    CREATE TABLE IF NOT EXISTS audit_log (
+                        id INTEGER PRIMARY KEY AUTOINCREMENT,
+                        guild_id INTEGER NOT NULL,
+                        user_id INTEGER NOT NULL,
+                        action TEXT NOT NULL,
+                        target TEXT NOT NULL,
+                        created_at INTEGER NOT NULL
+                    );
+
+                    -- Index for listing a guild's most recent actions
+                    CREATE INDEX IF NOT EXISTS idx_audit_log_guild ON audit_log(guild_id, created_at);
+
+                    -- When each player was last online on each server (one row per player)
+                    CREATE TABLE IF NOT EXISTS player_last_seen (
+                        api_key_hash TEXT NOT NULL,
+                        player_name TEXT NOT NULL,
+                        last_seen INTEGER NOT NULL,
+                        PRIMARY KEY (api_key_hash, player_name),
+                        FOREIGN KEY (api_key_hash) REFERENCES servers(api_key_hash) ON DELETE CASCADE
+                    );
+
+                    -- Completed play sessions (one row per join/leave pair)
+                    CREATE TABLE IF NOT EXISTS player_sessions (
+                        api_key_hash TEXT NOT NULL,
+                        player_name TEXT NOT NULL,
+                        joined_at INTEGER NOT NULL,
+                        left_at INTEGER NOT NULL,
+                        FOREIGN KEY (api_key_hash) REFERENCES servers(api_key_hash) ON DELETE CASCADE
+                    );
+                    CREATE INDEX IF NOT EXISTS idx_player_sessions_server ON player_sessions(api_key_hash);
+
+                    -- Online players flushed from the cache on shutdown, restored on startup
+                    CREATE TABLE IF NOT EXISTS online_snapshot (
+                        api_key_hash TEXT NOT NULL,
+                        player_name TEXT NOT NULL,
+                        joined_at INTEGER NOT NULL,
+                        PRIMARY KEY (api_key_hash, player_name),
+                        FOREIGN KEY (api_key_hash) REFERENCES servers(api_key_hash) ON DELETE CASCADE
+                    );
                     "#,
                 )?;
+
+                // Databases created before these columns existed need them added
+                for (table, column, definition) in [
+                    ("servers", "created_at", "created_at INTEGER NOT NULL DEFAULT 0"),
+                    ("servers", "tag", "tag TEXT"),
+                    ("servers", "last_sync_at", "last_sync_at INTEGER"),
+                    ("servers", "peak_players", "peak_players INTEGER NOT NULL DEFAULT 0"),
+                    ("servers", "peak_at", "peak_at INTEGER"),
+                    ("pending_links", "relink", "relink INTEGER NOT NULL DEFAULT 0"),
+                ] {
+                    let has_column: bool = conn.query_row(
+                        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+                        params![table, column],
+                        |row| row.get(0),
+                    )?;
+                    if !has_column {
+                        conn.execute_batch(&format!(
+                            "ALTER TABLE {} ADD COLUMN {}",
+                            table, definition
+                        ))?;
+                    }
+                }
+
+                // Server names are unique per guild regardless of case. Older
+                // databases may already hold names differing only in case, which
+                // would make lookups by name ambiguous, so those are renamed first.
+                let tx = conn.transaction()?;
+                rename_case_duplicates(&tx)?;
+                tx.execute_batch(
+                    "CREATE UNIQUE INDEX IF NOT EXISTS idx_servers_guild_name_nocase ON servers(guild_id, name COLLATE NOCASE)",
+                )?;
+                tx.commit()?;
                 Ok(())
             })
             .await?;
@@ -119,25 +446,204 @@ impl Database {
     }
 
     /// Pre-populate the cache with all existing servers.
-    /// All servers start with synced_since_boot = false.
+    /// All servers start with synced_since_boot = false (last_sync_at is kept).
+    ///
+    /// Players saved by `flush_cache` on the last shutdown are restored, then
+    /// the snapshot is cleared so a later crash can't resurrect stale lists.
     async fn populate_cache(&self) -> Result<()> {
-        let api_key_hashes: Vec<String> = self
+        let (servers, snapshot) = self
             .conn
             .call(|conn| {
-                let mut stmt = conn.prepare_cached("SELECT api_key_hash FROM servers")?;
-                let hashes = stmt
-                    .query_map([], |row| row.get(0))?
+                let mut stmt = conn.prepare_cached(
+                    "SELECT api_key_hash, last_sync_at, peak_players FROM servers",
+                )?;
+                let servers: Vec<(String, Option<i64>, usize)> = stmt
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
                     .collect::<std::result::Result<Vec<_>, _>>()?;
-                Ok(hashes)
+
+                let mut stmt = conn.prepare_cached(
+                    "SELECT api_key_hash, player_name, joined_at FROM online_snapshot",
+                )?;
+                let snapshot: Vec<(String, String, i64)> = stmt
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+
+                conn.execute("DELETE FROM online_snapshot", [])?;
+                Ok((servers, snapshot))
             })
             .await?;
 
-        let count = api_key_hashes.len();
-        for hash in api_key_hashes {
-            let _ = self.cache.insert_async(hash, ServerState::new()).await;
+        let count = servers.len();
+        for (hash, last_sync_at, peak_players) in servers {
+            let state = ServerState {
+                last_sync_at,
+                peak_players,
+                ..ServerState::new()
+            };
+            let _ = self.cache.insert_async(hash, state).await;
+        }
+
+        let restored = snapshot.len();
+        for (api_key_hash, player_name, joined_at) in snapshot {
+            let Ok(player_name) = PlayerName::from(&player_name) else {
+                continue;
+            };
+            if let Some(mut entry) = self.cache.get_async(&api_key_hash).await {
+                entry.get_mut().players.push((player_name, joined_at));
+            }
+        }
+
+        info!(
+            count,
+            restored, "pre-populated cache with servers (awaiting sync)"
+        );
+        Ok(())
+    }
+
+    /// Save all online players to SQLite so they survive a restart.
+    /// Called on graceful shutdown; returns the number of players saved.
+    ///
+    /// Players in their leave grace period are left out, as in `save_snapshot`.
+    pub async fn flush_cache(&self) -> Result<usize> {
+        let mut rows: Vec<(String, PlayerName, i64)> = Vec::new();
+        self.cache
+            .iter_async(|api_key_hash, state| {
+                for (name, joined_at) in state.persistent_players() {
+                    rows.push((api_key_hash.clone(), name, joined_at));
+                }
+                true
+            })
+            .await;
+
+        let count = rows.len();
+        call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM online_snapshot", [])?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT OR IGNORE INTO online_snapshot (api_key_hash, player_name, joined_at)
+                     SELECT ?1, ?2, ?3 WHERE EXISTS (SELECT 1 FROM servers WHERE api_key_hash = ?1)",
+                )?;
+                for (api_key_hash, name, joined_at) in &rows {
+                    stmt.execute(params![api_key_hash, name.as_str(), joined_at])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+        info!(count, "flushed online players to database");
+        Ok(count)
+    }
+
+    /// Write all online players to a JSON snapshot file so they survive a
+    /// restart, as an alternative to `flush_cache`. Called on graceful
+    /// shutdown; returns the number of players saved.
+    ///
+    /// Players in their leave grace period are left out, since the timer that
+    /// would remove them doesn't survive the restart. The file is written
+    /// beside `path` and renamed over it, so a crash mid-write can't leave a
+    /// truncated snapshot.
+    pub async fn save_snapshot(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let mut snapshot = CacheSnapshot::default();
+        self.cache
+            .iter_async(|api_key_hash, state| {
+                let players: Vec<(String, i64)> = state
+                    .persistent_players()
+                    .map(|(name, joined_at)| (name.to_string(), joined_at))
+                    .collect();
+                if !players.is_empty() {
+                    snapshot.servers.insert(api_key_hash.clone(), players);
+                }
+                true
+            })
+            .await;
+
+        let count = snapshot.player_count();
+        let json = serde_json::to_vec(&snapshot).map_err(std::io::Error::from)?;
+        let path = path.as_ref();
+        let partial = path.with_extension("tmp");
+        tokio::fs::write(&partial, json).await?;
+        tokio::fs::rename(&partial, path).await?;
+
+        info!(count, path = %path.display(), "saved online players snapshot");
+        Ok(count)
+    }
+
+    /// Restore online players from a file written by `save_snapshot`,
+    /// returning how many were restored. Servers still await a sync, as with
+    /// players restored from the database.
+    ///
+    /// The file is removed once read, so a later crash can't bring back a
+    /// stale snapshot. A missing or corrupt file is logged and skipped, and
+    /// the cache starts without players.
+    pub async fn load_snapshot(&self, path: impl AsRef<Path>) -> usize {
+        let path = path.as_ref();
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!(path = %path.display(), "no online players snapshot to restore");
+                return 0;
+            }
+            Err(e) => {
+                warn!(path = %path.display(), "failed to read online players snapshot: {}", e);
+                return 0;
+            }
+        };
+        if let Err(e) = tokio::fs::remove_file(path).await {
+            warn!(path = %path.display(), "failed to remove online players snapshot: {}", e);
+        }
+        let snapshot: CacheSnapshot = match serde_json::from_slice(&data) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring corrupt online players snapshot: {}", e);
+                return 0;
+            }
+        };
+
+        let mut restored = 0;
+        for (api_key_hash, players) in snapshot.servers {
+            // Servers unlinked since the snapshot was taken aren't in the cache
+            let Some(mut entry) = self.cache.get_async(&api_key_hash).await else {
+                continue;
+            };
+            let players: Vec<(PlayerName, i64)> = players
+                .into_iter()
+                .filter_map(|(name, joined_at)| Some((PlayerName::from(&name).ok()?, joined_at)))
+                .collect();
+            restored += players.len();
+            entry.get_mut().players = players;
         }
 
-        info!(count, "pre-populated cache with servers (awaiting sync)");
+        info!(restored, "restored online players from snapshot");
+        restored
+    }
+
+    /// Run a trivial query to check that the database is reachable.
+    pub async fn ping(&self) -> Result<()> {
+        self.conn
+            .call(|conn| {
+                conn.query_row("SELECT 1", [], |_| Ok(()))?;
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Reclaim free pages and refresh query planner statistics.
+    ///
+    /// Safe to run while the server is live: in WAL mode readers keep working
+    /// while this holds the write lock.
+    pub async fn maintenance(&self) -> Result<()> {
+        self.conn
+            .call(|conn| {
+                conn.execute_batch("PRAGMA incremental_vacuum; ANALYZE;")?;
+                Ok(())
+            })
+            .await?;
+
+        debug!("ran database maintenance");
         Ok(())
     }
 
@@ -149,14 +655,33 @@ impl Database {
         }
     }
 
+    /// When a server last sent a full player list (None if it never has).
+    pub async fn get_last_sync(&self, api_key_hash: &str) -> Result<Option<i64>> {
+        if let Some(entry) = self.cache.get_async(api_key_hash).await {
+            return Ok(entry.get().last_sync_at);
+        }
+        let hash = api_key_hash.to_string();
+        let last_sync_at = self
+            .reader()
+            .call(move |conn| {
+                let last_sync_at: Option<Option<i64>> = conn
+                    .prepare_cached("SELECT last_sync_at FROM servers WHERE api_key_hash = ?1")?
+                    .query_row(params![&hash], |row| row.get(0))
+                    .optional()?;
+                Ok(last_sync_at.flatten())
+            })
+            .await?;
+        Ok(last_sync_at)
+    }
+
     /// Check if a server has synced since backend restart (by guild and name).
     pub async fn is_server_synced_by_name(&self, guild_id: u64, server_name: &str) -> Result<bool> {
         let name = server_name.to_string();
         let api_key_hash: Option<String> = self
-            .conn
+            .reader()
             .call(move |conn| {
                 conn.prepare_cached(
-                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 AND name = ?2",
+                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE",
                 )?
                 .query_row(params![guild_id, &name], |row| row.get(0))
                 .optional()
@@ -174,30 +699,108 @@ impl Database {
     // ========================================================================
 
     /// Create a new pending link.
-    /// Returns an error if a server with that name already exists in the guild.
+    /// Returns an error if a server with that name (ignoring case) already exists
+    /// in the guild, or if the guild's linked servers and unexpired codes for
+    /// other names already add up to `max_servers`.
     pub async fn create_pending_link(
         &self,
         code: String,
         guild_id: u64,
         server_name: String,
         now: i64,
+        max_servers: u32,
+    ) -> Result<PendingLink> {
+        let mut links = self
+            .create_pending_links(guild_id, vec![(code, server_name)], now, max_servers)
+            .await?;
+        Ok(links.remove(0))
+    }
+
+    /// Create several pending links, given as (code, server name) pairs, at once.
+    /// Either all are created or none are: a name that conflicts with a linked
+    /// server, or more links than the guild has room for under `max_servers`,
+    /// fails the whole batch.
+    pub async fn create_pending_links(
+        &self,
+        guild_id: u64,
+        links: Vec<(String, String)>,
+        now: i64,
+        max_servers: u32,
+    ) -> Result<Vec<PendingLink>> {
+        let result = self
+            .conn
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+
+                // Refuse links that would take the guild past its server limit
+                let names = links.iter().map(|(_, name)| name.as_str());
+                if over_server_limit(&tx, guild_id, names, now, max_servers)? {
+                    return Ok(Err(DbError::ServerLimitReached));
+                }
+
+                let mut created = Vec::with_capacity(links.len());
+                for (code, server_name) in links {
+                    // Check if server name already exists in this guild
+                    let exists: bool = tx
+                        .prepare_cached("SELECT EXISTS(SELECT 1 FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE)")?
+                        .query_row(params![guild_id, &server_name], |row| row.get(0))?;
+
+                    // Dropping the transaction rolls back links already inserted
+                    if exists {
+                        return Ok(Err(DbError::ServerNameConflict));
+                    }
+
+                    tx.prepare_cached(
+                        "INSERT INTO pending_links (code, guild_id, server_name, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    )?
+                        .execute(params![&code, guild_id, &server_name, now])?;
+
+                    created.push(PendingLink {
+                        code,
+                        guild_id,
+                        server_name,
+                        created_at: now,
+                        relink: false,
+                    });
+                }
+
+                tx.commit()?;
+                Ok(Ok(created))
+            })
+            .await??;
+
+        for link in &result {
+            debug!(%link.code, link.guild_id, %link.server_name, "created pending link");
+        }
+        Ok(result)
+    }
+
+    /// Create a code that re-links an existing server, e.g. one whose key was
+    /// lost in a crash. Claiming it gives the server a new key (see
+    /// [`Database::relink_server`]); the old key keeps working until then.
+    /// Returns ServerNotFound if the guild has no server with this name.
+    pub async fn create_relink_code(
+        &self,
+        code: String,
+        guild_id: u64,
+        server_name: String,
+        now: i64,
     ) -> Result<PendingLink> {
         let result = self
             .conn
             .call(move |conn| {
                 let tx = conn.transaction()?;
 
-                // Check if server name already exists in this guild
+                // Re-linking doesn't add a server, so the guild limit doesn't apply
                 let exists: bool = tx
-                    .prepare_cached("SELECT EXISTS(SELECT 1 FROM servers WHERE guild_id = ?1 AND name = ?2)")?
+                    .prepare_cached("SELECT EXISTS(SELECT 1 FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE)")?
                     .query_row(params![guild_id, &server_name], |row| row.get(0))?;
-
-                if exists {
-                    return Ok(Err(DbError::ServerNameConflict));
+                if !exists {
+                    return Ok(Err(DbError::ServerNotFound));
                 }
 
                 tx.prepare_cached(
-                    "INSERT INTO pending_links (code, guild_id, server_name, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    "INSERT INTO pending_links (code, guild_id, server_name, created_at, relink) VALUES (?1, ?2, ?3, ?4, 1)",
                 )?
                     .execute(params![&code, guild_id, &server_name, now])?;
 
@@ -207,11 +810,97 @@ impl Database {
                     guild_id,
                     server_name,
                     created_at: now,
+                    relink: true,
                 }))
             })
             .await??;
 
-        debug!(%result.code, result.guild_id, %result.server_name, "created pending link");
+        debug!(%result.code, result.guild_id, %result.server_name, "created relink code");
+        Ok(result)
+    }
+
+    /// Replace the guild's unexpired pending link for `server_name` (ignoring
+    /// case) with `new_code`, restarting its expiry. The old code stops working
+    /// and a relink code stays a relink code. If there is no such link, a new
+    /// one is created with the same checks as [`Database::create_pending_link`].
+    pub async fn refresh_pending_link(
+        &self,
+        guild_id: u64,
+        server_name: String,
+        new_code: String,
+        now: i64,
+        max_servers: u32,
+    ) -> Result<PendingLink> {
+        let result = self
+            .conn
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+
+                let cutoff = now - PendingLink::TTL_SECONDS;
+                let existing: Option<(String, String, bool)> = tx
+                    .prepare_cached(
+                        "SELECT code, server_name, relink FROM pending_links
+                         WHERE guild_id = ?1 AND server_name = ?2 COLLATE NOCASE AND created_at >= ?3
+                         ORDER BY created_at DESC LIMIT 1",
+                    )?
+                    .query_row(params![guild_id, &server_name, cutoff], |row| {
+                        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                    })
+                    .optional()?;
+
+                let link = match existing {
+                    Some((old_code, server_name, relink)) => {
+                        tx.prepare_cached(
+                            "UPDATE pending_links SET code = ?1, created_at = ?2 WHERE code = ?3",
+                        )?
+                        .execute(params![&new_code, now, &old_code])?;
+                        PendingLink {
+                            code: new_code,
+                            guild_id,
+                            server_name,
+                            created_at: now,
+                            relink,
+                        }
+                    }
+                    None => {
+                        let names = [server_name.as_str()];
+                        if over_server_limit(&tx, guild_id, names, now, max_servers)? {
+                            return Ok(Err(DbError::ServerLimitReached));
+                        }
+
+                        let exists: bool = tx
+                            .prepare_cached("SELECT EXISTS(SELECT 1 FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE)")?
+                            .query_row(params![guild_id, &server_name], |row| row.get(0))?;
+                        if exists {
+                            return Ok(Err(DbError::ServerNameConflict));
+                        }
+
+                        tx.prepare_cached(
+                            "INSERT INTO pending_links (code, guild_id, server_name, created_at) VALUES (?1, ?2, ?3, ?4)",
+                        )?
+                        .execute(params![&new_code, guild_id, &server_name, now])?;
+                        PendingLink {
+                            code: new_code,
+                            guild_id,
+                            server_name,
+                            created_at: now,
+                            relink: false,
+                        }
+                    }
+                };
+
+                // Any older codes for the same name go too, so only the new one works
+                tx.prepare_cached(
+                    "DELETE FROM pending_links WHERE guild_id = ?1 AND server_name = ?2 COLLATE NOCASE AND code != ?3",
+                )?
+                .execute(params![guild_id, &link.server_name, &link.code])?;
+
+                tx.commit()?;
+                Ok(Ok(link))
+            })
+            .await??;
+
+        debug!(%result.code, result.guild_id, %result.server_name, "refreshed pending link");
         Ok(result)
     }
 
@@ -219,11 +908,11 @@ impl Database {
     /// Returns None if not found.
     pub async fn get_pending_link(&self, code: String) -> Result<Option<PendingLink>> {
         let link = self
-            .conn
+            .reader()
             .call(move |conn| {
                 conn
           .prepare_cached(
-            "SELECT code, guild_id, server_name, created_at FROM pending_links WHERE code = ?1",
+            "SELECT code, guild_id, server_name, created_at, relink FROM pending_links WHERE code = ?1",
           )?
           .query_row(params![&code], |row| {
             Ok(PendingLink {
@@ -231,6 +920,7 @@ impl Database {
               guild_id: row.get(1)?,
               server_name: row.get(2)?,
               created_at: row.get(3)?,
+              relink: row.get(4)?,
             })
           })
           .optional()
@@ -240,17 +930,49 @@ impl Database {
         Ok(link)
     }
 
+    /// Get a guild's pending links that haven't expired yet, newest first.
+    pub async fn get_pending_links_by_guild(
+        &self,
+        guild_id: u64,
+        now: i64,
+    ) -> Result<Vec<PendingLink>> {
+        let links = self
+            .reader()
+            .call(move |conn| {
+                let cutoff = now - PendingLink::TTL_SECONDS;
+                let links = conn
+                    .prepare_cached(
+                        "SELECT code, guild_id, server_name, created_at, relink FROM pending_links
+                         WHERE guild_id = ?1 AND created_at >= ?2
+                         ORDER BY created_at DESC",
+                    )?
+                    .query_map(params![guild_id, cutoff], |row| {
+                        Ok(PendingLink {
+                            code: row.get(0)?,
+                            guild_id: row.get(1)?,
+                            server_name: row.get(2)?,
+                            created_at: row.get(3)?,
+                            relink: row.get(4)?,
+                        })
+                    })?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(links)
+            })
+            .await?;
+
+        Ok(links)
+    }
+
     /// Consume a pending link (delete it and return it).
-    /// Returns an error if not found or expired.
+    /// Returns PendingLinkAlreadyUsed if the code was claimed before and hasn't
+    /// expired yet, or PendingLinkNotFound if it never existed or has expired.
     pub async fn consume_pending_link(&self, code: String, now: i64) -> Result<PendingLink> {
-        let result = self
-            .conn
-            .call(move |conn| {
-                let tx = conn.transaction()?;
+        let result = call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
 
-                let link: Option<PendingLink> = tx
+            let link: Option<PendingLink> = tx
           .prepare_cached(
-            "SELECT code, guild_id, server_name, created_at FROM pending_links WHERE code = ?1",
+            "SELECT code, guild_id, server_name, created_at, relink FROM pending_links WHERE code = ?1",
           )?
           .query_row(params![&code], |row| {
             Ok(PendingLink {
@@ -258,28 +980,45 @@ impl Database {
               guild_id: row.get(1)?,
               server_name: row.get(2)?,
               created_at: row.get(3)?,
+              relink: row.get(4)?,
             })
           })
           .optional()?;
 
-                let link = match link {
-                    Some(l) => l,
-                    None => return Ok(Err(DbError::PendingLinkNotFound)),
-                };
-
-                if link.is_expired(now) {
-                    tx.prepare_cached("DELETE FROM pending_links WHERE code = ?1")?
-                        .execute(params![&code])?;
-                    tx.commit()?;
-                    return Ok(Err(DbError::PendingLinkNotFound));
+            let link = match link {
+                Some(l) => l,
+                None => {
+                    // Tell a losing racer (or a replay) apart from a wrong code
+                    let claimed_at: Option<i64> = tx
+                        .prepare_cached("SELECT created_at FROM claimed_links WHERE code = ?1")?
+                        .query_row(params![&code], |row| row.get(0))
+                        .optional()?;
+                    return Ok(Err(match claimed_at {
+                        Some(created_at) if now - created_at <= PendingLink::TTL_SECONDS => {
+                            DbError::PendingLinkAlreadyUsed
+                        }
+                        _ => DbError::PendingLinkNotFound,
+                    }));
                 }
+            };
 
+            if link.is_expired(now) {
                 tx.prepare_cached("DELETE FROM pending_links WHERE code = ?1")?
                     .execute(params![&code])?;
                 tx.commit()?;
-                Ok(Ok(link))
-            })
-            .await??;
+                return Ok(Err(DbError::PendingLinkNotFound));
+            }
+
+            tx.prepare_cached("DELETE FROM pending_links WHERE code = ?1")?
+                .execute(params![&code])?;
+            tx.prepare_cached(
+                "INSERT OR REPLACE INTO claimed_links (code, created_at) VALUES (?1, ?2)",
+            )?
+            .execute(params![&code, link.created_at])?;
+            tx.commit()?;
+            Ok(Ok(link))
+        })
+        .await??;
 
         debug!(%result.code, "consumed pending link");
         Ok(result)
@@ -290,12 +1029,13 @@ impl Database {
         let deleted = self
             .conn
             .call(move |conn| {
-                const TTL_SECONDS: i64 = 600;
-                let cutoff = now - TTL_SECONDS;
+                let cutoff = now - PendingLink::TTL_SECONDS;
 
                 let deleted = conn
                     .prepare_cached("DELETE FROM pending_links WHERE created_at < ?1")?
                     .execute(params![cutoff])?;
+                conn.prepare_cached("DELETE FROM claimed_links WHERE created_at < ?1")?
+                    .execute(params![cutoff])?;
                 Ok(deleted as u64)
             })
             .await?;
@@ -317,22 +1057,22 @@ impl Database {
         api_key_hash: String,
         name: String,
         guild_id: u64,
+        now: i64,
     ) -> Result<Server> {
-        let server = self
-            .conn
-            .call(move |conn| {
-                conn.prepare_cached(
-                    "INSERT INTO servers (api_key_hash, name, guild_id) VALUES (?1, ?2, ?3)",
-                )?
-                .execute(params![&api_key_hash, &name, guild_id])?;
-
-                Ok(Server {
-                    api_key_hash,
-                    name,
-                    guild_id,
-                })
+        let server = call_with_retry(&self.conn, move |conn| {
+            conn.prepare_cached(
+                "INSERT INTO servers (api_key_hash, name, guild_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![&api_key_hash, &name, guild_id, now])?;
+
+            Ok(Server {
+                api_key_hash,
+                name,
+                guild_id,
+                created_at: now,
             })
-            .await?;
+        })
+        .await?;
 
         debug!(%server.name, server.guild_id, "created server");
         Ok(server)
@@ -341,16 +1081,17 @@ impl Database {
     /// Get a server by API key hash.
     pub async fn get_server_by_api_key(&self, api_key_hash: String) -> Result<Option<Server>> {
         let server = self
-            .conn
+            .reader()
             .call(move |conn| {
                 conn.prepare_cached(
-                    "SELECT api_key_hash, name, guild_id FROM servers WHERE api_key_hash = ?1",
+                    "SELECT api_key_hash, name, guild_id, created_at FROM servers WHERE api_key_hash = ?1",
                 )?
                 .query_row(params![&api_key_hash], |row| {
                     Ok(Server {
                         api_key_hash: row.get(0)?,
                         name: row.get(1)?,
                         guild_id: row.get(2)?,
+                        created_at: row.get(3)?,
                     })
                 })
                 .optional()
@@ -360,13 +1101,28 @@ impl Database {
         Ok(server)
     }
 
+    /// Count the servers linked to a guild.
+    pub async fn count_servers_in_guild(&self, guild_id: u64) -> Result<u32> {
+        let count = self
+            .reader()
+            .call(move |conn| {
+                let count: u32 = conn
+                    .prepare_cached("SELECT COUNT(*) FROM servers WHERE guild_id = ?1")?
+                    .query_row(params![guild_id], |row| row.get(0))?;
+                Ok(count)
+            })
+            .await?;
+
+        Ok(count)
+    }
+
     /// Get all servers for a guild.
     pub async fn get_servers_by_guild(&self, guild_id: u64) -> Result<Vec<Server>> {
         let servers = self
-            .conn
+            .reader()
             .call(move |conn| {
                 let mut stmt = conn.prepare_cached(
-                    "SELECT api_key_hash, name, guild_id FROM servers WHERE guild_id = ?1",
+                    "SELECT api_key_hash, name, guild_id, created_at FROM servers WHERE guild_id = ?1",
                 )?;
 
                 let servers = stmt
@@ -375,6 +1131,7 @@ impl Database {
                             api_key_hash: row.get(0)?,
                             name: row.get(1)?,
                             guild_id: row.get(2)?,
+                            created_at: row.get(3)?,
                         })
                     })?
                     .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -386,79 +1143,262 @@ impl Database {
         Ok(servers)
     }
 
-    /// Get server summaries for a guild (with player counts).
-    pub async fn get_server_summaries(&self, guild_id: u64) -> Result<Vec<ServerSummary>> {
-        // Get servers from SQLite
-        let servers: Vec<(String, String)> = self
-            .conn
+    /// Get one page of a guild's servers, ordered by name, plus the total
+    /// number of servers in the guild.
+    pub async fn get_servers_paginated(
+        &self,
+        guild_id: u64,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<Server>, u64)> {
+        let page = self
+            .reader()
             .call(move |conn| {
+                let total: u64 = conn
+                    .prepare_cached("SELECT COUNT(*) FROM servers WHERE guild_id = ?1")?
+                    .query_row(params![guild_id], |row| row.get(0))?;
+
                 let mut stmt = conn.prepare_cached(
-                    "SELECT api_key_hash, name FROM servers WHERE guild_id = ?1 ORDER BY name",
+                    "SELECT api_key_hash, name, guild_id, created_at FROM servers
+                     WHERE guild_id = ?1 ORDER BY name LIMIT ?2 OFFSET ?3",
                 )?;
                 let servers = stmt
-                    .query_map(params![guild_id], |row| Ok((row.get(0)?, row.get(1)?)))?
-                    .collect::<std::result::Result<Vec<_>, _>>()?;
-                Ok(servers)
-            })
-            .await?;
+                    .query_map(params![guild_id, limit, offset], |row| {
+                        Ok(Server {
+                            api_key_hash: row.get(0)?,
+                            name: row.get(1)?,
+                            guild_id: row.get(2)?,
+                            created_at: row.get(3)?,
+                        })
+                    })?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+
+                Ok((servers, total))
+            })
+            .await?;
+
+        Ok(page)
+    }
+
+    /// Get server summaries for a guild (with player counts and tags).
+    pub async fn get_server_summaries(&self, guild_id: u64) -> Result<Vec<ServerSummary>> {
+        // Get servers from SQLite
+        let servers: Vec<(String, String, Option<String>)> = self
+            .reader()
+            .call(move |conn| {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT api_key_hash, name, tag FROM servers WHERE guild_id = ?1 ORDER BY name",
+                )?;
+                let servers = stmt
+                    .query_map(params![guild_id], |row| {
+                        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                    })?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(servers)
+            })
+            .await?;
 
         // Get player counts from in-memory cache
         let mut summaries = Vec::with_capacity(servers.len());
-        for (api_key_hash, name) in servers {
-            let player_count = match self.cache.get_async(&api_key_hash).await {
-                Some(entry) => entry.get().player_count() as u32,
-                None => 0,
+        for (api_key_hash, name, tag) in servers {
+            let (player_count, last_sync_at) = match self.cache.get_async(&api_key_hash).await {
+                Some(entry) => (entry.get().player_count() as u32, entry.get().last_sync_at),
+                None => (0, None),
             };
-            summaries.push(ServerSummary { name, player_count });
+            summaries.push(ServerSummary {
+                name,
+                player_count,
+                tag,
+                last_sync_at,
+            });
         }
 
         Ok(summaries)
     }
 
-    /// Delete a server by guild and name.
-    pub async fn delete_server(&self, guild_id: u64, name: String) -> Result<()> {
-        // First get the api_key_hash so we can clean up the cache
-        let name_clone = name.clone();
-        let api_key_hash: Option<String> = self
-            .conn
+    /// Get server summaries for a guild, busiest first (ties broken by name).
+    pub async fn get_servers_by_activity(&self, guild_id: u64) -> Result<Vec<ServerSummary>> {
+        let mut summaries = self.get_server_summaries(guild_id).await?;
+        // Summaries arrive sorted by name and the sort is stable
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.player_count));
+        Ok(summaries)
+    }
+
+    /// Get aggregate stats for a guild: server count, online total and busiest server.
+    ///
+    /// Uses a single query for the guild's servers; counts come from the cache.
+    pub async fn get_guild_stats(&self, guild_id: u64) -> Result<GuildStats> {
+        let summaries = self.get_server_summaries(guild_id).await?;
+
+        let online_players = summaries.iter().map(|s| s.player_count as u64).sum();
+        // Summaries are sorted by name, so the first maximum wins ties
+        let busiest_server = summaries
+            .iter()
+            .filter(|s| s.player_count > 0)
+            .fold(None::<&ServerSummary>, |best, s| match best {
+                Some(b) if b.player_count >= s.player_count => Some(b),
+                _ => Some(s),
+            })
+            .cloned();
+
+        Ok(GuildStats {
+            server_count: summaries.len() as u32,
+            online_players,
+            busiest_server,
+        })
+    }
+
+    /// Get the total number of online players across all servers in a guild.
+    ///
+    /// Returns 0 for guilds with no servers or no online players.
+    pub async fn get_guild_online_total(&self, guild_id: u64) -> Result<u64> {
+        // Get server keys from SQLite
+        let hashes: Vec<String> = self
+            .reader()
             .call(move |conn| {
-                conn.prepare_cached(
-                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 AND name = ?2",
-                )?
-                .query_row(params![guild_id, &name_clone], |row| row.get(0))
-                .optional()
+                let mut stmt =
+                    conn.prepare_cached("SELECT api_key_hash FROM servers WHERE guild_id = ?1")?;
+                let hashes = stmt
+                    .query_map(params![guild_id], |row| row.get(0))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(hashes)
             })
             .await?;
 
-        let result = self
-            .conn
-            .call(move |conn| {
-                let deleted = conn
-                    .prepare_cached("DELETE FROM servers WHERE guild_id = ?1 AND name = ?2")?
-                    .execute(params![guild_id, &name])?;
+        // Sum player counts from in-memory cache
+        let mut total = 0u64;
+        for api_key_hash in hashes {
+            if let Some(entry) = self.cache.get_async(&api_key_hash).await {
+                total += entry.get().player_count() as u64;
+            }
+        }
 
-                if deleted == 0 {
-                    return Ok(Err(DbError::ServerNotFound));
-                }
+        Ok(total)
+    }
 
-                Ok(Ok(()))
+    /// Find which server in a guild a player is currently online on.
+    ///
+    /// Returns the server name, or None if the player isn't online anywhere in
+    /// the guild. Online players live in the cache, so this is one indexed
+    /// lookup of the guild's servers followed by cache checks.
+    pub async fn is_player_online(
+        &self,
+        guild_id: u64,
+        player_name: PlayerName,
+    ) -> Result<Option<String>> {
+        // Get server keys and names from SQLite
+        let servers: Vec<(String, String)> = self
+            .reader()
+            .call(move |conn| {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT api_key_hash, name FROM servers WHERE guild_id = ?1 ORDER BY name",
+                )?;
+                let servers = stmt
+                    .query_map(params![guild_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(servers)
             })
-            .await??;
+            .await?;
 
-        // Clean up cache
-        if let Some(hash) = api_key_hash {
-            let _ = self.cache.remove_async(&hash).await;
+        // Check each server's players in the in-memory cache
+        for (api_key_hash, name) in servers {
+            if let Some(entry) = self.cache.get_async(&api_key_hash).await
+                && entry.get().players.iter().any(|(p, _)| *p == player_name)
+            {
+                return Ok(Some(name));
+            }
         }
 
+        Ok(None)
+    }
+
+    /// Set or clear (`None`) the tag used to group a server in `/list`.
+    /// Returns `ServerNotFound` if the guild has no server with that name.
+    pub async fn set_server_tag(
+        &self,
+        guild_id: u64,
+        name: String,
+        tag: Option<String>,
+    ) -> Result<()> {
+        call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            let api_key_hash: Option<String> = tx
+                .prepare_cached(
+                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE",
+                )?
+                .query_row(params![guild_id, &name], |row| row.get(0))
+                .optional()?;
+            let Some(api_key_hash) = api_key_hash else {
+                return Ok(Err(DbError::ServerNotFound));
+            };
+            tx.prepare_cached("UPDATE servers SET tag = ?2 WHERE api_key_hash = ?1")?
+                .execute(params![&api_key_hash, &tag])?;
+            tx.commit()?;
+            Ok(Ok(()))
+        })
+        .await??;
+
+        debug!(guild_id, "set server tag");
+        Ok(())
+    }
+
+    /// Delete a server by guild and name.
+    pub async fn delete_server(&self, guild_id: u64, name: String) -> Result<()> {
+        // Delete by the api_key_hash found, which is also the cache key
+        let api_key_hash = call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            let api_key_hash: Option<String> = tx
+                .prepare_cached(
+                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE",
+                )?
+                .query_row(params![guild_id, &name], |row| row.get(0))
+                .optional()?;
+            let Some(api_key_hash) = api_key_hash else {
+                return Ok(Err(DbError::ServerNotFound));
+            };
+            tx.prepare_cached("DELETE FROM servers WHERE api_key_hash = ?1")?
+                .execute(params![&api_key_hash])?;
+            tx.commit()?;
+            Ok(Ok(api_key_hash))
+        })
+        .await??;
+
+        // Clean up cache
+        let _ = self.cache.remove_async(&api_key_hash).await;
+
         debug!(guild_id, "deleted server");
-        Ok(result)
+        Ok(())
+    }
+
+    /// Delete every server linked to a guild, returning how many were removed.
+    pub async fn delete_servers_by_guild(&self, guild_id: u64) -> Result<u64> {
+        let hashes = call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            let hashes = tx
+                .prepare_cached("SELECT api_key_hash FROM servers WHERE guild_id = ?1")?
+                .query_map(params![guild_id], |row| row.get::<_, String>(0))?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            tx.prepare_cached("DELETE FROM servers WHERE guild_id = ?1")?
+                .execute(params![guild_id])?;
+            tx.commit()?;
+            Ok(hashes)
+        })
+        .await?;
+
+        // Clean up cache
+        for hash in &hashes {
+            let _ = self.cache.remove_async(hash).await;
+        }
+
+        let deleted = hashes.len() as u64;
+        debug!(guild_id, deleted, "deleted all servers in guild");
+        Ok(deleted)
     }
 
     /// Delete a server by API key hash (for self-disconnect).
     pub async fn delete_server_by_api_key(&self, api_key_hash: String) -> Result<()> {
         let hash_clone = api_key_hash.clone();
-        let result = self
-            .conn
+        self.conn
             .call(move |conn| {
                 let deleted = conn
                     .prepare_cached("DELETE FROM servers WHERE api_key_hash = ?1")?
@@ -476,16 +1416,129 @@ impl Database {
         let _ = self.cache.remove_async(&api_key_hash).await;
 
         debug!("deleted server by api key");
-        Ok(result)
+        Ok(())
+    }
+
+    /// Replace a server's API key hash, keeping its players and history.
+    ///
+    /// The server row and every table keyed by its hash are updated in one
+    /// transaction; the cached online players move to the new key afterwards.
+    /// Returns `InvalidApiKey` if no server has `old_hash`.
+    pub async fn rotate_api_key(&self, old_hash: String, new_hash: String) -> Result<()> {
+        let (old, new) = (old_hash.clone(), new_hash.clone());
+        call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            if !rekey_server(&tx, &old, &new)? {
+                return Ok(Err(DbError::InvalidApiKey));
+            }
+            tx.commit()?;
+            Ok(Ok(()))
+        })
+        .await??;
+
+        // Move online players to the new key
+        if let Some((_, state)) = self.cache.remove_async(&old_hash).await {
+            let _ = self.cache.insert_async(new_hash, state).await;
+        }
+
+        debug!("rotated server api key");
+        Ok(())
+    }
+
+    /// Give the server named `name` in a guild a new API key hash, keeping its
+    /// history and online players; the old key stops working.
+    /// Returns ServerNotFound if the guild has no server with this name.
+    pub async fn relink_server(
+        &self,
+        guild_id: u64,
+        name: String,
+        new_hash: String,
+    ) -> Result<Server> {
+        let new = new_hash.clone();
+        let (old_hash, server) = call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            let existing: Option<(String, String, i64)> = tx
+                .prepare_cached(
+                    "SELECT api_key_hash, name, created_at FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE",
+                )?
+                .query_row(params![guild_id, &name], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })
+                .optional()?;
+            let Some((old, name, created_at)) = existing else {
+                return Ok(Err(DbError::ServerNotFound));
+            };
+
+            rekey_server(&tx, &old, &new)?;
+            tx.commit()?;
+            Ok(Ok((
+                old,
+                Server {
+                    api_key_hash: new,
+                    name,
+                    guild_id,
+                    created_at,
+                },
+            )))
+        })
+        .await??;
+
+        // Move online players to the new key
+        if let Some((_, state)) = self.cache.remove_async(&old_hash).await {
+            let _ = self.cache.insert_async(new_hash, state).await;
+        }
+
+        debug!(%server.name, server.guild_id, "relinked server");
+        Ok(server)
+    }
+
+    /// Move a server to another guild, keeping its API key and history.
+    /// Returns ServerNotFound if no server has this API key hash, or
+    /// ServerNameConflict if the destination guild already has a server
+    /// with its name.
+    pub async fn transfer_server(&self, api_key_hash: String, new_guild_id: u64) -> Result<Server> {
+        let server = call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            let existing: Option<(String, i64)> = tx
+                .prepare_cached("SELECT name, created_at FROM servers WHERE api_key_hash = ?1")?
+                .query_row(params![&api_key_hash], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?;
+            let Some((name, created_at)) = existing else {
+                return Ok(Err(DbError::ServerNotFound));
+            };
+
+            let conflict: bool = tx
+                .prepare_cached(
+                    "SELECT EXISTS(SELECT 1 FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE AND api_key_hash != ?3)",
+                )?
+                .query_row(params![new_guild_id, &name, &api_key_hash], |row| row.get(0))?;
+            if conflict {
+                return Ok(Err(DbError::ServerNameConflict));
+            }
+
+            tx.prepare_cached("UPDATE servers SET guild_id = ?2 WHERE api_key_hash = ?1")?
+                .execute(params![&api_key_hash, new_guild_id])?;
+            tx.commit()?;
+            Ok(Ok(Server {
+                api_key_hash,
+                name,
+                guild_id: new_guild_id,
+                created_at,
+            }))
+        })
+        .await??;
+
+        debug!(%server.name, server.guild_id, "transferred server");
+        Ok(server)
     }
 
-    /// Check if a server name exists in a guild.
+    /// Check if a server name exists in a guild, ignoring case.
     pub async fn server_name_exists(&self, guild_id: u64, name: String) -> Result<bool> {
         let exists =
-            self.conn
+            self.reader()
                 .call(move |conn| {
                     let exists: bool = conn
-          .prepare_cached("SELECT EXISTS(SELECT 1 FROM servers WHERE guild_id = ?1 AND name = ?2)")?
+          .prepare_cached("SELECT EXISTS(SELECT 1 FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE)")?
           .query_row(params![guild_id, &name], |row| row.get(0))?;
 
                     Ok(exists)
@@ -503,10 +1556,10 @@ impl Database {
     ) -> Result<Option<String>> {
         let name = name.to_string();
         let hash = self
-            .conn
+            .reader()
             .call(move |conn| {
                 conn.prepare_cached(
-                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 AND name = ?2",
+                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE",
                 )?
                 .query_row(params![guild_id, &name], |row| row.get(0))
                 .optional()
@@ -533,34 +1586,195 @@ impl Database {
         }
 
         // Update in-memory cache
-        self.cache
-            .entry_async(api_key_hash)
-            .await
-            .or_insert_with(|| ServerState::new())
-            .get_mut()
-            .add_player(player_name, now);
+        let peak = {
+            let mut entry = self
+                .cache
+                .entry_async(api_key_hash.clone())
+                .await
+                .or_insert_with(ServerState::new);
+            let state = entry.get_mut();
+            state.add_player(player_name, now);
+            state.note_peak()
+        };
+
+        self.record_peak(api_key_hash, peak, now).await?;
 
         debug!(player_name = %player_name, "player joined");
         Ok(())
     }
 
     /// Record a player leaving.
-    pub async fn player_leave(&self, api_key_hash: String, player_name: PlayerName) -> Result<()> {
+    pub async fn player_leave(
+        &self,
+        api_key_hash: String,
+        player_name: PlayerName,
+        now: i64,
+    ) -> Result<()> {
         // Verify the server exists in SQLite
         let exists = self.server_exists(&api_key_hash).await?;
         if !exists {
             return Err(DbError::InvalidApiKey);
         }
 
-        // Update in-memory cache
-        if let Some(mut entry) = self.cache.get_async(&api_key_hash).await {
-            entry.get_mut().remove_player(&player_name);
+        if !self.leave_grace.is_zero() {
+            let leave_id = match self.cache.get_async(&api_key_hash).await {
+                Some(mut entry) => entry.get_mut().begin_leave(player_name),
+                None => None,
+            };
+            if let Some(leave_id) = leave_id {
+                self.schedule_leave(api_key_hash, player_name, leave_id, now);
+                debug!(player_name = %player_name, "player leaving after grace period");
+                return Ok(());
+            }
         }
 
+        // Update in-memory cache
+        let joined_at = match self.cache.get_async(&api_key_hash).await {
+            Some(mut entry) => entry.get_mut().remove_player(&player_name),
+            None => None,
+        };
+
+        self.record_departures(api_key_hash, vec![(player_name, joined_at)], now)
+            .await?;
+
         debug!(player_name = %player_name, "player left");
         Ok(())
     }
 
+    /// Finish leave `leave_id` (begun at `left_at`) once the grace period is over.
+    fn schedule_leave(
+        &self,
+        api_key_hash: String,
+        player_name: PlayerName,
+        leave_id: u64,
+        left_at: i64,
+    ) {
+        let db = self.clone();
+        let deadline = tokio::time::Instant::now() + self.leave_grace;
+        tokio::spawn(async move {
+            tokio::time::sleep_until(deadline).await;
+            if let Err(e) = db
+                .finish_leave(api_key_hash, player_name, leave_id, left_at)
+                .await
+            {
+                warn!(player_name = %player_name, "failed to finish leave: {}", e);
+            }
+        });
+    }
+
+    /// Remove a player once the grace period for leave `leave_id` is over,
+    /// unless they rejoined (or left again) in the meantime.
+    async fn finish_leave(
+        &self,
+        api_key_hash: String,
+        player_name: PlayerName,
+        leave_id: u64,
+        left_at: i64,
+    ) -> Result<()> {
+        let joined_at = match self.cache.get_async(&api_key_hash).await {
+            Some(mut entry) => match entry.get_mut().finish_leave(&player_name, leave_id) {
+                Some(joined_at) => joined_at,
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        self.record_departures(
+            api_key_hash.clone(),
+            vec![(player_name, joined_at)],
+            left_at,
+        )
+        .await?;
+        // The cached status image was drawn while the player was still listed
+        self.clear_render_cache(api_key_hash).await?;
+
+        debug!(player_name = %player_name, "player left after grace period");
+        Ok(())
+    }
+
+    /// Apply a batch of buffered joins and leaves for a server.
+    ///
+    /// Joins are applied before leaves, so a player listed in both ends up
+    /// offline. Use `apply_ordered_events` when the order between them matters.
+    pub async fn apply_events(
+        &self,
+        api_key_hash: String,
+        joins: Vec<PlayerName>,
+        leaves: Vec<PlayerName>,
+        now: i64,
+    ) -> Result<()> {
+        let events = joins
+            .into_iter()
+            .map(PlayerEvent::Join)
+            .chain(leaves.into_iter().map(PlayerEvent::Leave))
+            .collect();
+        self.apply_ordered_events(api_key_hash, events, now).await
+    }
+
+    /// Apply a batch of buffered join and leave events for a server.
+    ///
+    /// All events are applied together under a single cache entry lock, in the
+    /// order given, and nothing is applied if the server does not exist. Leaves
+    /// get the same grace period as `player_leave`.
+    pub async fn apply_ordered_events(
+        &self,
+        api_key_hash: String,
+        events: Vec<PlayerEvent>,
+        now: i64,
+    ) -> Result<()> {
+        // Verify the server exists in SQLite
+        let exists = self.server_exists(&api_key_hash).await?;
+        if !exists {
+            return Err(DbError::InvalidApiKey);
+        }
+
+        let event_count = events.len();
+
+        // Update in-memory cache
+        let mut departures = Vec::new();
+        let mut pending = Vec::new();
+        let mut peak = None;
+        {
+            let mut entry = self
+                .cache
+                .entry_async(api_key_hash.clone())
+                .await
+                .or_insert_with(ServerState::new);
+            let state = entry.get_mut();
+            for event in events {
+                match event {
+                    PlayerEvent::Join(player_name) => {
+                        state.add_player(player_name, now);
+                        peak = state.note_peak().or(peak);
+                    }
+                    PlayerEvent::Leave(player_name) => {
+                        let leave_id = if self.leave_grace.is_zero() {
+                            None
+                        } else {
+                            state.begin_leave(player_name)
+                        };
+                        match leave_id {
+                            Some(leave_id) => pending.push((player_name, leave_id)),
+                            None => {
+                                departures.push((player_name, state.remove_player(&player_name)))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for (player_name, leave_id) in pending {
+            self.schedule_leave(api_key_hash.clone(), player_name, leave_id, now);
+        }
+
+        self.record_peak(api_key_hash.clone(), peak, now).await?;
+        self.record_departures(api_key_hash, departures, now)
+            .await?;
+
+        debug!(event_count, "applied player events");
+        Ok(())
+    }
+
     /// Sync the player list (replace all players for a server).
     pub async fn sync_players(
         &self,
@@ -574,146 +1788,422 @@ impl Database {
             return Err(DbError::InvalidApiKey);
         }
 
-        let count = players.len();
+        // Convert to (PlayerName, i64) pairs, dropping repeated names
+        // (keeps the first occurrence so a buggy client can't duplicate players)
+        let mut players_with_time: Vec<(PlayerName, i64)> = Vec::with_capacity(players.len());
+        for player in players {
+            if !players_with_time.iter().any(|(name, _)| *name == player) {
+                players_with_time.push((player, now));
+            }
+        }
+        let count = players_with_time.len();
 
-        // Convert to (PlayerName, i64) pairs
-        let players_with_time: Vec<(PlayerName, i64)> =
-            players.into_iter().map(|p| (p, now)).collect();
+        // Update in-memory cache, noting who dropped off the list
+        let (dropped, peak): (Vec<(PlayerName, Option<i64>)>, _) = {
+            let mut entry = self
+                .cache
+                .entry_async(api_key_hash.clone())
+                .await
+                .or_insert_with(ServerState::new);
+            let state = entry.get_mut();
+            let dropped = state
+                .players
+                .iter()
+                .filter(|(name, _)| !players_with_time.iter().any(|(p, _)| p == name))
+                .map(|(name, joined_at)| (*name, Some(*joined_at)))
+                .collect();
+            state.sync_players(players_with_time, now);
+            (dropped, state.note_peak())
+        };
 
-        // Update in-memory cache
-        self.cache
-            .entry_async(api_key_hash)
-            .await
-            .or_insert_with(|| ServerState::new())
-            .get_mut()
-            .sync_players(players_with_time);
+        let hash = api_key_hash.clone();
+        call_with_retry(&self.conn, move |conn| {
+            conn.prepare_cached("UPDATE servers SET last_sync_at = ?1 WHERE api_key_hash = ?2")?
+                .execute(params![now, &hash])?;
+            Ok(())
+        })
+        .await?;
+
+        self.record_peak(api_key_hash.clone(), peak, now).await?;
+        self.record_departures(api_key_hash, dropped, now).await?;
 
         debug!(count, "synced players");
         Ok(())
     }
 
-    /// Get online players for a server (sorted by name).
-    pub async fn get_online_players(&self, api_key_hash: String) -> Result<Vec<PlayerName>> {
-        let mut players: Vec<PlayerName> = match self.cache.get_async(&api_key_hash).await {
-            Some(entry) => entry.get().players.iter().map(|(name, _)| *name).collect(),
-            None => Vec::new(),
+    /// Remove all of a server's online players without recording them as
+    /// leaving (their real leave times are unknown, e.g. after a crash).
+    /// Returns how many players were cleared.
+    pub async fn clear_players(&self, api_key_hash: String) -> Result<usize> {
+        // Verify the server exists in SQLite
+        let exists = self.server_exists(&api_key_hash).await?;
+        if !exists {
+            return Err(DbError::InvalidApiKey);
+        }
+
+        let cleared = match self.cache.get_async(&api_key_hash).await {
+            Some(mut entry) => entry.get_mut().clear_players(),
+            None => 0,
         };
-        players.sort();
-        Ok(players)
+
+        debug!(cleared, "cleared online players");
+        Ok(cleared)
     }
 
-    /// Helper to check if a server exists in SQLite.
-    async fn server_exists(&self, api_key_hash: &str) -> Result<bool> {
-        let hash = api_key_hash.to_string();
-        let exists = self
-            .conn
-            .call(move |conn| {
-                let exists: bool = conn
-                    .prepare_cached("SELECT EXISTS(SELECT 1 FROM servers WHERE api_key_hash = ?1)")?
-                    .query_row(params![&hash], |row| row.get(0))?;
-                Ok(exists)
-            })
-            .await?;
-        Ok(exists)
+    /// Store a new peak player count (from `ServerState::note_peak`), if there is one.
+    async fn record_peak(&self, api_key_hash: String, peak: Option<usize>, now: i64) -> Result<()> {
+        let Some(peak) = peak else {
+            return Ok(());
+        };
+        call_with_retry(&self.conn, move |conn| {
+            conn.prepare_cached(
+                "UPDATE servers SET peak_players = ?1, peak_at = ?2 WHERE api_key_hash = ?3 AND peak_players < ?1",
+            )?
+            .execute(params![peak, now, &api_key_hash])?;
+            Ok(())
+        })
+        .await?;
+        Ok(())
     }
 
-    /// Get all servers with their online players for a guild.
-    pub async fn get_servers_with_players(&self, guild_id: u64) -> Result<Vec<ServerWithPlayers>> {
-        // Get servers from SQLite
-        let servers: Vec<(String, String)> = self
-            .conn
-            .call(move |conn| {
-                let mut stmt = conn.prepare_cached(
-                    "SELECT api_key_hash, name FROM servers WHERE guild_id = ?1 ORDER BY name",
+    /// Upsert the last-seen time for players who went offline on a server,
+    /// and store a session for each one whose join time is known.
+    async fn record_departures(
+        &self,
+        api_key_hash: String,
+        players: Vec<(PlayerName, Option<i64>)>,
+        now: i64,
+    ) -> Result<()> {
+        if players.is_empty() {
+            return Ok(());
+        }
+
+        call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut last_seen = tx.prepare_cached(
+                    "INSERT INTO player_last_seen (api_key_hash, player_name, last_seen) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(api_key_hash, player_name) DO UPDATE SET last_seen = excluded.last_seen",
                 )?;
-                let servers = stmt
-                    .query_map(params![guild_id], |row| Ok((row.get(0)?, row.get(1)?)))?
-                    .collect::<std::result::Result<Vec<_>, _>>()?;
-                Ok(servers)
+                let mut session = tx.prepare_cached(
+                    "INSERT INTO player_sessions (api_key_hash, player_name, joined_at, left_at) VALUES (?1, ?2, ?3, ?4)",
+                )?;
+                for (player_name, joined_at) in &players {
+                    last_seen.execute(params![&api_key_hash, player_name.as_str(), now])?;
+                    if let Some(joined_at) = joined_at {
+                        session.execute(params![
+                            &api_key_hash,
+                            player_name.as_str(),
+                            joined_at,
+                            now
+                        ])?;
+                    }
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Get when a player was last seen leaving a server, if ever.
+    pub async fn get_last_seen(
+        &self,
+        api_key_hash: String,
+        player_name: PlayerName,
+    ) -> Result<Option<i64>> {
+        let last_seen = self
+            .reader()
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "SELECT last_seen FROM player_last_seen WHERE api_key_hash = ?1 AND player_name = ?2",
+                )?
+                .query_row(params![&api_key_hash, player_name.as_str()], |row| row.get(0))
+                .optional()
             })
             .await?;
+        Ok(last_seen)
+    }
 
-        // Get players from in-memory cache
-        let mut result = Vec::with_capacity(servers.len());
-        for (api_key_hash, name) in servers {
-            let mut players: Vec<PlayerInfo> = match self.cache.get_async(&api_key_hash).await {
-                Some(entry) => entry
-                    .get()
-                    .players
-                    .iter()
-                    .map(|(player_name, joined_at)| PlayerInfo {
-                        player_name: *player_name,
-                        joined_at: *joined_at,
-                    })
-                    .collect(),
-                None => Vec::new(),
-            };
-            // Sort by player name for consistent ordering
-            players.sort_by(|a, b| a.player_name.cmp(&b.player_name));
-            result.push(ServerWithPlayers { name, players });
-        }
+    /// Count the distinct players who have ever been on a server: everyone
+    /// recorded as leaving it, plus anyone online who hasn't left yet.
+    pub async fn count_unique_players(&self, api_key_hash: &str) -> Result<u64> {
+        let online = self.get_online_players(api_key_hash.to_string()).await?;
+        let hash = api_key_hash.to_string();
+        let count = self
+            .reader()
+            .call(move |conn| {
+                let mut count: u64 = conn
+                    .prepare_cached("SELECT COUNT(*) FROM player_last_seen WHERE api_key_hash = ?1")?
+                    .query_row(params![&hash], |row| row.get(0))?;
+                let mut seen = conn.prepare_cached(
+                    "SELECT EXISTS(SELECT 1 FROM player_last_seen WHERE api_key_hash = ?1 AND player_name = ?2)",
+                )?;
+                for player_name in &online {
+                    let left_before: bool = seen
+                        .query_row(params![&hash, player_name.as_str()], |row| row.get(0))?;
+                    if !left_before {
+                        count += 1;
+                    }
+                }
+                Ok(count)
+            })
+            .await?;
+        Ok(count)
+    }
 
-        Ok(result)
+    /// Mean length in seconds of a server's completed sessions,
+    /// or None if nobody has left it yet.
+    pub async fn get_avg_session_secs(&self, api_key_hash: &str) -> Result<Option<f64>> {
+        let hash = api_key_hash.to_string();
+        let avg = self
+            .reader()
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "SELECT AVG(left_at - joined_at) FROM player_sessions WHERE api_key_hash = ?1",
+                )?
+                .query_row(params![&hash], |row| row.get(0))
+            })
+            .await?;
+        Ok(avg)
     }
 
-    /// Get a specific server with its online players.
-    pub async fn get_server_with_players(
-        &self,
-        guild_id: u64,
-        server_name: String,
-    ) -> Result<ServerWithPlayers> {
-        // Get server from SQLite
-        let server_name_clone = server_name.clone();
-        let api_key_hash: Option<String> = self
-            .conn
+    /// Most players a server has had online at once and when that was first
+    /// reached, or None if nobody has joined it yet.
+    pub async fn get_peak_players(&self, api_key_hash: &str) -> Result<Option<(u64, i64)>> {
+        let hash = api_key_hash.to_string();
+        let peak = self
+            .reader()
             .call(move |conn| {
                 conn.prepare_cached(
-                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 AND name = ?2",
+                    "SELECT peak_players, peak_at FROM servers WHERE api_key_hash = ?1 AND peak_at IS NOT NULL",
                 )?
-                .query_row(params![guild_id, &server_name_clone], |row| row.get(0))
+                .query_row(params![&hash], |row| Ok((row.get(0)?, row.get(1)?)))
                 .optional()
             })
             .await?;
+        Ok(peak)
+    }
 
-        let api_key_hash = match api_key_hash {
-            Some(h) => h,
-            None => return Err(DbError::ServerNotFound),
-        };
+    /// Delete completed sessions that ended more than `older_than_secs` before `now`.
+    /// Returns how many were deleted.
+    pub async fn prune_sessions(&self, older_than_secs: i64, now: i64) -> Result<u64> {
+        let cutoff = now - older_than_secs;
+        let pruned = call_with_retry(&self.conn, move |conn| {
+            let pruned = conn
+                .prepare_cached("DELETE FROM player_sessions WHERE left_at < ?1")?
+                .execute(params![cutoff])?;
+            Ok(pruned as u64)
+        })
+        .await?;
 
-        // Get players from in-memory cache
-        let mut players: Vec<PlayerInfo> = match self.cache.get_async(&api_key_hash).await {
+        if pruned > 0 {
+            debug!(pruned, "pruned old sessions");
+        }
+        Ok(pruned)
+    }
+
+    /// Get players who left a server at or after `since` and aren't online now.
+    ///
+    /// Returns (player, last_seen) pairs, most recently seen first.
+    pub async fn get_recently_left(
+        &self,
+        api_key_hash: String,
+        since: i64,
+    ) -> Result<Vec<(PlayerName, i64)>> {
+        let hash = api_key_hash.clone();
+        let rows: Vec<(String, i64)> = self
+            .reader()
+            .call(move |conn| {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT player_name, last_seen FROM player_last_seen
+                     WHERE api_key_hash = ?1 AND last_seen >= ?2
+                     ORDER BY last_seen DESC, player_name",
+                )?;
+                let rows = stmt
+                    .query_map(params![&hash, since], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        // Players who rejoined since leaving are online, not "recent"
+        let online = self.get_online_players(api_key_hash).await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(name, last_seen)| {
+                let name = PlayerName::from(&name).ok()?;
+                (!online.contains(&name)).then_some((name, last_seen))
+            })
+            .collect())
+    }
+
+    /// Get online players for a server with their join times, longest online first
+    /// (ties sorted by name).
+    pub async fn get_online_players_detailed(
+        &self,
+        api_key_hash: String,
+    ) -> Result<Vec<OnlinePlayer>> {
+        let mut players: Vec<OnlinePlayer> = match self.cache.get_async(&api_key_hash).await {
             Some(entry) => entry
                 .get()
                 .players
                 .iter()
-                .map(|(player_name, joined_at)| PlayerInfo {
-                    player_name: *player_name,
-                    joined_at: *joined_at,
+                .map(|&(player_name, joined_at)| OnlinePlayer {
+                    api_key_hash: api_key_hash.clone(),
+                    player_name,
+                    joined_at,
                 })
                 .collect(),
             None => Vec::new(),
         };
-        // Sort by player name for consistent ordering
-        players.sort_by(|a, b| a.player_name.cmp(&b.player_name));
+        players.sort_by_key(|p| (p.joined_at, p.player_name));
+        Ok(players)
+    }
 
-        Ok(ServerWithPlayers {
-            name: server_name,
-            players,
+    /// Get a server's online players who joined at or after `since`, earliest
+    /// arrival first (ties sorted by name).
+    ///
+    /// Online players live in the in-memory cache, not SQLite, so the filter
+    /// runs over the cached join times.
+    pub async fn get_players_since(
+        &self,
+        api_key_hash: String,
+        since: i64,
+    ) -> Result<Vec<PlayerName>> {
+        let mut players: Vec<(i64, PlayerName)> = match self.cache.get_async(&api_key_hash).await {
+            Some(entry) => entry
+                .get()
+                .players
+                .iter()
+                .filter(|&&(_, joined_at)| joined_at >= since)
+                .map(|&(player_name, joined_at)| (joined_at, player_name))
+                .collect(),
+            None => Vec::new(),
+        };
+        players.sort();
+        Ok(players.into_iter().map(|(_, name)| name).collect())
+    }
+
+    /// Get online players for a server (sorted by name).
+    pub async fn get_online_players(&self, api_key_hash: String) -> Result<Vec<PlayerName>> {
+        let mut players: Vec<PlayerName> = match self.cache.get_async(&api_key_hash).await {
+            Some(entry) => entry.get().players.iter().map(|(name, _)| *name).collect(),
+            None => Vec::new(),
+        };
+        players.sort();
+        Ok(players)
+    }
+
+    /// Count a server's online players without collecting their names.
+    pub async fn count_online_players(&self, api_key_hash: &str) -> Result<u64> {
+        Ok(match self.cache.get_async(api_key_hash).await {
+            Some(entry) => entry.get().player_count() as u64,
+            None => 0,
         })
     }
 
-    // ========================================================================
-    // Skins and Rendered Heads
-    // ========================================================================
+    /// Count online players summed across every server.
+    pub async fn count_all_online_players(&self) -> u64 {
+        let mut total = 0u64;
+        self.cache
+            .iter_async(|_, state| {
+                total += state.player_count() as u64;
+                true
+            })
+            .await;
+        total
+    }
 
-    /// Check if a skin exists by texture hash.
-    pub async fn skin_exists(&self, texture_hash: &str) -> Result<bool> {
-        let hash = texture_hash.to_string();
+    /// Find every linked server a player is currently online on, across guilds.
+    ///
+    /// Online players only live in the cache, so this scans it for the name and
+    /// then looks up each match's guild and server name. Sorted by guild, then name.
+    pub async fn find_player_servers(&self, player_name: PlayerName) -> Result<Vec<(u64, String)>> {
+        let mut hashes = Vec::new();
+        self.cache
+            .iter_async(|api_key_hash, state| {
+                if state.players.iter().any(|(name, _)| *name == player_name) {
+                    hashes.push(api_key_hash.clone());
+                }
+                true
+            })
+            .await;
+        if hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut servers: Vec<(u64, String)> = self
+            .reader()
+            .call(move |conn| {
+                let mut stmt = conn
+                    .prepare_cached("SELECT guild_id, name FROM servers WHERE api_key_hash = ?1")?;
+                let mut servers = Vec::with_capacity(hashes.len());
+                for hash in &hashes {
+                    if let Some(server) = stmt
+                        .query_row(params![hash], |row| Ok((row.get(0)?, row.get(1)?)))
+                        .optional()?
+                    {
+                        servers.push(server);
+                    }
+                }
+                Ok(servers)
+            })
+            .await?;
+        servers.sort();
+        Ok(servers)
+    }
+
+    /// Move every online player from one server to another, for networks that
+    /// run one world behind several proxies. Returns the destination's player
+    /// count afterwards.
+    ///
+    /// Players already on the destination keep the earlier of their two join
+    /// times, so nobody is listed twice. The source is left with no players.
+    pub async fn merge_players(&self, src_hash: String, dst_hash: String) -> Result<usize> {
+        for hash in [&src_hash, &dst_hash] {
+            if !self.server_exists(hash).await? {
+                return Err(DbError::ServerNotFound);
+            }
+        }
+
+        // Take the source list first: scc entries on the same map can't be
+        // held at once without risking a deadlock
+        let moved = match self.cache.get_async(&src_hash).await {
+            Some(mut entry) => std::mem::take(&mut entry.get_mut().players),
+            None => Vec::new(),
+        };
+        let moved_count = moved.len();
+
+        let mut entry = self
+            .cache
+            .entry_async(dst_hash)
+            .await
+            .or_insert_with(ServerState::new);
+        let players = &mut entry.get_mut().players;
+        for (name, joined_at) in moved {
+            match players.iter_mut().find(|(existing, _)| *existing == name) {
+                Some((_, existing_joined_at)) => {
+                    *existing_joined_at = (*existing_joined_at).min(joined_at)
+                }
+                None => players.push((name, joined_at)),
+            }
+        }
+        let count = players.len();
+
+        debug!(moved_count, count, "merged server players");
+        Ok(count)
+    }
+
+    /// Helper to check if a server exists in SQLite.
+    async fn server_exists(&self, api_key_hash: &str) -> Result<bool> {
+        let hash = api_key_hash.to_string();
         let exists = self
-            .conn
+            .reader()
             .call(move |conn| {
                 let exists: bool = conn
-                    .prepare_cached("SELECT EXISTS(SELECT 1 FROM skins WHERE texture_hash = ?1)")?
+                    .prepare_cached("SELECT EXISTS(SELECT 1 FROM servers WHERE api_key_hash = ?1)")?
                     .query_row(params![&hash], |row| row.get(0))?;
                 Ok(exists)
             })
@@ -721,106 +2211,515 @@ impl Database {
         Ok(exists)
     }
 
-    /// Store a skin (raw PNG data).
-    pub async fn store_skin(
-        &self,
-        texture_hash: String,
-        texture_url: Option<String>,
-        skin_data: Vec<u8>,
-    ) -> Result<()> {
-        let hash_for_log = texture_hash.clone();
-        self.conn
+    /// Get all servers with their online players for a guild.
+    pub async fn get_servers_with_players(&self, guild_id: u64) -> Result<Vec<ServerWithPlayers>> {
+        // Get server keys from SQLite, sorted by server name
+        let hashes: Vec<String> = self
+            .reader()
             .call(move |conn| {
-                conn.prepare_cached(
-                    "INSERT OR REPLACE INTO skins (texture_hash, texture_url, skin_data) VALUES (?1, ?2, ?3)",
-                )?
-                .execute(params![&texture_hash, &texture_url, &skin_data])?;
-                Ok(())
+                let mut stmt = conn.prepare_cached(
+                    "SELECT api_key_hash FROM servers WHERE guild_id = ?1 ORDER BY name",
+                )?;
+                let hashes = stmt
+                    .query_map(params![guild_id], |row| row.get(0))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(hashes)
             })
             .await?;
 
-        debug!(texture_hash = %hash_for_log, "stored skin");
-        Ok(())
+        self.get_many_servers_with_players(&hashes).await
     }
 
-    /// Get skin data by texture hash.
-    pub async fn get_skin_data(&self, texture_hash: &str) -> Result<Option<Vec<u8>>> {
-        let hash = texture_hash.to_string();
-        let skin_data = self
-            .conn
+    /// Get several servers with their online players, in the order of `api_key_hashes`.
+    ///
+    /// Server names and skin hashes are fetched with one `IN (...)` query each
+    /// instead of a round trip per server. Unknown hashes are skipped.
+    pub async fn get_many_servers_with_players(
+        &self,
+        api_key_hashes: &[String],
+    ) -> Result<Vec<ServerWithPlayers>> {
+        if api_key_hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Get players from in-memory cache, sorted by name for consistent ordering
+        let mut cached = Vec::with_capacity(api_key_hashes.len());
+        for api_key_hash in api_key_hashes {
+            let mut players: Vec<(PlayerName, i64)> = match self.cache.get_async(api_key_hash).await
+            {
+                Some(entry) => entry.get().players.clone(),
+                None => Vec::new(),
+            };
+            players.sort_by_key(|(name, _)| *name);
+            cached.push((api_key_hash.clone(), players));
+        }
+
+        let servers = self
+            .reader()
             .call(move |conn| {
-                conn.prepare_cached("SELECT skin_data FROM skins WHERE texture_hash = ?1")?
-                    .query_row(params![&hash], |row| row.get(0))
-                    .optional()
+                let placeholders = |n: usize| vec!["?"; n].join(", ");
+
+                let names: HashMap<String, String> = conn
+                    .prepare(&format!(
+                        "SELECT api_key_hash, name FROM servers WHERE api_key_hash IN ({})",
+                        placeholders(cached.len())
+                    ))?
+                    .query_map(params_from_iter(cached.iter().map(|(hash, _)| hash)), |row| {
+                        Ok((row.get(0)?, row.get(1)?))
+                    })?
+                    .collect::<std::result::Result<_, _>>()?;
+
+                let mut player_names: Vec<&str> = cached
+                    .iter()
+                    .flat_map(|(_, players)| players.iter().map(|(name, _)| name.as_str()))
+                    .collect();
+                player_names.sort_unstable();
+                player_names.dedup();
+                let skins: HashMap<String, String> = if player_names.is_empty() {
+                    HashMap::new()
+                } else {
+                    conn.prepare(&format!(
+                        "SELECT player_name, texture_hash FROM player_skins WHERE player_name IN ({})",
+                        placeholders(player_names.len())
+                    ))?
+                    .query_map(params_from_iter(&player_names), |row| {
+                        Ok((row.get(0)?, row.get(1)?))
+                    })?
+                    .collect::<std::result::Result<_, _>>()?
+                };
+                let uuids: HashMap<String, String> = if player_names.is_empty() {
+                    HashMap::new()
+                } else {
+                    conn.prepare(&format!(
+                        "SELECT player_name, uuid FROM player_uuids WHERE player_name IN ({})",
+                        placeholders(player_names.len())
+                    ))?
+                    .query_map(params_from_iter(&player_names), |row| {
+                        Ok((row.get(0)?, row.get(1)?))
+                    })?
+                    .collect::<std::result::Result<_, _>>()?
+                };
+
+                let servers = cached
+                    .into_iter()
+                    .filter_map(|(api_key_hash, players)| {
+                        let name = names.get(&api_key_hash)?.clone();
+                        let players = players
+                            .into_iter()
+                            .map(|(player_name, joined_at)| PlayerInfo {
+                                player_name,
+                                joined_at,
+                                texture_hash: skins.get(player_name.as_str()).cloned(),
+                                uuid: uuids.get(player_name.as_str()).cloned(),
+                            })
+                            .collect();
+                        Some(ServerWithPlayers { name, players })
+                    })
+                    .collect();
+                Ok(servers)
             })
             .await?;
-        Ok(skin_data)
+        Ok(servers)
     }
 
-    /// Update player's current skin mapping.
-    pub async fn update_player_skin(
+    /// Get a specific server with its online players.
+    pub async fn get_server_with_players(
         &self,
-        player_name: &str,
-        texture_hash: &str,
-        now: i64,
-    ) -> Result<()> {
-        let name = player_name.to_string();
-        let hash = texture_hash.to_string();
-        self.conn
+        guild_id: u64,
+        server_name: String,
+    ) -> Result<ServerWithPlayers> {
+        // Get server from SQLite, keeping the name as it was linked
+        let server: Option<(String, String)> = self
+            .reader()
             .call(move |conn| {
                 conn.prepare_cached(
-                    "INSERT OR REPLACE INTO player_skins (player_name, texture_hash, last_updated) VALUES (?1, ?2, ?3)",
+                    "SELECT api_key_hash, name FROM servers WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE",
                 )?
-                .execute(params![&name, &hash, now])?;
-                Ok(())
+                .query_row(params![guild_id, &server_name], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .optional()
             })
             .await?;
 
-        debug!(player_name, texture_hash, "updated player skin");
-        Ok(())
+        let (api_key_hash, name) = match server {
+            Some(s) => s,
+            None => return Err(DbError::ServerNotFound),
+        };
+
+        let players = self.get_player_infos(&api_key_hash).await?;
+
+        Ok(ServerWithPlayers { name, players })
     }
 
-    /// Get a player's current texture hash.
-    pub async fn get_player_texture_hash(&self, player_name: &str) -> Result<Option<String>> {
-        let name = player_name.to_string();
-        let hash = self
-            .conn
+    /// Get a server's online players (sorted by name) with their known skin hashes and UUIDs.
+    async fn get_player_infos(&self, api_key_hash: &str) -> Result<Vec<PlayerInfo>> {
+        // Get players from in-memory cache
+        let mut players: Vec<(PlayerName, i64)> = match self.cache.get_async(api_key_hash).await {
+            Some(entry) => entry.get().players.clone(),
+            None => Vec::new(),
+        };
+        // Sort by player name for consistent ordering
+        players.sort_by_key(|(name, _)| *name);
+        if players.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Look up skin hashes and UUIDs from SQLite in one call
+        let infos = self
+            .reader()
             .call(move |conn| {
-                conn.prepare_cached("SELECT texture_hash FROM player_skins WHERE player_name = ?1")?
-                    .query_row(params![&name], |row| row.get(0))
-                    .optional()
+                let mut skin_stmt = conn.prepare_cached(
+                    "SELECT texture_hash FROM player_skins WHERE player_name = ?1",
+                )?;
+                let mut uuid_stmt =
+                    conn.prepare_cached("SELECT uuid FROM player_uuids WHERE player_name = ?1")?;
+                let mut infos = Vec::with_capacity(players.len());
+                for (player_name, joined_at) in players {
+                    let texture_hash = skin_stmt
+                        .query_row(params![player_name.as_str()], |row| row.get(0))
+                        .optional()?;
+                    let uuid = uuid_stmt
+                        .query_row(params![player_name.as_str()], |row| row.get(0))
+                        .optional()?;
+                    infos.push(PlayerInfo {
+                        player_name,
+                        joined_at,
+                        texture_hash,
+                        uuid,
+                    });
+                }
+                Ok(infos)
             })
             .await?;
-        Ok(hash)
+        Ok(infos)
     }
 
-    /// Store a rendered head image.
-    pub async fn store_rendered_head(
+    // ========================================================================
+    // Guild Tokens
+    // ========================================================================
+
+    /// Store a guild-scoped token hash, replacing any previous token for the guild.
+    pub async fn create_guild_token(
         &self,
-        texture_hash: String,
-        head_data: Vec<u8>,
+        token_hash: String,
+        guild_id: u64,
         now: i64,
     ) -> Result<()> {
-        let hash_for_log = texture_hash.clone();
         self.conn
             .call(move |conn| {
-                conn.prepare_cached(
-                    "INSERT OR REPLACE INTO rendered_heads (texture_hash, head_data, rendered_at) VALUES (?1, ?2, ?3)",
+                let tx = conn.transaction()?;
+                tx.prepare_cached("DELETE FROM guild_tokens WHERE guild_id = ?1")?
+                    .execute(params![guild_id])?;
+                tx.prepare_cached(
+                    "INSERT INTO guild_tokens (token_hash, guild_id, created_at) VALUES (?1, ?2, ?3)",
                 )?
-                .execute(params![&texture_hash, &head_data, now])?;
+                .execute(params![&token_hash, guild_id, now])?;
+                tx.commit()?;
                 Ok(())
             })
             .await?;
 
-        debug!(texture_hash = %hash_for_log, "stored rendered head");
+        debug!(guild_id, "created guild token");
         Ok(())
     }
 
-    /// Get a rendered head by texture hash.
+    /// Verify that a token hash belongs to the given guild.
+    /// Returns `InvalidGuildToken` if it doesn't.
+    pub async fn verify_guild_token(&self, guild_id: u64, token_hash: String) -> Result<()> {
+        self.reader()
+            .call(move |conn| {
+                let valid: bool = conn
+                    .prepare_cached(
+                        "SELECT EXISTS(SELECT 1 FROM guild_tokens WHERE token_hash = ?1 AND guild_id = ?2)",
+                    )?
+                    .query_row(params![&token_hash, guild_id], |row| row.get(0))?;
+                if !valid {
+                    return Ok(Err(DbError::InvalidGuildToken));
+                }
+                Ok(Ok(()))
+            })
+            .await?
+    }
+
+    // ========================================================================
+    // Guild Settings
+    // ========================================================================
+
+    /// Get a guild's Discord embed color as 0xRRGGBB, if one was set.
+    pub async fn get_embed_color(&self, guild_id: u64) -> Result<Option<u32>> {
+        let color = self
+            .reader()
+            .call(move |conn| {
+                let color: Option<Option<u32>> = conn
+                    .prepare_cached("SELECT embed_color FROM guild_settings WHERE guild_id = ?1")?
+                    .query_row(params![guild_id], |row| row.get(0))
+                    .optional()?;
+                Ok(color.flatten())
+            })
+            .await?;
+        Ok(color)
+    }
+
+    /// Set a guild's Discord embed color (0xRRGGBB).
+    pub async fn set_embed_color(&self, guild_id: u64, color: u32) -> Result<()> {
+        self.conn
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "INSERT INTO guild_settings (guild_id, embed_color) VALUES (?1, ?2)
+                     ON CONFLICT(guild_id) DO UPDATE SET embed_color = excluded.embed_color",
+                )?
+                .execute(params![guild_id, color])?;
+                Ok(())
+            })
+            .await?;
+
+        debug!(guild_id, color, "set embed color");
+        Ok(())
+    }
+
+    /// Get the URL a guild's join/leave webhooks are sent to, if any.
+    pub async fn get_guild_webhook(&self, guild_id: u64) -> Result<Option<String>> {
+        let url = self
+            .reader()
+            .call(move |conn| {
+                let url: Option<String> = conn
+                    .prepare_cached("SELECT url FROM guild_webhooks WHERE guild_id = ?1")?
+                    .query_row(params![guild_id], |row| row.get(0))
+                    .optional()?;
+                Ok(url)
+            })
+            .await?;
+        Ok(url)
+    }
+
+    /// Set (or with `None`, remove) a guild's join/leave webhook URL.
+    pub async fn set_guild_webhook(&self, guild_id: u64, url: Option<String>) -> Result<()> {
+        let removed = url.is_none();
+        self.conn
+            .call(move |conn| {
+                match url {
+                    Some(url) => conn
+                        .prepare_cached(
+                            "INSERT INTO guild_webhooks (guild_id, url) VALUES (?1, ?2)
+                             ON CONFLICT(guild_id) DO UPDATE SET url = excluded.url",
+                        )?
+                        .execute(params![guild_id, url])?,
+                    None => conn
+                        .prepare_cached("DELETE FROM guild_webhooks WHERE guild_id = ?1")?
+                        .execute(params![guild_id])?,
+                };
+                Ok(())
+            })
+            .await?;
+
+        debug!(guild_id, removed, "set guild webhook");
+        Ok(())
+    }
+
+    // ========================================================================
+    // Audit Log
+    // ========================================================================
+
+    /// Record an admin action taken in a guild.
+    pub async fn record_audit(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        action: String,
+        target: String,
+        now: i64,
+    ) -> Result<()> {
+        call_with_retry(&self.conn, move |conn| {
+            conn.prepare_cached(
+                "INSERT INTO audit_log (guild_id, user_id, action, target, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![guild_id, user_id, &action, &target, now])?;
+            Ok(())
+        })
+        .await?;
+
+        debug!(guild_id, user_id, "recorded audit entry");
+        Ok(())
+    }
+
+    /// Get a guild's most recent audit entries, newest first.
+    pub async fn get_audit_log(&self, guild_id: u64, limit: u32) -> Result<Vec<AuditEntry>> {
+        let entries = self
+            .reader()
+            .call(move |conn| {
+                let entries = conn
+                    .prepare_cached(
+                        "SELECT guild_id, user_id, action, target, created_at FROM audit_log
+                         WHERE guild_id = ?1
+                         ORDER BY created_at DESC, id DESC
+                         LIMIT ?2",
+                    )?
+                    .query_map(params![guild_id, limit], |row| {
+                        Ok(AuditEntry {
+                            guild_id: row.get(0)?,
+                            user_id: row.get(1)?,
+                            action: row.get(2)?,
+                            target: row.get(3)?,
+                            created_at: row.get(4)?,
+                        })
+                    })?
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok(entries)
+            })
+            .await?;
+        Ok(entries)
+    }
+
+    // ========================================================================
+    // Skins and Rendered Heads
+    // ========================================================================
+
+    /// Check if a skin exists by texture hash.
+    pub async fn skin_exists(&self, texture_hash: &str) -> Result<bool> {
+        let hash = texture_hash.to_string();
+        let exists = self
+            .reader()
+            .call(move |conn| {
+                let exists: bool = conn
+                    .prepare_cached("SELECT EXISTS(SELECT 1 FROM skins WHERE texture_hash = ?1)")?
+                    .query_row(params![&hash], |row| row.get(0))?;
+                Ok(exists)
+            })
+            .await?;
+        Ok(exists)
+    }
+
+    /// Store a skin (raw PNG data).
+    pub async fn store_skin(
+        &self,
+        texture_hash: String,
+        texture_url: Option<String>,
+        skin_data: Vec<u8>,
+    ) -> Result<()> {
+        let hash_for_log = texture_hash.clone();
+        self.conn
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "INSERT OR REPLACE INTO skins (texture_hash, texture_url, skin_data) VALUES (?1, ?2, ?3)",
+                )?
+                .execute(params![&texture_hash, &texture_url, &skin_data])?;
+                Ok(())
+            })
+            .await?;
+
+        debug!(texture_hash = %hash_for_log, "stored skin");
+        Ok(())
+    }
+
+    /// Get skin data by texture hash.
+    pub async fn get_skin_data(&self, texture_hash: &str) -> Result<Option<Vec<u8>>> {
+        let hash = texture_hash.to_string();
+        let skin_data = self
+            .reader()
+            .call(move |conn| {
+                conn.prepare_cached("SELECT skin_data FROM skins WHERE texture_hash = ?1")?
+                    .query_row(params![&hash], |row| row.get(0))
+                    .optional()
+            })
+            .await?;
+        Ok(skin_data)
+    }
+
+    /// Update player's current skin mapping.
+    pub async fn update_player_skin(
+        &self,
+        player_name: &str,
+        texture_hash: &str,
+        now: i64,
+    ) -> Result<()> {
+        let name = player_name.to_string();
+        let hash = texture_hash.to_string();
+        self.conn
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "INSERT OR REPLACE INTO player_skins (player_name, texture_hash, last_updated) VALUES (?1, ?2, ?3)",
+                )?
+                .execute(params![&name, &hash, now])?;
+                Ok(())
+            })
+            .await?;
+
+        debug!(player_name, texture_hash, "updated player skin");
+        Ok(())
+    }
+
+    /// Remember the UUIDs servers reported for these players.
+    pub async fn update_player_uuids(
+        &self,
+        players: Vec<(PlayerName, String)>,
+        now: i64,
+    ) -> Result<()> {
+        if players.is_empty() {
+            return Ok(());
+        }
+        let count = players.len();
+        call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT OR REPLACE INTO player_uuids (player_name, uuid, last_updated) VALUES (?1, ?2, ?3)",
+                )?;
+                for (name, uuid) in &players {
+                    stmt.execute(params![name.as_str(), uuid, now])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+        debug!(count, "updated player uuids");
+        Ok(())
+    }
+
+    /// Get a player's current texture hash.
+    pub async fn get_player_texture_hash(&self, player_name: &str) -> Result<Option<String>> {
+        let name = player_name.to_string();
+        let hash = self
+            .reader()
+            .call(move |conn| {
+                conn.prepare_cached("SELECT texture_hash FROM player_skins WHERE player_name = ?1")?
+                    .query_row(params![&name], |row| row.get(0))
+                    .optional()
+            })
+            .await?;
+        Ok(hash)
+    }
+
+    /// Store a rendered head image.
+    pub async fn store_rendered_head(
+        &self,
+        texture_hash: String,
+        head_data: Vec<u8>,
+        now: i64,
+    ) -> Result<()> {
+        let hash_for_log = texture_hash.clone();
+        self.conn
+            .call(move |conn| {
+                conn.prepare_cached(
+                    "INSERT OR REPLACE INTO rendered_heads (texture_hash, head_data, rendered_at) VALUES (?1, ?2, ?3)",
+                )?
+                .execute(params![&texture_hash, &head_data, now])?;
+                Ok(())
+            })
+            .await?;
+
+        debug!(texture_hash = %hash_for_log, "stored rendered head");
+        Ok(())
+    }
+
+    /// Get a rendered head by texture hash.
     pub async fn get_rendered_head(&self, texture_hash: &str) -> Result<Option<Vec<u8>>> {
         let hash = texture_hash.to_string();
         let head_data = self
-            .conn
+            .reader()
             .call(move |conn| {
                 conn.prepare_cached("SELECT head_data FROM rendered_heads WHERE texture_hash = ?1")?
                     .query_row(params![&hash], |row| row.get(0))
@@ -835,31 +2734,69 @@ impl Database {
     // ========================================================================
 
     /// Store a cached status composite image for a server.
+    /// The server's cached GIF was drawn from the old player list, so it's dropped.
     pub async fn store_status_image(
         &self,
         api_key_hash: String,
         image_data: Vec<u8>,
         now: i64,
     ) -> Result<()> {
-        self.conn
-            .call(move |conn| {
-                conn.prepare_cached(
-                    "INSERT OR REPLACE INTO status_images (api_key_hash, image_data, updated_at) VALUES (?1, ?2, ?3)",
-                )?
-                .execute(params![&api_key_hash, &image_data, now])?;
-                Ok(())
-            })
-            .await?;
+        call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            tx.prepare_cached(
+                "INSERT OR REPLACE INTO status_images (api_key_hash, image_data, updated_at) VALUES (?1, ?2, ?3)",
+            )?
+            .execute(params![&api_key_hash, &image_data, now])?;
+            tx.prepare_cached("DELETE FROM status_gifs WHERE api_key_hash = ?1")?
+                .execute(params![&api_key_hash])?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
 
         debug!("stored status image");
         Ok(())
     }
 
+    /// Store a cached animated status GIF for a server.
+    pub async fn store_status_gif(
+        &self,
+        api_key_hash: String,
+        image_data: Vec<u8>,
+        now: i64,
+    ) -> Result<()> {
+        call_with_retry(&self.conn, move |conn| {
+            conn.prepare_cached(
+                "INSERT OR REPLACE INTO status_gifs (api_key_hash, image_data, updated_at) VALUES (?1, ?2, ?3)",
+            )?
+            .execute(params![&api_key_hash, &image_data, now])?;
+            Ok(())
+        })
+        .await?;
+
+        debug!("stored status gif");
+        Ok(())
+    }
+
+    /// Get a cached animated status GIF.
+    pub async fn get_status_gif(&self, api_key_hash: &str) -> Result<Option<Vec<u8>>> {
+        let hash = api_key_hash.to_string();
+        let image_data = self
+            .reader()
+            .call(move |conn| {
+                conn.prepare_cached("SELECT image_data FROM status_gifs WHERE api_key_hash = ?1")?
+                    .query_row(params![&hash], |row| row.get(0))
+                    .optional()
+            })
+            .await?;
+        Ok(image_data)
+    }
+
     /// Get a cached status composite image.
     pub async fn get_status_image(&self, api_key_hash: &str) -> Result<Option<Vec<u8>>> {
         let hash = api_key_hash.to_string();
         let image_data = self
-            .conn
+            .reader()
             .call(move |conn| {
                 conn.prepare_cached("SELECT image_data FROM status_images WHERE api_key_hash = ?1")?
                     .query_row(params![&hash], |row| row.get(0))
@@ -869,27 +2806,32 @@ impl Database {
         Ok(image_data)
     }
 
-    /// Get players with their texture hashes for a server (for composite rendering).
-    pub async fn get_players_with_heads(
-        &self,
-        api_key_hash: &str,
-    ) -> Result<Vec<(PlayerName, Option<String>)>> {
-        // Get online players from cache
-        let players: Vec<PlayerName> = match self.cache.get_async(api_key_hash).await {
-            Some(entry) => entry.get().players.iter().map(|(name, _)| *name).collect(),
-            None => Vec::new(),
-        };
+    /// Drop a server's cached status image and GIF so they are rendered again
+    /// on next use. Rendered heads are shared by every server and keyed by
+    /// texture hash, so they can't go stale and are kept. Returns how many
+    /// images were removed.
+    pub async fn clear_render_cache(&self, api_key_hash: String) -> Result<u64> {
+        let cleared = call_with_retry(&self.conn, move |conn| {
+            let tx = conn.transaction()?;
+            let mut cleared = 0;
+            for table in ["status_images", "status_gifs"] {
+                cleared += tx
+                    .prepare_cached(&format!("DELETE FROM {table} WHERE api_key_hash = ?1"))?
+                    .execute(params![&api_key_hash])?;
+            }
+            tx.commit()?;
+            Ok(cleared as u64)
+        })
+        .await?;
 
-        // Look up texture hashes for each player
-        let mut result = Vec::with_capacity(players.len());
-        for player_name in players {
-            let hash = self.get_player_texture_hash(player_name.as_str()).await?;
-            result.push((player_name, hash));
-        }
+        debug!(cleared, "cleared render cache");
+        Ok(cleared)
+    }
 
-        // Sort by player name for consistent ordering
-        result.sort_by(|a, b| a.0.cmp(&b.0));
-        Ok(result)
+    /// Get online players for a server with their texture hashes and UUIDs
+    /// (sorted by name), for rendering status images.
+    pub async fn get_players_with_heads(&self, api_key_hash: &str) -> Result<Vec<PlayerInfo>> {
+        self.get_player_infos(api_key_hash).await
     }
 }
 
@@ -906,185 +2848,2140 @@ mod tests {
         PlayerName::from(s).unwrap()
     }
 
-    #[tokio::test]
-    async fn test_pending_link_lifecycle() {
-        let db = Database::open_in_memory().await.unwrap();
+    #[tokio::test]
+    async fn test_pending_link_lifecycle() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        // Create a pending link
+        let link = db
+            .create_pending_link(
+                "oxeye-abc123".to_string(),
+                12345,
+                "Survival SMP".to_string(),
+                now(),
+                25,
+            )
+            .await
+            .unwrap();
+        assert_eq!(link.code, "oxeye-abc123");
+        assert_eq!(link.guild_id, 12345);
+        assert_eq!(link.server_name, "Survival SMP");
+
+        // Get it
+        let link = db
+            .get_pending_link("oxeye-abc123".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(link.code, "oxeye-abc123");
+
+        // Consume it
+        let link = db
+            .consume_pending_link("oxeye-abc123".to_string(), now())
+            .await
+            .unwrap();
+        assert_eq!(link.server_name, "Survival SMP");
+
+        // Should be gone now
+        assert!(
+            db.get_pending_link("oxeye-abc123".to_string())
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_consume_pending_link_twice() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_pending_link(
+            "oxeye-abc123".to_string(),
+            12345,
+            "Survival".to_string(),
+            now(),
+            25,
+        )
+        .await
+        .unwrap();
+        db.consume_pending_link("oxeye-abc123".to_string(), now())
+            .await
+            .unwrap();
+
+        // A second claim is told the code was used, not that it's unknown
+        assert!(matches!(
+            db.consume_pending_link("oxeye-abc123".to_string(), now() + 1)
+                .await,
+            Err(DbError::PendingLinkAlreadyUsed)
+        ));
+        assert!(matches!(
+            db.consume_pending_link("oxeye-zzz999".to_string(), now())
+                .await,
+            Err(DbError::PendingLinkNotFound)
+        ));
+
+        // Once the code would have expired, it's just unknown again
+        db.cleanup_expired_links(now() + 660).await.unwrap();
+        assert!(matches!(
+            db.consume_pending_link("oxeye-abc123".to_string(), now() + 660)
+                .await,
+            Err(DbError::PendingLinkNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_pending_links_by_guild_skips_expired() {
+        let db = Database::open_in_memory().await.unwrap();
+        let now = now();
+        for (code, guild_id, name, created_at) in [
+            (
+                "oxeye-old111",
+                12345,
+                "Stale",
+                now - PendingLink::TTL_SECONDS - 1,
+            ),
+            ("oxeye-new111", 12345, "Survival", now - 60),
+            ("oxeye-new222", 12345, "Creative", now),
+            ("oxeye-other1", 99999, "Elsewhere", now),
+        ] {
+            db.create_pending_link(code.to_string(), guild_id, name.to_string(), created_at, 25)
+                .await
+                .unwrap();
+        }
+
+        let codes: Vec<String> = db
+            .get_pending_links_by_guild(12345, now)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|link| link.code)
+            .collect();
+        assert_eq!(codes, vec!["oxeye-new222", "oxeye-new111"]);
+    }
+
+    #[tokio::test]
+    async fn test_last_sync_survives_restart() {
+        let path = std::env::temp_dir().join(format!("oxeye-sync-test-{}.db", std::process::id()));
+        let db = Database::open(&path).await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+
+        // Joins don't count as a sync
+        db.player_join("hash1".to_string(), pn("Steve"), 1_000)
+            .await
+            .unwrap();
+        assert_eq!(db.get_last_sync("hash1").await.unwrap(), None);
+
+        db.sync_players("hash1".to_string(), vec![pn("Steve")], 2_000)
+            .await
+            .unwrap();
+        assert_eq!(db.get_last_sync("hash1").await.unwrap(), Some(2_000));
+        let summaries = db.get_server_summaries(12345).await.unwrap();
+        assert_eq!(summaries[0].last_sync_at, Some(2_000));
+
+        // A restarted backend still knows when the last sync was
+        drop(db);
+        let db = Database::open(&path).await.unwrap();
+        assert_eq!(db.get_last_sync("hash1").await.unwrap(), Some(2_000));
+        assert!(!db.is_server_synced("hash1").await);
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reads_on_file_database() {
+        let path = std::env::temp_dir().join(format!("oxeye-pool-test-{}.db", std::process::id()));
+        let db = Database::open(&path).await.unwrap();
+        assert_eq!(db.readers.len(), READER_CONNECTIONS);
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+
+        // Readers see the writer's committed data
+        let mut handles = Vec::new();
+        for _ in 0..64 {
+            let db = db.clone();
+            handles.push(tokio::spawn(async move {
+                let server = db
+                    .get_server_by_api_key("hash1".to_string())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(server.name, "Survival");
+                db.get_server_summaries(12345).await.unwrap().len()
+            }));
+        }
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), 1);
+        }
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_writes_succeed_under_contention() {
+        let path = std::env::temp_dir().join(format!("oxeye-busy-test-{}.db", std::process::id()));
+        let db = Database::open(&path).await.unwrap();
+        // Fail with SQLITE_BUSY at once instead of waiting in SQLite, so the
+        // write only gets through by being retried
+        db.conn
+            .call(|conn| conn.busy_timeout(Duration::ZERO))
+            .await
+            .unwrap();
+
+        // Another connection grabs the write lock and holds it briefly
+        let blocker = rusqlite::Connection::open(&path).unwrap();
+        blocker.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            blocker.execute_batch("COMMIT").unwrap();
+        });
+
+        let started = std::time::Instant::now();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= BUSY_RETRY_BASE_DELAY);
+        release.join().unwrap();
+        assert!(
+            db.get_server_by_api_key("hash1".to_string())
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_with_retry_retries_busy_errors() {
+        let db = Database::open_in_memory().await.unwrap();
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        // Fails with SQLITE_BUSY twice, then succeeds
+        let counter = attempts.clone();
+        let value = call_with_retry(&db.conn, move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                return Err(rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+                    None,
+                ));
+            }
+            Ok(42)
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // Gives up after the attempt limit
+        let counter = attempts.clone();
+        counter.store(0, Ordering::SeqCst);
+        let result: tokio_rusqlite::Result<()> = call_with_retry(&db.conn, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_LOCKED),
+                None,
+            ))
+        })
+        .await;
+        assert!(result.as_ref().is_err_and(is_busy));
+        assert_eq!(
+            attempts.load(Ordering::SeqCst),
+            BUSY_RETRY_ATTEMPTS as usize
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flush_cache_restores_players_on_reopen() {
+        let path = std::env::temp_dir().join(format!("oxeye-flush-test-{}.db", std::process::id()));
+        let db = Database::open(&path).await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Alex"), now() + 60)
+            .await
+            .unwrap();
+
+        assert_eq!(db.flush_cache().await.unwrap(), 2);
+        drop(db);
+
+        // Players come back, but the server still awaits a sync
+        let db = Database::open(&path).await.unwrap();
+        let players = db.get_online_players("hash1".to_string()).await.unwrap();
+        assert_eq!(players, vec![pn("Alex"), pn("Steve")]);
+        assert!(!db.is_server_synced("hash1").await);
+        drop(db);
+
+        // The snapshot is consumed on startup
+        let db = Database::open(&path).await.unwrap();
+        assert!(
+            db.get_online_players("hash1".to_string())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flush_cache_skips_players_in_leave_grace() {
+        let path =
+            std::env::temp_dir().join(format!("oxeye-flush-grace-test-{}.db", std::process::id()));
+        let db = Database::open(&path)
+            .await
+            .unwrap()
+            .with_leave_grace(Duration::from_secs(60));
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+        db.player_leave("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+
+        // Steve is still listed during his grace period, but isn't flushed
+        assert_eq!(
+            db.get_online_players("hash1".to_string()).await.unwrap(),
+            vec![pn("Alex"), pn("Steve")]
+        );
+        assert_eq!(db.flush_cache().await.unwrap(), 1);
+        drop(db);
+
+        let db = Database::open(&path).await.unwrap();
+        let players = db.get_online_players("hash1".to_string()).await.unwrap();
+        assert_eq!(players, vec![pn("Alex")]);
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let db_path =
+            std::env::temp_dir().join(format!("oxeye-snapshot-test-{}.db", std::process::id()));
+        let path = db_path.with_extension("json");
+        let db = Database::open(&db_path).await.unwrap();
+        for hash in ["hash1", "hash2"] {
+            db.create_server(hash.to_string(), hash.to_string(), 12345, now())
+                .await
+                .unwrap();
+        }
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Alex"), now() + 60)
+            .await
+            .unwrap();
+        db.player_join("hash2".to_string(), pn("Notch"), now())
+            .await
+            .unwrap();
+
+        assert_eq!(db.save_snapshot(&path).await.unwrap(), 3);
+        let joins = |players: Vec<OnlinePlayer>| {
+            players
+                .into_iter()
+                .map(|p| (p.player_name, p.joined_at))
+                .collect::<Vec<_>>()
+        };
+        let mut before = Vec::new();
+        for hash in ["hash1", "hash2"] {
+            before.push(joins(
+                db.get_online_players_detailed(hash.to_string())
+                    .await
+                    .unwrap(),
+            ));
+        }
+        drop(db);
+
+        // After a restart the players come back, join times included, but
+        // the servers still await a sync
+        let db = Database::open(&db_path).await.unwrap();
+        assert_eq!(db.load_snapshot(&path).await, 3);
+        let mut after = Vec::new();
+        for hash in ["hash1", "hash2"] {
+            after.push(joins(
+                db.get_online_players_detailed(hash.to_string())
+                    .await
+                    .unwrap(),
+            ));
+        }
+        assert_eq!(before, after);
+        assert!(!db.is_server_synced("hash1").await);
+
+        // The snapshot is consumed on load
+        assert!(!path.exists());
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", db_path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_snapshot_missing_or_corrupt_starts_empty() {
+        let path = std::env::temp_dir().join(format!(
+            "oxeye-corrupt-snapshot-test-{}.json",
+            std::process::id()
+        ));
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+
+        assert_eq!(db.load_snapshot(&path).await, 0);
+
+        std::fs::write(&path, b"{\"servers\": {\"hash1\": [[\"Steve\"").unwrap();
+        assert_eq!(db.load_snapshot(&path).await, 0);
+        assert!(
+            db.get_online_players("hash1".to_string())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_api_key_preserves_players() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("old".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.player_join("old".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_leave("old".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+
+        db.rotate_api_key("old".to_string(), "new".to_string())
+            .await
+            .unwrap();
+
+        // Old key no longer works
+        assert!(
+            db.get_server_by_api_key("old".to_string())
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            db.player_join("old".to_string(), pn("Notch"), now()).await,
+            Err(DbError::InvalidApiKey)
+        ));
+
+        // Server, online players and history moved to the new key
+        let server = db
+            .get_server_by_api_key("new".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(server.name, "Survival");
+        assert_eq!(
+            db.get_online_players("new".to_string()).await.unwrap(),
+            vec![pn("Steve")]
+        );
+        assert!(db.is_server_synced("new").await);
+        assert_eq!(
+            db.get_last_seen("new".to_string(), pn("Alex"))
+                .await
+                .unwrap(),
+            Some(now())
+        );
+
+        // Unknown key
+        assert!(matches!(
+            db.rotate_api_key("old".to_string(), "newer".to_string())
+                .await,
+            Err(DbError::InvalidApiKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recently_left() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        for (player, left_at) in [
+            ("Old", now() - 3600),
+            ("Steve", now() - 60),
+            ("Alex", now() - 600),
+        ] {
+            db.player_join("hash1".to_string(), pn(player), left_at - 10)
+                .await
+                .unwrap();
+            db.player_leave("hash1".to_string(), pn(player), left_at)
+                .await
+                .unwrap();
+        }
+        // Notch left recently but has since rejoined
+        db.player_leave("hash1".to_string(), pn("Notch"), now() - 120)
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Notch"), now())
+            .await
+            .unwrap();
+
+        let recent = db
+            .get_recently_left("hash1".to_string(), now() - 1800)
+            .await
+            .unwrap();
+        assert_eq!(
+            recent,
+            vec![(pn("Steve"), now() - 60), (pn("Alex"), now() - 600)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_maintenance() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_pending_link(
+            "oxeye-abc123".to_string(),
+            12345,
+            "Creative".to_string(),
+            now(),
+            25,
+        )
+        .await
+        .unwrap();
+        db.player_leave("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.cleanup_expired_links(now() + 3600).await.unwrap();
+
+        db.maintenance().await.unwrap();
+
+        let auto_vacuum: i64 = db
+            .conn
+            .call(|conn| conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0)))
+            .await
+            .unwrap();
+        assert_eq!(auto_vacuum, 2); // INCREMENTAL
+        assert!(
+            db.get_server_by_api_key("hash1".to_string())
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.ping().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_link() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_pending_link(
+            "oxeye-expired".to_string(),
+            12345,
+            "Test".to_string(),
+            now(),
+            25,
+        )
+        .await
+        .unwrap();
+
+        // Try to consume after expiry (11 minutes later)
+        let result = db
+            .consume_pending_link("oxeye-expired".to_string(), now() + 660)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_server_lifecycle() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        // Create a server
+        let server = db
+            .create_server(
+                "hash123".to_string(),
+                "Survival SMP".to_string(),
+                12345,
+                now(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(server.name, "Survival SMP");
+
+        // Get it by API key
+        let server = db
+            .get_server_by_api_key("hash123".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(server.name, "Survival SMP");
+        assert_eq!(server.created_at, now());
+
+        // Get servers by guild
+        let servers = db.get_servers_by_guild(12345).await.unwrap();
+        assert_eq!(servers.len(), 1);
+
+        // Check name exists
+        assert!(
+            db.server_name_exists(12345, "Survival SMP".to_string())
+                .await
+                .unwrap()
+        );
+        assert!(
+            !db.server_name_exists(12345, "Creative".to_string())
+                .await
+                .unwrap()
+        );
+
+        // Delete it
+        db.delete_server(12345, "Survival SMP".to_string())
+            .await
+            .unwrap();
+        assert!(
+            db.get_server_by_api_key("hash123".to_string())
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_player_tracking() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        // Create a server first
+        db.create_server(
+            "hash123".to_string(),
+            "Survival SMP".to_string(),
+            12345,
+            now(),
+        )
+        .await
+        .unwrap();
+
+        // Player joins
+        db.player_join("hash123".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash123".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+
+        // Get online players
+        let players = db.get_online_players("hash123".to_string()).await.unwrap();
+        assert_eq!(players, vec![pn("Alex"), pn("Steve")]);
+
+        // Player leaves
+        db.player_leave("hash123".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        let players = db.get_online_players("hash123".to_string()).await.unwrap();
+        assert_eq!(players, vec![pn("Alex")]);
+
+        // Sync players
+        db.sync_players("hash123".to_string(), vec![pn("Notch"), pn("jeb_")], now())
+            .await
+            .unwrap();
+        let players = db.get_online_players("hash123".to_string()).await.unwrap();
+        assert_eq!(players, vec![pn("Notch"), pn("jeb_")]);
+    }
+
+    #[tokio::test]
+    async fn test_delete_server_by_api_key_removes_players() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_leave("hash1".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+
+        db.delete_server_by_api_key("hash1".to_string())
+            .await
+            .unwrap();
+
+        // Server row, cached players and cascaded history are all gone
+        assert!(
+            db.get_server_by_api_key("hash1".to_string())
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(db.get_servers_by_guild(12345).await.unwrap().is_empty());
+        assert!(
+            db.get_online_players("hash1".to_string())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(db.count_all_online_players().await, 0);
+        assert_eq!(
+            db.get_last_seen("hash1".to_string(), pn("Alex"))
+                .await
+                .unwrap(),
+            None
+        );
+
+        // A second delete finds nothing
+        assert!(matches!(
+            db.delete_server_by_api_key("hash1".to_string()).await,
+            Err(DbError::InvalidApiKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sync_deduplicates_players() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.sync_players(
+            "hash1".to_string(),
+            vec![pn("Steve"), pn("Alex"), pn("Steve"), pn("Alex")],
+            now(),
+        )
+        .await
+        .unwrap();
+
+        let players = db.get_online_players("hash1".to_string()).await.unwrap();
+        assert_eq!(players, vec![pn("Alex"), pn("Steve")]);
+    }
+
+    #[tokio::test]
+    async fn test_last_seen_tracking() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_last_seen("hash1".to_string(), pn("Steve"))
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Leaving records the leave time
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_leave("hash1".to_string(), pn("Steve"), now() + 300)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_last_seen("hash1".to_string(), pn("Steve"))
+                .await
+                .unwrap(),
+            Some(now() + 300)
+        );
+
+        // Dropping off a sync counts as leaving; later leaves overwrite
+        db.sync_players(
+            "hash1".to_string(),
+            vec![pn("Steve"), pn("Alex")],
+            now() + 400,
+        )
+        .await
+        .unwrap();
+        db.sync_players("hash1".to_string(), vec![pn("Alex")], now() + 500)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_last_seen("hash1".to_string(), pn("Steve"))
+                .await
+                .unwrap(),
+            Some(now() + 500)
+        );
+        assert_eq!(
+            db.get_last_seen("hash1".to_string(), pn("Alex"))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_events() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+
+        // Joins apply before leaves, so Jeb ends up offline
+        db.apply_events(
+            "hash1".to_string(),
+            vec![pn("Alex"), pn("Notch"), pn("Jeb")],
+            vec![pn("Steve"), pn("Jeb")],
+            now(),
+        )
+        .await
+        .unwrap();
+
+        let players = db.get_online_players("hash1".to_string()).await.unwrap();
+        assert_eq!(players, vec![pn("Alex"), pn("Notch")]);
+
+        // Unknown server applies nothing
+        let result = db
+            .apply_events("missing".to_string(), vec![pn("Steve")], vec![], now())
+            .await;
+        assert!(matches!(result, Err(DbError::InvalidApiKey)));
+        assert!(
+            db.get_online_players("missing".to_string())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_ordered_events() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+
+        // Events apply in order: Steve reconnects, Jeb joins then leaves
+        db.apply_ordered_events(
+            "hash1".to_string(),
+            vec![
+                PlayerEvent::Leave(pn("Steve")),
+                PlayerEvent::Join(pn("Jeb")),
+                PlayerEvent::Join(pn("Steve")),
+                PlayerEvent::Leave(pn("Jeb")),
+            ],
+            now(),
+        )
+        .await
+        .unwrap();
+
+        let players = db.get_online_players("hash1".to_string()).await.unwrap();
+        assert_eq!(players, vec![pn("Steve")]);
+    }
+
+    #[tokio::test]
+    async fn test_server_summaries() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash2".to_string(), "Creative".to_string(), 12345, now())
+            .await
+            .unwrap();
+
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+
+        let summaries = db.get_server_summaries(12345).await.unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].name, "Creative");
+        assert_eq!(summaries[0].player_count, 0);
+        assert_eq!(summaries[1].name, "Survival");
+        assert_eq!(summaries[1].player_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_servers_by_activity() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash2".to_string(), "Creative".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash3".to_string(), "Anarchy".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash4".to_string(), "Hub".to_string(), 12345, now())
+            .await
+            .unwrap();
+
+        db.sync_players("hash1".to_string(), vec![pn("Steve")], now())
+            .await
+            .unwrap();
+        db.sync_players(
+            "hash2".to_string(),
+            vec![pn("Alex"), pn("Notch"), pn("jeb_")],
+            now(),
+        )
+        .await
+        .unwrap();
+        db.sync_players("hash4".to_string(), vec![pn("Herobrine")], now())
+            .await
+            .unwrap();
+
+        let servers = db.get_servers_by_activity(12345).await.unwrap();
+        let order: Vec<(&str, u32)> = servers
+            .iter()
+            .map(|s| (s.name.as_str(), s.player_count))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("Creative", 3), ("Hub", 1), ("Survival", 1), ("Anarchy", 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_player_servers() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 222, now())
+            .await
+            .unwrap();
+        db.create_server("hash2".to_string(), "Creative".to_string(), 111, now())
+            .await
+            .unwrap();
+        db.create_server("hash3".to_string(), "Anarchy".to_string(), 222, now())
+            .await
+            .unwrap();
+
+        // Not online anywhere yet
+        assert!(
+            db.find_player_servers(pn("Steve"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash2".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash3".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.find_player_servers(pn("Steve")).await.unwrap(),
+            vec![(111, "Creative".to_string()), (222, "Survival".to_string())]
+        );
+        assert_eq!(
+            db.find_player_servers(pn("Alex")).await.unwrap(),
+            vec![(222, "Anarchy".to_string())]
+        );
+
+        // Leaving removes the server from the result
+        db.player_leave("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        assert_eq!(
+            db.find_player_servers(pn("Steve")).await.unwrap(),
+            vec![(111, "Creative".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_embed_color_round_trip() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        // Unset by default
+        assert_eq!(db.get_embed_color(12345).await.unwrap(), None);
+
+        db.set_embed_color(12345, 0xFF8800).await.unwrap();
+        assert_eq!(db.get_embed_color(12345).await.unwrap(), Some(0xFF8800));
+
+        // Setting again replaces it, other guilds are unaffected
+        db.set_embed_color(12345, 0x00AA55).await.unwrap();
+        assert_eq!(db.get_embed_color(12345).await.unwrap(), Some(0x00AA55));
+        assert_eq!(db.get_embed_color(67890).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_guild_webhook_round_trip() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        assert_eq!(db.get_guild_webhook(12345).await.unwrap(), None);
+
+        db.set_guild_webhook(12345, Some("https://example.com/a".to_string()))
+            .await
+            .unwrap();
+        db.set_guild_webhook(12345, Some("https://example.com/b".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_guild_webhook(12345).await.unwrap().as_deref(),
+            Some("https://example.com/b")
+        );
+        assert_eq!(db.get_guild_webhook(67890).await.unwrap(), None);
+
+        // Removing it
+        db.set_guild_webhook(12345, None).await.unwrap();
+        assert_eq!(db.get_guild_webhook(12345).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_merge_players_dedups_names() {
+        let db = Database::open_in_memory().await.unwrap();
+        for hash in ["proxy-a", "proxy-b"] {
+            db.create_server(hash.to_string(), hash.to_string(), 12345, now())
+                .await
+                .unwrap();
+        }
+        db.sync_players("proxy-a".to_string(), vec![pn("Steve"), pn("Alex")], 1_000)
+            .await
+            .unwrap();
+        db.sync_players("proxy-b".to_string(), vec![pn("Alex"), pn("Notch")], 2_000)
+            .await
+            .unwrap();
+
+        let count = db
+            .merge_players("proxy-a".to_string(), "proxy-b".to_string())
+            .await
+            .unwrap();
+
+        // Alex is only listed once, with the earlier join time
+        assert_eq!(count, 3);
+        let merged = db
+            .get_server_with_players(12345, "proxy-b".to_string())
+            .await
+            .unwrap();
+        let mut players: Vec<(&str, i64)> = merged
+            .players
+            .iter()
+            .map(|p| (p.player_name.as_str(), p.joined_at))
+            .collect();
+        players.sort();
+        assert_eq!(
+            players,
+            vec![("Alex", 1_000), ("Notch", 2_000), ("Steve", 1_000)]
+        );
+        assert!(
+            db.get_online_players("proxy-a".to_string())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // Both servers must exist
+        assert!(matches!(
+            db.merge_players("proxy-a".to_string(), "missing".to_string())
+                .await,
+            Err(DbError::ServerNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_player_uuids_in_server_players() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.sync_players("hash1".to_string(), vec![pn("Steve"), pn("Alex")], now())
+            .await
+            .unwrap();
+        db.update_player_uuids(
+            vec![(pn("Steve"), "069a79f444e94726a5befca90e38aaf5".to_string())],
+            now(),
+        )
+        .await
+        .unwrap();
+
+        let server = db
+            .get_server_with_players(12345, "Survival".to_string())
+            .await
+            .unwrap();
+        let uuids: Vec<(&str, Option<&str>)> = server
+            .players
+            .iter()
+            .map(|p| (p.player_name.as_str(), p.uuid.as_deref()))
+            .collect();
+        assert_eq!(
+            uuids,
+            vec![
+                ("Alex", None),
+                ("Steve", Some("069a79f444e94726a5befca90e38aaf5"))
+            ]
+        );
+
+        let many = db
+            .get_many_servers_with_players(&["hash1".to_string()])
+            .await
+            .unwrap();
+        assert!(many[0].players.iter().any(|p| p.uuid.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_get_servers_paginated() {
+        let db = Database::open_in_memory().await.unwrap();
+        for name in ["Echo", "Alpha", "Delta", "Charlie", "Bravo"] {
+            db.create_server(format!("hash-{}", name), name.to_string(), 12345, now())
+                .await
+                .unwrap();
+        }
+        db.create_server("other".to_string(), "Other".to_string(), 67890, now())
+            .await
+            .unwrap();
+        let names = |servers: Vec<Server>| servers.into_iter().map(|s| s.name).collect::<Vec<_>>();
+
+        // First page
+        let (servers, total) = db.get_servers_paginated(12345, 2, 0).await.unwrap();
+        assert_eq!(names(servers), vec!["Alpha", "Bravo"]);
+        assert_eq!(total, 5);
+
+        // Last (partial) page
+        let (servers, total) = db.get_servers_paginated(12345, 2, 4).await.unwrap();
+        assert_eq!(names(servers), vec!["Echo"]);
+        assert_eq!(total, 5);
+
+        // Past the end
+        let (servers, total) = db.get_servers_paginated(12345, 2, 5).await.unwrap();
+        assert!(servers.is_empty());
+        assert_eq!(total, 5);
+    }
+
+    #[tokio::test]
+    async fn test_count_online_players() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+        assert_eq!(db.count_online_players("hash1").await.unwrap(), 0);
+
+        for name in ["Steve", "Alex", "Notch"] {
+            db.player_join("hash1".to_string(), pn(name), now())
+                .await
+                .unwrap();
+        }
+        db.player_leave("hash1".to_string(), pn("Notch"), now())
+            .await
+            .unwrap();
+
+        let online = db.get_online_players("hash1".to_string()).await.unwrap();
+        assert_eq!(db.count_online_players("hash1").await.unwrap(), 2);
+        assert_eq!(online.len(), 2);
+        // Unknown servers have nobody online
+        assert_eq!(db.count_online_players("missing").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_relink_existing_server() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("old_hash".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+        db.player_join("old_hash".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+
+        // A normal code for the same name conflicts; a relink code doesn't
+        assert!(matches!(
+            db.create_pending_link(
+                "oxeye-new111".to_string(),
+                1,
+                "Survival".to_string(),
+                now(),
+                25
+            )
+            .await,
+            Err(DbError::ServerNameConflict)
+        ));
+        db.create_relink_code("oxeye-new222".to_string(), 1, "Survival".to_string(), now())
+            .await
+            .unwrap();
+        let link = db
+            .consume_pending_link("oxeye-new222".to_string(), now())
+            .await
+            .unwrap();
+        assert!(link.relink);
+
+        let server = db
+            .relink_server(1, "Survival".to_string(), "new_hash".to_string())
+            .await
+            .unwrap();
+        assert_eq!(server.api_key_hash, "new_hash");
+        assert_eq!(server.created_at, now());
+
+        // The old key is gone and the players moved with the server
+        assert!(
+            db.get_server_by_api_key("old_hash".to_string())
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            db.get_online_players("new_hash".to_string()).await.unwrap(),
+            vec![pn("Steve")]
+        );
+
+        // Nothing to re-link for unknown names
+        assert!(matches!(
+            db.relink_server(1, "Creative".to_string(), "other".to_string())
+                .await,
+            Err(DbError::ServerNotFound)
+        ));
+        assert!(matches!(
+            db.create_relink_code("oxeye-new333".to_string(), 1, "Creative".to_string(), now())
+                .await,
+            Err(DbError::ServerNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.record_audit(1, 42, "connect".to_string(), "Survival".to_string(), now())
+            .await
+            .unwrap();
+        db.record_audit(
+            1,
+            43,
+            "settag".to_string(),
+            "Survival".to_string(),
+            now() + 10,
+        )
+        .await
+        .unwrap();
+        db.record_audit(2, 42, "unlinkall".to_string(), String::new(), now())
+            .await
+            .unwrap();
+
+        // Newest first, only for the requested guild
+        let entries = db.get_audit_log(1, 10).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0],
+            AuditEntry {
+                guild_id: 1,
+                user_id: 43,
+                action: "settag".to_string(),
+                target: "Survival".to_string(),
+                created_at: now() + 10,
+            }
+        );
+        assert_eq!(entries[1].action, "connect");
+
+        // The limit keeps only the newest entries
+        let entries = db.get_audit_log(1, 1).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].user_id, 43);
+    }
+
+    /// Move the paused clock on, then let the leave timers that came due run.
+    async fn advance(duration: Duration) {
+        tokio::time::advance(duration).await;
+        tokio::task::yield_now().await;
+    }
+
+    #[tokio::test]
+    async fn test_leave_grace_period() {
+        let db = Database::open_in_memory()
+            .await
+            .unwrap()
+            .with_leave_grace(Duration::from_secs(60));
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        for player in ["Steve", "Alex"] {
+            db.player_join("hash1".to_string(), pn(player), now())
+                .await
+                .unwrap();
+        }
+
+        // Steve flickers out and back in; Alex really leaves
+        db.player_leave("hash1".to_string(), pn("Steve"), now() + 5)
+            .await
+            .unwrap();
+        db.player_leave("hash1".to_string(), pn("Alex"), now() + 5)
+            .await
+            .unwrap();
+        assert_eq!(db.count_online_players("hash1").await.unwrap(), 2);
+        db.player_join("hash1".to_string(), pn("Steve"), now() + 6)
+            .await
+            .unwrap();
+
+        // Paused only now: the clock jumps ahead on its own while waiting on SQLite
+        tokio::time::pause();
+        advance(Duration::from_secs(61)).await;
+        assert_eq!(db.count_online_players("hash1").await.unwrap(), 1);
+
+        // Steve never disappeared and keeps his original join time
+        let players = db.get_players_with_heads("hash1").await.unwrap();
+        assert_eq!(players.len(), 1);
+        assert_eq!(players[0].player_name, pn("Steve"));
+        assert_eq!(players[0].joined_at, now());
+
+        // Alex's departure is recorded at the time he actually left
+        let recent = db
+            .get_recently_left("hash1".to_string(), now() - 60)
+            .await
+            .unwrap();
+        assert_eq!(recent, vec![(pn("Alex"), now() + 5)]);
+    }
+
+    #[tokio::test]
+    async fn test_leave_grace_restarts_on_second_leave() {
+        let db = Database::open_in_memory()
+            .await
+            .unwrap()
+            .with_leave_grace(Duration::from_secs(60));
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+
+        // Steve leaves, rejoins, and leaves again within the same second
+        db.player_leave("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        tokio::time::pause();
+        advance(Duration::from_secs(30)).await;
+        tokio::time::resume();
+        db.player_leave("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+
+        // The first leave's timer doesn't cut the second grace period short
+        tokio::time::pause();
+        advance(Duration::from_secs(31)).await;
+        assert_eq!(db.count_online_players("hash1").await.unwrap(), 1);
+        advance(Duration::from_secs(30)).await;
+        assert_eq!(db.count_online_players("hash1").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_batched_leaves_get_grace_period() {
+        let db = Database::open_in_memory()
+            .await
+            .unwrap()
+            .with_leave_grace(Duration::from_secs(60));
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+
+        // A batched leave keeps Steve listed like a single /leave would
+        db.apply_events("hash1".to_string(), vec![], vec![pn("Steve")], now())
+            .await
+            .unwrap();
+        assert_eq!(db.count_online_players("hash1").await.unwrap(), 1);
+
+        tokio::time::pause();
+        advance(Duration::from_secs(61)).await;
+        assert_eq!(db.count_online_players("hash1").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_server_names_are_case_insensitive() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+
+        // "survival" conflicts with "Survival", both as a pending link and directly
+        assert!(matches!(
+            db.create_pending_link(
+                "oxeye-abc123".to_string(),
+                1,
+                "survival".to_string(),
+                now(),
+                25
+            )
+            .await,
+            Err(DbError::ServerNameConflict)
+        ));
+        assert!(
+            db.create_server("hash2".to_string(), "SURVIVAL".to_string(), 1, now())
+                .await
+                .is_err()
+        );
+        // Other guilds can still use the name
+        db.create_server("hash3".to_string(), "survival".to_string(), 2, now())
+            .await
+            .unwrap();
+
+        // Lookups ignore case and report the stored name
+        let server = db
+            .get_server_with_players(1, "SURVIVAL".to_string())
+            .await
+            .unwrap();
+        assert_eq!(server.name, "Survival");
+        db.delete_server(1, "survival".to_string()).await.unwrap();
+        assert!(
+            !db.server_name_exists(1, "Survival".to_string())
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_pending_link() {
+        let db = Database::open_in_memory().await.unwrap();
+        let issued = now() - 300;
+        db.create_pending_link(
+            "oxeye-old111".to_string(),
+            1,
+            "Lobby".to_string(),
+            issued,
+            25,
+        )
+        .await
+        .unwrap();
+
+        let link = db
+            .refresh_pending_link(
+                1,
+                "lobby".to_string(),
+                "oxeye-new111".to_string(),
+                now(),
+                25,
+            )
+            .await
+            .unwrap();
+        assert_eq!(link.server_name, "Lobby");
+        assert_eq!(link.created_at, now());
+
+        // The old code is gone and the new one works
+        assert!(
+            db.get_pending_link("oxeye-old111".to_string())
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            db.consume_pending_link("oxeye-old111".to_string(), now())
+                .await,
+            Err(DbError::PendingLinkNotFound)
+        ));
+        let consumed = db
+            .consume_pending_link("oxeye-new111".to_string(), now())
+            .await
+            .unwrap();
+        assert_eq!(consumed.server_name, "Lobby");
+
+        // With nothing pending, a new link is created
+        let link = db
+            .refresh_pending_link(
+                1,
+                "Skyblock".to_string(),
+                "oxeye-new222".to_string(),
+                now(),
+                25,
+            )
+            .await
+            .unwrap();
+        assert!(!link.relink);
+        let pending = db.get_pending_links_by_guild(1, now()).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].code, link.code);
+    }
+
+    #[tokio::test]
+    async fn test_create_pending_links_rolls_back_on_conflict() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Creative".to_string(), 1, now())
+            .await
+            .unwrap();
+        let links = |names: &[&str]| {
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (format!("oxeye-bulk{i:02}"), name.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        // One conflicting name means none of the links are created
+        assert!(matches!(
+            db.create_pending_links(1, links(&["Lobby", "creative", "Skyblock"]), now(), 25)
+                .await,
+            Err(DbError::ServerNameConflict)
+        ));
+        assert!(
+            db.get_pending_links_by_guild(1, now())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // So does going over the server limit
+        assert!(matches!(
+            db.create_pending_links(1, links(&["Lobby", "Skyblock"]), now(), 2)
+                .await,
+            Err(DbError::ServerLimitReached)
+        ));
+
+        let created = db
+            .create_pending_links(1, links(&["Lobby", "Skyblock"]), now(), 25)
+            .await
+            .unwrap();
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[1].server_name, "Skyblock");
+        assert_eq!(
+            db.get_pending_links_by_guild(1, now()).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_count_unique_players() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+        assert_eq!(db.count_unique_players("hash1").await.unwrap(), 0);
+
+        // Steve plays twice, an hour apart
+        for start in [now(), now() + 3600] {
+            db.player_join("hash1".to_string(), pn("Steve"), start)
+                .await
+                .unwrap();
+            db.player_leave("hash1".to_string(), pn("Steve"), start + 60)
+                .await
+                .unwrap();
+        }
+        assert_eq!(db.count_unique_players("hash1").await.unwrap(), 1);
+
+        // Players count while online, and again only once after rejoining
+        db.player_join("hash1".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now() + 7200)
+            .await
+            .unwrap();
+        assert_eq!(db.count_unique_players("hash1").await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_server() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+        db.create_server("hash2".to_string(), "survival".to_string(), 3, now())
+            .await
+            .unwrap();
+
+        let server = db.transfer_server("hash1".to_string(), 2).await.unwrap();
+        assert_eq!(server.guild_id, 2);
+        assert!(db.get_servers_by_guild(1).await.unwrap().is_empty());
+        let moved = db.get_servers_by_guild(2).await.unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].api_key_hash, "hash1");
+
+        // The destination can't already have a server with the same name
+        assert!(matches!(
+            db.transfer_server("hash1".to_string(), 3).await,
+            Err(DbError::ServerNameConflict)
+        ));
+        assert!(matches!(
+            db.transfer_server("missing".to_string(), 3).await,
+            Err(DbError::ServerNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_clear_players_keeps_server() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+        db.sync_players("hash1".to_string(), vec![pn("Steve"), pn("Alex")], now())
+            .await
+            .unwrap();
+
+        assert_eq!(db.clear_players("hash1".to_string()).await.unwrap(), 2);
+        assert!(
+            db.get_online_players("hash1".to_string())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        // Ghosts aren't recorded as having left
+        assert_eq!(
+            db.get_last_seen("hash1".to_string(), pn("Steve"))
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(db.server_exists("hash1").await.unwrap());
+        assert!(matches!(
+            db.clear_players("missing".to_string()).await,
+            Err(DbError::InvalidApiKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prune_sessions() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+        const DAY: i64 = 24 * 60 * 60;
+
+        // Steve played 100 days ago, Alex an hour ago
+        let long_ago = now() - 100 * DAY;
+        for (player, start) in [("Steve", long_ago), ("Alex", now() - 3600)] {
+            db.player_join("hash1".to_string(), pn(player), start)
+                .await
+                .unwrap();
+            db.player_leave("hash1".to_string(), pn(player), start + 60)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(db.prune_sessions(90 * DAY, now()).await.unwrap(), 1);
+        // Only Alex's hour-ago session is left
+        assert_eq!(db.get_avg_session_secs("hash1").await.unwrap(), Some(60.0));
+        assert_eq!(db.prune_sessions(90 * DAY, now()).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_online_players_detailed() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now() + 60)
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+
+        let players = db
+            .get_online_players_detailed("hash1".to_string())
+            .await
+            .unwrap();
+        let players: Vec<_> = players
+            .iter()
+            .map(|p| (p.player_name.as_str(), p.joined_at))
+            .collect();
+        assert_eq!(players, vec![("Alex", now()), ("Steve", now() + 60)]);
+    }
+
+    #[tokio::test]
+    async fn test_get_players_since() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 1, now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Alex"), now() + 60)
+            .await
+            .unwrap();
+
+        let all = db.get_players_since("hash1".to_string(), 0).await.unwrap();
+        assert_eq!(all, vec![pn("Steve"), pn("Alex")]);
+
+        let recent = db
+            .get_players_since("hash1".to_string(), now() + 60)
+            .await
+            .unwrap();
+        assert_eq!(recent, vec![pn("Alex")]);
+
+        let none = db
+            .get_players_since("hash1".to_string(), now() + 61)
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_many_servers_with_players() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        for (hash, name) in [
+            ("hash1", "Survival"),
+            ("hash2", "Creative"),
+            ("hash3", "Hub"),
+        ] {
+            db.create_server(hash.to_string(), name.to_string(), 12345, now())
+                .await
+                .unwrap();
+        }
+        db.sync_players("hash1".to_string(), vec![pn("Steve"), pn("Alex")], now())
+            .await
+            .unwrap();
+        db.sync_players("hash3".to_string(), vec![pn("Notch")], now())
+            .await
+            .unwrap();
+        db.store_skin("a".repeat(64), None, vec![1, 2, 3])
+            .await
+            .unwrap();
+        db.update_player_skin("Steve", &"a".repeat(64), now())
+            .await
+            .unwrap();
+
+        // Input order is preserved and unknown hashes are skipped
+        let hashes = ["hash3", "missing", "hash1", "hash2"].map(String::from);
+        let servers = db.get_many_servers_with_players(&hashes).await.unwrap();
+        let names: Vec<&str> = servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Hub", "Survival", "Creative"]);
+
+        // Same players as asking for each server separately
+        for (server, hash) in servers.iter().zip(["hash3", "hash1", "hash2"]) {
+            let players: Vec<PlayerName> = server.players.iter().map(|p| p.player_name).collect();
+            assert_eq!(
+                players,
+                db.get_online_players(hash.to_string()).await.unwrap()
+            );
+        }
+        assert_eq!(servers[1].players[1].player_name, pn("Steve"));
+        assert_eq!(servers[1].players[1].texture_hash, Some("a".repeat(64)));
+        assert_eq!(servers[1].players[0].texture_hash, None);
+
+        assert!(
+            db.get_many_servers_with_players(&[])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_tags() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash2".to_string(), "Bedwars".to_string(), 12345, now())
+            .await
+            .unwrap();
+
+        db.set_server_tag(12345, "Bedwars".to_string(), Some("Minigames".to_string()))
+            .await
+            .unwrap();
+        let summaries = db.get_server_summaries(12345).await.unwrap();
+        assert_eq!(summaries[0].name, "Bedwars");
+        assert_eq!(summaries[0].tag.as_deref(), Some("Minigames"));
+        assert_eq!(summaries[1].tag, None);
+
+        // Clearing the tag
+        db.set_server_tag(12345, "Bedwars".to_string(), None)
+            .await
+            .unwrap();
+        let summaries = db.get_server_summaries(12345).await.unwrap();
+        assert_eq!(summaries[0].tag, None);
+
+        // Unknown server (or another guild's)
+        assert!(matches!(
+            db.set_server_tag(67890, "Bedwars".to_string(), Some("SMP".to_string()))
+                .await,
+            Err(DbError::ServerNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_player_lookups_use_indexes() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        // Online players live in the cache; these are the SQL lookups that
+        // find_player_servers and the player list make per match
+        let queries = [
+            "SELECT guild_id, name FROM servers WHERE api_key_hash = ?1",
+            "SELECT texture_hash FROM player_skins WHERE player_name = ?1",
+            "SELECT last_seen FROM player_last_seen WHERE api_key_hash = ?1 AND player_name = ?2",
+        ];
+        for query in queries {
+            let sql = format!("EXPLAIN QUERY PLAN {}", query);
+            let placeholders = vec!["x"; query.matches('?').count()];
+            let plan = db
+                .conn
+                .call(move |conn| -> rusqlite::Result<Vec<String>> {
+                    conn.prepare(&sql)?
+                        .query_map(params_from_iter(placeholders), |row| row.get(3))?
+                        .collect()
+                })
+                .await
+                .unwrap();
+            assert!(
+                !plan.is_empty() && plan.iter().all(|step| step.starts_with("SEARCH")),
+                "{} uses {:?}",
+                query,
+                plan
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_avg_session_secs() {
+        let db = Database::open_in_memory().await.unwrap();
+        let start = now();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, start)
+            .await
+            .unwrap();
+        assert_eq!(db.get_avg_session_secs("hash1").await.unwrap(), None);
+
+        // Sessions of 60s (leave), 180s (leave) and 300s (dropped by a sync)
+        for player in ["Steve", "Alex", "Notch"] {
+            db.player_join("hash1".to_string(), pn(player), start)
+                .await
+                .unwrap();
+        }
+        db.player_leave("hash1".to_string(), pn("Steve"), start + 60)
+            .await
+            .unwrap();
+        db.apply_events("hash1".to_string(), vec![], vec![pn("Alex")], start + 180)
+            .await
+            .unwrap();
+        db.sync_players("hash1".to_string(), vec![], start + 300)
+            .await
+            .unwrap();
+
+        // Leaving while not online doesn't count as a session
+        db.player_leave("hash1".to_string(), pn("Herobrine"), start + 400)
+            .await
+            .unwrap();
+
+        assert_eq!(db.get_avg_session_secs("hash1").await.unwrap(), Some(180.0));
+    }
+
+    #[tokio::test]
+    async fn test_peak_players() {
+        let db = Database::open_in_memory().await.unwrap();
+        let start = now();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, start)
+            .await
+            .unwrap();
+        assert_eq!(db.get_peak_players("hash1").await.unwrap(), None);
+
+        for player in ["Steve", "Alex"] {
+            db.player_join("hash1".to_string(), pn(player), start)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            db.get_peak_players("hash1").await.unwrap(),
+            Some((2, start))
+        );
+
+        // Getting back to the peak doesn't move it
+        db.player_leave("hash1".to_string(), pn("Alex"), start + 5)
+            .await
+            .unwrap();
+        db.apply_events("hash1".to_string(), vec![pn("Notch")], vec![], start + 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_peak_players("hash1").await.unwrap(),
+            Some((2, start))
+        );
+
+        // A sync with more players raises it
+        db.sync_players(
+            "hash1".to_string(),
+            vec![pn("Steve"), pn("Alex"), pn("Notch")],
+            start + 20,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            db.get_peak_players("hash1").await.unwrap(),
+            Some((3, start + 20))
+        );
 
-        // Create a pending link
-        let link = db
-            .create_pending_link(
-                "oxeye-abc123".to_string(),
-                12345,
-                "Survival SMP".to_string(),
-                now(),
-            )
+        // Emptying the server keeps the peak
+        db.sync_players("hash1".to_string(), vec![], start + 30)
             .await
             .unwrap();
-        assert_eq!(link.code, "oxeye-abc123");
-        assert_eq!(link.guild_id, 12345);
-        assert_eq!(link.server_name, "Survival SMP");
-
-        // Get it
-        let link = db
-            .get_pending_link("oxeye-abc123".to_string())
+        db.player_join("hash1".to_string(), pn("Steve"), start + 40)
             .await
-            .unwrap()
             .unwrap();
-        assert_eq!(link.code, "oxeye-abc123");
+        assert_eq!(
+            db.get_peak_players("hash1").await.unwrap(),
+            Some((3, start + 20))
+        );
+    }
 
-        // Consume it
-        let link = db
-            .consume_pending_link("oxeye-abc123".to_string(), now())
+    #[tokio::test]
+    async fn test_delete_servers_by_guild() {
+        let db = Database::open_in_memory().await.unwrap();
+        for (hash, name, guild_id) in [
+            ("hash1", "Survival", 12345),
+            ("hash2", "Creative", 12345),
+            ("hash3", "Skyblock", 12345),
+            ("hash4", "Survival", 99999),
+        ] {
+            db.create_server(hash.to_string(), name.to_string(), guild_id, now())
+                .await
+                .unwrap();
+        }
+        db.player_join("hash1".to_string(), pn("Steve"), now())
             .await
             .unwrap();
-        assert_eq!(link.server_name, "Survival SMP");
 
-        // Should be gone now
+        assert_eq!(db.delete_servers_by_guild(12345).await.unwrap(), 3);
+
+        assert!(db.get_servers_by_guild(12345).await.unwrap().is_empty());
         assert!(
-            db.get_pending_link("oxeye-abc123".to_string())
+            db.get_online_players("hash1".to_string())
                 .await
                 .unwrap()
-                .is_none()
+                .is_empty()
         );
+        assert_eq!(db.get_servers_by_guild(99999).await.unwrap().len(), 1);
+        assert_eq!(db.delete_servers_by_guild(12345).await.unwrap(), 0);
     }
 
     #[tokio::test]
-    async fn test_expired_link() {
+    async fn test_guild_online_total() {
         let db = Database::open_in_memory().await.unwrap();
 
-        db.create_pending_link(
-            "oxeye-expired".to_string(),
-            12345,
-            "Test".to_string(),
-            now(),
-        )
-        .await
-        .unwrap();
+        // Unknown guild has no servers
+        assert_eq!(db.get_guild_online_total(12345).await.unwrap(), 0);
 
-        // Try to consume after expiry (11 minutes later)
-        let result = db
-            .consume_pending_link("oxeye-expired".to_string(), now() + 660)
-            .await;
-        assert!(result.is_err());
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash2".to_string(), "Creative".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash3".to_string(), "Other".to_string(), 67890, now())
+            .await
+            .unwrap();
+
+        // Servers with no players
+        assert_eq!(db.get_guild_online_total(12345).await.unwrap(), 0);
+
+        db.player_join("hash1".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash1".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
+        db.player_join("hash2".to_string(), pn("Notch"), now())
+            .await
+            .unwrap();
+        db.player_join("hash3".to_string(), pn("Herobrine"), now())
+            .await
+            .unwrap();
+
+        assert_eq!(db.get_guild_online_total(12345).await.unwrap(), 3);
+        assert_eq!(db.get_guild_online_total(67890).await.unwrap(), 1);
     }
 
     #[tokio::test]
-    async fn test_server_lifecycle() {
+    async fn test_is_player_online() {
         let db = Database::open_in_memory().await.unwrap();
 
-        // Create a server
-        let server = db
-            .create_server("hash123".to_string(), "Survival SMP".to_string(), 12345)
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
             .await
             .unwrap();
-        assert_eq!(server.name, "Survival SMP");
-
-        // Get it by API key
-        let server = db
-            .get_server_by_api_key("hash123".to_string())
+        db.create_server("hash2".to_string(), "Creative".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash3".to_string(), "Other".to_string(), 67890, now())
             .await
-            .unwrap()
             .unwrap();
-        assert_eq!(server.name, "Survival SMP");
 
-        // Get servers by guild
-        let servers = db.get_servers_by_guild(12345).await.unwrap();
-        assert_eq!(servers.len(), 1);
+        db.player_join("hash2".to_string(), pn("Steve"), now())
+            .await
+            .unwrap();
+        db.player_join("hash3".to_string(), pn("Alex"), now())
+            .await
+            .unwrap();
 
-        // Check name exists
-        assert!(
-            db.server_name_exists(12345, "Survival SMP".to_string())
-                .await
-                .unwrap()
-        );
-        assert!(
-            !db.server_name_exists(12345, "Creative".to_string())
-                .await
-                .unwrap()
+        assert_eq!(
+            db.is_player_online(12345, pn("Steve")).await.unwrap(),
+            Some("Creative".to_string())
         );
+        // Online, but on another guild's server
+        assert_eq!(db.is_player_online(12345, pn("Alex")).await.unwrap(), None);
+        assert_eq!(db.is_player_online(12345, pn("Notch")).await.unwrap(), None);
 
-        // Delete it
-        db.delete_server(12345, "Survival SMP".to_string())
+        db.player_leave("hash2".to_string(), pn("Steve"), now())
             .await
             .unwrap();
-        assert!(
-            db.get_server_by_api_key("hash123".to_string())
-                .await
-                .unwrap()
-                .is_none()
-        );
+        assert_eq!(db.is_player_online(12345, pn("Steve")).await.unwrap(), None);
     }
 
     #[tokio::test]
-    async fn test_player_tracking() {
+    async fn test_clear_render_cache_keeps_heads_and_skins() {
         let db = Database::open_in_memory().await.unwrap();
-
-        // Create a server first
-        db.create_server("hash123".to_string(), "Survival SMP".to_string(), 12345)
+        let (steve_hash, other_hash) = ("a".repeat(64), "b".repeat(64));
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
             .await
             .unwrap();
-
-        // Player joins
-        db.player_join("hash123".to_string(), pn("Steve"), now())
-            .await
-            .unwrap();
-        db.player_join("hash123".to_string(), pn("Alex"), now())
+        for hash in [&steve_hash, &other_hash] {
+            db.store_skin(hash.clone(), None, vec![1, 2, 3])
+                .await
+                .unwrap();
+            db.store_rendered_head(hash.clone(), vec![4, 5, 6], now())
+                .await
+                .unwrap();
+        }
+        db.store_status_image("hash1".to_string(), vec![7, 8, 9], now())
             .await
             .unwrap();
 
-        // Get online players
-        let players = db.get_online_players("hash123".to_string()).await.unwrap();
-        assert_eq!(players, vec![pn("Alex"), pn("Steve")]);
+        let cleared = db.clear_render_cache("hash1".to_string()).await.unwrap();
+        assert_eq!(cleared, 1);
 
-        // Player leaves
-        db.player_leave("hash123".to_string(), pn("Steve"))
+        // Heads are shared with other servers, so only the status image goes
+        assert!(db.get_status_image("hash1").await.unwrap().is_none());
+        for hash in [&steve_hash, &other_hash] {
+            assert!(db.get_rendered_head(hash).await.unwrap().is_some());
+            assert!(db.skin_exists(hash).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_status_image_drops_cached_gif() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
             .await
             .unwrap();
-        let players = db.get_online_players("hash123".to_string()).await.unwrap();
-        assert_eq!(players, vec![pn("Alex")]);
+        db.store_status_gif("hash1".to_string(), vec![1, 2, 3], now())
+            .await
+            .unwrap();
+        assert!(db.get_status_gif("hash1").await.unwrap().is_some());
 
-        // Sync players
-        db.sync_players("hash123".to_string(), vec![pn("Notch"), pn("jeb_")], now())
+        // The player list changed, so the GIF drawn from the old one goes
+        db.store_status_image("hash1".to_string(), vec![4, 5, 6], now())
             .await
             .unwrap();
-        let players = db.get_online_players("hash123".to_string()).await.unwrap();
-        assert_eq!(players, vec![pn("Notch"), pn("jeb_")]);
+        assert!(db.get_status_gif("hash1").await.unwrap().is_none());
     }
 
     #[tokio::test]
-    async fn test_server_summaries() {
+    async fn test_server_with_players_includes_texture_hash() {
         let db = Database::open_in_memory().await.unwrap();
+        let texture_hash = "a".repeat(64);
 
-        db.create_server("hash1".to_string(), "Survival".to_string(), 12345)
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
             .await
             .unwrap();
-        db.create_server("hash2".to_string(), "Creative".to_string(), 12345)
+        db.store_skin(texture_hash.clone(), None, vec![1, 2, 3])
             .await
             .unwrap();
-
         db.player_join("hash1".to_string(), pn("Steve"), now())
             .await
             .unwrap();
         db.player_join("hash1".to_string(), pn("Alex"), now())
             .await
             .unwrap();
+        db.update_player_skin("Steve", &texture_hash, now())
+            .await
+            .unwrap();
 
-        let summaries = db.get_server_summaries(12345).await.unwrap();
-        assert_eq!(summaries.len(), 2);
-        assert_eq!(summaries[0].name, "Creative");
-        assert_eq!(summaries[0].player_count, 0);
-        assert_eq!(summaries[1].name, "Survival");
-        assert_eq!(summaries[1].player_count, 2);
+        let server = db
+            .get_server_with_players(12345, "Survival".to_string())
+            .await
+            .unwrap();
+        assert_eq!(server.players[0].player_name, pn("Alex"));
+        assert_eq!(server.players[0].texture_hash, None);
+        assert_eq!(server.players[1].player_name, pn("Steve"));
+        assert_eq!(server.players[1].texture_hash, Some(texture_hash.clone()));
+
+        let servers = db.get_servers_with_players(12345).await.unwrap();
+        assert_eq!(servers[0].players[1].texture_hash, Some(texture_hash));
+    }
+
+    #[tokio::test]
+    async fn test_guild_stats() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        // Unknown guild
+        let stats = db.get_guild_stats(12345).await.unwrap();
+        assert_eq!(stats.server_count, 0);
+        assert_eq!(stats.online_players, 0);
+        assert!(stats.busiest_server.is_none());
+
+        for (hash, name) in [
+            ("hash1", "Survival"),
+            ("hash2", "Creative"),
+            ("hash3", "Empty"),
+        ] {
+            db.create_server(hash.to_string(), name.to_string(), 12345, now())
+                .await
+                .unwrap();
+        }
+
+        // Servers but nobody online
+        let stats = db.get_guild_stats(12345).await.unwrap();
+        assert_eq!(stats.server_count, 3);
+        assert!(stats.busiest_server.is_none());
+
+        for (hash, player) in [("hash1", "Steve"), ("hash1", "Alex"), ("hash2", "Notch")] {
+            db.player_join(hash.to_string(), pn(player), now())
+                .await
+                .unwrap();
+        }
+
+        let stats = db.get_guild_stats(12345).await.unwrap();
+        assert_eq!(stats.server_count, 3);
+        assert_eq!(stats.online_players, 3);
+        let busiest = stats.busiest_server.unwrap();
+        assert_eq!(busiest.name, "Survival");
+        assert_eq!(busiest.player_count, 2);
+
+        // Ties go to the first server by name
+        db.player_join("hash2".to_string(), pn("Herobrine"), now())
+            .await
+            .unwrap();
+        let stats = db.get_guild_stats(12345).await.unwrap();
+        assert_eq!(stats.busiest_server.unwrap().name, "Creative");
     }
 
     #[tokio::test]
     async fn test_servers_with_players() {
         let db = Database::open_in_memory().await.unwrap();
 
-        db.create_server("hash1".to_string(), "Survival".to_string(), 12345)
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
             .await
             .unwrap();
-        db.create_server("hash2".to_string(), "Creative".to_string(), 12345)
+        db.create_server("hash2".to_string(), "Creative".to_string(), 12345, now())
             .await
             .unwrap();
 
@@ -1120,11 +5017,208 @@ mod tests {
         assert_eq!(player_names, vec!["Alex", "Steve"]);
     }
 
+    #[tokio::test]
+    async fn test_guild_token_lifecycle() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        // No token yet
+        let result = db.verify_guild_token(12345, "token1".to_string()).await;
+        assert!(matches!(result, Err(DbError::InvalidGuildToken)));
+
+        db.create_guild_token("token1".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.verify_guild_token(12345, "token1".to_string())
+            .await
+            .unwrap();
+
+        // Token is scoped to its guild
+        let result = db.verify_guild_token(67890, "token1".to_string()).await;
+        assert!(matches!(result, Err(DbError::InvalidGuildToken)));
+
+        // A new token replaces the old one
+        db.create_guild_token("token2".to_string(), 12345, now())
+            .await
+            .unwrap();
+        let result = db.verify_guild_token(12345, "token1".to_string()).await;
+        assert!(matches!(result, Err(DbError::InvalidGuildToken)));
+        db.verify_guild_token(12345, "token2".to_string())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_servers_created_at_added_to_old_schema() {
+        let path =
+            std::env::temp_dir().join(format!("oxeye-created-at-test-{}.db", std::process::id()));
+        {
+            let conn = tokio_rusqlite::rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE servers (
+                    api_key_hash TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    guild_id INTEGER NOT NULL,
+                    UNIQUE(guild_id, name)
+                );
+                INSERT INTO servers (api_key_hash, name, guild_id) VALUES ('hash1', 'Old', 12345);",
+            )
+            .unwrap();
+        }
+
+        let db = Database::open(&path).await.unwrap();
+        let server = db
+            .get_server_by_api_key("hash1".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(server.created_at, 0);
+        let summaries = db.get_server_summaries(12345).await.unwrap();
+        assert_eq!(summaries[0].tag, None);
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_case_duplicate_names_renamed_on_open() {
+        let path =
+            std::env::temp_dir().join(format!("oxeye-case-dup-test-{}.db", std::process::id()));
+        {
+            let conn = tokio_rusqlite::rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE servers (
+                    api_key_hash TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    guild_id INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT 0,
+                    UNIQUE(guild_id, name)
+                );
+                INSERT INTO servers (api_key_hash, name, guild_id, created_at) VALUES
+                    ('hash1', 'survival', 1, 20),
+                    ('hash2', 'Survival', 1, 10),
+                    ('hash3', 'SURVIVAL', 1, 30),
+                    ('hash4', 'survival', 2, 10);",
+            )
+            .unwrap();
+        }
+
+        // The oldest server keeps the name; the others get a suffix
+        let db = Database::open(&path).await.unwrap();
+        let name = |hash: &str| {
+            let db = db.clone();
+            let hash = hash.to_string();
+            async move { db.get_server_by_api_key(hash).await.unwrap().unwrap().name }
+        };
+        assert_eq!(name("hash2").await, "Survival");
+        assert_eq!(name("hash1").await, "survival (2)");
+        assert_eq!(name("hash3").await, "SURVIVAL (3)");
+        assert_eq!(name("hash4").await, "survival");
+
+        // Deleting by name now removes only the one server
+        db.delete_server(1, "survival".to_string()).await.unwrap();
+        assert!(
+            db.get_server_by_api_key("hash2".to_string())
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(name("hash1").await, "survival (2)");
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_limit_per_guild() {
+        let db = Database::open_in_memory().await.unwrap();
+
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        db.create_server("hash2".to_string(), "Creative".to_string(), 12345, now())
+            .await
+            .unwrap();
+        assert_eq!(db.count_servers_in_guild(12345).await.unwrap(), 2);
+        assert_eq!(db.count_servers_in_guild(67890).await.unwrap(), 0);
+
+        // At the limit
+        let result = db
+            .create_pending_link(
+                "oxeye-abc123".to_string(),
+                12345,
+                "Hardcore".to_string(),
+                now(),
+                2,
+            )
+            .await;
+        assert!(matches!(result, Err(DbError::ServerLimitReached)));
+
+        // Below the limit
+        db.create_pending_link(
+            "oxeye-abc123".to_string(),
+            12345,
+            "Hardcore".to_string(),
+            now(),
+            3,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_server_limit_counts_unclaimed_codes() {
+        let db = Database::open_in_memory().await.unwrap();
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
+            .await
+            .unwrap();
+        let link = |code: &str, name: &str, created_at: i64| {
+            db.create_pending_link(code.to_string(), 12345, name.to_string(), created_at, 2)
+        };
+
+        // One server plus one unclaimed code fills a limit of two
+        link("oxeye-abc111", "Creative", now()).await.unwrap();
+        assert!(matches!(
+            link("oxeye-abc222", "Hardcore", now()).await,
+            Err(DbError::ServerLimitReached)
+        ));
+        // Another code for the same name doesn't take another slot
+        link("oxeye-abc333", "creative", now()).await.unwrap();
+
+        // Nor does a relink code
+        db.create_relink_code(
+            "oxeye-abc444".to_string(),
+            12345,
+            "Survival".to_string(),
+            now(),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            db.refresh_pending_link(
+                12345,
+                "Hardcore".to_string(),
+                "oxeye-abc555".to_string(),
+                now(),
+                2
+            )
+            .await,
+            Err(DbError::ServerLimitReached)
+        ));
+
+        // Expired codes free their slot
+        let later = now() + PendingLink::TTL_SECONDS + 1;
+        link("oxeye-abc666", "Hardcore", later).await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_server_name_conflict() {
         let db = Database::open_in_memory().await.unwrap();
 
-        db.create_server("hash1".to_string(), "Survival".to_string(), 12345)
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
             .await
             .unwrap();
 
@@ -1135,6 +5229,7 @@ mod tests {
                 12345,
                 "Survival".to_string(),
                 now(),
+                25,
             )
             .await;
         assert!(result.is_err());
@@ -1144,7 +5239,7 @@ mod tests {
     async fn test_player_join_times_and_time_online_calculation() {
         let db = Database::open_in_memory().await.unwrap();
 
-        db.create_server("hash1".to_string(), "Survival".to_string(), 12345)
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
             .await
             .unwrap();
 
@@ -1220,7 +5315,7 @@ mod tests {
     async fn test_player_time_online_with_join_leave() {
         let db = Database::open_in_memory().await.unwrap();
 
-        db.create_server("hash1".to_string(), "Survival".to_string(), 12345)
+        db.create_server("hash1".to_string(), "Survival".to_string(), 12345, now())
             .await
             .unwrap();
 
@@ -1244,7 +5339,7 @@ mod tests {
             .unwrap();
 
         // Bob leaves after 10 minutes (doesn't affect others' join times)
-        db.player_leave("hash1".to_string(), pn("Bob"))
+        db.player_leave("hash1".to_string(), pn("Bob"), now())
             .await
             .unwrap();
 
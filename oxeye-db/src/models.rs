@@ -1,7 +1,20 @@
 use arrayvec::ArrayString;
 
-/// Minecraft player name - max 16 characters, stored inline (no heap allocation).
-pub type PlayerName = ArrayString<16>;
+/// Longest player name that fits in a `PlayerName`, in bytes.
+///
+/// Wide enough for Bedrock gamertags; Java's 16-character limit is enforced by
+/// the backend's name policy rather than the type.
+pub const MAX_PLAYER_NAME_LEN: usize = 32;
+
+/// Minecraft player name - max 32 bytes, stored inline (no heap allocation).
+pub type PlayerName = ArrayString<MAX_PLAYER_NAME_LEN>;
+
+/// A player joining or leaving, as reported in a batch of events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerEvent {
+    Join(PlayerName),
+    Leave(PlayerName),
+}
 
 /// A pending connection code waiting for a Minecraft server to claim it.
 #[derive(Debug, Clone)]
@@ -14,19 +27,23 @@ pub struct PendingLink {
     pub server_name: String,
     /// Unix timestamp when this was created
     pub created_at: i64,
+    /// Claiming the code re-keys the existing server with this name instead
+    /// of linking a new one
+    pub relink: bool,
 }
 
 impl PendingLink {
-    /// Check if this pending link has expired (10 minute TTL)
+    /// How long a code stays valid after creation
+    pub const TTL_SECONDS: i64 = 600; // 10 minutes
+
+    /// Check if this pending link has expired
     pub fn is_expired(&self, now: i64) -> bool {
-        const TTL_SECONDS: i64 = 600; // 10 minutes
-        now - self.created_at > TTL_SECONDS
+        now - self.created_at > Self::TTL_SECONDS
     }
 
     /// Seconds remaining until expiry
     pub fn expires_in(&self, now: i64) -> i64 {
-        const TTL_SECONDS: i64 = 600;
-        (self.created_at + TTL_SECONDS - now).max(0)
+        (self.created_at + Self::TTL_SECONDS - now).max(0)
     }
 }
 
@@ -39,6 +56,8 @@ pub struct Server {
     pub name: String,
     /// Discord guild ID this server is linked to
     pub guild_id: u64,
+    /// Unix timestamp when the server was linked (0 if linked before this was tracked)
+    pub created_at: i64,
 }
 
 /// An online player on a server.
@@ -57,6 +76,22 @@ pub struct OnlinePlayer {
 pub struct ServerSummary {
     pub name: String,
     pub player_count: u32,
+    /// Group shown in `/list` (e.g. "SMP"), if the admin set one.
+    pub tag: Option<String>,
+    /// When the server last sent a full player list, if ever.
+    pub last_sync_at: Option<i64>,
+}
+
+/// Aggregate counts for a guild's linked servers.
+#[derive(Debug, Clone)]
+pub struct GuildStats {
+    /// Number of linked servers
+    pub server_count: u32,
+    /// Online players across all linked servers
+    pub online_players: u64,
+    /// Server with the most online players (first by name on ties),
+    /// or None if nobody is online
+    pub busiest_server: Option<ServerSummary>,
 }
 
 /// Player info without server context (for use in ServerWithPlayers).
@@ -66,6 +101,25 @@ pub struct PlayerInfo {
     pub player_name: PlayerName,
     /// Unix timestamp when they joined
     pub joined_at: i64,
+    /// Hash of the player's current skin texture, if known
+    pub texture_hash: Option<String>,
+    /// Player's Mojang UUID, if the server has reported it
+    pub uuid: Option<String>,
+}
+
+/// A recorded admin action from a Discord command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// Discord guild the action was taken in
+    pub guild_id: u64,
+    /// Discord user who ran the command
+    pub user_id: u64,
+    /// Command that made the change (e.g. "connect", "settag")
+    pub action: String,
+    /// What it changed (e.g. the server name), empty if it applies to the whole guild
+    pub target: String,
+    /// Unix timestamp of the action
+    pub created_at: i64,
 }
 
 /// Server with its online players.
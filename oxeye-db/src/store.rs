@@ -0,0 +1,347 @@
+//! The storage interface the backend's routes and Discord commands use.
+//!
+//! `Database` (SQLite plus the in-memory online player cache) implements it.
+
+use crate::{
+    AuditEntry, Database, GuildStats, OnlinePlayer, PendingLink, PlayerEvent, PlayerInfo,
+    PlayerName, Result, Server, ServerSummary, ServerWithPlayers,
+};
+use async_trait::async_trait;
+
+/// Declares `Store` and implements it for `Database` by calling the inherent
+/// method of the same name, so the two can't drift apart.
+macro_rules! store {
+    ($($(#[$doc:meta])* fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        /// Everything the backend needs from storage.
+        #[async_trait]
+        pub trait Store: Send + Sync {
+            $($(#[$doc])* async fn $name(&self $(, $arg: $ty)*) -> $ret;)*
+        }
+
+        #[async_trait]
+        impl Store for Database {
+            $(async fn $name(&self $(, $arg: $ty)*) -> $ret {
+                Database::$name(self $(, $arg)*).await
+            })*
+        }
+
+        #[cfg(feature = "test-util")]
+        #[async_trait]
+        impl<S: Store> Store for RecordingStore<S> {
+            $(async fn $name(&self $(, $arg: $ty)*) -> $ret {
+                self.calls.record(stringify!($name));
+                self.inner.$name($($arg),*).await
+            })*
+        }
+    };
+}
+
+/// A `Store` that records the name of every method called on it before
+/// passing the call on, so tests can check which storage calls a request made.
+#[cfg(feature = "test-util")]
+pub struct RecordingStore<S> {
+    inner: S,
+    calls: CallLog,
+}
+
+#[cfg(feature = "test-util")]
+impl<S: Store> RecordingStore<S> {
+    /// Wrap `inner`, returning the wrapper and a handle to its call log.
+    pub fn new(inner: S) -> (Self, CallLog) {
+        let calls = CallLog::default();
+        (
+            Self {
+                inner,
+                calls: calls.clone(),
+            },
+            calls,
+        )
+    }
+}
+
+/// Names of the `Store` methods a `RecordingStore` was called with, in order.
+#[cfg(feature = "test-util")]
+#[derive(Clone, Default)]
+pub struct CallLog(std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>);
+
+#[cfg(feature = "test-util")]
+impl CallLog {
+    fn record(&self, method: &'static str) {
+        self.0.lock().unwrap().push(method);
+    }
+
+    /// How many times `method` was called.
+    pub fn count(&self, method: &str) -> usize {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| **m == method)
+            .count()
+    }
+}
+
+store! {
+    /// Run a trivial query to check that the database is reachable.
+    fn ping(&self) -> Result<()>;
+
+    /// Check if a server has synced since backend restart.
+    fn is_server_synced(&self, api_key_hash: &str) -> bool;
+
+    /// When a server last sent a full player list (None if it never has).
+    fn get_last_sync(&self, api_key_hash: &str) -> Result<Option<i64>>;
+
+    /// Check if a server has synced since backend restart (by guild and name).
+    fn is_server_synced_by_name(&self, guild_id: u64, server_name: &str) -> Result<bool>;
+
+    /// Create a new pending link.
+    /// Returns an error if a server with that name (ignoring case) already exists
+    /// in the guild, or if the guild's linked servers and unexpired codes for
+    /// other names already add up to `max_servers`.
+    fn create_pending_link(&self, code: String, guild_id: u64, server_name: String, now: i64, max_servers: u32) -> Result<PendingLink>;
+
+    /// Create several pending links, given as (code, server name) pairs, at once.
+    /// Either all are created or none are: a name that conflicts with a linked
+    /// server, or more links than the guild has room for under `max_servers`,
+    /// fails the whole batch.
+    fn create_pending_links(&self, guild_id: u64, links: Vec<(String, String)>, now: i64, max_servers: u32) -> Result<Vec<PendingLink>>;
+
+    /// Create a code that re-links an existing server, e.g. one whose key was
+    /// lost in a crash. Claiming it gives the server a new key (see
+    /// [`Database::relink_server`]); the old key keeps working until then.
+    /// Returns ServerNotFound if the guild has no server with this name.
+    fn create_relink_code(&self, code: String, guild_id: u64, server_name: String, now: i64) -> Result<PendingLink>;
+
+    /// Replace the guild's unexpired pending link for `server_name` (ignoring
+    /// case) with `new_code`, restarting its expiry. The old code stops working
+    /// and a relink code stays a relink code. If there is no such link, a new
+    /// one is created with the same checks as [`Database::create_pending_link`].
+    fn refresh_pending_link(&self, guild_id: u64, server_name: String, new_code: String, now: i64, max_servers: u32) -> Result<PendingLink>;
+
+    /// Get a pending link by code.
+    /// Returns None if not found.
+    fn get_pending_link(&self, code: String) -> Result<Option<PendingLink>>;
+
+    /// Get a guild's pending links that haven't expired yet, newest first.
+    fn get_pending_links_by_guild(&self, guild_id: u64, now: i64) -> Result<Vec<PendingLink>>;
+
+    /// Consume a pending link (delete it and return it).
+    /// Returns PendingLinkAlreadyUsed if the code was claimed before and hasn't
+    /// expired yet, or PendingLinkNotFound if it never existed or has expired.
+    fn consume_pending_link(&self, code: String, now: i64) -> Result<PendingLink>;
+
+    /// Clean up expired pending links.
+    fn cleanup_expired_links(&self, now: i64) -> Result<u64>;
+
+    /// Create a new server.
+    fn create_server(&self, api_key_hash: String, name: String, guild_id: u64, now: i64) -> Result<Server>;
+
+    /// Get a server by API key hash.
+    fn get_server_by_api_key(&self, api_key_hash: String) -> Result<Option<Server>>;
+
+    /// Count the servers linked to a guild.
+    fn count_servers_in_guild(&self, guild_id: u64) -> Result<u32>;
+
+    /// Get all servers for a guild.
+    fn get_servers_by_guild(&self, guild_id: u64) -> Result<Vec<Server>>;
+
+    /// Get one page of a guild's servers, ordered by name, plus the total
+    /// number of servers in the guild.
+    fn get_servers_paginated(&self, guild_id: u64, limit: u32, offset: u32) -> Result<(Vec<Server>, u64)>;
+
+    /// Get server summaries for a guild (with player counts and tags).
+    fn get_server_summaries(&self, guild_id: u64) -> Result<Vec<ServerSummary>>;
+
+    /// Get server summaries for a guild, busiest first (ties broken by name).
+    fn get_servers_by_activity(&self, guild_id: u64) -> Result<Vec<ServerSummary>>;
+
+    /// Get aggregate stats for a guild: server count, online total and busiest server.
+    fn get_guild_stats(&self, guild_id: u64) -> Result<GuildStats>;
+
+    /// Get the total number of online players across all servers in a guild.
+    fn get_guild_online_total(&self, guild_id: u64) -> Result<u64>;
+
+    /// Find which server in a guild a player is currently online on.
+    fn is_player_online(&self, guild_id: u64, player_name: PlayerName) -> Result<Option<String>>;
+
+    /// Set or clear (`None`) the tag used to group a server in `/list`.
+    /// Returns `ServerNotFound` if the guild has no server with that name.
+    fn set_server_tag(&self, guild_id: u64, name: String, tag: Option<String>) -> Result<()>;
+
+    /// Delete a server by guild and name.
+    fn delete_server(&self, guild_id: u64, name: String) -> Result<()>;
+
+    /// Delete every server linked to a guild, returning how many were removed.
+    fn delete_servers_by_guild(&self, guild_id: u64) -> Result<u64>;
+
+    /// Delete a server by API key hash (for self-disconnect).
+    fn delete_server_by_api_key(&self, api_key_hash: String) -> Result<()>;
+
+    /// Replace a server's API key hash, keeping its players and history.
+    fn rotate_api_key(&self, old_hash: String, new_hash: String) -> Result<()>;
+
+    /// Give the server named `name` in a guild a new API key hash, keeping its
+    /// history and online players; the old key stops working.
+    /// Returns ServerNotFound if the guild has no server with this name.
+    fn relink_server(&self, guild_id: u64, name: String, new_hash: String) -> Result<Server>;
+
+    /// Move a server to another guild, keeping its API key and history.
+    /// Returns ServerNotFound if no server has this API key hash, or
+    /// ServerNameConflict if the destination guild already has a server
+    /// with its name.
+    fn transfer_server(&self, api_key_hash: String, new_guild_id: u64) -> Result<Server>;
+
+    /// Check if a server name exists in a guild, ignoring case.
+    fn server_name_exists(&self, guild_id: u64, name: String) -> Result<bool>;
+
+    /// Get a server's API key hash by guild and name.
+    fn get_api_key_hash_by_name(&self, guild_id: u64, name: &str) -> Result<Option<String>>;
+
+    /// Record a player joining.
+    fn player_join(&self, api_key_hash: String, player_name: PlayerName, now: i64) -> Result<()>;
+
+    /// Record a player leaving.
+    fn player_leave(&self, api_key_hash: String, player_name: PlayerName, now: i64) -> Result<()>;
+
+    /// Apply a batch of buffered joins and leaves for a server, joins first.
+    fn apply_events(&self, api_key_hash: String, joins: Vec<PlayerName>, leaves: Vec<PlayerName>, now: i64) -> Result<()>;
+
+    /// Apply a batch of buffered join and leave events for a server, in order.
+    fn apply_ordered_events(&self, api_key_hash: String, events: Vec<PlayerEvent>, now: i64) -> Result<()>;
+
+    /// Sync the player list (replace all players for a server).
+    fn sync_players(&self, api_key_hash: String, players: Vec<PlayerName>, now: i64) -> Result<()>;
+
+    /// Remove all of a server's online players without recording them as
+    /// leaving (their real leave times are unknown, e.g. after a crash).
+    /// Returns how many players were cleared.
+    fn clear_players(&self, api_key_hash: String) -> Result<usize>;
+
+    /// Get when a player was last seen leaving a server, if ever.
+    fn get_last_seen(&self, api_key_hash: String, player_name: PlayerName) -> Result<Option<i64>>;
+
+    /// Count the distinct players who have ever been on a server: everyone
+    /// recorded as leaving it, plus anyone online who hasn't left yet.
+    fn count_unique_players(&self, api_key_hash: &str) -> Result<u64>;
+
+    /// Mean length in seconds of a server's completed sessions,
+    /// or None if nobody has left it yet.
+    fn get_avg_session_secs(&self, api_key_hash: &str) -> Result<Option<f64>>;
+
+    /// Most players a server has had online at once and when that was first
+    /// reached, or None if nobody has joined it yet.
+    fn get_peak_players(&self, api_key_hash: &str) -> Result<Option<(u64, i64)>>;
+
+    /// Delete completed sessions that ended more than `older_than_secs` before `now`.
+    /// Returns how many were deleted.
+    fn prune_sessions(&self, older_than_secs: i64, now: i64) -> Result<u64>;
+
+    /// Get players who left a server at or after `since` and aren't online now.
+    fn get_recently_left(&self, api_key_hash: String, since: i64) -> Result<Vec<(PlayerName, i64)>>;
+
+    /// Get online players for a server with their join times, longest online first
+    /// (ties sorted by name).
+    fn get_online_players_detailed(&self, api_key_hash: String) -> Result<Vec<OnlinePlayer>>;
+
+    /// Get a server's online players who joined at or after `since`, earliest
+    /// arrival first (ties sorted by name).
+    fn get_players_since(&self, api_key_hash: String, since: i64) -> Result<Vec<PlayerName>>;
+
+    /// Get online players for a server (sorted by name).
+    fn get_online_players(&self, api_key_hash: String) -> Result<Vec<PlayerName>>;
+
+    /// Count a server's online players without collecting their names.
+    fn count_online_players(&self, api_key_hash: &str) -> Result<u64>;
+
+    /// Count online players summed across every server.
+    fn count_all_online_players(&self) -> u64;
+
+    /// Find every linked server a player is currently online on, across guilds.
+    fn find_player_servers(&self, player_name: PlayerName) -> Result<Vec<(u64, String)>>;
+
+    /// Move every online player from one server to another, for networks that
+    /// run one world behind several proxies. Returns the destination's player
+    /// count afterwards.
+    fn merge_players(&self, src_hash: String, dst_hash: String) -> Result<usize>;
+
+    /// Get all servers with their online players for a guild.
+    fn get_servers_with_players(&self, guild_id: u64) -> Result<Vec<ServerWithPlayers>>;
+
+    /// Get several servers with their online players, in the order of `api_key_hashes`.
+    fn get_many_servers_with_players(&self, api_key_hashes: &[String]) -> Result<Vec<ServerWithPlayers>>;
+
+    /// Get a specific server with its online players.
+    fn get_server_with_players(&self, guild_id: u64, server_name: String) -> Result<ServerWithPlayers>;
+
+    /// Store a guild-scoped token hash, replacing any previous token for the guild.
+    fn create_guild_token(&self, token_hash: String, guild_id: u64, now: i64) -> Result<()>;
+
+    /// Verify that a token hash belongs to the given guild.
+    /// Returns `InvalidGuildToken` if it doesn't.
+    fn verify_guild_token(&self, guild_id: u64, token_hash: String) -> Result<()>;
+
+    /// Get a guild's Discord embed color as 0xRRGGBB, if one was set.
+    fn get_embed_color(&self, guild_id: u64) -> Result<Option<u32>>;
+
+    /// Set a guild's Discord embed color (0xRRGGBB).
+    fn set_embed_color(&self, guild_id: u64, color: u32) -> Result<()>;
+
+    /// Get the URL a guild's join/leave webhooks are sent to, if any.
+    fn get_guild_webhook(&self, guild_id: u64) -> Result<Option<String>>;
+
+    /// Set (or with `None`, remove) a guild's join/leave webhook URL.
+    fn set_guild_webhook(&self, guild_id: u64, url: Option<String>) -> Result<()>;
+
+    /// Record an admin action taken in a guild.
+    fn record_audit(&self, guild_id: u64, user_id: u64, action: String, target: String, now: i64) -> Result<()>;
+
+    /// Get a guild's most recent audit entries, newest first.
+    fn get_audit_log(&self, guild_id: u64, limit: u32) -> Result<Vec<AuditEntry>>;
+
+    /// Check if a skin exists by texture hash.
+    fn skin_exists(&self, texture_hash: &str) -> Result<bool>;
+
+    /// Store a skin (raw PNG data).
+    fn store_skin(&self, texture_hash: String, texture_url: Option<String>, skin_data: Vec<u8>) -> Result<()>;
+
+    /// Get skin data by texture hash.
+    fn get_skin_data(&self, texture_hash: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Update player's current skin mapping.
+    fn update_player_skin(&self, player_name: &str, texture_hash: &str, now: i64) -> Result<()>;
+
+    /// Remember the UUIDs servers reported for these players.
+    fn update_player_uuids(&self, players: Vec<(PlayerName, String)>, now: i64) -> Result<()>;
+
+    /// Get a player's current texture hash.
+    fn get_player_texture_hash(&self, player_name: &str) -> Result<Option<String>>;
+
+    /// Store a rendered head image.
+    fn store_rendered_head(&self, texture_hash: String, head_data: Vec<u8>, now: i64) -> Result<()>;
+
+    /// Get a rendered head by texture hash.
+    fn get_rendered_head(&self, texture_hash: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store a cached status composite image for a server.
+    /// The server's cached GIF was drawn from the old player list, so it's dropped.
+    fn store_status_image(&self, api_key_hash: String, image_data: Vec<u8>, now: i64) -> Result<()>;
+
+    /// Store a cached animated status GIF for a server.
+    fn store_status_gif(&self, api_key_hash: String, image_data: Vec<u8>, now: i64) -> Result<()>;
+
+    /// Get a cached animated status GIF.
+    fn get_status_gif(&self, api_key_hash: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Get a cached status composite image.
+    fn get_status_image(&self, api_key_hash: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Drop a server's cached status image and GIF so they are rendered again
+    /// on next use. Rendered heads are shared by every server and keyed by
+    /// texture hash, so they can't go stale and are kept. Returns how many
+    /// images were removed.
+    fn clear_render_cache(&self, api_key_hash: String) -> Result<u64>;
+
+    /// Get online players for a server with their texture hashes and UUIDs
+    /// (sorted by name), for rendering status images.
+    fn get_players_with_heads(&self, api_key_hash: &str) -> Result<Vec<PlayerInfo>>;
+}